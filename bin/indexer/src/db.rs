@@ -0,0 +1,793 @@
+use std::path::Path;
+
+use fixed::types::I80F48;
+use mango_v4_client::events::MangoEvent;
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+
+use crate::source::IndexedTransaction;
+
+// I80F48 values are stored as REAL: precise enough for analytics.
+// u64 values are stored as INTEGER with their bits reinterpreted as i64.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS transactions (
+    signature TEXT PRIMARY KEY,
+    slot INTEGER NOT NULL,
+    block_time INTEGER
+);
+CREATE INDEX IF NOT EXISTS transactions_slot ON transactions (slot);
+
+CREATE TABLE IF NOT EXISTS perp_fills (
+    mango_group TEXT NOT NULL,
+    market_index INTEGER NOT NULL,
+    seq_num INTEGER NOT NULL,
+    signature TEXT NOT NULL,
+    slot INTEGER NOT NULL,
+    timestamp INTEGER NOT NULL,
+    taker_side INTEGER NOT NULL,
+    maker_slot INTEGER NOT NULL,
+    maker_out INTEGER NOT NULL,
+    maker TEXT NOT NULL,
+    maker_client_order_id INTEGER NOT NULL,
+    maker_fee REAL NOT NULL,
+    maker_timestamp INTEGER NOT NULL,
+    taker TEXT NOT NULL,
+    taker_client_order_id INTEGER NOT NULL,
+    taker_fee REAL NOT NULL,
+    price INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    -- only known for FillLogV3 and later
+    maker_realized_pnl REAL,
+    maker_entry_price_per_base_lot REAL,
+    taker_realized_pnl REAL,
+    taker_entry_price_per_base_lot REAL,
+    PRIMARY KEY (mango_group, market_index, seq_num)
+);
+
+CREATE TABLE IF NOT EXISTS perp_funding_updates (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    mango_group TEXT NOT NULL,
+    market_index INTEGER NOT NULL,
+    long_funding REAL NOT NULL,
+    short_funding REAL NOT NULL,
+    price REAL NOT NULL,
+    stable_price REAL NOT NULL,
+    fees_accrued REAL NOT NULL,
+    open_interest INTEGER NOT NULL,
+    instantaneous_funding_rate REAL NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS token_index_updates (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    mango_group TEXT NOT NULL,
+    token_index INTEGER NOT NULL,
+    deposit_index REAL NOT NULL,
+    borrow_index REAL NOT NULL,
+    avg_utilization REAL NOT NULL,
+    price REAL NOT NULL,
+    stable_price REAL NOT NULL,
+    collected_fees REAL NOT NULL,
+    loan_fee_rate REAL NOT NULL,
+    total_borrows REAL NOT NULL,
+    total_deposits REAL NOT NULL,
+    borrow_rate REAL NOT NULL,
+    deposit_rate REAL NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+CREATE TABLE IF NOT EXISTS token_balances (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    mango_group TEXT NOT NULL,
+    mango_account TEXT NOT NULL,
+    token_index INTEGER NOT NULL,
+    indexed_position REAL NOT NULL,
+    deposit_index REAL NOT NULL,
+    borrow_index REAL NOT NULL,
+    balance REAL NOT NULL,
+    cumulative_deposit_interest REAL,
+    cumulative_borrow_interest REAL,
+    cumulative_deposits REAL,
+    cumulative_withdraws REAL,
+    PRIMARY KEY (signature, event_index)
+);
+CREATE INDEX IF NOT EXISTS token_balances_account ON token_balances (mango_account, token_index);
+
+-- kind: 'liq_token_with_token' or 'liq_token_bankruptcy'
+CREATE TABLE IF NOT EXISTS token_liquidations (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    kind TEXT NOT NULL,
+    mango_group TEXT NOT NULL,
+    liqee TEXT NOT NULL,
+    liqor TEXT NOT NULL,
+    asset_token_index INTEGER,
+    liab_token_index INTEGER NOT NULL,
+    asset_transfer REAL,
+    liab_transfer REAL,
+    asset_price REAL,
+    liab_price REAL NOT NULL,
+    insurance_transfer REAL,
+    socialized_loss REAL,
+    bankruptcy INTEGER NOT NULL,
+    PRIMARY KEY (signature, event_index)
+);
+
+-- kind: 'liq_base_or_positive_pnl', 'liq_negative_pnl_or_bankruptcy',
+-- 'liq_bankruptcy' or 'liq_isolated'
+CREATE TABLE IF NOT EXISTS perp_liquidations (
+    signature TEXT NOT NULL,
+    event_index INTEGER NOT NULL,
+    slot INTEGER NOT NULL,
+    block_time INTEGER,
+    kind TEXT NOT NULL,
+    mango_group TEXT NOT NULL,
+    perp_market_index INTEGER NOT NULL,
+    liqee TEXT NOT NULL,
+    liqor TEXT NOT NULL,
+    base_transfer INTEGER,
+    quote_transfer REAL,
+    pnl_transfer REAL,
+    insurance_transfer REAL,
+    socialized_loss REAL,
+    price REAL,
+    PRIMARY KEY (signature, event_index)
+);
+";
+
+fn f(bits: i128) -> f64 {
+    I80F48::from_bits(bits).to_num::<f64>()
+}
+
+pub struct Database {
+    conn: Connection,
+}
+
+impl Database {
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    #[cfg(test)]
+    fn open_in_memory() -> anyhow::Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> anyhow::Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    /// Signature of the newest indexed transaction
+    pub fn last_signature(&self) -> anyhow::Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT signature FROM transactions ORDER BY slot DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    /// Stores the events of a transaction
+    ///
+    /// Rows are keyed by the fill sequence number or by the transaction signature and
+    /// event position, so storing the same transaction again changes nothing.
+    /// Returns the number of stored events.
+    pub fn store(
+        &mut self,
+        tx: &IndexedTransaction,
+        events: &[MangoEvent],
+    ) -> anyhow::Result<usize> {
+        let db_tx = self.conn.transaction()?;
+        db_tx.execute(
+            "INSERT INTO transactions (signature, slot, block_time) VALUES (?1, ?2, ?3)
+             ON CONFLICT (signature) DO UPDATE SET slot = excluded.slot, block_time = excluded.block_time",
+            params![tx.signature, tx.slot as i64, tx.block_time],
+        )?;
+        let mut stored = 0;
+        for (event_index, event) in events.iter().enumerate() {
+            if is_superseded(event, events.get(event_index + 1)) {
+                continue;
+            }
+            if store_event(&db_tx, tx, event_index, event)? {
+                stored += 1;
+            }
+        }
+        db_tx.commit()?;
+        Ok(stored)
+    }
+}
+
+/// Whether `event` is a TokenBalanceLog that is repeated by the TokenBalanceLogV2 after it
+///
+/// The program emits both versions for every token balance change, only the newer one
+/// is stored then.
+fn is_superseded(event: &MangoEvent, next: Option<&MangoEvent>) -> bool {
+    match (event, next) {
+        (MangoEvent::TokenBalanceLog(v1), Some(MangoEvent::TokenBalanceLogV2(v2))) => {
+            v1.mango_account == v2.mango_account && v1.token_index == v2.token_index
+        }
+        _ => false,
+    }
+}
+
+/// Insert a token balance log; only the newer version has the cumulative totals
+macro_rules! insert_token_balance {
+    (
+        $db_tx:expr, $signature:expr, $event_index:expr, $slot:expr, $block_time:expr,
+        $balance:expr, $totals:expr
+    ) => {{
+        let indexed_position = I80F48::from_bits($balance.indexed_position);
+        let index = if indexed_position.is_negative() {
+            $balance.borrow_index
+        } else {
+            $balance.deposit_index
+        };
+        let totals: Option<[f64; 4]> = $totals;
+        $db_tx.execute(
+            "INSERT OR REPLACE INTO token_balances (
+                signature, event_index, slot, block_time, mango_group, mango_account,
+                token_index, indexed_position, deposit_index, borrow_index, balance,
+                cumulative_deposit_interest, cumulative_borrow_interest,
+                cumulative_deposits, cumulative_withdraws
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            params![
+                $signature,
+                $event_index,
+                $slot,
+                $block_time,
+                $balance.mango_group.to_string(),
+                $balance.mango_account.to_string(),
+                $balance.token_index,
+                indexed_position.to_num::<f64>(),
+                f($balance.deposit_index),
+                f($balance.borrow_index),
+                (indexed_position * I80F48::from_bits(index)).to_num::<f64>(),
+                totals.map(|t| t[0]),
+                totals.map(|t| t[1]),
+                totals.map(|t| t[2]),
+                totals.map(|t| t[3]),
+            ],
+        )?;
+    }};
+}
+
+/// Insert a fill log; the versions share all fields except the realized pnl ones
+macro_rules! insert_fill {
+    (
+        $db_tx:expr, $signature:expr, $slot:expr, $fill:expr,
+        $maker_realized_pnl:expr, $maker_entry_price:expr,
+        $taker_realized_pnl:expr, $taker_entry_price:expr
+    ) => {
+        $db_tx.execute(
+            "INSERT OR REPLACE INTO perp_fills (
+                mango_group, market_index, seq_num, signature, slot, timestamp,
+                taker_side, maker_slot, maker_out, maker, maker_client_order_id,
+                maker_fee, maker_timestamp, taker, taker_client_order_id, taker_fee,
+                price, quantity, maker_realized_pnl, maker_entry_price_per_base_lot,
+                taker_realized_pnl, taker_entry_price_per_base_lot
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+            params![
+                $fill.mango_group.to_string(),
+                $fill.market_index,
+                $fill.seq_num as i64,
+                $signature,
+                $slot,
+                $fill.timestamp as i64,
+                $fill.taker_side,
+                $fill.maker_slot,
+                $fill.maker_out,
+                $fill.maker.to_string(),
+                $fill.maker_client_order_id as i64,
+                $fill.maker_fee,
+                $fill.maker_timestamp as i64,
+                $fill.taker.to_string(),
+                $fill.taker_client_order_id as i64,
+                $fill.taker_fee,
+                $fill.price,
+                $fill.quantity,
+                $maker_realized_pnl,
+                $maker_entry_price,
+                $taker_realized_pnl,
+                $taker_entry_price,
+            ],
+        )?
+    };
+}
+
+/// Returns false for events that aren't indexed
+fn store_event(
+    db_tx: &Transaction,
+    tx: &IndexedTransaction,
+    event_index: usize,
+    event: &MangoEvent,
+) -> anyhow::Result<bool> {
+    let signature = &tx.signature;
+    let slot = tx.slot as i64;
+    let block_time = tx.block_time;
+    let event_index = event_index as i64;
+    match event {
+        MangoEvent::FillLogV2(fill) => {
+            insert_fill!(
+                db_tx,
+                signature,
+                slot,
+                fill,
+                None::<f64>,
+                None::<f64>,
+                None::<f64>,
+                None::<f64>
+            );
+        }
+        MangoEvent::FillLogV3(fill) => {
+            insert_fill!(
+                db_tx,
+                signature,
+                slot,
+                fill,
+                Some(f(fill.maker_realized_pnl)),
+                Some(fill.maker_entry_price_per_base_lot),
+                Some(f(fill.taker_realized_pnl)),
+                Some(fill.taker_entry_price_per_base_lot)
+            );
+        }
+        MangoEvent::PerpUpdateFundingLog(funding) => {
+            db_tx.execute(
+                "INSERT OR REPLACE INTO perp_funding_updates (
+                    signature, event_index, slot, block_time, mango_group, market_index,
+                    long_funding, short_funding, price, stable_price, fees_accrued,
+                    open_interest, instantaneous_funding_rate
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    funding.mango_group.to_string(),
+                    funding.market_index,
+                    f(funding.long_funding),
+                    f(funding.short_funding),
+                    f(funding.price),
+                    f(funding.stable_price),
+                    f(funding.fees_accrued),
+                    funding.open_interest,
+                    f(funding.instantaneous_funding_rate),
+                ],
+            )?;
+        }
+        MangoEvent::UpdateIndexLog(update) => {
+            db_tx.execute(
+                "INSERT OR REPLACE INTO token_index_updates (
+                    signature, event_index, slot, block_time, mango_group, token_index,
+                    deposit_index, borrow_index, avg_utilization, price, stable_price,
+                    collected_fees, loan_fee_rate, total_borrows, total_deposits,
+                    borrow_rate, deposit_rate
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    update.mango_group.to_string(),
+                    update.token_index,
+                    f(update.deposit_index),
+                    f(update.borrow_index),
+                    f(update.avg_utilization),
+                    f(update.price),
+                    f(update.stable_price),
+                    f(update.collected_fees),
+                    f(update.loan_fee_rate),
+                    f(update.total_borrows),
+                    f(update.total_deposits),
+                    f(update.borrow_rate),
+                    f(update.deposit_rate),
+                ],
+            )?;
+        }
+        MangoEvent::TokenBalanceLog(balance) => {
+            insert_token_balance!(
+                db_tx,
+                signature,
+                event_index,
+                slot,
+                block_time,
+                balance,
+                None
+            );
+        }
+        MangoEvent::TokenBalanceLogV2(balance) => {
+            insert_token_balance!(
+                db_tx,
+                signature,
+                event_index,
+                slot,
+                block_time,
+                balance,
+                Some([
+                    balance.cumulative_deposit_interest,
+                    balance.cumulative_borrow_interest,
+                    balance.cumulative_deposits,
+                    balance.cumulative_withdraws,
+                ])
+            );
+        }
+        MangoEvent::TokenLiqWithTokenLog(liq) => {
+            insert_token_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_token_with_token",
+                    liq.mango_group.to_string(),
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    liq.asset_token_index,
+                    liq.liab_token_index,
+                    f(liq.asset_transfer),
+                    f(liq.liab_transfer),
+                    f(liq.asset_price),
+                    f(liq.liab_price),
+                    None::<f64>,
+                    None::<f64>,
+                    liq.bankruptcy,
+                ],
+            )?;
+        }
+        MangoEvent::TokenLiqBankruptcyLog(liq) => {
+            insert_token_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_token_bankruptcy",
+                    liq.mango_group.to_string(),
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    None::<u16>,
+                    liq.liab_token_index,
+                    None::<f64>,
+                    f(liq.initial_liab_native),
+                    None::<f64>,
+                    f(liq.liab_price),
+                    f(liq.insurance_transfer),
+                    f(liq.socialized_loss),
+                    true,
+                ],
+            )?;
+        }
+        MangoEvent::PerpLiqBaseOrPositivePnlLog(liq) => {
+            insert_perp_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_base_or_positive_pnl",
+                    liq.mango_group.to_string(),
+                    liq.perp_market_index,
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    liq.base_transfer,
+                    f(liq.quote_transfer),
+                    f(liq.pnl_transfer),
+                    None::<f64>,
+                    None::<f64>,
+                    f(liq.price),
+                ],
+            )?;
+        }
+        MangoEvent::PerpLiqNegativePnlOrBankruptcyLog(liq) => {
+            insert_perp_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_negative_pnl_or_bankruptcy",
+                    liq.mango_group.to_string(),
+                    liq.perp_market_index,
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    None::<i64>,
+                    None::<f64>,
+                    f(liq.settlement),
+                    None::<f64>,
+                    None::<f64>,
+                    None::<f64>,
+                ],
+            )?;
+        }
+        MangoEvent::PerpLiqBankruptcyLog(liq) => {
+            insert_perp_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_bankruptcy",
+                    liq.mango_group.to_string(),
+                    liq.perp_market_index,
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    None::<i64>,
+                    None::<f64>,
+                    None::<f64>,
+                    f(liq.insurance_transfer),
+                    f(liq.socialized_loss),
+                    None::<f64>,
+                ],
+            )?;
+        }
+        MangoEvent::PerpLiqIsolatedLog(liq) => {
+            insert_perp_liquidation(
+                db_tx,
+                params![
+                    signature,
+                    event_index,
+                    slot,
+                    block_time,
+                    "liq_isolated",
+                    liq.mango_group.to_string(),
+                    liq.perp_market_index,
+                    liq.liqee.to_string(),
+                    liq.liqor.to_string(),
+                    liq.base_transfer,
+                    f(liq.quote_transfer),
+                    f(liq.collateral_transfer),
+                    None::<f64>,
+                    f(liq.bankruptcy_loss),
+                    f(liq.price),
+                ],
+            )?;
+        }
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+fn insert_token_liquidation(
+    db_tx: &Transaction,
+    params: &[&dyn rusqlite::ToSql],
+) -> anyhow::Result<()> {
+    db_tx.execute(
+        "INSERT OR REPLACE INTO token_liquidations (
+            signature, event_index, slot, block_time, kind, mango_group, liqee, liqor,
+            asset_token_index, liab_token_index, asset_transfer, liab_transfer,
+            asset_price, liab_price, insurance_transfer, socialized_loss, bankruptcy
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        params,
+    )?;
+    Ok(())
+}
+
+fn insert_perp_liquidation(
+    db_tx: &Transaction,
+    params: &[&dyn rusqlite::ToSql],
+) -> anyhow::Result<()> {
+    db_tx.execute(
+        "INSERT OR REPLACE INTO perp_liquidations (
+            signature, event_index, slot, block_time, kind, mango_group,
+            perp_market_index, liqee, liqor, base_transfer, quote_transfer,
+            pnl_transfer, insurance_transfer, socialized_loss, price
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+        params,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Event;
+    use mango_v4::logs::{FillLogV2, FillLogV3, TokenBalanceLog, TokenBalanceLogV2};
+    use solana_sdk::pubkey::Pubkey;
+
+    fn count(db: &Database, table: &str) -> i64 {
+        db.conn
+            .query_row(&format!("SELECT COUNT(*) FROM {table}"), [], |row| {
+                row.get(0)
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_store_is_idempotent() {
+        let mut db = Database::open_in_memory().unwrap();
+        assert_eq!(db.last_signature().unwrap(), None);
+
+        let fill = FillLogV2 {
+            mango_group: Pubkey::new_unique(),
+            market_index: 1,
+            taker_side: 0,
+            maker_slot: 0,
+            maker_out: false,
+            timestamp: 1000,
+            seq_num: 5,
+            maker: Pubkey::new_unique(),
+            maker_client_order_id: u64::MAX,
+            maker_fee: -0.0001,
+            maker_timestamp: 900,
+            taker: Pubkey::new_unique(),
+            taker_client_order_id: 1,
+            taker_fee: 0.0004,
+            price: 100,
+            quantity: 2,
+        };
+        let balance = TokenBalanceLog {
+            mango_group: fill.mango_group,
+            mango_account: fill.taker,
+            token_index: 0,
+            indexed_position: I80F48::from_num(-10).to_bits(),
+            deposit_index: I80F48::from_num(1).to_bits(),
+            borrow_index: I80F48::from_num(1.5).to_bits(),
+        };
+        let tx = IndexedTransaction {
+            signature: "sig1".into(),
+            slot: 10,
+            block_time: Some(1000),
+            logs: vec![],
+        };
+        let events = || {
+            vec![
+                MangoEvent::decode(&fill.data()).unwrap().unwrap(),
+                MangoEvent::decode(&balance.data()).unwrap().unwrap(),
+            ]
+        };
+
+        assert_eq!(db.store(&tx, &events()).unwrap(), 2);
+        assert_eq!(db.store(&tx, &events()).unwrap(), 2);
+        assert_eq!(count(&db, "transactions"), 1);
+        assert_eq!(count(&db, "perp_fills"), 1);
+        assert_eq!(count(&db, "token_balances"), 1);
+
+        // the same fill seen in another transaction is still one row
+        let tx2 = IndexedTransaction {
+            signature: "sig2".into(),
+            slot: 11,
+            ..tx.clone()
+        };
+        db.store(&tx2, &events()[..1]).unwrap();
+        assert_eq!(count(&db, "perp_fills"), 1);
+        assert_eq!(db.last_signature().unwrap(), Some("sig2".to_string()));
+
+        let (client_order_id, balance_native): (i64, f64) = db
+            .conn
+            .query_row(
+                "SELECT f.maker_client_order_id, b.balance FROM perp_fills f, token_balances b",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(client_order_id as u64, u64::MAX);
+        assert_eq!(balance_native, -15.0);
+    }
+
+    #[test]
+    fn test_token_balance_versions() {
+        let mut db = Database::open_in_memory().unwrap();
+        let v1 = TokenBalanceLog {
+            mango_group: Pubkey::new_unique(),
+            mango_account: Pubkey::new_unique(),
+            token_index: 1,
+            indexed_position: I80F48::from_num(20).to_bits(),
+            deposit_index: I80F48::from_num(1.5).to_bits(),
+            borrow_index: I80F48::from_num(2).to_bits(),
+        };
+        let v2 = TokenBalanceLogV2 {
+            mango_group: v1.mango_group,
+            mango_account: v1.mango_account,
+            token_index: v1.token_index,
+            indexed_position: v1.indexed_position,
+            deposit_index: v1.deposit_index,
+            borrow_index: v1.borrow_index,
+            cumulative_deposit_interest: 1.0,
+            cumulative_borrow_interest: 0.0,
+            cumulative_deposits: 30.0,
+            cumulative_withdraws: 10.0,
+        };
+        let tx = IndexedTransaction {
+            signature: "sig1".into(),
+            slot: 10,
+            block_time: Some(1000),
+            logs: vec![],
+        };
+
+        // the old version is skipped when the new one repeats it
+        let events = vec![
+            MangoEvent::decode(&v1.data()).unwrap().unwrap(),
+            MangoEvent::decode(&v2.data()).unwrap().unwrap(),
+        ];
+        assert_eq!(db.store(&tx, &events).unwrap(), 1);
+        let (balance, deposits): (f64, Option<f64>) = db
+            .conn
+            .query_row(
+                "SELECT balance, cumulative_deposits FROM token_balances",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(balance, 30.0);
+        assert_eq!(deposits, Some(30.0));
+
+        // logs from before the new version existed have no totals
+        let tx2 = IndexedTransaction {
+            signature: "sig2".into(),
+            ..tx
+        };
+        assert_eq!(db.store(&tx2, &events[..1]).unwrap(), 1);
+        let deposits: Option<f64> = db
+            .conn
+            .query_row(
+                "SELECT cumulative_deposits FROM token_balances WHERE signature = 'sig2'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(deposits, None);
+    }
+
+    #[test]
+    fn test_fill_realized_pnl() {
+        let mut db = Database::open_in_memory().unwrap();
+        let fill = FillLogV3 {
+            mango_group: Pubkey::new_unique(),
+            market_index: 1,
+            taker_side: 1,
+            maker_slot: 0,
+            maker_out: true,
+            timestamp: 1000,
+            seq_num: 6,
+            maker: Pubkey::new_unique(),
+            maker_client_order_id: 2,
+            maker_fee: 0.0,
+            maker_timestamp: 900,
+            taker: Pubkey::new_unique(),
+            taker_client_order_id: 3,
+            taker_fee: 0.0004,
+            price: 110,
+            quantity: 2,
+            maker_realized_pnl: I80F48::from_num(-20).to_bits(),
+            maker_entry_price_per_base_lot: 100.0,
+            taker_realized_pnl: I80F48::ZERO.to_bits(),
+            taker_entry_price_per_base_lot: 110.0,
+        };
+        let tx = IndexedTransaction {
+            signature: "sig1".into(),
+            slot: 10,
+            block_time: Some(1000),
+            logs: vec![],
+        };
+        let events = vec![MangoEvent::decode(&fill.data()).unwrap().unwrap()];
+        assert_eq!(db.store(&tx, &events).unwrap(), 1);
+
+        let (maker_realized_pnl, taker_entry_price): (f64, f64) = db
+            .conn
+            .query_row(
+                "SELECT maker_realized_pnl, taker_entry_price_per_base_lot FROM perp_fills",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(maker_realized_pnl, -20.0);
+        assert_eq!(taker_entry_price, 110.0);
+    }
+}
@@ -0,0 +1,148 @@
+mod db;
+mod source;
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::{Parser, Subcommand};
+use solana_client::nonblocking::rpc_client::RpcClient as RpcClientAsync;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::db::Database;
+use crate::source::IndexedTransaction;
+
+#[derive(Parser, Debug)]
+#[clap()]
+struct CliDotenv {
+    // When --dotenv <file> is passed, read the specified dotenv file before parsing args
+    #[clap(long)]
+    dotenv: std::path::PathBuf,
+
+    remaining_args: Vec<std::ffi::OsString>,
+}
+
+#[derive(Parser, Debug)]
+#[clap()]
+struct Cli {
+    /// the SQLite database to write to, created if it doesn't exist
+    #[clap(long, env, default_value = "mango-indexer.sqlite")]
+    sqlite_path: PathBuf,
+
+    /// address of the mango program whose logs are indexed
+    #[clap(long, env, default_value_t = mango_v4::id())]
+    program: Pubkey,
+
+    #[clap(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Poll an rpc node for new transactions of the program
+    Rpc {
+        #[clap(short, long, env)]
+        rpc_url: String,
+
+        /// seconds between polls
+        #[clap(long, env, default_value_t = 10)]
+        interval: u64,
+
+        /// signatures requested per getSignaturesForAddress call; on an empty
+        /// database this is also how many recent transactions are indexed
+        #[clap(long, env, default_value_t = 100)]
+        page_size: usize,
+    },
+    /// Index the transactions in a replay file, one JSON object per line with
+    /// signature, slot, block_time and logs
+    Replay { file: PathBuf },
+}
+
+fn index_transaction(
+    db: &mut Database,
+    program: &Pubkey,
+    tx: &IndexedTransaction,
+) -> anyhow::Result<()> {
+    let events = mango_v4_client::events::decode_program_log_messages(&tx.logs, program);
+    let stored = db.store(tx, &events)?;
+    log::debug!(
+        "indexed {stored} of {} events in {}",
+        events.len(),
+        tx.signature
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init_from_env(
+        env_logger::Env::default().filter_or(env_logger::DEFAULT_FILTER_ENV, "info"),
+    );
+
+    let args = if let Ok(cli_dotenv) = CliDotenv::try_parse() {
+        dotenv::from_path(cli_dotenv.dotenv)?;
+        cli_dotenv.remaining_args
+    } else {
+        dotenv::dotenv().ok();
+        std::env::args_os().collect()
+    };
+    let cli = Cli::parse_from(args);
+
+    let mut db = Database::open(&cli.sqlite_path)?;
+
+    match cli.command {
+        Command::Replay { file } => {
+            let transactions = source::read_replay_file(&file)?;
+            for tx in &transactions {
+                index_transaction(&mut db, &cli.program, tx)?;
+            }
+            log::info!(
+                "indexed {} transactions from {}",
+                transactions.len(),
+                file.display()
+            );
+        }
+        Command::Rpc {
+            rpc_url,
+            interval,
+            page_size,
+        } => {
+            let rpc = RpcClientAsync::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            let mut interval = tokio::time::interval(Duration::from_secs(interval));
+            loop {
+                interval.tick().await;
+
+                let last_signature = db.last_signature()?;
+                let transactions = match source::fetch_new_transactions(
+                    &rpc,
+                    &cli.program,
+                    last_signature.as_deref(),
+                    page_size,
+                )
+                .await
+                {
+                    Ok(transactions) => transactions,
+                    Err(e) => {
+                        log::error!("fetching new transactions: {e:?}");
+                        continue;
+                    }
+                };
+
+                // oldest first, so the last stored signature is a safe place to resume from
+                //
+                // A transaction that can't be indexed is logged and skipped: retrying it
+                // on every tick would stop the indexer for good.
+                for tx in &transactions {
+                    if let Err(e) = index_transaction(&mut db, &cli.program, tx) {
+                        log::error!("indexing transaction {}: {e:?}", tx.signature);
+                    }
+                }
+                if !transactions.is_empty() {
+                    log::info!("indexed {} transactions", transactions.len());
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
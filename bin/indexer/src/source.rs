@@ -0,0 +1,114 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient as RpcClientAsync;
+use solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config;
+use solana_client::rpc_config::RpcTransactionConfig;
+use solana_sdk::commitment_config::CommitmentConfig;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_transaction_status::UiTransactionEncoding;
+
+/// The parts of a successful transaction that the indexer needs
+///
+/// This is also the format of replay files: one JSON object per line.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct IndexedTransaction {
+    pub signature: String,
+    pub slot: u64,
+    pub block_time: Option<i64>,
+    pub logs: Vec<String>,
+}
+
+/// Read a replay file, skipping empty lines
+pub fn read_replay_file(path: &Path) -> anyhow::Result<Vec<IndexedTransaction>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("opening replay file {}", path.display()))?;
+    std::io::BufReader::new(file)
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Ok(l) if l.trim().is_empty()))
+        .map(|(i, line)| -> anyhow::Result<IndexedTransaction> {
+            serde_json::from_str(&line?)
+                .with_context(|| format!("parsing line {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+/// Successful transactions of `program` that are newer than `until`, oldest first
+///
+/// Transactions that can't be fetched are logged and skipped.
+///
+/// Without `until`, only the newest `page_size` transactions are returned, so a fresh
+/// database doesn't start by backfilling the program's whole history.
+pub async fn fetch_new_transactions(
+    rpc: &RpcClientAsync,
+    program: &Pubkey,
+    until: Option<&str>,
+    page_size: usize,
+) -> anyhow::Result<Vec<IndexedTransaction>> {
+    let until = until.map(Signature::from_str).transpose()?;
+    let mut signatures = vec![];
+    let mut before = None;
+    loop {
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                program,
+                GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    until,
+                    limit: Some(page_size),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                },
+            )
+            .await?;
+        let last = match page.last() {
+            Some(last) => Signature::from_str(&last.signature)?,
+            None => break,
+        };
+        let full_page = page.len() == page_size;
+        signatures.extend(page.into_iter().filter(|s| s.err.is_none()));
+        if until.is_none() || !full_page {
+            break;
+        }
+        before = Some(last);
+    }
+
+    let mut transactions = Vec::with_capacity(signatures.len());
+    for status in signatures.into_iter().rev() {
+        let tx = rpc
+            .get_transaction_with_config(
+                &Signature::from_str(&status.signature)?,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await;
+        let tx = match tx {
+            Ok(tx) => tx,
+            Err(e) => {
+                log::error!(
+                    "fetching transaction {}, skipping it: {e:?}",
+                    status.signature
+                );
+                continue;
+            }
+        };
+        let logs: Option<Vec<String>> = tx
+            .transaction
+            .meta
+            .and_then(|meta| meta.log_messages.into());
+        transactions.push(IndexedTransaction {
+            signature: status.signature,
+            slot: tx.slot,
+            block_time: tx.block_time,
+            logs: logs.unwrap_or_default(),
+        });
+    }
+    Ok(transactions)
+}
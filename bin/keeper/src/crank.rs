@@ -1,14 +1,35 @@
-use std::{collections::HashSet, sync::Arc, time::Duration, time::Instant};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+    time::Duration,
+    time::Instant,
+};
 
 use crate::MangoClient;
 use itertools::Itertools;
+use mango_v4_client::metrics::{MetricU64, Metrics};
+use mango_v4_client::{
+    account_fetcher_fetch_anchor_account, account_update_stream, chain_data, websocket_source,
+    AccountFetcher, AnyhowWrap, AsyncChannelSendUnlessFull, RetryPolicy, RpcAccountFetcher,
+    TransactionBuilder, TransactionBuilderConfig,
+};
 
-use anchor_lang::{__private::bytemuck::cast_ref, solana_program};
+use anchor_lang::{__private::bytemuck::cast_ref, solana_program, Discriminator};
+use fixed::types::I80F48;
 use futures::Future;
-use mango_v4::state::{EventQueue, EventType, FillEvent, OutEvent, PerpMarket, TokenIndex};
+use mango_v4::accounts_zerocopy::{KeyedAccountSharedData, LoadZeroCopy};
+use mango_v4::error::MangoError;
+use mango_v4::state::{
+    Bank, EventQueue, EventType, FillEvent, MangoAccount, MangoAccountValue, OutEvent, PerpMarket,
+    PerpMarketIndex, Serum3MarketIndex, TokenIndex,
+};
 use solana_sdk::{
-    instruction::{AccountMeta, Instruction},
+    account::ReadableAccount,
+    hash::Hash,
+    instruction::{AccountMeta, Instruction, InstructionError},
     pubkey::Pubkey,
+    signer::Signer,
+    transaction::{TransactionError, VersionedTransaction},
 };
 use tokio::time;
 
@@ -17,57 +38,68 @@ use tokio::time;
 pub async fn runner(
     mango_client: Arc<MangoClient>,
     debugging_handle: impl Future,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
+    rpc_ws_url: String,
+    skip_perp_markets: HashSet<PerpMarketIndex>,
     interval_update_banks: u64,
     interval_consume_events: u64,
     interval_update_funding: u64,
     interval_check_new_listings_and_abort: u64,
+    interval_serum3_settle_funds: u64,
+    serum3_settle_min_free_value: f64,
+    interval_health_snapshot: u64,
 ) -> Result<(), anyhow::Error> {
-    let handles1 = mango_client
-        .context
-        .tokens
-        .keys()
-        // TODO: grouping tokens whose oracle might have less confidencen e.g. ORCA with the rest, fails whole ix
-        // TokenUpdateIndexAndRate is known to take max 71k cu
-        // from cargo test-bpf local tests
-        // chunk size of 8 seems to be max before encountering "VersionedTransaction too large" issues
-        .chunks(8)
-        .into_iter()
-        .map(|chunk| {
-            loop_update_index_and_rate(
-                mango_client.clone(),
-                chunk.copied().collect::<Vec<TokenIndex>>(),
-                interval_update_banks,
-            )
-        })
-        .collect::<Vec<_>>();
+    let handles1 = vec![loop_update_index_and_rate(
+        mango_client.clone(),
+        metrics.clone(),
+        retry_policy,
+        mango_client
+            .context
+            .tokens
+            .keys()
+            .copied()
+            .sorted()
+            .collect::<Vec<TokenIndex>>(),
+        interval_update_banks,
+    )];
 
-    let handles2 = mango_client
+    let active_perp_markets = mango_client
         .context
         .perp_markets
         .values()
-        .filter(|perp|
-            // MNGO-PERP-OLD
-            perp.market.perp_market_index != 1)
+        .filter(|perp| !skip_perp_markets.contains(&perp.market.perp_market_index))
+        .collect::<Vec<_>>();
+
+    // Event queue updates from the websocket trigger consume_events for their market
+    let chain_data = Arc::new(RwLock::new(chain_data::ChainData::new()));
+    let mut consume_triggers = HashMap::new();
+    let handles2 = active_perp_markets
+        .iter()
         .map(|perp| {
+            let (trigger_sender, trigger_receiver) = async_channel::bounded::<()>(1);
+            consume_triggers.insert(perp.market.event_queue, trigger_sender);
             loop_consume_events(
                 mango_client.clone(),
+                metrics.clone(),
+                retry_policy,
                 perp.address,
                 perp.market,
                 interval_consume_events,
+                chain_data.clone(),
+                trigger_receiver,
             )
         })
         .collect::<Vec<_>>();
+    start_event_queue_watcher(rpc_ws_url, chain_data, consume_triggers, &metrics);
 
-    let handles3 = mango_client
-        .context
-        .perp_markets
-        .values()
-        .filter(|perp|
-            // MNGO-PERP-OLD
-            perp.market.perp_market_index != 1)
+    let handles3 = active_perp_markets
+        .iter()
         .map(|perp| {
             loop_update_funding(
                 mango_client.clone(),
+                metrics.clone(),
+                retry_policy,
                 perp.address,
                 perp.market,
                 interval_update_funding,
@@ -83,12 +115,106 @@ pub async fn runner(
             mango_client.clone(),
             interval_check_new_listings_and_abort
         ),
+        loop_serum3_settle_funds(
+            mango_client.clone(),
+            metrics.clone(),
+            retry_policy,
+            serum3_settle_min_free_value,
+            interval_serum3_settle_funds,
+        ),
+        loop_health_snapshots(
+            mango_client.clone(),
+            metrics.clone(),
+            retry_policy,
+            interval_health_snapshot,
+        ),
         debugging_handle
     );
 
     Ok(())
 }
 
+/// Stream event queue accounts into chain_data and notify the consume loop of
+/// a market when its event queue has entries.
+///
+/// The seconds since the last update was received are exposed as the
+/// event_queue_update_age metric, to notice a stalled websocket.
+fn start_event_queue_watcher(
+    rpc_ws_url: String,
+    chain_data: Arc<RwLock<chain_data::ChainData>>,
+    consume_triggers: HashMap<Pubkey, async_channel::Sender<()>>,
+    metrics: &Metrics,
+) {
+    let (account_update_sender, account_update_receiver) =
+        async_channel::unbounded::<account_update_stream::Message>();
+    websocket_source::start_account_subscriptions(
+        rpc_ws_url,
+        consume_triggers.keys().copied().collect(),
+        account_update_sender,
+    );
+
+    let mut metric_update_age = metrics.register_u64("event_queue_update_age".into());
+    tokio::spawn(async move {
+        let mut last_update = Instant::now();
+        loop {
+            let message =
+                match time::timeout(Duration::from_secs(1), account_update_receiver.recv()).await {
+                    Ok(message) => message.expect("channel not closed"),
+                    Err(_) => {
+                        metric_update_age.set(last_update.elapsed().as_secs());
+                        continue;
+                    }
+                };
+            last_update = Instant::now();
+            metric_update_age.set(0);
+            message.update_chain_data(&mut chain_data.write().unwrap());
+
+            if let account_update_stream::Message::Account(account_write) = message {
+                let trigger = match consume_triggers.get(&account_write.pubkey) {
+                    Some(trigger) => trigger,
+                    None => continue,
+                };
+                let has_events = account_write
+                    .account
+                    .load::<EventQueue>()
+                    .map(|event_queue| !event_queue.is_empty())
+                    .unwrap_or(false);
+                if has_events {
+                    trigger.send_unless_full(()).unwrap();
+                }
+            }
+        }
+    });
+}
+
+/// Mango accounts referenced by the first `limit` events and the number of events
+fn event_queue_accounts(
+    event_queue: &EventQueue,
+    limit: usize,
+) -> anyhow::Result<(HashSet<Pubkey>, u32)> {
+    let mut set = HashSet::new();
+    let mut num_of_events = 0;
+    for event in event_queue.iter().take(limit) {
+        match EventType::try_from(event.event_type)? {
+            EventType::Fill => {
+                let fill: &FillEvent = cast_ref(event);
+                set.insert(fill.maker);
+                set.insert(fill.taker);
+                if let Some(referrer) = fill.referrer() {
+                    set.insert(referrer);
+                }
+            }
+            EventType::Out => {
+                let out: &OutEvent = cast_ref(event);
+                set.insert(out.owner);
+            }
+            EventType::Liquidate => {}
+        }
+        num_of_events += 1;
+    }
+    Ok((set, num_of_events))
+}
+
 pub async fn loop_check_new_listings_and_abort(mango_client: Arc<MangoClient>, interval: u64) {
     let mut interval = time::interval(Duration::from_secs(interval));
     loop {
@@ -110,122 +236,381 @@ pub async fn loop_check_new_listings_and_abort(mango_client: Arc<MangoClient>, i
     }
 }
 
+// TokenUpdateIndexAndRate is known to take max 71k cu
+// from cargo test-bpf local tests
+const DEFAULT_UPDATE_INDEX_AND_RATE_CU: u64 = 71_000;
+const MAX_TX_CU: u64 = 1_400_000;
+
+fn token_update_index_and_rate_ix(client: &MangoClient, token_index: TokenIndex) -> Instruction {
+    let token = client.context.token(token_index);
+    let mut ix = Instruction {
+        program_id: mango_v4::id(),
+        accounts: anchor_lang::ToAccountMetas::to_account_metas(
+            &mango_v4::accounts::TokenUpdateIndexAndRate {
+                group: token.mint_info.group,
+                mint_info: token.mint_info_address,
+                oracle: token.mint_info.oracle,
+                instructions: solana_program::sysvar::instructions::id(),
+            },
+            None,
+        ),
+        data: anchor_lang::InstructionData::data(
+            &mango_v4::instruction::TokenUpdateIndexAndRate {},
+        ),
+    };
+    let mut banks = token
+        .mint_info
+        .banks()
+        .iter()
+        .map(|bank_pubkey| AccountMeta {
+            pubkey: *bank_pubkey,
+            is_signer: false,
+            is_writable: true,
+        })
+        .collect::<Vec<_>>();
+    ix.accounts.append(&mut banks);
+    ix
+}
+
+fn permissionless_tx(
+    client: &MangoClient,
+    instructions: Vec<Instruction>,
+    config: TransactionBuilderConfig,
+    blockhash: Hash,
+) -> anyhow::Result<VersionedTransaction> {
+    let fee_payer = &*client.client.fee_payer;
+    TransactionBuilder {
+        instructions,
+        address_lookup_tables: vec![],
+        payer: fee_payer.pubkey(),
+        signers: vec![fee_payer],
+        config,
+    }
+    .transaction_with_blockhash(blockhash)
+}
+
+/// Split tokens into chunks whose update instructions fit into a transaction,
+/// both in size and in the compute units each token was measured to use
+fn chunk_tokens(
+    client: &MangoClient,
+    token_indices: &[TokenIndex],
+    compute_estimates: &HashMap<TokenIndex, u64>,
+) -> anyhow::Result<Vec<Vec<TokenIndex>>> {
+    let config = client.client.current_transaction_builder_config();
+    let mut chunks = vec![];
+    let mut chunk: Vec<TokenIndex> = vec![];
+    let mut chunk_cu = 0;
+    for &token_index in token_indices {
+        let cu = compute_estimates
+            .get(&token_index)
+            .copied()
+            .unwrap_or(DEFAULT_UPDATE_INDEX_AND_RATE_CU);
+        let fits = if chunk.is_empty() {
+            true
+        } else if chunk_cu + cu > MAX_TX_CU {
+            false
+        } else {
+            let instructions = chunk
+                .iter()
+                .chain(std::iter::once(&token_index))
+                .map(|ti| token_update_index_and_rate_ix(client, *ti))
+                .collect();
+            let tx = permissionless_tx(client, instructions, config, Hash::default())?;
+            bincode::serialize(&tx)?.len() < solana_sdk::packet::PACKET_DATA_SIZE
+        };
+        if !fits {
+            chunks.push(std::mem::take(&mut chunk));
+            chunk_cu = 0;
+        }
+        chunk.push(token_index);
+        chunk_cu += cu;
+    }
+    if !chunk.is_empty() {
+        chunks.push(chunk);
+    }
+    Ok(chunks)
+}
+
+/// Compute units used by each top-level mango instruction, from simulation logs
+fn mango_instruction_compute_units(logs: &[String]) -> Vec<u64> {
+    let prefix = format!("Program {} consumed ", mango_v4::id());
+    logs.iter()
+        .filter_map(|line| {
+            line.strip_prefix(&prefix)?
+                .split_whitespace()
+                .next()?
+                .parse::<u64>()
+                .ok()
+        })
+        .collect()
+}
+
+/// Index of the instruction that failed because of a bad oracle, if that is why
+/// the transaction failed
+fn oracle_error_instruction_index(err: &TransactionError) -> Option<usize> {
+    match err {
+        TransactionError::InstructionError(index, InstructionError::Custom(code))
+            if *code == MangoError::OracleConfidence.error_code()
+                || *code == MangoError::OracleStale.error_code() =>
+        {
+            Some(*index as usize)
+        }
+        _ => None,
+    }
+}
+
+/// Simulate updating the tokens and drop tokens whose oracle makes the update fail
+///
+/// Returns the tokens that can be updated. Measured compute units are stored in
+/// compute_estimates.
+async fn isolate_failing_tokens(
+    client: &MangoClient,
+    mut token_indices: Vec<TokenIndex>,
+    compute_estimates: &mut HashMap<TokenIndex, u64>,
+    metric_isolated: &mut MetricU64,
+) -> anyhow::Result<Vec<TokenIndex>> {
+    let rpc = client.client.rpc_async();
+    // no compute budget instructions, so instruction indexes match token_indices
+    let config = TransactionBuilderConfig {
+        prioritization_micro_lamports: None,
+    };
+    while !token_indices.is_empty() {
+        let instructions = token_indices
+            .iter()
+            .map(|ti| token_update_index_and_rate_ix(client, *ti))
+            .collect();
+        let tx = permissionless_tx(
+            client,
+            instructions,
+            config,
+            rpc.get_latest_blockhash().await?,
+        )?;
+        let simulation = rpc.simulate_transaction(&tx).await?.value;
+
+        let err = match simulation.err {
+            None => {
+                let units = mango_instruction_compute_units(&simulation.logs.unwrap_or_default());
+                if units.len() == token_indices.len() {
+                    for (token_index, cu) in token_indices.iter().zip(units) {
+                        compute_estimates.insert(*token_index, cu);
+                    }
+                }
+                break;
+            }
+            Some(err) => err,
+        };
+        match oracle_error_instruction_index(&err) {
+            Some(index) if index < token_indices.len() => {
+                let token_index = token_indices.remove(index);
+                metric_isolated.increment();
+                log::warn!(
+                    "skipping update of token {} because of its oracle: {err:?}",
+                    client.context.token(token_index).name
+                );
+            }
+            _ => anyhow::bail!("simulation failed: {err:?}"),
+        }
+    }
+    Ok(token_indices)
+}
+
+// Tokens whose oracle made their update fail are left out for this many intervals
+const ISOLATED_TOKEN_SKIP_INTERVALS: u64 = 5;
+
+/// Send one transaction updating the tokens, returns whether it succeeded
+async fn send_update_index_and_rate(
+    client: &MangoClient,
+    retry_policy: &RetryPolicy,
+    token_indices: &[TokenIndex],
+    metric_success: &mut MetricU64,
+    metric_failure: &mut MetricU64,
+) -> bool {
+    let token_names = token_indices
+        .iter()
+        .map(|token_index| client.context.token(*token_index).name.to_owned())
+        .join(",");
+    let instructions = token_indices
+        .iter()
+        .map(|token_index| token_update_index_and_rate_ix(client, *token_index))
+        .collect();
+
+    let pre = Instant::now();
+    let sig_result = client
+        .send_and_confirm_permissionless_tx_with_retry(instructions, retry_policy)
+        .await;
+
+    if let Err(e) = sig_result {
+        metric_failure.increment();
+        log::info!(
+            "metricName=UpdateTokensV4Failure tokens={} durationMs={} error={}",
+            token_names,
+            pre.elapsed().as_millis(),
+            e
+        );
+        log::error!("{:?}", e);
+        false
+    } else {
+        metric_success.increment();
+        log::info!(
+            "metricName=UpdateTokensV4Success tokens={} durationMs={}",
+            token_names,
+            pre.elapsed().as_millis(),
+        );
+        log::info!("{:?}", sig_result);
+        true
+    }
+}
+
 pub async fn loop_update_index_and_rate(
     mango_client: Arc<MangoClient>,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
     token_indices: Vec<TokenIndex>,
     interval: u64,
 ) {
     let mut interval = time::interval(Duration::from_secs(interval));
+    let mut metric_success = metrics.register_counter("update_index_and_rate_success".into());
+    let mut metric_failure = metrics.register_counter("update_index_and_rate_failure".into());
+    let mut metric_isolated = metrics.register_counter("update_index_and_rate_isolated".into());
+    let mut compute_estimates = HashMap::<TokenIndex, u64>::new();
+    // token -> iteration from which it is updated again
+    let mut isolated_until = HashMap::<TokenIndex, u64>::new();
+    let mut iteration = 0;
     loop {
         interval.tick().await;
+        iteration += 1;
 
         let client = mango_client.clone();
 
-        let token_indices_clone = token_indices.clone();
-
-        let token_names = token_indices_clone
+        isolated_until.retain(|_, until| *until > iteration);
+        let tokens = token_indices
             .iter()
-            .map(|token_index| client.context.token(*token_index).name.to_owned())
-            .join(",");
-
-        let mut instructions = vec![];
-        for token_index in token_indices_clone.iter() {
-            let token = client.context.token(*token_index);
-            let banks_for_a_token = token.mint_info.banks();
-            let oracle = token.mint_info.oracle;
-
-            let mut ix = Instruction {
-                program_id: mango_v4::id(),
-                accounts: anchor_lang::ToAccountMetas::to_account_metas(
-                    &mango_v4::accounts::TokenUpdateIndexAndRate {
-                        group: token.mint_info.group,
-                        mint_info: token.mint_info_address,
-                        oracle,
-                        instructions: solana_program::sysvar::instructions::id(),
-                    },
-                    None,
-                ),
-                data: anchor_lang::InstructionData::data(
-                    &mango_v4::instruction::TokenUpdateIndexAndRate {},
-                ),
-            };
-            let mut banks = banks_for_a_token
-                .iter()
-                .map(|bank_pubkey| AccountMeta {
-                    pubkey: *bank_pubkey,
-                    is_signer: false,
-                    is_writable: true,
-                })
-                .collect::<Vec<_>>();
-            ix.accounts.append(&mut banks);
-            instructions.push(ix);
-        }
-        let pre = Instant::now();
-        let sig_result = client
-            .send_and_confirm_permissionless_tx(instructions)
-            .await;
+            .filter(|token_index| !isolated_until.contains_key(*token_index))
+            .copied()
+            .collect::<Vec<_>>();
 
-        if let Err(e) = sig_result {
-            log::info!(
-                "metricName=UpdateTokensV4Failure tokens={} durationMs={} error={}",
-                token_names,
-                pre.elapsed().as_millis(),
-                e
-            );
-            log::error!("{:?}", e)
-        } else {
-            log::info!(
-                "metricName=UpdateTokensV4Success tokens={} durationMs={}",
-                token_names,
-                pre.elapsed().as_millis(),
-            );
-            log::info!("{:?}", sig_result);
+        // chunks are recomputed every time to take the latest compute measurements into account
+        let chunks = match chunk_tokens(&client, &tokens, &compute_estimates) {
+            Ok(chunks) => chunks,
+            Err(e) => {
+                log::error!("chunking tokens for update_index_and_rate: {e:?}");
+                continue;
+            }
+        };
+
+        for chunk in chunks {
+            if send_update_index_and_rate(
+                &client,
+                &retry_policy,
+                &chunk,
+                &mut metric_success,
+                &mut metric_failure,
+            )
+            .await
+            {
+                continue;
+            }
+
+            // one bad oracle must not prevent the other tokens in the chunk from updating,
+            // look for it only after the chunk failed to keep rpc load low
+            match isolate_failing_tokens(
+                &client,
+                chunk.clone(),
+                &mut compute_estimates,
+                &mut metric_isolated,
+            )
+            .await
+            {
+                Ok(remaining) => {
+                    if remaining.len() == chunk.len() {
+                        // the failure was not caused by an oracle
+                        continue;
+                    }
+                    for token_index in chunk.iter().filter(|ti| !remaining.contains(ti)) {
+                        isolated_until
+                            .insert(*token_index, iteration + ISOLATED_TOKEN_SKIP_INTERVALS);
+                    }
+                    if !remaining.is_empty() {
+                        send_update_index_and_rate(
+                            &client,
+                            &retry_policy,
+                            &remaining,
+                            &mut metric_success,
+                            &mut metric_failure,
+                        )
+                        .await;
+                    }
+                }
+                Err(e) => {
+                    log::warn!("could not isolate failing tokens, sending one by one: {e:?}");
+                    for token_index in chunk {
+                        send_update_index_and_rate(
+                            &client,
+                            &retry_policy,
+                            &[token_index],
+                            &mut metric_success,
+                            &mut metric_failure,
+                        )
+                        .await;
+                    }
+                }
+            }
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn loop_consume_events(
     mango_client: Arc<MangoClient>,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
     pk: Pubkey,
     perp_market: PerpMarket,
     interval: u64,
+    chain_data: Arc<RwLock<chain_data::ChainData>>,
+    trigger: async_channel::Receiver<()>,
 ) {
+    // TODO: future, choose better constant of how many max events to pack
+    // TODO: future, choose better constant of how many max mango accounts to pack
+    const MAX_EVENTS: usize = 10;
+
+    // Polling is the backstop for when the websocket doesn't deliver updates
     let mut interval = time::interval(Duration::from_secs(interval));
+    let mut metric_success = metrics.register_counter("consume_events_success".into());
+    let mut metric_failure = metrics.register_counter("consume_events_failure".into());
+    let mut metric_events_consumed = metrics.register_counter("events_consumed".into());
     loop {
-        interval.tick().await;
+        let triggered = tokio::select! {
+            _ = trigger.recv() => true,
+            _ = interval.tick() => false,
+        };
 
         let client = mango_client.clone();
 
         let find_accounts = || async {
-            let mut num_of_events = 0;
-            let mut event_queue: EventQueue = client
-                .client
-                .rpc_anchor_account(&perp_market.event_queue)
-                .await?;
-
-            // TODO: future, choose better constant of how many max events to pack
-            // TODO: future, choose better constant of how many max mango accounts to pack
-            let mut set = HashSet::new();
-            for _ in 0..10 {
-                let event = match event_queue.peek_front() {
-                    None => break,
-                    Some(e) => e,
-                };
-                match EventType::try_from(event.event_type)? {
-                    EventType::Fill => {
-                        let fill: &FillEvent = cast_ref(event);
-                        set.insert(fill.maker);
-                        set.insert(fill.taker);
-                    }
-                    EventType::Out => {
-                        let out: &OutEvent = cast_ref(event);
-                        set.insert(out.owner);
-                    }
-                    EventType::Liquidate => {}
+            // chain_data is only fresh when the websocket just delivered an update,
+            // polling always reads the event queue from rpc
+            let from_chain_data = if triggered {
+                let chain = chain_data.read().unwrap();
+                chain
+                    .account(&perp_market.event_queue)
+                    .ok()
+                    .map(|account| -> anyhow::Result<_> {
+                        event_queue_accounts(account.load::<EventQueue>()?, MAX_EVENTS)
+                    })
+            } else {
+                None
+            };
+            let (set, num_of_events) = match from_chain_data {
+                Some(result) => result?,
+                None => {
+                    let event_queue: EventQueue = client
+                        .client
+                        .rpc_anchor_account(&perp_market.event_queue)
+                        .await?;
+                    event_queue_accounts(&event_queue, MAX_EVENTS)?
                 }
-                event_queue.pop_front()?;
-                num_of_events += 1;
-            }
+            };
 
             if num_of_events == 0 {
                 return Ok(None);
@@ -272,13 +657,16 @@ pub async fn loop_consume_events(
                 ams
             },
             data: anchor_lang::InstructionData::data(&mango_v4::instruction::PerpConsumeEvents {
-                limit: 10,
+                limit: MAX_EVENTS,
             }),
         };
 
-        let sig_result = client.send_and_confirm_permissionless_tx(vec![ix]).await;
+        let sig_result = client
+            .send_and_confirm_permissionless_tx_with_retry(vec![ix], &retry_policy)
+            .await;
 
         if let Err(e) = sig_result {
+            metric_failure.increment();
             log::info!(
                 "metricName=ConsumeEventsV4Failure market={} durationMs={} consumed={} error={}",
                 perp_market.name(),
@@ -288,6 +676,8 @@ pub async fn loop_consume_events(
             );
             log::error!("{:?}", e)
         } else {
+            metric_success.increment();
+            metric_events_consumed.add(num_of_events as u64);
             log::info!(
                 "metricName=ConsumeEventsV4Success market={} durationMs={} consumed={}",
                 perp_market.name(),
@@ -301,11 +691,15 @@ pub async fn loop_consume_events(
 
 pub async fn loop_update_funding(
     mango_client: Arc<MangoClient>,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
     pk: Pubkey,
     perp_market: PerpMarket,
     interval: u64,
 ) {
     let mut interval = time::interval(Duration::from_secs(interval));
+    let mut metric_success = metrics.register_counter("update_funding_success".into());
+    let mut metric_failure = metrics.register_counter("update_funding_failure".into());
     loop {
         interval.tick().await;
 
@@ -326,9 +720,12 @@ pub async fn loop_update_funding(
             ),
             data: anchor_lang::InstructionData::data(&mango_v4::instruction::PerpUpdateFunding {}),
         };
-        let sig_result = client.send_and_confirm_permissionless_tx(vec![ix]).await;
+        let sig_result = client
+            .send_and_confirm_permissionless_tx_with_retry(vec![ix], &retry_policy)
+            .await;
 
         if let Err(e) = sig_result {
+            metric_failure.increment();
             log::error!(
                 "metricName=UpdateFundingV4Error market={} durationMs={} error={}",
                 perp_market.name(),
@@ -337,6 +734,7 @@ pub async fn loop_update_funding(
             );
             log::error!("{:?}", e)
         } else {
+            metric_success.increment();
             log::info!(
                 "metricName=UpdateFundingV4Success market={} durationMs={}",
                 perp_market.name(),
@@ -346,3 +744,284 @@ pub async fn loop_update_funding(
         }
     }
 }
+
+/// Settle the serum3 open orders of all accounts in the group that have at least
+/// `min_free_value` of free funds, in $ native units
+///
+/// The keeper's own mango account receives the settle fee configured on the market.
+pub async fn loop_serum3_settle_funds(
+    mango_client: Arc<MangoClient>,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
+    min_free_value: f64,
+    interval: u64,
+) {
+    let mut interval = time::interval(Duration::from_secs(interval));
+    let mut metric_success = metrics.register_counter("serum3_settle_funds_success".into());
+    let mut metric_failure = metrics.register_counter("serum3_settle_funds_failure".into());
+    loop {
+        interval.tick().await;
+
+        let settleable =
+            match serum3_settleable_open_orders(&mango_client, I80F48::from_num(min_free_value))
+                .await
+            {
+                Ok(settleable) => settleable,
+                Err(e) => {
+                    log::error!("could not find settleable serum3 open orders: {:?}", e);
+                    continue;
+                }
+            };
+
+        for (address, account, market_index) in settleable {
+            let market_name = mango_client.context.serum3_markets[&market_index]
+                .market
+                .name();
+            let pre = Instant::now();
+            let sig_result = match mango_client
+                .serum3_settle_funds_permissionless_instruction(market_index, (&address, &account))
+            {
+                Ok(ix) => {
+                    mango_client
+                        .send_and_confirm_permissionless_tx_with_retry(vec![ix], &retry_policy)
+                        .await
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Err(e) = sig_result {
+                metric_failure.increment();
+                log::error!(
+                    "metricName=Serum3SettleFundsV4Error market={} account={} durationMs={} error={}",
+                    market_name,
+                    address,
+                    pre.elapsed().as_millis(),
+                    e.to_string()
+                );
+                log::error!("{:?}", e)
+            } else {
+                metric_success.increment();
+                log::info!(
+                    "metricName=Serum3SettleFundsV4Success market={} account={} durationMs={}",
+                    market_name,
+                    address,
+                    pre.elapsed().as_millis(),
+                );
+                log::info!("{:?}", sig_result);
+            }
+        }
+    }
+}
+
+// Rough upper bound of AccountHealthSnapshot compute units per snapshotted account
+const HEALTH_SNAPSHOT_CU_PER_ACCOUNT: u64 = 60_000;
+
+/// Split accounts into AccountHealthSnapshot transactions that fit into the
+/// transaction size and compute limits
+///
+/// Returns the instructions of each transaction and the number of accounts it snapshots.
+/// Accounts that don't fit into a transaction on their own are skipped.
+fn health_snapshot_transactions(
+    client: &MangoClient,
+    accounts: Vec<(Pubkey, MangoAccountValue)>,
+) -> anyhow::Result<Vec<(Vec<Instruction>, usize)>> {
+    let config = client.client.current_transaction_builder_config();
+    let instructions_for = |chunk: &[(Pubkey, MangoAccountValue)]| -> anyhow::Result<_> {
+        let cu = (chunk.len() as u64 * HEALTH_SNAPSHOT_CU_PER_ACCOUNT).min(MAX_TX_CU);
+        Ok(vec![
+            solana_sdk::compute_budget::ComputeBudgetInstruction::set_compute_unit_limit(cu as u32),
+            client.account_health_snapshot_instruction(chunk)?,
+        ])
+    };
+
+    let fits = |chunk: &[(Pubkey, MangoAccountValue)]| -> anyhow::Result<bool> {
+        if chunk.len() as u64 * HEALTH_SNAPSHOT_CU_PER_ACCOUNT > MAX_TX_CU {
+            return Ok(false);
+        }
+        let tx = permissionless_tx(client, instructions_for(chunk)?, config, Hash::default())?;
+        Ok(bincode::serialize(&tx)?.len() < solana_sdk::packet::PACKET_DATA_SIZE)
+    };
+
+    let mut transactions = vec![];
+    let mut chunk: Vec<(Pubkey, MangoAccountValue)> = vec![];
+    for account in accounts {
+        chunk.push(account);
+        if fits(&chunk)? {
+            continue;
+        }
+        let account = chunk.pop().unwrap();
+        if !chunk.is_empty() {
+            transactions.push((instructions_for(&chunk)?, chunk.len()));
+        }
+        chunk = if fits(std::slice::from_ref(&account))? {
+            vec![account]
+        } else {
+            log::warn!(
+                "skipping health snapshot of account {}, it doesn't fit into a transaction",
+                account.0
+            );
+            vec![]
+        };
+    }
+    if !chunk.is_empty() {
+        transactions.push((instructions_for(&chunk)?, chunk.len()));
+    }
+    Ok(transactions)
+}
+
+/// Emit AccountHealthSnapshotLog for all accounts of the group that have positions,
+/// so indexers get a health and equity history without recomputing health off-chain
+pub async fn loop_health_snapshots(
+    mango_client: Arc<MangoClient>,
+    metrics: Metrics,
+    retry_policy: RetryPolicy,
+    interval: u64,
+) {
+    let mut interval = time::interval(Duration::from_secs(interval));
+    let mut metric_success = metrics.register_counter("health_snapshot_success".into());
+    let mut metric_failure = metrics.register_counter("health_snapshot_failure".into());
+    let mut metric_accounts = metrics.register_counter("health_snapshot_accounts".into());
+    loop {
+        interval.tick().await;
+
+        // bypass the client's account cache, positions need to be fresh
+        let fetcher = RpcAccountFetcher {
+            rpc: mango_client.client.rpc_async(),
+        };
+        let accounts = match fetch_group_accounts(&mango_client, &fetcher).await {
+            Ok(accounts) => accounts
+                .into_iter()
+                .filter(|(_, account)| {
+                    account.active_token_positions().next().is_some()
+                        || account.active_perp_positions().next().is_some()
+                })
+                .collect::<Vec<_>>(),
+            Err(e) => {
+                log::error!("could not fetch accounts for health snapshots: {:?}", e);
+                continue;
+            }
+        };
+
+        let transactions = match health_snapshot_transactions(&mango_client, accounts) {
+            Ok(transactions) => transactions,
+            Err(e) => {
+                log::error!("building health snapshot transactions: {:?}", e);
+                continue;
+            }
+        };
+
+        for (instructions, num_accounts) in transactions {
+            let pre = Instant::now();
+            let sig_result = mango_client
+                .send_and_confirm_permissionless_tx_with_retry(instructions, &retry_policy)
+                .await;
+
+            if let Err(e) = sig_result {
+                metric_failure.increment();
+                log::error!(
+                    "metricName=HealthSnapshotV4Error accounts={} durationMs={} error={}",
+                    num_accounts,
+                    pre.elapsed().as_millis(),
+                    e.to_string()
+                );
+                log::error!("{:?}", e)
+            } else {
+                metric_success.increment();
+                metric_accounts.add(num_accounts as u64);
+                log::info!(
+                    "metricName=HealthSnapshotV4Success accounts={} durationMs={}",
+                    num_accounts,
+                    pre.elapsed().as_millis(),
+                );
+                log::info!("{:?}", sig_result);
+            }
+        }
+    }
+}
+
+/// Accounts of the group and their serum3 markets where the free funds in the open
+/// orders account are worth at least `min_free_value`
+async fn serum3_settleable_open_orders(
+    mango_client: &MangoClient,
+    min_free_value: I80F48,
+) -> anyhow::Result<Vec<(Pubkey, MangoAccountValue, Serum3MarketIndex)>> {
+    // bypass the client's account cache, balances and prices need to be fresh
+    let fetcher = RpcAccountFetcher {
+        rpc: mango_client.client.rpc_async(),
+    };
+
+    let accounts = fetch_group_accounts(mango_client, &fetcher).await?;
+
+    let mut prices = HashMap::<TokenIndex, I80F48>::new();
+    let mut result = vec![];
+    for (address, account) in accounts {
+        for serum3_orders in account.active_serum3_orders() {
+            let open_orders_account = fetcher
+                .fetch_raw_account(&serum3_orders.open_orders)
+                .await?;
+            let open_orders = mango_v4::serum3_cpi::load_open_orders(&open_orders_account)?;
+            let base_free = I80F48::from(open_orders.native_coin_free);
+            let quote_free =
+                I80F48::from(open_orders.native_pc_free + open_orders.referrer_rebates_accrued);
+            if base_free == 0 && quote_free == 0 {
+                continue;
+            }
+
+            let base_price = cached_token_price(
+                mango_client,
+                &fetcher,
+                &mut prices,
+                serum3_orders.base_token_index,
+            )
+            .await?;
+            let quote_price = cached_token_price(
+                mango_client,
+                &fetcher,
+                &mut prices,
+                serum3_orders.quote_token_index,
+            )
+            .await?;
+            let free_value = base_free * base_price + quote_free * quote_price;
+            if free_value >= min_free_value {
+                result.push((address, account.clone(), serum3_orders.market_index));
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// All mango accounts of the client's group
+async fn fetch_group_accounts(
+    mango_client: &MangoClient,
+    fetcher: &RpcAccountFetcher,
+) -> anyhow::Result<Vec<(Pubkey, MangoAccountValue)>> {
+    let group = mango_client.group();
+    fetcher
+        .fetch_program_accounts(&mango_v4::id(), MangoAccount::discriminator())
+        .await?
+        .into_iter()
+        .map(|(address, data)| -> anyhow::Result<_> {
+            Ok((address, MangoAccountValue::from_bytes(&data.data()[8..])?))
+        })
+        .filter_ok(|(_, account)| account.fixed.group == group)
+        .collect()
+}
+
+async fn cached_token_price(
+    mango_client: &MangoClient,
+    fetcher: &RpcAccountFetcher,
+    prices: &mut HashMap<TokenIndex, I80F48>,
+    token_index: TokenIndex,
+) -> anyhow::Result<I80F48> {
+    if let Some(price) = prices.get(&token_index) {
+        return Ok(*price);
+    }
+    let mint_info = mango_client.context.mint_info(token_index);
+    let bank: Bank = account_fetcher_fetch_anchor_account(fetcher, &mint_info.first_bank()).await?;
+    let oracle = fetcher.fetch_raw_account(&mint_info.oracle).await?;
+    let price = bank
+        .oracle_price(&KeyedAccountSharedData::new(mint_info.oracle, oracle), None)
+        .map_err_anyhow()?;
+    prices.insert(token_index, price);
+    Ok(price)
+}
@@ -7,7 +7,12 @@ use std::time::Duration;
 use anchor_client::Cluster;
 
 use clap::{Parser, Subcommand};
-use mango_v4_client::{keypair_from_cli, Client, MangoClient, TransactionBuilderConfig};
+use mango_v4_client::priority_fees::{
+    PriorityFeeEstimator, PriorityFeeEstimatorConfig, PriorityFeeMode,
+};
+use mango_v4_client::{
+    keypair_from_cli, metrics, Client, MangoClient, RetryPolicy, TransactionBuilderConfig,
+};
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use tokio::time;
@@ -57,12 +62,46 @@ struct Cli {
     #[clap(long, env, default_value_t = 120)]
     interval_check_new_listings_and_abort: u64,
 
+    #[clap(long, env, default_value_t = 300)]
+    interval_serum3_settle_funds: u64,
+
+    /// settle serum3 open orders of any account once their free funds are worth this much,
+    /// in $ native units (like USDC native)
+    #[clap(long, env, default_value_t = 100_000_000.0)]
+    serum3_settle_min_free_value: f64,
+
+    /// emit health and equity snapshots of all accounts with positions this often
+    #[clap(long, env, default_value_t = 3600)]
+    interval_health_snapshot: u64,
+
     #[clap(long, env, default_value_t = 10)]
     timeout: u64,
 
     /// prioritize each transaction with this many microlamports/cu
+    ///
+    /// with a priority fee mode other than fixed, this is the minimum
     #[clap(long, env, default_value = "0")]
     prioritization_micro_lamports: u64,
+
+    /// how to choose priority fees: fixed, recent-fees or landing-rate
+    #[clap(long, env, default_value = "fixed")]
+    priority_fee_mode: PriorityFeeMode,
+
+    /// never pay more than this many microlamports/cu
+    #[clap(long, env, default_value = "10000")]
+    max_prioritization_micro_lamports: u64,
+
+    /// send a transaction this many times at most if it doesn't land
+    #[clap(long, env, default_value_t = 3)]
+    max_send_attempts: u32,
+
+    /// perp market indexes that are not cranked, like inactive markets
+    #[clap(long, env, use_value_delimiter = true)]
+    skip_perp_markets: Vec<u16>,
+
+    /// serve metrics in Prometheus format on http://<address>/metrics, like 127.0.0.1:9091
+    #[clap(long, env)]
+    prometheus_address: Option<std::net::SocketAddr>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -91,30 +130,66 @@ async fn main() -> Result<(), anyhow::Error> {
     let rpc_url = cli.rpc_url;
     let ws_url = rpc_url.replace("https", "wss");
 
-    let cluster = Cluster::Custom(rpc_url, ws_url);
+    let cluster = Cluster::Custom(rpc_url, ws_url.clone());
     let commitment = match cli.command {
         Command::Crank { .. } => CommitmentConfig::confirmed(),
         Command::Taker { .. } => CommitmentConfig::confirmed(),
     };
 
+    let metrics = metrics::start(metrics::MetricsConfig {
+        namespace: "keeper".into(),
+        output_interval: Duration::from_secs(60),
+        prometheus_address: cli.prometheus_address,
+    });
+
+    let mut client = Client::new(
+        cluster,
+        commitment,
+        owner.clone(),
+        Some(Duration::from_secs(cli.timeout)),
+        TransactionBuilderConfig {
+            prioritization_micro_lamports: (cli.prioritization_micro_lamports > 0)
+                .then_some(cli.prioritization_micro_lamports),
+        },
+    );
+    client.transaction_metrics = Some(metrics::TransactionMetrics::new(&metrics));
+    let priority_fee_estimator = Arc::new(PriorityFeeEstimator::new(PriorityFeeEstimatorConfig {
+        mode: cli.priority_fee_mode,
+        base_fee: cli.prioritization_micro_lamports,
+        max_fee: cli.max_prioritization_micro_lamports,
+        recent_fees_percentile: 75,
+        target_landing_rate: 0.9,
+    }));
+    client.priority_fee_estimator = Some(priority_fee_estimator.clone());
+
     let mango_client = Arc::new(
-        MangoClient::new_for_existing_account(
-            Client::new(
-                cluster,
-                commitment,
-                owner.clone(),
-                Some(Duration::from_secs(cli.timeout)),
-                TransactionBuilderConfig {
-                    prioritization_micro_lamports: (cli.prioritization_micro_lamports > 0)
-                        .then_some(cli.prioritization_micro_lamports),
-                },
-            ),
-            cli.mango_account,
-            owner.clone(),
-        )
-        .await?,
+        MangoClient::new_for_existing_account(client, cli.mango_account, owner.clone()).await?,
     );
 
+    // Recent fees are requested for the accounts that the crank instructions write-lock
+    priority_fee_estimator.start_recent_fees_updater(
+        mango_client.client.rpc_async(),
+        mango_client
+            .context
+            .tokens
+            .values()
+            .flat_map(|token| token.mint_info.banks().iter().copied())
+            .chain(
+                mango_client
+                    .context
+                    .perp_markets
+                    .values()
+                    .map(|p| p.address),
+            )
+            .collect(),
+        Duration::from_secs(10),
+    );
+
+    let retry_policy = RetryPolicy {
+        max_attempts: cli.max_send_attempts.max(1),
+        ..RetryPolicy::default()
+    };
+
     let debugging_handle = async {
         let mut interval = time::interval(time::Duration::from_secs(5));
         loop {
@@ -135,10 +210,17 @@ async fn main() -> Result<(), anyhow::Error> {
             crank::runner(
                 client,
                 debugging_handle,
+                metrics,
+                retry_policy,
+                ws_url,
+                cli.skip_perp_markets.iter().copied().collect(),
                 cli.interval_update_banks,
                 cli.interval_consume_events,
                 cli.interval_update_funding,
                 cli.interval_check_new_listings_and_abort,
+                cli.interval_serum3_settle_funds,
+                cli.serum3_settle_min_free_value,
+                cli.interval_health_snapshot,
             )
             .await
         }
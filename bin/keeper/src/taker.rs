@@ -187,6 +187,7 @@ pub async fn loop_blocking_orders(
                     Serum3OrderType::ImmediateOrCancel,
                     SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
                     10,
+                    false,
                 )
                 .await;
             if let Err(e) = res {
@@ -206,6 +207,7 @@ pub async fn loop_blocking_orders(
                     Serum3OrderType::ImmediateOrCancel,
                     SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64,
                     10,
+                    false,
                 )
                 .await;
             if let Err(e) = res {
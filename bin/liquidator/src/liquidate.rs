@@ -1,21 +1,28 @@
+use std::cell::Cell;
 use std::collections::HashSet;
 use std::time::Duration;
 
 use mango_v4::accounts_zerocopy::KeyedAccountSharedData;
 use mango_v4::health::{HealthCache, HealthType};
 use mango_v4::state::{
-    Bank, MangoAccountValue, PerpMarketIndex, Side, TokenIndex, QUOTE_TOKEN_INDEX,
+    Bank, Group, MangoAccountValue, PerpMarketIndex, Side, TokenIndex, QUOTE_TOKEN_INDEX,
+};
+use mango_v4_client::{
+    account_fetcher_fetch_anchor_account, chain_data, health_cache, AccountFetcher,
+    JupiterSwapMode, MangoClient,
 };
-use mango_v4_client::{chain_data, health_cache, AccountFetcher, JupiterSwapMode, MangoClient};
 use solana_sdk::signature::Signature;
 
 use futures::{stream, StreamExt, TryStreamExt};
 use rand::seq::SliceRandom;
 use {anyhow::Context, fixed::types::I80F48, solana_sdk::pubkey::Pubkey};
 
+use crate::profit;
+
 pub struct Config {
     pub min_health_ratio: f64,
     pub refresh_timeout: Duration,
+    pub profit: profit::Config,
 }
 
 pub async fn jupiter_market_can_buy(
@@ -81,12 +88,44 @@ struct LiquidateHelper<'a> {
     liqee: &'a MangoAccountValue,
     health_cache: &'a HealthCache,
     maint_health: I80F48,
+    /// Fraction of the liquidation fees the liqor gets, below one during liquidation fee auctions
+    liquidation_fee_fraction: I80F48,
     liqor_min_health_ratio: I80F48,
     allowed_asset_tokens: HashSet<Pubkey>,
     allowed_liab_tokens: HashSet<Pubkey>,
+    profit_config: &'a profit::Config,
+    profit_metrics: &'a profit::Metrics,
+    /// Set when a liquidation step was not executed due to dry-run or the profit threshold
+    skipped: Cell<bool>,
 }
 
 impl<'a> LiquidateHelper<'a> {
+    /// Returns true if the liquidation described by `estimate` should be sent
+    fn check_estimate(&self, estimate: &profit::Estimate) -> bool {
+        let execute = self
+            .profit_metrics
+            .check(self.profit_config, self.pubkey, estimate);
+        if !execute {
+            self.skipped.set(true);
+        }
+        execute
+    }
+
+    /// Force cancels have no fee and are sent regardless of profit, except in dry-run mode
+    fn check_force_cancel(&self, kind: &str, market_index: u16) -> bool {
+        if self.profit_config.dry_run {
+            log::info!(
+                "dry run: would {} on account {}, market index {}",
+                kind,
+                self.pubkey,
+                market_index
+            );
+            self.skipped.set(true);
+            return false;
+        }
+        true
+    }
+
     async fn serum3_close_orders(&self) -> anyhow::Result<Option<Signature>> {
         // look for any open serum orders or settleable balances
         let serum_oos: anyhow::Result<Vec<_>> = stream::iter(self.liqee.active_serum3_orders())
@@ -118,6 +157,9 @@ impl<'a> LiquidateHelper<'a> {
         }
         // Cancel all orders on a random serum market
         let serum_orders = serum_force_cancels.choose(&mut rand::thread_rng()).unwrap();
+        if !self.check_force_cancel("force cancel serum orders", serum_orders.market_index) {
+            return Ok(None);
+        }
         let sig = self
             .client
             .serum3_liq_force_cancel_orders(
@@ -148,6 +190,9 @@ impl<'a> LiquidateHelper<'a> {
 
         // Cancel all orders on a random perp market
         let perp_market_index = *perp_force_cancels.choose(&mut rand::thread_rng()).unwrap();
+        if !self.check_force_cancel("force cancel perp orders", perp_market_index) {
+            return Ok(None);
+        }
         let sig = self
             .client
             .perp_liq_force_cancel_orders((self.pubkey, &self.liqee), perp_market_index)
@@ -264,6 +309,37 @@ impl<'a> LiquidateHelper<'a> {
         };
         log::info!("computed max_base_transfer: {max_base_transfer_abs}, max_pnl_transfer: {max_pnl_transfer}");
 
+        let estimate = {
+            let base_transfer_lots = base_lots.abs().min(max_base_transfer_abs);
+            let base_transfer_value =
+                I80F48::from(base_transfer_lots * perp.market.base_lot_size) * *price;
+            let pnl_transfer = if *base_lots == 0 {
+                let quote = self
+                    .liqee
+                    .perp_position(*perp_market_index)?
+                    .quote_position_native();
+                quote.max(I80F48::ZERO).min(I80F48::from(max_pnl_transfer))
+            } else {
+                I80F48::ZERO
+            };
+            profit::Estimate {
+                kind: "perp_liq_base_or_positive_pnl",
+                transfer_value: base_transfer_value + pnl_transfer,
+                fee_capture: base_transfer_value
+                    * perp.market.base_liquidation_fee
+                    * self.liquidation_fee_fraction
+                    + pnl_transfer * perp.market.positive_pnl_liquidation_fee,
+                rebalance_cost: profit::perp_close_cost(
+                    self.profit_config,
+                    perp.market.taker_fee,
+                    base_transfer_value,
+                ),
+            }
+        };
+        if !self.check_estimate(&estimate) {
+            return Ok(None);
+        }
+
         let sig = self
             .client
             .perp_liq_base_or_positive_pnl(
@@ -380,7 +456,20 @@ impl<'a> LiquidateHelper<'a> {
         if perp_negative_pnl.is_empty() {
             return Ok(None);
         }
-        let (perp_market_index, _) = perp_negative_pnl.first().unwrap();
+        let (perp_market_index, quote) = perp_negative_pnl.first().unwrap();
+
+        // Upper bound: the liquidation fee is only paid on the part that is covered
+        // by the insurance fund.
+        let perp = self.client.context.perp(*perp_market_index);
+        let estimate = profit::Estimate {
+            kind: "perp_liq_negative_pnl_or_bankruptcy",
+            transfer_value: -*quote,
+            fee_capture: -*quote * perp.market.base_liquidation_fee,
+            rebalance_cost: I80F48::ZERO,
+        };
+        if !self.check_estimate(&estimate) {
+            return Ok(None);
+        }
 
         let sig = self
             .client
@@ -471,6 +560,63 @@ impl<'a> LiquidateHelper<'a> {
         Ok(amount)
     }
 
+    /// Mirrors the transfer amount and fee computation of the program's token_liq_with_token
+    /// without taking the liqee's health into account.
+    async fn token_liq_estimate(
+        &self,
+        tokens: &[(TokenIndex, I80F48, I80F48)],
+        asset_token_index: TokenIndex,
+        liab_token_index: TokenIndex,
+        max_liab_transfer: I80F48,
+    ) -> anyhow::Result<profit::Estimate> {
+        let find = |index: TokenIndex| {
+            tokens
+                .iter()
+                .find(|(token_index, _, _)| *token_index == index)
+                .map(|(_, price, value)| (*price, *value))
+                .unwrap()
+        };
+        let (asset_price, asset_value) = find(asset_token_index);
+        let (liab_price, liab_value) = find(liab_token_index);
+        let asset_bank = self.client.first_bank(asset_token_index).await?;
+        let liab_bank = self.client.first_bank(liab_token_index).await?;
+
+        let fee_factor = I80F48::ONE
+            + (asset_bank.liquidation_fee + liab_bank.liquidation_fee)
+                * self.liquidation_fee_fraction;
+        let liab_price_adjusted = liab_price * fee_factor;
+        let liab_possible = asset_value / liab_price_adjusted;
+        let liab_transfer = (-liab_value / liab_price)
+            .min(liab_possible)
+            .min(max_liab_transfer);
+        let asset_transfer = liab_transfer * liab_price_adjusted / asset_price;
+        let transfer_value = liab_transfer * liab_price;
+
+        let rebalance_cost = profit::sell_cost(
+            self.client,
+            self.profit_config,
+            asset_token_index,
+            asset_transfer,
+            asset_price,
+        )
+        .await
+            + profit::buy_cost(
+                self.client,
+                self.profit_config,
+                liab_token_index,
+                liab_transfer,
+                liab_price,
+            )
+            .await;
+
+        Ok(profit::Estimate {
+            kind: "token_liq_with_token",
+            transfer_value,
+            fee_capture: transfer_value * (fee_factor - I80F48::ONE),
+            rebalance_cost,
+        })
+    }
+
     async fn token_liq(&self) -> anyhow::Result<Option<Signature>> {
         if !self.health_cache.has_spot_assets() || !self.health_cache.has_spot_borrows() {
             return Ok(None);
@@ -517,6 +663,18 @@ impl<'a> LiquidateHelper<'a> {
             .await
             .context("getting max_liab_transfer")?;
 
+        let estimate = self
+            .token_liq_estimate(
+                &tokens,
+                asset_token_index,
+                liab_token_index,
+                max_liab_transfer,
+            )
+            .await?;
+        if !self.check_estimate(&estimate) {
+            return Ok(None);
+        }
+
         //
         // TODO: log liqor's assets in UI form
         // TODO: log liquee's liab_needed, need to refactor program code to be able to be accessed from client side
@@ -575,6 +733,32 @@ impl<'a> LiquidateHelper<'a> {
             .max_token_liab_transfer(liab_token_index, quote_token_index)
             .await?;
 
+        let estimate = {
+            let liab_bank = self.client.first_bank(liab_token_index).await?;
+            let (_, liab_price, liab_value) = tokens
+                .iter()
+                .find(|(token_index, _, _)| *token_index == liab_token_index)
+                .unwrap();
+            let liab_transfer = (-*liab_value / *liab_price).min(max_liab_transfer);
+            let transfer_value = liab_transfer * *liab_price;
+            profit::Estimate {
+                kind: "token_liq_bankruptcy",
+                transfer_value,
+                fee_capture: transfer_value * liab_bank.liquidation_fee,
+                rebalance_cost: profit::buy_cost(
+                    self.client,
+                    self.profit_config,
+                    liab_token_index,
+                    liab_transfer,
+                    *liab_price,
+                )
+                .await,
+            }
+        };
+        if !self.check_estimate(&estimate) {
+            return Ok(None);
+        }
+
         let sig = self
             .client
             .token_liq_bankruptcy(
@@ -610,9 +794,15 @@ impl<'a> LiquidateHelper<'a> {
         if let Some(txsig) = self.perp_close_orders().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
         if let Some(txsig) = self.serum3_close_orders().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
 
         if self.health_cache.has_phase1_liquidatable() {
             anyhow::bail!(
@@ -629,10 +819,16 @@ impl<'a> LiquidateHelper<'a> {
         if let Some(txsig) = self.perp_liq_base_or_positive_pnl().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
 
         if let Some(txsig) = self.token_liq().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
 
         if self.health_cache.has_perp_open_fills() {
             log::info!(
@@ -659,11 +855,17 @@ impl<'a> LiquidateHelper<'a> {
         if let Some(txsig) = self.perp_liq_negative_pnl_or_bankruptcy().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
 
         // Socialize/insurance fund unliquidatable borrows
         if let Some(txsig) = self.token_liq_bankruptcy().await? {
             return Ok(Some(txsig));
         }
+        if self.skipped.get() {
+            return Ok(None);
+        }
 
         // TODO: What about unliquidatable positive perp pnl?
 
@@ -681,6 +883,7 @@ pub async fn maybe_liquidate_account(
     account_fetcher: &chain_data::AccountFetcher,
     pubkey: &Pubkey,
     config: &Config,
+    profit_metrics: &profit::Metrics,
 ) -> anyhow::Result<bool> {
     let liqor_min_health_ratio = I80F48::from_num(config.min_health_ratio);
 
@@ -713,6 +916,24 @@ pub async fn maybe_liquidate_account(
 
     let maint_health = health_cache.health(HealthType::Maint);
 
+    // With liquidation fee auctions, the fee depends on how long the account has been
+    // liquidated and how deep its maint health deficit is
+    let group =
+        account_fetcher_fetch_anchor_account::<Group>(account_fetcher, &mango_client.context.group)
+            .await?;
+    let now_ts: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs();
+    let being_liquidated_duration = if account.fixed.being_liquidated() {
+        account.fixed.being_liquidated_duration(now_ts)
+    } else {
+        0
+    };
+    let liquidation_fee_fraction = group.liquidation_fee_fraction(
+        being_liquidated_duration,
+        health_cache.maint_deficit_depth(),
+    );
+
     let all_token_mints = HashSet::from_iter(
         mango_client
             .context
@@ -722,19 +943,27 @@ pub async fn maybe_liquidate_account(
     );
 
     // try liquidating
-    let maybe_txsig = LiquidateHelper {
+    let helper = LiquidateHelper {
         client: mango_client,
         account_fetcher,
         pubkey,
         liqee: &account,
         health_cache: &health_cache,
         maint_health,
+        liquidation_fee_fraction,
         liqor_min_health_ratio,
         allowed_asset_tokens: all_token_mints.clone(),
         allowed_liab_tokens: all_token_mints,
+        profit_config: &config.profit,
+        profit_metrics,
+        skipped: Cell::new(false),
+    };
+    let maybe_txsig = helper.send_liq_tx().await?;
+
+    // Skipped due to dry-run or lack of profit: let the caller continue with other accounts
+    if helper.skipped.get() {
+        return Ok(false);
     }
-    .send_liq_tx()
-    .await?;
 
     if let Some(txsig) = maybe_txsig {
         let slot = account_fetcher.transaction_max_slot(&[txsig]).await?;
@@ -5,20 +5,21 @@ use std::time::Duration;
 use anchor_client::Cluster;
 use clap::Parser;
 use log::*;
-use mango_v4::state::{PerpMarketIndex, TokenIndex};
+use mango_v4::state::{PerpMarketIndex, TokenIndex, QUOTE_TOKEN_INDEX};
 use mango_v4_client::{
-    account_update_stream, chain_data, keypair_from_cli, snapshot_source, websocket_source,
-    AsyncChannelSendUnlessFull, Client, MangoClient, MangoClientError, MangoGroupContext,
-    TransactionBuilderConfig,
+    account_update_stream, chain_data, keypair_from_cli, metrics, snapshot_source,
+    websocket_source, AsyncChannelSendUnlessFull, Client, MangoClient, MangoClientError,
+    MangoGroupContext, TransactionBuilderConfig,
 };
 
+use fixed::types::I80F48;
 use itertools::Itertools;
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashSet;
 
 pub mod liquidate;
-pub mod metrics;
+pub mod profit;
 pub mod rebalance;
 pub mod util;
 
@@ -76,6 +77,18 @@ struct Cli {
     /// prioritize each transaction with this many microlamports/cu
     #[clap(long, env, default_value = "0")]
     prioritization_micro_lamports: u64,
+
+    /// serve metrics in Prometheus format on http://<address>/metrics, like 127.0.0.1:9091
+    #[clap(long, env)]
+    prometheus_address: Option<std::net::SocketAddr>,
+
+    /// only log and record estimated liquidation profits, never send liquidation transactions
+    #[clap(long, env)]
+    dry_run: bool,
+
+    /// minimum estimated profit in quote tokens (ui amount) for sending a liquidation
+    #[clap(long, env, default_value = "0")]
+    min_liquidation_profit: f64,
 }
 
 pub fn encode_address(addr: &Pubkey) -> String {
@@ -101,7 +114,7 @@ async fn main() -> anyhow::Result<()> {
     let rpc_timeout = Duration::from_secs(10);
     let cluster = Cluster::Custom(rpc_url.clone(), ws_url.clone());
     let commitment = CommitmentConfig::processed();
-    let client = Client::new(
+    let mut client = Client::new(
         cluster.clone(),
         commitment,
         liqor_owner.clone(),
@@ -144,7 +157,12 @@ async fn main() -> anyhow::Result<()> {
     solana_logger::setup_with_default("info");
     info!("startup");
 
-    let metrics = metrics::start();
+    let metrics = metrics::start(metrics::MetricsConfig {
+        namespace: "liquidator".into(),
+        output_interval: Duration::from_secs(60),
+        prometheus_address: cli.prometheus_address,
+    });
+    client.transaction_metrics = Some(metrics::TransactionMetrics::new(&metrics));
 
     let (account_update_sender, account_update_receiver) =
         async_channel::unbounded::<account_update_stream::Message>();
@@ -182,7 +200,7 @@ async fn main() -> anyhow::Result<()> {
         account_update_sender,
     );
 
-    start_chain_data_metrics(chain_data.clone(), &metrics);
+    metrics::start_chain_data_metrics(chain_data.clone(), client.rpc_async(), &metrics);
 
     let shared_state = Arc::new(RwLock::new(SharedState::default()));
 
@@ -199,10 +217,17 @@ async fn main() -> anyhow::Result<()> {
         )?)
     };
 
+    let quote_decimals = mango_client.context.token(QUOTE_TOKEN_INDEX).decimals;
     let liq_config = liquidate::Config {
         min_health_ratio: cli.min_health_ratio,
         // TODO: config
         refresh_timeout: Duration::from_secs(30),
+        profit: profit::Config {
+            dry_run: cli.dry_run,
+            min_profit: I80F48::from_num(cli.min_liquidation_profit)
+                * I80F48::from(10u64.pow(quote_decimals.into())),
+            slippage_bps: cli.rebalance_slippage_bps,
+        },
     };
 
     let mut rebalance_interval = tokio::time::interval(Duration::from_secs(5));
@@ -225,6 +250,7 @@ async fn main() -> anyhow::Result<()> {
         account_fetcher,
         liquidation_config: liq_config,
         rebalancer: rebalancer.clone(),
+        profit_metrics: profit::Metrics::new(&metrics),
         accounts_with_errors: Default::default(),
         error_skip_threshold: 5,
         error_skip_duration: std::time::Duration::from_secs(120),
@@ -330,10 +356,11 @@ async fn main() -> anyhow::Result<()> {
 
     let rebalance_job = tokio::spawn({
         let shared_state = shared_state.clone();
+        let dry_run = cli.dry_run;
         async move {
             loop {
                 rebalance_interval.tick().await;
-                if !shared_state.read().unwrap().one_snapshot_done {
+                if dry_run || !shared_state.read().unwrap().one_snapshot_done {
                     continue;
                 }
                 if let Err(err) = rebalancer.zero_all_non_quote().await {
@@ -349,6 +376,7 @@ async fn main() -> anyhow::Result<()> {
     });
 
     let liquidation_job = tokio::spawn({
+        let mut metric_accounts_checked = metrics.register_counter("accounts_checked".into());
         async move {
             loop {
                 liquidation_trigger_receiver.recv().await.unwrap();
@@ -368,6 +396,7 @@ async fn main() -> anyhow::Result<()> {
                     state.health_check_accounts = vec![];
                 }
 
+                metric_accounts_checked.add(account_addresses.len() as u64);
                 liquidation
                     .maybe_liquidate_one_and_rebalance(account_addresses.iter())
                     .await
@@ -413,6 +442,7 @@ struct LiquidationState {
     account_fetcher: Arc<chain_data::AccountFetcher>,
     rebalancer: Arc<rebalance::Rebalancer>,
     liquidation_config: liquidate::Config,
+    profit_metrics: profit::Metrics,
     accounts_with_errors: HashMap<Pubkey, ErrorTracking>,
     error_skip_threshold: u64,
     error_skip_duration: std::time::Duration,
@@ -474,6 +504,7 @@ impl LiquidationState {
             &self.account_fetcher,
             pubkey,
             &self.liquidation_config,
+            &self.profit_metrics,
         )
         .await;
 
@@ -515,22 +546,3 @@ impl LiquidationState {
         result
     }
 }
-
-fn start_chain_data_metrics(chain: Arc<RwLock<chain_data::ChainData>>, metrics: &metrics::Metrics) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(600));
-
-    let mut metric_slots_count = metrics.register_u64("chain_data_slots_count".into());
-    let mut metric_accounts_count = metrics.register_u64("chain_data_accounts_count".into());
-    let mut metric_account_write_count =
-        metrics.register_u64("chain_data_account_write_count".into());
-
-    tokio::spawn(async move {
-        loop {
-            interval.tick().await;
-            let chain_lock = chain.read().unwrap();
-            metric_slots_count.set(chain_lock.slots_count() as u64);
-            metric_accounts_count.set(chain_lock.accounts_count() as u64);
-            metric_account_write_count.set(chain_lock.account_writes_count() as u64);
-        }
-    });
-}
@@ -0,0 +1,162 @@
+use mango_v4::state::{TokenIndex, QUOTE_TOKEN_INDEX};
+use mango_v4_client::{metrics, JupiterSwapMode, MangoClient};
+
+use fixed::types::I80F48;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone)]
+pub struct Config {
+    /// Only log and record estimates, never send liquidation transactions
+    pub dry_run: bool,
+    /// Liquidation instructions are only sent if the estimated profit (in native quote)
+    /// is at least this large
+    pub min_profit: I80F48,
+    /// Slippage used for the Jupiter quotes that estimate rebalancing cost.
+    /// Also the fallback cost estimate when no quote is available.
+    pub slippage_bps: u64,
+}
+
+/// Expected outcome of a liquidation instruction for the liqor.
+///
+/// All values are in native quote units.
+#[derive(Debug, Clone)]
+pub struct Estimate {
+    /// Name of the liquidation instruction this is for
+    pub kind: &'static str,
+    /// Oracle value of the position that the liqor takes over
+    pub transfer_value: I80F48,
+    /// Value the liqor gains from the liquidation fee
+    pub fee_capture: I80F48,
+    /// Expected cost of closing the taken-over position again
+    pub rebalance_cost: I80F48,
+}
+
+impl Estimate {
+    pub fn profit(&self) -> I80F48 {
+        self.fee_capture - self.rebalance_cost
+    }
+}
+
+pub struct Metrics {
+    candidates: metrics::MetricU64,
+    skipped: metrics::MetricU64,
+    last_profit: metrics::MetricI64,
+    total_profit: metrics::MetricI64,
+}
+
+impl Metrics {
+    pub fn new(metrics: &metrics::Metrics) -> Self {
+        Self {
+            candidates: metrics.register_counter("liquidation_estimate_candidates".into()),
+            skipped: metrics.register_counter("liquidation_estimate_skipped".into()),
+            last_profit: metrics.register_i64("liquidation_estimate_last_profit".into()),
+            total_profit: metrics.register_i64("liquidation_estimate_total_profit".into()),
+        }
+    }
+
+    /// Record the estimate and return whether the liquidation should be executed.
+    pub fn check(&self, config: &Config, liqee: &Pubkey, estimate: &Estimate) -> bool {
+        let profit = estimate.profit();
+        let execute = !config.dry_run && profit >= config.min_profit;
+        log::info!(
+            "liquidation estimate for {}: {}, transfer value {}, fee capture {}, rebalance cost {}, profit {}{}",
+            liqee,
+            estimate.kind,
+            estimate.transfer_value,
+            estimate.fee_capture,
+            estimate.rebalance_cost,
+            profit,
+            if config.dry_run {
+                " (dry run)"
+            } else if !execute {
+                " (below min profit, skipping)"
+            } else {
+                ""
+            }
+        );
+
+        let profit_i64 = profit.round().checked_to_num::<i64>().unwrap_or(i64::MAX);
+        self.candidates.clone().increment();
+        self.last_profit.clone().set(profit_i64);
+        self.total_profit.clone().add(profit_i64);
+        if !execute {
+            self.skipped.clone().increment();
+        }
+
+        execute
+    }
+}
+
+fn slippage_cost(value: I80F48, slippage_bps: u64) -> I80F48 {
+    value.abs() * I80F48::from(slippage_bps) / I80F48::from(10_000)
+}
+
+/// Estimate the cost of selling `amount` native tokens of `token_index` for quote.
+///
+/// Uses a Jupiter quote if available and falls back to the configured slippage.
+pub async fn sell_cost(
+    client: &MangoClient,
+    config: &Config,
+    token_index: TokenIndex,
+    amount: I80F48,
+    price: I80F48,
+) -> I80F48 {
+    let value = amount * price;
+    if token_index == QUOTE_TOKEN_INDEX || amount <= 0 {
+        return I80F48::ZERO;
+    }
+    let token_mint = client.context.token(token_index).mint_info.mint;
+    let quote_mint = client.context.token(QUOTE_TOKEN_INDEX).mint_info.mint;
+    let route = client
+        .jupiter_route(
+            token_mint,
+            quote_mint,
+            amount.floor().to_num::<u64>(),
+            config.slippage_bps,
+            JupiterSwapMode::ExactIn,
+        )
+        .await;
+    match route.map(|r| r.out_amount.parse::<u64>()) {
+        Ok(Ok(out_amount)) => (value - I80F48::from(out_amount)).max(I80F48::ZERO),
+        _ => slippage_cost(value, config.slippage_bps),
+    }
+}
+
+/// Estimate the cost of buying `amount` native tokens of `token_index` with quote.
+///
+/// Uses a Jupiter quote if available and falls back to the configured slippage.
+pub async fn buy_cost(
+    client: &MangoClient,
+    config: &Config,
+    token_index: TokenIndex,
+    amount: I80F48,
+    price: I80F48,
+) -> I80F48 {
+    let value = amount * price;
+    if token_index == QUOTE_TOKEN_INDEX || amount <= 0 {
+        return I80F48::ZERO;
+    }
+    let token_mint = client.context.token(token_index).mint_info.mint;
+    let quote_mint = client.context.token(QUOTE_TOKEN_INDEX).mint_info.mint;
+    let route = client
+        .jupiter_route(
+            quote_mint,
+            token_mint,
+            amount.ceil().to_num::<u64>(),
+            config.slippage_bps,
+            JupiterSwapMode::ExactOut,
+        )
+        .await;
+    match route.map(|r| r.in_amount.parse::<u64>()) {
+        Ok(Ok(in_amount)) => (I80F48::from(in_amount) - value).max(I80F48::ZERO),
+        _ => slippage_cost(value, config.slippage_bps),
+    }
+}
+
+/// Estimate the cost of closing a perp base position of the given oracle value.
+///
+/// The rebalancer closes perp positions with taker orders on the perp book, so this
+/// is the taker fee plus the configured slippage.
+pub fn perp_close_cost(config: &Config, taker_fee: I80F48, value: I80F48) -> I80F48 {
+    value.abs() * taker_fee.max(I80F48::ZERO) + slippage_cost(value, config.slippage_bps)
+}
@@ -333,6 +333,7 @@ impl Rebalancer {
                         true, // reduce only
                         0,
                         10,
+                        None,
                     )
                     .await?;
                 log::info!(
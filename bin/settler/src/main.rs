@@ -7,8 +7,9 @@ use clap::Parser;
 use log::*;
 use mango_v4::state::{PerpMarketIndex, TokenIndex};
 use mango_v4_client::{
-    account_update_stream, chain_data, keypair_from_cli, snapshot_source, websocket_source,
-    AsyncChannelSendUnlessFull, Client, MangoClient, MangoGroupContext, TransactionBuilderConfig,
+    account_update_stream, chain_data, keypair_from_cli, metrics, snapshot_source,
+    websocket_source, AsyncChannelSendUnlessFull, Client, MangoClient, MangoGroupContext,
+    TransactionBuilderConfig,
 };
 
 use itertools::Itertools;
@@ -16,7 +17,6 @@ use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::pubkey::Pubkey;
 use std::collections::HashSet;
 
-pub mod metrics;
 pub mod settle;
 pub mod util;
 
@@ -67,6 +67,10 @@ struct Cli {
     /// prioritize each transaction with this many microlamports/cu
     #[clap(long, env, default_value = "0")]
     prioritization_micro_lamports: u64,
+
+    /// serve metrics in Prometheus format on http://<address>/metrics, like 127.0.0.1:9091
+    #[clap(long, env)]
+    prometheus_address: Option<std::net::SocketAddr>,
 }
 
 pub fn encode_address(addr: &Pubkey) -> String {
@@ -92,7 +96,7 @@ async fn main() -> anyhow::Result<()> {
     let rpc_timeout = Duration::from_secs(10);
     let cluster = Cluster::Custom(rpc_url.clone(), ws_url.clone());
     let commitment = CommitmentConfig::processed();
-    let client = Client::new(
+    let mut client = Client::new(
         cluster.clone(),
         commitment,
         settler_owner.clone(),
@@ -135,7 +139,12 @@ async fn main() -> anyhow::Result<()> {
     solana_logger::setup_with_default("info");
     info!("startup");
 
-    let metrics = metrics::start();
+    let metrics = metrics::start(metrics::MetricsConfig {
+        namespace: "settler".into(),
+        output_interval: Duration::from_secs(60),
+        prometheus_address: cli.prometheus_address,
+    });
+    client.transaction_metrics = Some(metrics::TransactionMetrics::new(&metrics));
 
     let (account_update_sender, account_update_receiver) =
         async_channel::unbounded::<account_update_stream::Message>();
@@ -173,7 +182,7 @@ async fn main() -> anyhow::Result<()> {
         account_update_sender.clone(),
     );
 
-    start_chain_data_metrics(chain_data.clone(), &metrics);
+    metrics::start_chain_data_metrics(chain_data.clone(), client.rpc_async(), &metrics);
 
     let shared_state = Arc::new(RwLock::new(SharedState::default()));
 
@@ -299,11 +308,12 @@ async fn main() -> anyhow::Result<()> {
 
     let settle_job = tokio::spawn({
         let shared_state = shared_state.clone();
+        let mut metric_accounts_checked = metrics.register_counter("accounts_checked".into());
         async move {
             loop {
                 settle_trigger_receiver.recv().await.unwrap();
 
-                let account_addresses;
+                let account_addresses: Vec<Pubkey>;
                 {
                     let mut state = shared_state.write().unwrap();
                     if !state.one_snapshot_done {
@@ -314,6 +324,7 @@ async fn main() -> anyhow::Result<()> {
                     account_addresses = state.mango_accounts.iter().cloned().collect();
                 }
 
+                metric_accounts_checked.add(account_addresses.len() as u64);
                 settlement.settle(account_addresses).await.unwrap();
             }
         }
@@ -343,22 +354,3 @@ struct SharedState {
     /// Check all accounts?
     health_check_all: bool,
 }
-
-fn start_chain_data_metrics(chain: Arc<RwLock<chain_data::ChainData>>, metrics: &metrics::Metrics) {
-    let mut interval = tokio::time::interval(std::time::Duration::from_secs(600));
-
-    let mut metric_slots_count = metrics.register_u64("chain_data_slots_count".into());
-    let mut metric_accounts_count = metrics.register_u64("chain_data_accounts_count".into());
-    let mut metric_account_write_count =
-        metrics.register_u64("chain_data_account_write_count".into());
-
-    tokio::spawn(async move {
-        loop {
-            interval.tick().await;
-            let chain_lock = chain.read().unwrap();
-            metric_slots_count.set(chain_lock.slots_count() as u64);
-            metric_accounts_count.set(chain_lock.accounts_count() as u64);
-            metric_account_write_count.set(chain_lock.account_writes_count() as u64);
-        }
-    });
-}
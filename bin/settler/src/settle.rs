@@ -256,7 +256,7 @@ impl<'a> SettleBatchProcessor<'a> {
             address_lookup_tables: self.address_lookup_tables.clone(),
             payer: fee_payer.pubkey(),
             signers: vec![fee_payer],
-            config: client.transaction_builder_config,
+            config: client.current_transaction_builder_config(),
         }
         .transaction_with_blockhash(self.blockhash)
     }
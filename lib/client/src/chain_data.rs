@@ -245,4 +245,8 @@ impl ChainData {
     pub fn account_writes_count(&self) -> usize {
         self.accounts.values().map(|v| v.len()).sum()
     }
+
+    pub fn newest_processed_slot(&self) -> u64 {
+        self.newest_processed_slot
+    }
 }
@@ -17,8 +17,8 @@ use itertools::Itertools;
 
 use mango_v4::accounts_ix::{Serum3OrderType, Serum3SelfTradeBehavior, Serum3Side};
 use mango_v4::state::{
-    Bank, Group, MangoAccountValue, PerpMarketIndex, PlaceOrderType, Serum3MarketIndex, Side,
-    TokenIndex,
+    Bank, Group, MangoAccountValue, PerpMarket, PerpMarketIndex, PlaceOrderType, Serum3MarketIndex,
+    Side, TokenIndex,
 };
 
 use solana_address_lookup_table_program::state::AddressLookupTable;
@@ -34,6 +34,8 @@ use crate::account_fetcher::*;
 use crate::context::{MangoGroupContext, Serum3MarketContext, TokenContext};
 use crate::gpa::{fetch_anchor_account, fetch_mango_accounts};
 use crate::jupiter;
+use crate::metrics::TransactionMetrics;
+use crate::priority_fees::PriorityFeeEstimator;
 
 use anyhow::Context;
 use solana_sdk::account::ReadableAccount;
@@ -51,6 +53,11 @@ pub struct Client {
     pub timeout: Option<Duration>,
     pub transaction_builder_config: TransactionBuilderConfig,
     pub rpc_send_transaction_config: RpcSendTransactionConfig,
+    /// If set, all transaction sends are recorded here
+    pub transaction_metrics: Option<TransactionMetrics>,
+    /// If set, overrides transaction_builder_config.prioritization_micro_lamports
+    /// and is informed about whether sent transactions landed
+    pub priority_fee_estimator: Option<Arc<PriorityFeeEstimator>>,
 }
 
 impl Client {
@@ -71,9 +78,21 @@ impl Client {
                 preflight_commitment: Some(CommitmentLevel::Processed),
                 ..Default::default()
             },
+            transaction_metrics: None,
+            priority_fee_estimator: None,
         }
     }
 
+    /// The transaction builder config with the current priority fee estimate applied
+    pub fn current_transaction_builder_config(&self) -> TransactionBuilderConfig {
+        let mut config = self.transaction_builder_config;
+        if let Some(estimator) = self.priority_fee_estimator.as_ref() {
+            let fee = estimator.fee();
+            config.prioritization_micro_lamports = (fee > 0).then_some(fee);
+        }
+        config
+    }
+
     pub fn rpc_async(&self) -> RpcClientAsync {
         let url = self.cluster.url().to_string();
         if let Some(timeout) = self.timeout.as_ref() {
@@ -215,7 +234,7 @@ impl MangoClient {
             address_lookup_tables: vec![],
             payer: payer.pubkey(),
             signers: vec![owner, payer],
-            config: client.transaction_builder_config,
+            config: client.current_transaction_builder_config(),
         }
         .send_and_confirm(&client)
         .await?;
@@ -509,6 +528,7 @@ impl MangoClient {
         order_type: Serum3OrderType,
         client_order_id: u64,
         limit: u16,
+        reduce_only: bool,
     ) -> anyhow::Result<Signature> {
         let s3 = self.serum3_data_by_market_name(name)?;
 
@@ -609,7 +629,7 @@ impl MangoClient {
                 ams.extend(health_check_metas.into_iter());
                 ams
             },
-            data: anchor_lang::InstructionData::data(&mango_v4::instruction::Serum3PlaceOrder {
+            data: anchor_lang::InstructionData::data(&mango_v4::instruction::Serum3PlaceOrderV2 {
                 side,
                 limit_price,
                 max_base_qty,
@@ -618,6 +638,7 @@ impl MangoClient {
                 order_type,
                 client_order_id,
                 limit,
+                reduce_only,
             }),
         };
         self.send_and_confirm_owner_tx(vec![ix]).await
@@ -656,6 +677,84 @@ impl MangoClient {
         self.send_and_confirm_owner_tx(vec![ix]).await
     }
 
+    pub fn serum3_settle_funds_permissionless_instruction(
+        &self,
+        market_index: Serum3MarketIndex,
+        account: (&Pubkey, &MangoAccountValue),
+    ) -> anyhow::Result<Instruction> {
+        let s3 = self.serum3_data_by_market_index(market_index)?;
+        let open_orders = account.1.serum3_orders(market_index)?.open_orders;
+
+        Ok(Instruction {
+            program_id: mango_v4::id(),
+            accounts: anchor_lang::ToAccountMetas::to_account_metas(
+                &mango_v4::accounts::Serum3SettleFundsPermissionless {
+                    v1: mango_v4::accounts::Serum3SettleFunds {
+                        group: self.group(),
+                        account: *account.0,
+                        open_orders,
+                        quote_bank: s3.quote.mint_info.first_bank(),
+                        quote_vault: s3.quote.mint_info.first_vault(),
+                        base_bank: s3.base.mint_info.first_bank(),
+                        base_vault: s3.base.mint_info.first_vault(),
+                        serum_market: s3.market.address,
+                        serum_program: s3.market.market.serum_program,
+                        serum_market_external: s3.market.market.serum_market_external,
+                        market_base_vault: s3.market.coin_vault,
+                        market_quote_vault: s3.market.pc_vault,
+                        market_vault_signer: s3.market.vault_signer,
+                        owner: self.owner(),
+                        token_program: Token::id(),
+                    },
+                    v2: mango_v4::accounts::Serum3SettleFundsV2Extra {
+                        quote_oracle: s3.quote.mint_info.oracle,
+                        base_oracle: s3.base.mint_info.oracle,
+                    },
+                    settler: self.mango_account_address,
+                },
+                None,
+            ),
+            data: anchor_lang::InstructionData::data(
+                &mango_v4::instruction::Serum3SettleFundsPermissionless {},
+            ),
+        })
+    }
+
+    /// Emits an AccountHealthSnapshotLog for each of the accounts
+    pub fn account_health_snapshot_instruction(
+        &self,
+        accounts: &[(Pubkey, MangoAccountValue)],
+    ) -> anyhow::Result<Instruction> {
+        let health_remaining_ams = self
+            .context
+            .derive_health_check_remaining_account_metas_many(
+                &accounts.iter().map(|(_, a)| a).collect::<Vec<_>>(),
+                &[],
+            )?;
+
+        Ok(Instruction {
+            program_id: mango_v4::id(),
+            accounts: {
+                let mut ams = anchor_lang::ToAccountMetas::to_account_metas(
+                    &mango_v4::accounts::AccountHealthSnapshot {
+                        group: self.group(),
+                    },
+                    None,
+                );
+                ams.extend(accounts.iter().map(|(pubkey, _)| AccountMeta {
+                    pubkey: *pubkey,
+                    is_writable: false,
+                    is_signer: false,
+                }));
+                ams.extend(health_remaining_ams.into_iter());
+                ams
+            },
+            data: anchor_lang::InstructionData::data(
+                &mango_v4::instruction::AccountHealthSnapshot {},
+            ),
+        })
+    }
+
     pub async fn serum3_cancel_all_orders(
         &self,
         market_name: &str,
@@ -792,6 +891,7 @@ impl MangoClient {
         reduce_only: bool,
         expiry_timestamp: u64,
         limit: u8,
+        referrer: Option<Pubkey>,
     ) -> anyhow::Result<Signature> {
         let perp = self.context.perp(market_index);
 
@@ -820,6 +920,10 @@ impl MangoClient {
                     None,
                 );
                 ams.extend(health_check_metas.into_iter());
+                // the referrer goes after the health accounts
+                if let Some(referrer) = referrer {
+                    ams.push(AccountMeta::new_readonly(referrer, false));
+                }
                 ams
             },
             data: anchor_lang::InstructionData::data(&mango_v4::instruction::PerpPlaceOrder {
@@ -1046,6 +1150,95 @@ impl MangoClient {
         self.send_and_confirm_owner_tx(vec![ix]).await
     }
 
+    /// Deleverages `counterparty` against the bankrupt `liqee`.
+    ///
+    /// `candidates` are the competing opposite-side accounts, see perp_pnl::fetch_adl_candidates().
+    /// The market's stored next candidate for the side is added if it's missing.
+    pub async fn perp_adl(
+        &self,
+        market_index: PerpMarketIndex,
+        liqee: (&Pubkey, &MangoAccountValue),
+        counterparty: (&Pubkey, &MangoAccountValue),
+        candidates: &[(Pubkey, MangoAccountValue)],
+        max_base_transfer: u64,
+    ) -> anyhow::Result<Signature> {
+        let group = account_fetcher_fetch_anchor_account::<Group>(
+            &*self.account_fetcher,
+            &self.context.group,
+        )
+        .await?;
+
+        let perp = self.context.perp(market_index);
+        let perp_market = account_fetcher_fetch_anchor_account::<PerpMarket>(
+            &*self.account_fetcher,
+            &perp.address,
+        )
+        .await?;
+
+        let mut candidate_pks = candidates.iter().map(|(pk, _)| *pk).collect::<Vec<_>>();
+        let mut health_accounts = [liqee.1, counterparty.1]
+            .into_iter()
+            .chain(candidates.iter().map(|(_, acc)| acc))
+            .collect::<Vec<_>>();
+        let counterparty_base_lots = counterparty
+            .1
+            .perp_position(market_index)?
+            .base_position_lots();
+        let next_candidate = if counterparty_base_lots > 0 {
+            perp_market.adl_next_long
+        } else {
+            perp_market.adl_next_short
+        };
+        // A closed next candidate is still passed, but needs no health accounts
+        let next_candidate_account;
+        if next_candidate != Pubkey::default()
+            && next_candidate != *counterparty.0
+            && !candidate_pks.contains(&next_candidate)
+        {
+            candidate_pks.push(next_candidate);
+            next_candidate_account =
+                account_fetcher_fetch_mango_account(&*self.account_fetcher, &next_candidate)
+                    .await
+                    .ok();
+            if let Some(acc) = next_candidate_account.as_ref() {
+                health_accounts.push(acc);
+            }
+        }
+
+        let health_remaining_ams = self
+            .context
+            .derive_health_check_remaining_account_metas_many(&health_accounts, &[])?;
+
+        let ix = Instruction {
+            program_id: mango_v4::id(),
+            accounts: {
+                let mut ams = anchor_lang::ToAccountMetas::to_account_metas(
+                    &mango_v4::accounts::PerpAdl {
+                        group: self.group(),
+                        perp_market: perp.address,
+                        oracle: perp.market.oracle,
+                        liqee: *liqee.0,
+                        counterparty: *counterparty.0,
+                        insurance_vault: group.insurance_vault,
+                    },
+                    None,
+                );
+                ams.extend(candidate_pks.iter().map(|&pubkey| AccountMeta {
+                    pubkey,
+                    is_writable: false,
+                    is_signer: false,
+                }));
+                ams.extend(health_remaining_ams.into_iter());
+                ams
+            },
+            data: anchor_lang::InstructionData::data(&mango_v4::instruction::PerpAdl {
+                max_base_transfer,
+                num_candidates: candidate_pks.len().try_into()?,
+            }),
+        };
+        self.send_and_confirm_permissionless_tx(vec![ix]).await
+    }
+
     //
     // Liquidation
     //
@@ -1378,7 +1571,7 @@ impl MangoClient {
             address_lookup_tables,
             payer,
             signers: vec![&*self.owner],
-            config: self.client.transaction_builder_config,
+            config: self.client.current_transaction_builder_config(),
         }
         .send_and_confirm(&self.client)
         .await
@@ -1466,7 +1659,7 @@ impl MangoClient {
             address_lookup_tables: vec![],
             payer: self.client.fee_payer.pubkey(),
             signers: vec![&*self.owner, &*self.client.fee_payer],
-            config: self.client.transaction_builder_config,
+            config: self.client.current_transaction_builder_config(),
         }
         .send_and_confirm(&self.client)
         .await
@@ -1481,11 +1674,99 @@ impl MangoClient {
             address_lookup_tables: vec![],
             payer: self.client.fee_payer.pubkey(),
             signers: vec![&*self.client.fee_payer],
-            config: self.client.transaction_builder_config,
+            config: self.client.current_transaction_builder_config(),
         }
         .send_and_confirm(&self.client)
         .await
     }
+
+    /// Like send_and_confirm_permissionless_tx(), but retries sending transactions that
+    /// did not land, with a fresh blockhash and priority fee each time.
+    pub async fn send_and_confirm_permissionless_tx_with_retry(
+        &self,
+        instructions: Vec<Instruction>,
+        retry_policy: &RetryPolicy,
+    ) -> anyhow::Result<Signature> {
+        let mut attempt = 1;
+        loop {
+            let result = self
+                .send_and_confirm_permissionless_tx(instructions.clone())
+                .await;
+            match result {
+                Err(err)
+                    if attempt < retry_policy.max_attempts && is_retryable_send_error(&err) =>
+                {
+                    log::info!(
+                        "transaction did not land on attempt {attempt}/{}, retrying: {err:?}",
+                        retry_policy.max_attempts
+                    );
+                    tokio::time::sleep(retry_policy.backoff * attempt).await;
+                    attempt += 1;
+                }
+                _ => return result,
+            }
+        }
+    }
+}
+
+/// How often and how fast to resend transactions that did not land
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    /// Total number of sends, including the first one
+    pub max_attempts: u32,
+    /// Wait this long times the attempt number before resending
+    pub backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Is it worth resending a transaction that failed with this error?
+///
+/// True for transactions that did not land, like expired blockhashes, confirmation
+/// timeouts and network issues. False for transactions that failed in execution or
+/// simulation, resending them would fail again.
+pub fn is_retryable_send_error(err: &anyhow::Error) -> bool {
+    use solana_client::client_error::ClientErrorKind;
+    use solana_client::rpc_request::RpcError;
+    if let Some(MangoClientError::SendTransactionPreflightFailure { err, .. }) =
+        err.downcast_ref::<MangoClientError>()
+    {
+        return matches!(err, Some(TransactionError::BlockhashNotFound));
+    }
+    match err.downcast_ref::<solana_client::client_error::ClientError>() {
+        Some(client_err) => match client_err.kind() {
+            ClientErrorKind::RpcError(RpcError::ForUser(_)) => true,
+            ClientErrorKind::Io(_) | ClientErrorKind::Reqwest(_) => true,
+            _ => false,
+        },
+        None => false,
+    }
+}
+
+/// Did a transaction that was sent and confirmed make it into a block?
+///
+/// None if that can't be determined, like when simulation failed.
+fn transaction_landed(
+    result: &Result<Signature, solana_client::client_error::ClientError>,
+) -> Option<bool> {
+    use solana_client::client_error::ClientErrorKind;
+    use solana_client::rpc_request::RpcError;
+    match result {
+        Ok(_) => Some(true),
+        Err(err) => match err.kind() {
+            ClientErrorKind::TransactionError(_) => Some(true),
+            // send_and_confirm_transaction() returns this when it gives up waiting
+            ClientErrorKind::RpcError(RpcError::ForUser(_)) => Some(false),
+            _ => None,
+        },
+    }
 }
 
 struct Serum3Data<'a> {
@@ -1563,6 +1844,9 @@ impl<'a> TransactionBuilder<'a> {
     pub async fn send(self, client: &Client) -> anyhow::Result<Signature> {
         let rpc = client.rpc_async();
         let tx = self.transaction(&rpc).await?;
+        if let Some(metrics) = client.transaction_metrics.as_ref() {
+            metrics.record_sent();
+        }
         rpc.send_transaction_with_config(&tx, client.rpc_send_transaction_config)
             .await
             .map_err(prettify_solana_client_error)
@@ -1571,10 +1855,21 @@ impl<'a> TransactionBuilder<'a> {
     pub async fn send_and_confirm(self, client: &Client) -> anyhow::Result<Signature> {
         let rpc = client.rpc_async();
         let tx = self.transaction(&rpc).await?;
+        if let Some(metrics) = client.transaction_metrics.as_ref() {
+            metrics.record_sent();
+        }
+        let start = std::time::Instant::now();
         // TODO: Wish we could use client.rpc_send_transaction_config here too!
-        rpc.send_and_confirm_transaction(&tx)
-            .await
-            .map_err(prettify_solana_client_error)
+        let result = rpc.send_and_confirm_transaction(&tx).await;
+        if let Some(metrics) = client.transaction_metrics.as_ref() {
+            metrics.record_confirmation(result.is_ok(), start.elapsed());
+        }
+        if let Some(estimator) = client.priority_fee_estimator.as_ref() {
+            if let Some(landed) = transaction_landed(&result) {
+                estimator.record_landing(landed);
+            }
+        }
+        result.map_err(prettify_solana_client_error)
     }
 }
 
@@ -281,14 +281,24 @@ impl MangoGroupContext {
         account1: &MangoAccountValue,
         account2: &MangoAccountValue,
         writable_banks: &[TokenIndex],
+    ) -> anyhow::Result<Vec<AccountMeta>> {
+        self.derive_health_check_remaining_account_metas_many(&[account2, account1], writable_banks)
+    }
+
+    /// Health accounts covering all of the passed accounts, in the layout
+    /// expected by the ScanningAccountRetriever
+    pub fn derive_health_check_remaining_account_metas_many(
+        &self,
+        accounts: &[&MangoAccountValue],
+        writable_banks: &[TokenIndex],
     ) -> anyhow::Result<Vec<AccountMeta>> {
         // figure out all the banks/oracles that need to be passed for the health check
         let mut banks = vec![];
         let mut oracles = vec![];
 
-        let token_indexes = account2
-            .active_token_positions()
-            .chain(account1.active_token_positions())
+        let token_indexes = accounts
+            .iter()
+            .flat_map(|a| a.active_token_positions())
             .map(|ta| ta.token_index)
             .unique();
 
@@ -299,13 +309,13 @@ impl MangoGroupContext {
             oracles.push(mint_info.oracle);
         }
 
-        let serum_oos = account2
-            .active_serum3_orders()
-            .chain(account1.active_serum3_orders())
+        let serum_oos = accounts
+            .iter()
+            .flat_map(|a| a.active_serum3_orders())
             .map(|&s| s.open_orders);
-        let perp_market_indexes = account2
-            .active_perp_positions()
-            .chain(account1.active_perp_positions())
+        let perp_market_indexes = accounts
+            .iter()
+            .flat_map(|a| a.active_perp_positions())
             .map(|&pa| pa.market_index)
             .unique()
             .collect::<Vec<_>>();
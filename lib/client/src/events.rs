@@ -0,0 +1,953 @@
+//! Decoding of the Anchor events that mango-v4 emits into transaction logs
+
+use anchor_lang::{AnchorDeserialize, Discriminator, Event};
+use anyhow::Context;
+use solana_sdk::pubkey::Pubkey;
+use solana_transaction_status::EncodedConfirmedTransactionWithStatusMeta;
+
+use mango_v4::events::MangoAccountData;
+use mango_v4::logs::*;
+
+const PROGRAM_DATA_PREFIX: &str = "Program data: ";
+
+macro_rules! mango_events {
+    ($($name:ident),* $(,)?) => {
+        /// Any event emitted by the mango-v4 program
+        pub enum MangoEvent {
+            $($name($name),)*
+        }
+
+        impl MangoEvent {
+            /// Names of all event types, like in the IDL
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+
+            /// Decodes event data: the 8 byte Anchor discriminator, followed by the
+            /// borsh serialized event
+            ///
+            /// Returns None if the discriminator belongs to no mango event.
+            pub fn decode(data: &[u8]) -> anyhow::Result<Option<Self>> {
+                if data.len() < 8 {
+                    return Ok(None);
+                }
+                let (discriminator, payload) = data.split_at(8);
+                $(
+                    if discriminator == $name::discriminator() {
+                        let event = $name::try_from_slice(payload)
+                            .with_context(|| format!("decoding {}", stringify!($name)))?;
+                        return Ok(Some(MangoEvent::$name(event)));
+                    }
+                )*
+                Ok(None)
+            }
+
+            pub fn name(&self) -> &'static str {
+                match self {
+                    $(MangoEvent::$name(_) => stringify!($name),)*
+                }
+            }
+
+            /// The event data, the inverse of decode()
+            pub fn data(&self) -> Vec<u8> {
+                match self {
+                    $(MangoEvent::$name(event) => event.data(),)*
+                }
+            }
+        }
+    };
+}
+
+mango_events! {
+    PerpBalanceLog,
+    TokenBalanceLog,
+    TokenBalanceLogV2,
+    FlashLoanLog,
+    FlashLoanSelfLiquidationLog,
+    WithdrawLog,
+    DepositLog,
+    FillLog,
+    FillLogV2,
+    FillLogV3,
+    PerpReferrerRewardLog,
+    PerpUpdateFundingLog,
+    UpdateIndexLog,
+    UpdateRateLog,
+    TokenLiqWithTokenLog,
+    Serum3OpenOrdersBalanceLog,
+    Serum3OpenOrdersBalanceLogV2,
+    Serum3OpenOrdersBalanceLogV3,
+    Serum3SettleFundsPermissionlessLog,
+    WithdrawLoanOriginationFeeLog,
+    TokenLiqBankruptcyLog,
+    DeactivateTokenPositionLog,
+    DeactivatePerpPositionLog,
+    TokenMetaDataLog,
+    PerpMarketMetaDataLog,
+    Serum3RegisterMarketLog,
+    PerpLiqBaseOrPositivePnlLog,
+    PerpLiqBankruptcyLog,
+    PerpAdlLog,
+    PerpLiqNegativePnlOrBankruptcyLog,
+    PerpSettlePnlLog,
+    PerpSettleFeesLog,
+    AccountBuybackFeesWithMngoLog,
+    TokenClaimRewardsLog,
+    PerpIsolatedCollateralLog,
+    PerpLiqIsolatedLog,
+    AccountHealthSnapshotLog,
+    MangoAccountData,
+}
+
+/// Decodes the mango events in the log messages of a transaction, in emission order
+///
+/// Only "Program data:" entries logged while the mango program is executing are
+/// decoded. Data logged by other programs, for example ones that invoke mango
+/// through CPI, is skipped. Data that can't be decoded is logged and skipped, it
+/// doesn't prevent decoding the other events.
+pub fn decode_log_messages(logs: &[String]) -> Vec<MangoEvent> {
+    decode_program_log_messages(logs, &mango_v4::id())
+}
+
+/// Like decode_log_messages(), for a mango program deployed at `program_id`
+pub fn decode_program_log_messages(logs: &[String], program_id: &Pubkey) -> Vec<MangoEvent> {
+    let program_id = program_id.to_string();
+    let mut invoke_stack: Vec<&str> = vec![];
+    let mut events = vec![];
+    for line in logs {
+        if let Some(data) = line.strip_prefix(PROGRAM_DATA_PREFIX) {
+            if invoke_stack.last() != Some(&program_id.as_str()) {
+                continue;
+            }
+            let event = base64::decode(data)
+                .context("base64 decoding")
+                .and_then(|bytes| MangoEvent::decode(&bytes));
+            match event {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => {}
+                Err(e) => log::warn!("skipping log line \"{line}\": {e:?}"),
+            }
+        } else if let Some(rest) = line.strip_prefix("Program ") {
+            let mut words = rest.split_whitespace();
+            let program = words.next().unwrap_or_default();
+            match words.next() {
+                Some("invoke") => invoke_stack.push(program),
+                Some("success") | Some("failed:") => {
+                    invoke_stack.pop();
+                }
+                _ => {}
+            }
+        }
+    }
+    events
+}
+
+/// Decodes the mango events of a transaction returned by the getTransaction rpc call
+///
+/// Failed transactions have no effect, so they produce no events.
+pub fn decode_transaction(tx: &EncodedConfirmedTransactionWithStatusMeta) -> Vec<MangoEvent> {
+    let meta = match &tx.transaction.meta {
+        Some(meta) => meta,
+        None => return vec![],
+    };
+    if meta.err.is_some() {
+        return vec![];
+    }
+    let logs: Option<Vec<String>> = meta.log_messages.clone().into();
+    decode_log_messages(&logs.unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::AnchorSerialize;
+    use fixed::types::I80F48;
+    use mango_v4::accounts_ix::FlashLoanType;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    fn key(n: u8) -> Pubkey {
+        Pubkey::new_from_array([n; 32])
+    }
+
+    fn i80(v: f64) -> i128 {
+        I80F48::from_num(v).to_bits()
+    }
+
+    /// One example of each mango event, as emitted into the logs
+    fn fixtures() -> Vec<(&'static str, Vec<u8>)> {
+        let group = key(1);
+        let account = key(2);
+        let other = key(3);
+        vec![
+            (
+                "PerpBalanceLog",
+                PerpBalanceLog {
+                    mango_group: group,
+                    mango_account: account,
+                    market_index: 2,
+                    base_position: -12,
+                    quote_position: i80(1234.5),
+                    long_settled_funding: i80(0.25),
+                    short_settled_funding: i80(-0.25),
+                    long_funding: i80(0.5),
+                    short_funding: i80(-0.5),
+                }
+                .data(),
+            ),
+            (
+                "TokenBalanceLog",
+                TokenBalanceLog {
+                    mango_group: group,
+                    mango_account: account,
+                    token_index: 3,
+                    indexed_position: i80(-100.0),
+                    deposit_index: i80(1.01),
+                    borrow_index: i80(1.02),
+                }
+                .data(),
+            ),
+            (
+                "TokenBalanceLogV2",
+                TokenBalanceLogV2 {
+                    mango_group: group,
+                    mango_account: account,
+                    token_index: 3,
+                    indexed_position: i80(-100.0),
+                    deposit_index: i80(1.01),
+                    borrow_index: i80(1.02),
+                    cumulative_deposit_interest: 1.5,
+                    cumulative_borrow_interest: 2.5,
+                    cumulative_deposits: 200.0,
+                    cumulative_withdraws: 300.0,
+                }
+                .data(),
+            ),
+            (
+                "FlashLoanLog",
+                FlashLoanLog {
+                    mango_group: group,
+                    mango_account: account,
+                    token_loan_details: vec![FlashLoanTokenDetail {
+                        token_index: 0,
+                        change_amount: i80(-10.0),
+                        loan: i80(100.0),
+                        loan_origination_fee: i80(0.05),
+                        deposit_index: i80(1.01),
+                        borrow_index: i80(1.02),
+                        price: i80(1.0),
+                    }],
+                    flash_loan_type: FlashLoanType::Swap,
+                }
+                .data(),
+            ),
+            (
+                "FlashLoanSelfLiquidationLog",
+                FlashLoanSelfLiquidationLog {
+                    mango_group: group,
+                    mango_account: account,
+                    asset_token_index: 1,
+                    liab_token_index: 0,
+                    liab_repaid: i80(50.0),
+                    fee: i80(0.5),
+                    liq_end_health_before: i80(-20.0),
+                    liq_end_health_after: i80(-5.0),
+                }
+                .data(),
+            ),
+            (
+                "WithdrawLog",
+                WithdrawLog {
+                    mango_group: group,
+                    mango_account: account,
+                    signer: other,
+                    token_index: 1,
+                    quantity: 1_000_000,
+                    price: i80(21.5),
+                }
+                .data(),
+            ),
+            (
+                "DepositLog",
+                DepositLog {
+                    mango_group: group,
+                    mango_account: account,
+                    signer: other,
+                    token_index: 1,
+                    quantity: 2_000_000,
+                    price: i80(21.5),
+                }
+                .data(),
+            ),
+            (
+                "FillLog",
+                FillLog {
+                    mango_group: group,
+                    market_index: 0,
+                    taker_side: 1,
+                    maker_slot: 3,
+                    maker_out: true,
+                    timestamp: 1_680_000_000,
+                    seq_num: 77,
+                    maker: account,
+                    maker_order_id: 1 << 70,
+                    maker_fee: i80(-0.0001),
+                    maker_timestamp: 1_679_999_000,
+                    taker: other,
+                    taker_order_id: 1 << 80,
+                    taker_client_order_id: 5,
+                    taker_fee: i80(0.0004),
+                    price: 2500,
+                    quantity: 3,
+                }
+                .data(),
+            ),
+            (
+                "FillLogV2",
+                FillLogV2 {
+                    mango_group: group,
+                    market_index: 0,
+                    taker_side: 0,
+                    maker_slot: 1,
+                    maker_out: false,
+                    timestamp: 1_680_000_000,
+                    seq_num: 78,
+                    maker: account,
+                    maker_client_order_id: 9,
+                    maker_fee: -0.0001,
+                    maker_timestamp: 1_679_999_000,
+                    taker: other,
+                    taker_client_order_id: 10,
+                    taker_fee: 0.0004,
+                    price: 2501,
+                    quantity: 4,
+                }
+                .data(),
+            ),
+            (
+                "FillLogV3",
+                FillLogV3 {
+                    mango_group: group,
+                    market_index: 0,
+                    taker_side: 1,
+                    maker_slot: 2,
+                    maker_out: true,
+                    timestamp: 1_680_000_100,
+                    seq_num: 79,
+                    maker: account,
+                    maker_client_order_id: 11,
+                    maker_fee: -0.0001,
+                    maker_timestamp: 1_680_000_000,
+                    taker: other,
+                    taker_client_order_id: 12,
+                    taker_fee: 0.0004,
+                    price: 2510,
+                    quantity: 3,
+                    maker_realized_pnl: i80(27.0),
+                    maker_entry_price_per_base_lot: 2501.0,
+                    taker_realized_pnl: i80(-4.5),
+                    taker_entry_price_per_base_lot: 2510.0,
+                }
+                .data(),
+            ),
+            (
+                "PerpReferrerRewardLog",
+                PerpReferrerRewardLog {
+                    mango_group: group,
+                    market_index: 0,
+                    referrer: other,
+                    taker: account,
+                    seq_num: 78,
+                    reward: i80(0.12),
+                }
+                .data(),
+            ),
+            (
+                "PerpUpdateFundingLog",
+                PerpUpdateFundingLog {
+                    mango_group: group,
+                    market_index: 1,
+                    long_funding: i80(10.5),
+                    short_funding: i80(10.25),
+                    price: i80(0.02),
+                    stable_price: i80(0.021),
+                    fees_accrued: i80(300.0),
+                    open_interest: 1000,
+                    instantaneous_funding_rate: i80(0.001),
+                }
+                .data(),
+            ),
+            (
+                "UpdateIndexLog",
+                UpdateIndexLog {
+                    mango_group: group,
+                    token_index: 0,
+                    deposit_index: i80(1.0001),
+                    borrow_index: i80(1.0002),
+                    avg_utilization: i80(0.5),
+                    price: i80(1.0),
+                    stable_price: i80(1.0),
+                    collected_fees: i80(12.0),
+                    loan_fee_rate: i80(0.0005),
+                    total_borrows: i80(5000.0),
+                    total_deposits: i80(10000.0),
+                    borrow_rate: i80(0.1),
+                    deposit_rate: i80(0.05),
+                }
+                .data(),
+            ),
+            (
+                "UpdateRateLog",
+                UpdateRateLog {
+                    mango_group: group,
+                    token_index: 0,
+                    rate0: i80(0.07),
+                    rate1: i80(0.9),
+                    max_rate: i80(1.5),
+                }
+                .data(),
+            ),
+            (
+                "TokenLiqWithTokenLog",
+                TokenLiqWithTokenLog {
+                    mango_group: group,
+                    liqee: account,
+                    liqor: other,
+                    asset_token_index: 1,
+                    liab_token_index: 0,
+                    asset_transfer: i80(3.0),
+                    liab_transfer: i80(60.0),
+                    asset_price: i80(21.0),
+                    liab_price: i80(1.0),
+                    bankruptcy: false,
+                }
+                .data(),
+            ),
+            (
+                "Serum3OpenOrdersBalanceLog",
+                Serum3OpenOrdersBalanceLog {
+                    mango_group: group,
+                    mango_account: account,
+                    base_token_index: 1,
+                    quote_token_index: 0,
+                    base_total: 100,
+                    base_free: 50,
+                    quote_total: 200,
+                    quote_free: 150,
+                    referrer_rebates_accrued: 3,
+                }
+                .data(),
+            ),
+            (
+                "Serum3OpenOrdersBalanceLogV2",
+                Serum3OpenOrdersBalanceLogV2 {
+                    mango_group: group,
+                    mango_account: account,
+                    market_index: 4,
+                    base_token_index: 1,
+                    quote_token_index: 0,
+                    base_total: 100,
+                    base_free: 50,
+                    quote_total: 200,
+                    quote_free: 150,
+                    referrer_rebates_accrued: 3,
+                }
+                .data(),
+            ),
+            (
+                "Serum3OpenOrdersBalanceLogV3",
+                Serum3OpenOrdersBalanceLogV3 {
+                    mango_group: group,
+                    mango_account: account,
+                    market_index: 4,
+                    base_token_index: 1,
+                    quote_token_index: 0,
+                    base_total: 100,
+                    base_free: 50,
+                    quote_total: 200,
+                    quote_free: 150,
+                    referrer_rebates_accrued: 3,
+                    base_bought: 10,
+                    quote_paid: 20,
+                    base_sold: 30,
+                    quote_received: 40,
+                    estimated_net_fees: -1,
+                    referrer_rebates_settled: 2,
+                }
+                .data(),
+            ),
+            (
+                "Serum3SettleFundsPermissionlessLog",
+                Serum3SettleFundsPermissionlessLog {
+                    mango_group: group,
+                    mango_account: account,
+                    market_index: 4,
+                    settler: other,
+                    settled_base: 50,
+                    settled_quote: 150,
+                    fee: i80(0.01),
+                }
+                .data(),
+            ),
+            (
+                "WithdrawLoanOriginationFeeLog",
+                WithdrawLoanOriginationFeeLog {
+                    mango_group: group,
+                    mango_account: account,
+                    token_index: 0,
+                    loan_origination_fee: i80(0.3),
+                    instruction: LoanOriginationFeeInstruction::TokenWithdraw,
+                }
+                .data(),
+            ),
+            (
+                "TokenLiqBankruptcyLog",
+                TokenLiqBankruptcyLog {
+                    mango_group: group,
+                    liqee: account,
+                    liqor: other,
+                    liab_token_index: 1,
+                    initial_liab_native: i80(400.0),
+                    liab_price: i80(21.0),
+                    insurance_token_index: 0,
+                    insurance_transfer: i80(100.0),
+                    socialized_loss: i80(300.0),
+                    starting_liab_deposit_index: i80(1.1),
+                    ending_liab_deposit_index: i80(1.05),
+                }
+                .data(),
+            ),
+            (
+                "DeactivateTokenPositionLog",
+                DeactivateTokenPositionLog {
+                    mango_group: group,
+                    mango_account: account,
+                    token_index: 2,
+                    cumulative_deposit_interest: 1.5,
+                    cumulative_borrow_interest: 0.25,
+                }
+                .data(),
+            ),
+            (
+                "DeactivatePerpPositionLog",
+                DeactivatePerpPositionLog {
+                    mango_group: group,
+                    mango_account: account,
+                    market_index: 1,
+                    cumulative_long_funding: 2.5,
+                    cumulative_short_funding: -1.5,
+                    maker_volume: 1000,
+                    taker_volume: 2000,
+                    perp_spot_transfers: -30,
+                }
+                .data(),
+            ),
+            (
+                "TokenMetaDataLog",
+                TokenMetaDataLog {
+                    mango_group: group,
+                    mint: key(4),
+                    token_index: 5,
+                    mint_decimals: 6,
+                    oracle: key(5),
+                    mint_info: key(6),
+                }
+                .data(),
+            ),
+            (
+                "PerpMarketMetaDataLog",
+                PerpMarketMetaDataLog {
+                    mango_group: group,
+                    perp_market: key(7),
+                    perp_market_index: 3,
+                    base_decimals: 9,
+                    base_lot_size: 10_000_000,
+                    quote_lot_size: 100,
+                    oracle: key(5),
+                }
+                .data(),
+            ),
+            (
+                "Serum3RegisterMarketLog",
+                Serum3RegisterMarketLog {
+                    mango_group: group,
+                    serum_market: key(8),
+                    market_index: 4,
+                    base_token_index: 1,
+                    quote_token_index: 0,
+                    serum_program: key(9),
+                    serum_program_external: key(10),
+                }
+                .data(),
+            ),
+            (
+                "PerpLiqBaseOrPositivePnlLog",
+                PerpLiqBaseOrPositivePnlLog {
+                    mango_group: group,
+                    perp_market_index: 1,
+                    liqor: other,
+                    liqee: account,
+                    base_transfer: 7,
+                    quote_transfer: i80(-650.0),
+                    pnl_transfer: i80(20.0),
+                    pnl_settle_limit_transfer: i80(15.0),
+                    price: i80(95.0),
+                }
+                .data(),
+            ),
+            (
+                "PerpLiqBankruptcyLog",
+                PerpLiqBankruptcyLog {
+                    mango_group: group,
+                    liqee: account,
+                    liqor: other,
+                    perp_market_index: 1,
+                    insurance_transfer: i80(40.0),
+                    socialized_loss: i80(60.0),
+                    starting_long_funding: i80(1.0),
+                    starting_short_funding: i80(1.0),
+                    ending_long_funding: i80(1.5),
+                    ending_short_funding: i80(1.5),
+                }
+                .data(),
+            ),
+            (
+                "PerpAdlLog",
+                PerpAdlLog {
+                    mango_group: group,
+                    liqee: account,
+                    counterparty: other,
+                    perp_market_index: 1,
+                    base_transfer_liqee: -3,
+                    quote_transfer_liqee: i80(315.0),
+                    bankruptcy_price: i80(105.0),
+                    adl_score: i80(0.75),
+                    price: i80(95.0),
+                }
+                .data(),
+            ),
+            (
+                "PerpLiqNegativePnlOrBankruptcyLog",
+                PerpLiqNegativePnlOrBankruptcyLog {
+                    mango_group: group,
+                    liqee: account,
+                    liqor: other,
+                    perp_market_index: 1,
+                    settlement: i80(80.0),
+                }
+                .data(),
+            ),
+            (
+                "PerpSettlePnlLog",
+                PerpSettlePnlLog {
+                    mango_group: group,
+                    mango_account_a: account,
+                    mango_account_b: other,
+                    perp_market_index: 1,
+                    settlement: i80(33.0),
+                    settler: key(11),
+                    fee: i80(0.1),
+                }
+                .data(),
+            ),
+            (
+                "PerpSettleFeesLog",
+                PerpSettleFeesLog {
+                    mango_group: group,
+                    mango_account: account,
+                    perp_market_index: 1,
+                    settlement: i80(4.0),
+                }
+                .data(),
+            ),
+            (
+                "AccountBuybackFeesWithMngoLog",
+                AccountBuybackFeesWithMngoLog {
+                    mango_group: group,
+                    mango_account: account,
+                    buyback_fees: i80(100.0),
+                    buyback_mngo: i80(4000.0),
+                    mngo_buyback_price: i80(0.0225),
+                    oracle_price: i80(0.025),
+                }
+                .data(),
+            ),
+            (
+                "TokenClaimRewardsLog",
+                TokenClaimRewardsLog {
+                    mango_group: group,
+                    mango_account: account,
+                    token_index: 2,
+                    rewards_mint: key(12),
+                    amount: 5000,
+                }
+                .data(),
+            ),
+            (
+                "PerpIsolatedCollateralLog",
+                PerpIsolatedCollateralLog {
+                    mango_group: group,
+                    mango_account: account,
+                    perp_market_index: 1,
+                    token_index: 0,
+                    change: i80(-50.0),
+                    indexed_collateral: i80(449.9),
+                    deposit_index: i80(1.0002),
+                }
+                .data(),
+            ),
+            (
+                "PerpLiqIsolatedLog",
+                PerpLiqIsolatedLog {
+                    mango_group: group,
+                    perp_market_index: 1,
+                    liqor: other,
+                    liqee: account,
+                    base_transfer: 5,
+                    quote_transfer: i80(-285.0),
+                    collateral_transfer: i80(450.0),
+                    bankruptcy_loss: i80(217.5),
+                    price: i80(0.6),
+                }
+                .data(),
+            ),
+            (
+                "AccountHealthSnapshotLog",
+                AccountHealthSnapshotLog {
+                    mango_group: group,
+                    mango_account: account,
+                    init_health: i80(100.0),
+                    maint_health: i80(150.0),
+                    liquidation_end_health: i80(100.0),
+                    equity: i80(1000.0),
+                    being_liquidated: false,
+                }
+                .data(),
+            ),
+            (
+                "MangoAccountData",
+                // an account without positions: empty health cache and equity
+                [
+                    MangoAccountData::discriminator().to_vec(),
+                    (0u32, 0u32, 0u32, 0u32, false).try_to_vec().unwrap(),
+                    (I80F48::from_num(7), I80F48::from_num(8))
+                        .try_to_vec()
+                        .unwrap(),
+                    (0u32, 0u32).try_to_vec().unwrap(),
+                ]
+                .concat(),
+            ),
+        ]
+    }
+
+    fn program_data_line(data: &[u8]) -> String {
+        format!("{}{}", PROGRAM_DATA_PREFIX, base64::encode(data))
+    }
+
+    #[test]
+    fn test_fixtures_cover_all_events() {
+        let fixture_names = fixtures()
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<HashSet<_>>();
+        let event_names = MangoEvent::NAMES.iter().copied().collect::<HashSet<_>>();
+        assert_eq!(fixture_names, event_names);
+        assert_eq!(fixture_names.len(), MangoEvent::NAMES.len());
+    }
+
+    #[test]
+    fn test_decode_all_events() {
+        for (name, data) in fixtures() {
+            let event = MangoEvent::decode(&data).unwrap().unwrap();
+            assert_eq!(event.name(), name);
+            assert_eq!(event.data(), data, "{name}");
+
+            let logs = vec![
+                format!("Program {} invoke [1]", mango_v4::id()),
+                "Program log: Instruction: Whatever".to_string(),
+                program_data_line(&data),
+                format!("Program {} success", mango_v4::id()),
+            ];
+            let events = decode_log_messages(&logs).unwrap();
+            assert_eq!(events.len(), 1, "{name}");
+            assert_eq!(events[0].name(), name);
+            assert_eq!(events[0].data(), data, "{name}");
+
+            // truncated data is an error, not a silently skipped event
+            assert!(
+                MangoEvent::decode(&data[..data.len() - 1]).is_err(),
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_event_fields() {
+        let fixtures = fixtures();
+        let data = |name| &fixtures.iter().find(|(n, _)| *n == name).unwrap().1;
+
+        match MangoEvent::decode(data("FillLogV2")).unwrap().unwrap() {
+            MangoEvent::FillLogV2(fill) => {
+                assert_eq!(fill.maker, key(2));
+                assert_eq!(fill.taker, key(3));
+                assert_eq!(fill.seq_num, 78);
+                assert_eq!(fill.maker_fee, -0.0001);
+                assert_eq!(fill.price, 2501);
+                assert_eq!(fill.quantity, 4);
+            }
+            _ => panic!("wrong event type"),
+        }
+        match MangoEvent::decode(data("FillLogV3")).unwrap().unwrap() {
+            MangoEvent::FillLogV3(fill) => {
+                assert_eq!(fill.seq_num, 79);
+                assert_eq!(
+                    I80F48::from_bits(fill.maker_realized_pnl),
+                    I80F48::from_num(27.0)
+                );
+                assert_eq!(fill.maker_entry_price_per_base_lot, 2501.0);
+                assert_eq!(
+                    I80F48::from_bits(fill.taker_realized_pnl),
+                    I80F48::from_num(-4.5)
+                );
+            }
+            _ => panic!("wrong event type"),
+        }
+        match MangoEvent::decode(data("TokenBalanceLog"))
+            .unwrap()
+            .unwrap()
+        {
+            MangoEvent::TokenBalanceLog(balance) => {
+                assert_eq!(balance.token_index, 3);
+                assert_eq!(
+                    I80F48::from_bits(balance.indexed_position),
+                    I80F48::from_num(-100.0)
+                );
+            }
+            _ => panic!("wrong event type"),
+        }
+        match MangoEvent::decode(data("TokenBalanceLogV2"))
+            .unwrap()
+            .unwrap()
+        {
+            MangoEvent::TokenBalanceLogV2(balance) => {
+                assert_eq!(balance.token_index, 3);
+                assert_eq!(balance.cumulative_withdraws, 300.0);
+            }
+            _ => panic!("wrong event type"),
+        }
+        match MangoEvent::decode(data("FlashLoanLog")).unwrap().unwrap() {
+            MangoEvent::FlashLoanLog(flash_loan) => {
+                assert_eq!(flash_loan.flash_loan_type, FlashLoanType::Swap);
+                assert_eq!(flash_loan.token_loan_details.len(), 1);
+            }
+            _ => panic!("wrong event type"),
+        }
+        match MangoEvent::decode(data("MangoAccountData"))
+            .unwrap()
+            .unwrap()
+        {
+            MangoEvent::MangoAccountData(account_data) => {
+                assert_eq!(account_data.init_health, I80F48::from_num(7));
+                assert_eq!(account_data.maint_health, I80F48::from_num(8));
+                assert!(account_data.equity.tokens.is_empty());
+            }
+            _ => panic!("wrong event type"),
+        }
+    }
+
+    #[test]
+    fn test_decode_log_messages() {
+        let fixtures = fixtures();
+        let data = |name| &fixtures.iter().find(|(n, _)| *n == name).unwrap().1;
+        let mango = mango_v4::id();
+        let other_program = key(99);
+
+        let logs = vec![
+            "Program ComputeBudget111111111111111111111111111111 invoke [1]".to_string(),
+            "Program ComputeBudget111111111111111111111111111111 success".to_string(),
+            format!("Program {other_program} invoke [1]"),
+            // a mango event layout emitted by another program doesn't count
+            program_data_line(data("DepositLog")),
+            format!("Program {mango} invoke [2]"),
+            "Program log: Instruction: TokenDeposit".to_string(),
+            program_data_line(data("TokenBalanceLog")),
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA invoke [3]".to_string(),
+            // non-mango data while mango invokes the token program
+            program_data_line(&[1, 2, 3, 4, 5, 6, 7, 8, 9]),
+            "Program TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA success".to_string(),
+            program_data_line(data("DepositLog")),
+            format!("Program {mango} consumed 30000 of 190000 compute units"),
+            format!("Program {mango} success"),
+            format!("Program {other_program} success"),
+            format!("Program {mango} invoke [1]"),
+            "Program log: Instruction: PerpConsumeEvents".to_string(),
+            program_data_line(data("FillLogV2")),
+            // unknown discriminators are skipped
+            program_data_line(&[0; 16]),
+            program_data_line(data("PerpBalanceLog")),
+            format!("Program {mango} failed: custom program error: 0x1771"),
+        ];
+        let names = decode_log_messages(&logs)
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                "TokenBalanceLog",
+                "DepositLog",
+                "FillLogV2",
+                "PerpBalanceLog"
+            ]
+        );
+
+        // the events are found for other program ids only when asked
+        assert!(decode_program_log_messages(&logs, &other_program)
+            .iter()
+            .all(|e| e.name() == "DepositLog"));
+
+        // broken base64 and truncated events are skipped, the other events still decode
+        let truncated = data("PerpBalanceLog");
+        let logs = vec![
+            format!("Program {mango} invoke [1]"),
+            format!("{}not base64!", PROGRAM_DATA_PREFIX),
+            program_data_line(&truncated[..truncated.len() - 1]),
+            program_data_line(data("DepositLog")),
+            format!("Program {mango} success"),
+        ];
+        let names = decode_log_messages(&logs)
+            .iter()
+            .map(|e| e.name())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["DepositLog"]);
+    }
+
+    #[test]
+    fn test_decode_golden_log_messages() {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push("resources/test/program_data_logs.txt");
+        let logs = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        let program_data_lines = logs
+            .iter()
+            .filter(|l| l.starts_with(PROGRAM_DATA_PREFIX))
+            .count();
+
+        let events = decode_log_messages(&logs);
+        assert_eq!(events.len(), program_data_lines);
+
+        // every event is covered and still decodes to the same data as its fixture
+        let fixtures = fixtures();
+        let names = events.iter().map(|e| e.name()).collect::<HashSet<_>>();
+        assert_eq!(names.len(), events.len());
+        assert_eq!(
+            names,
+            MangoEvent::NAMES.iter().copied().collect::<HashSet<_>>()
+        );
+        for event in events.iter() {
+            let (_, data) = fixtures.iter().find(|(n, _)| *n == event.name()).unwrap();
+            assert_eq!(&event.data(), data, "{}", event.name());
+        }
+    }
+}
@@ -9,10 +9,13 @@ pub mod chain_data;
 mod chain_data_fetcher;
 mod client;
 mod context;
+pub mod events;
 mod gpa;
 pub mod health_cache;
 mod jupiter;
+pub mod metrics;
 pub mod perp_pnl;
+pub mod priority_fees;
 pub mod snapshot_source;
 mod util;
 pub mod websocket_source;
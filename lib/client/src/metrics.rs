@@ -0,0 +1,487 @@
+use {
+    solana_client::nonblocking::rpc_client::RpcClient as RpcClientAsync,
+    std::collections::HashMap,
+    std::fmt::Write as _,
+    std::net::SocketAddr,
+    std::sync::{atomic, Arc, Mutex, RwLock},
+    std::time::Duration,
+    tokio::io::{AsyncReadExt, AsyncWriteExt},
+    tokio::time,
+};
+
+use crate::chain_data;
+
+#[derive(Debug)]
+enum Value {
+    Counter(Arc<atomic::AtomicU64>),
+    U64(Arc<atomic::AtomicU64>),
+    I64(Arc<atomic::AtomicI64>),
+    String(Arc<Mutex<String>>),
+    Histogram(Arc<Mutex<Histogram>>),
+}
+
+#[derive(Debug)]
+enum PrevValue {
+    U64(u64),
+    I64(i64),
+    String(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricU64 {
+    value: Arc<atomic::AtomicU64>,
+}
+impl MetricU64 {
+    pub fn value(&self) -> u64 {
+        self.value.load(atomic::Ordering::Acquire)
+    }
+
+    pub fn set(&mut self, value: u64) {
+        self.value.store(value, atomic::Ordering::Release);
+    }
+
+    pub fn set_max(&mut self, value: u64) {
+        self.value.fetch_max(value, atomic::Ordering::AcqRel);
+    }
+
+    pub fn add(&mut self, value: u64) {
+        self.value.fetch_add(value, atomic::Ordering::AcqRel);
+    }
+
+    pub fn increment(&mut self) {
+        self.value.fetch_add(1, atomic::Ordering::AcqRel);
+    }
+
+    pub fn decrement(&mut self) {
+        self.value.fetch_sub(1, atomic::Ordering::AcqRel);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricI64 {
+    value: Arc<atomic::AtomicI64>,
+}
+impl MetricI64 {
+    pub fn set(&mut self, value: i64) {
+        self.value.store(value, atomic::Ordering::Release);
+    }
+
+    pub fn add(&mut self, value: i64) {
+        self.value.fetch_add(value, atomic::Ordering::AcqRel);
+    }
+
+    pub fn increment(&mut self) {
+        self.value.fetch_add(1, atomic::Ordering::AcqRel);
+    }
+
+    pub fn decrement(&mut self) {
+        self.value.fetch_sub(1, atomic::Ordering::AcqRel);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricString {
+    value: Arc<Mutex<String>>,
+}
+
+impl MetricString {
+    pub fn set(&self, value: String) {
+        *self.value.lock().unwrap() = value;
+    }
+}
+
+#[derive(Debug)]
+struct Histogram {
+    /// Upper bounds of the buckets, ascending
+    bounds: Vec<f64>,
+    /// Number of observations in each bucket (not cumulative)
+    counts: Vec<u64>,
+    sum: f64,
+    count: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct MetricHistogram {
+    value: Arc<Mutex<Histogram>>,
+}
+
+impl MetricHistogram {
+    pub fn observe(&self, value: f64) {
+        let mut histogram = self.value.lock().unwrap();
+        if let Some(bucket) = histogram.bounds.iter().position(|bound| value <= *bound) {
+            histogram.counts[bucket] += 1;
+        }
+        histogram.sum += value;
+        histogram.count += 1;
+    }
+
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64());
+    }
+}
+
+/// Bucket bounds in seconds that work well for transaction and rpc latencies
+pub const LATENCY_BUCKETS: &[f64] = &[0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Clone, Debug)]
+pub struct Metrics {
+    registry: Arc<RwLock<HashMap<String, Value>>>,
+}
+
+impl Metrics {
+    /// A monotonically increasing value
+    pub fn register_counter(&self, name: String) -> MetricU64 {
+        let mut registry = self.registry.write().unwrap();
+        let value = registry
+            .entry(name)
+            .or_insert_with(|| Value::Counter(Arc::new(atomic::AtomicU64::new(0))));
+        MetricU64 {
+            value: match value {
+                Value::Counter(v) => v.clone(),
+                _ => panic!("bad metric type"),
+            },
+        }
+    }
+
+    /// A value that may go up and down
+    pub fn register_u64(&self, name: String) -> MetricU64 {
+        let mut registry = self.registry.write().unwrap();
+        let value = registry
+            .entry(name)
+            .or_insert_with(|| Value::U64(Arc::new(atomic::AtomicU64::new(0))));
+        MetricU64 {
+            value: match value {
+                Value::U64(v) => v.clone(),
+                _ => panic!("bad metric type"),
+            },
+        }
+    }
+
+    pub fn register_i64(&self, name: String) -> MetricI64 {
+        let mut registry = self.registry.write().unwrap();
+        let value = registry
+            .entry(name)
+            .or_insert_with(|| Value::I64(Arc::new(atomic::AtomicI64::new(0))));
+        MetricI64 {
+            value: match value {
+                Value::I64(v) => v.clone(),
+                _ => panic!("bad metric type"),
+            },
+        }
+    }
+
+    pub fn register_string(&self, name: String) -> MetricString {
+        let mut registry = self.registry.write().unwrap();
+        let value = registry
+            .entry(name)
+            .or_insert_with(|| Value::String(Arc::new(Mutex::new(String::new()))));
+        MetricString {
+            value: match value {
+                Value::String(v) => v.clone(),
+                _ => panic!("bad metric type"),
+            },
+        }
+    }
+
+    /// Histogram with the given ascending bucket upper bounds
+    pub fn register_histogram(&self, name: String, bounds: &[f64]) -> MetricHistogram {
+        let mut registry = self.registry.write().unwrap();
+        let value = registry.entry(name).or_insert_with(|| {
+            Value::Histogram(Arc::new(Mutex::new(Histogram {
+                bounds: bounds.to_vec(),
+                counts: vec![0; bounds.len()],
+                sum: 0.0,
+                count: 0,
+            })))
+        });
+        MetricHistogram {
+            value: match value {
+                Value::Histogram(v) => v.clone(),
+                _ => panic!("bad metric type"),
+            },
+        }
+    }
+
+    /// Render all metrics in the Prometheus text exposition format
+    ///
+    /// String metrics are exposed as an info-style gauge with a `value` label.
+    pub fn prometheus_text(&self, namespace: &str) -> String {
+        let mut out = String::new();
+
+        // Nested locking! Safe because the only other user locks registry for writing and doesn't
+        // acquire any interior locks.
+        let metrics = self.registry.read().unwrap();
+        let mut names = metrics.keys().collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            let full_name = prometheus_name(namespace, name);
+            match &metrics[name] {
+                Value::Counter(v) => {
+                    let _ = writeln!(out, "# TYPE {full_name} counter");
+                    let _ = writeln!(out, "{full_name} {}", v.load(atomic::Ordering::Acquire));
+                }
+                Value::U64(v) => {
+                    let _ = writeln!(out, "# TYPE {full_name} gauge");
+                    let _ = writeln!(out, "{full_name} {}", v.load(atomic::Ordering::Acquire));
+                }
+                Value::I64(v) => {
+                    let _ = writeln!(out, "# TYPE {full_name} gauge");
+                    let _ = writeln!(out, "{full_name} {}", v.load(atomic::Ordering::Acquire));
+                }
+                Value::String(v) => {
+                    let value = v.lock().unwrap().replace('\\', "\\\\").replace('"', "\\\"");
+                    let _ = writeln!(out, "# TYPE {full_name} gauge");
+                    let _ = writeln!(out, "{full_name}{{value=\"{value}\"}} 1");
+                }
+                Value::Histogram(v) => {
+                    let histogram = v.lock().unwrap();
+                    let _ = writeln!(out, "# TYPE {full_name} histogram");
+                    let mut cumulative = 0;
+                    for (bound, count) in histogram.bounds.iter().zip(histogram.counts.iter()) {
+                        cumulative += count;
+                        let _ = writeln!(out, "{full_name}_bucket{{le=\"{bound}\"}} {cumulative}");
+                    }
+                    let _ = writeln!(out, "{full_name}_bucket{{le=\"+Inf\"}} {}", histogram.count);
+                    let _ = writeln!(out, "{full_name}_sum {}", histogram.sum);
+                    let _ = writeln!(out, "{full_name}_count {}", histogram.count);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn prometheus_name(namespace: &str, name: &str) -> String {
+    let name = if namespace.is_empty() {
+        name.to_string()
+    } else {
+        format!("{namespace}_{name}")
+    };
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+pub struct MetricsConfig {
+    /// Prefix for all metric names in the Prometheus output, like "liquidator"
+    pub namespace: String,
+    /// How often to write all metrics to the log
+    pub output_interval: Duration,
+    /// If set, serve the metrics in Prometheus format on http://<address>/metrics
+    pub prometheus_address: Option<SocketAddr>,
+}
+
+pub fn start(config: MetricsConfig) -> Metrics {
+    let mut write_interval = time::interval(config.output_interval);
+
+    let registry = Arc::new(RwLock::new(HashMap::<String, Value>::new()));
+    let registry_c = Arc::clone(&registry);
+
+    tokio::spawn(async move {
+        let mut previous_values = HashMap::<String, PrevValue>::new();
+        loop {
+            write_interval.tick().await;
+
+            // Nested locking! Safe because the only other user locks registry for writing and doesn't
+            // acquire any interior locks.
+            let metrics = registry_c.read().unwrap();
+            for (name, value) in metrics.iter() {
+                let previous_value = previous_values.get_mut(name);
+                match value {
+                    Value::Counter(v) | Value::U64(v) => {
+                        let new_value = v.load(atomic::Ordering::Acquire);
+                        let previous_value = if let Some(PrevValue::U64(v)) = previous_value {
+                            let prev = *v;
+                            *v = new_value;
+                            prev
+                        } else {
+                            previous_values.insert(name.clone(), PrevValue::U64(new_value));
+                            0
+                        };
+                        let diff = new_value.wrapping_sub(previous_value) as i64;
+                        log::info!("metric: {}: {} ({:+})", name, new_value, diff);
+                    }
+                    Value::I64(v) => {
+                        let new_value = v.load(atomic::Ordering::Acquire);
+                        let previous_value = if let Some(PrevValue::I64(v)) = previous_value {
+                            let prev = *v;
+                            *v = new_value;
+                            prev
+                        } else {
+                            previous_values.insert(name.clone(), PrevValue::I64(new_value));
+                            0
+                        };
+                        let diff = new_value - previous_value;
+                        log::info!("metric: {}: {} ({:+})", name, new_value, diff);
+                    }
+                    Value::String(v) => {
+                        let new_value = v.lock().unwrap();
+                        let previous_value = if let Some(PrevValue::String(v)) = previous_value {
+                            let mut prev = new_value.clone();
+                            std::mem::swap(&mut prev, v);
+                            prev
+                        } else {
+                            previous_values
+                                .insert(name.clone(), PrevValue::String(new_value.clone()));
+                            "".into()
+                        };
+                        if *new_value == previous_value {
+                            log::info!("metric: {}: {} (unchanged)", name, &*new_value);
+                        } else {
+                            log::info!(
+                                "metric: {}: {} (before: {})",
+                                name,
+                                &*new_value,
+                                previous_value
+                            );
+                        }
+                    }
+                    Value::Histogram(v) => {
+                        let histogram = v.lock().unwrap();
+                        let mean = if histogram.count > 0 {
+                            histogram.sum / histogram.count as f64
+                        } else {
+                            0.0
+                        };
+                        log::info!(
+                            "metric: {}: count {} mean {:.3}",
+                            name,
+                            histogram.count,
+                            mean
+                        );
+                    }
+                }
+            }
+        }
+    });
+
+    let metrics = Metrics { registry };
+
+    if let Some(address) = config.prometheus_address {
+        start_prometheus_server(address, config.namespace, metrics.clone());
+    }
+
+    metrics
+}
+
+/// Minimal http server that answers GET /metrics with the Prometheus text output
+fn start_prometheus_server(address: SocketAddr, namespace: String, metrics: Metrics) {
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(err) => {
+                log::error!("could not bind prometheus metrics endpoint {address}: {err:?}");
+                return;
+            }
+        };
+        log::info!("serving prometheus metrics on http://{address}/metrics");
+        loop {
+            let (mut stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    log::warn!("prometheus metrics endpoint accept error: {err:?}");
+                    continue;
+                }
+            };
+            let metrics = metrics.clone();
+            let namespace = namespace.clone();
+            tokio::spawn(async move {
+                // Only the request line matters, ignore headers and bodies
+                let mut buffer = [0u8; 1024];
+                let len = match stream.read(&mut buffer).await {
+                    Ok(len) => len,
+                    Err(_) => return,
+                };
+                let request = String::from_utf8_lossy(&buffer[..len]);
+                let response = if request.starts_with("GET /metrics ") {
+                    let body = metrics.prometheus_text(&namespace);
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                } else {
+                    "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                        .to_string()
+                };
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+}
+
+/// Metrics about sent transactions, shared by all sends of a Client
+#[derive(Clone, Debug)]
+pub struct TransactionMetrics {
+    sent: MetricU64,
+    confirmed: MetricU64,
+    failed: MetricU64,
+    confirmation_latency: MetricHistogram,
+}
+
+impl TransactionMetrics {
+    pub fn new(metrics: &Metrics) -> Self {
+        Self {
+            sent: metrics.register_counter("tx_sent".into()),
+            confirmed: metrics.register_counter("tx_confirmed".into()),
+            failed: metrics.register_counter("tx_failed".into()),
+            confirmation_latency: metrics
+                .register_histogram("tx_confirmation_latency_seconds".into(), LATENCY_BUCKETS),
+        }
+    }
+
+    pub fn record_sent(&self) {
+        self.sent.clone().increment();
+    }
+
+    pub fn record_confirmation(&self, success: bool, latency: Duration) {
+        if success {
+            self.confirmed.clone().increment();
+            self.confirmation_latency.observe_duration(latency);
+        } else {
+            self.failed.clone().increment();
+        }
+    }
+}
+
+/// Periodically record the size of the chain_data and how far it lags behind the rpc node
+pub fn start_chain_data_metrics(
+    chain: Arc<RwLock<chain_data::ChainData>>,
+    rpc: RpcClientAsync,
+    metrics: &Metrics,
+) {
+    let mut interval = time::interval(Duration::from_secs(10));
+
+    let mut metric_slots_count = metrics.register_u64("chain_data_slots_count".into());
+    let mut metric_accounts_count = metrics.register_u64("chain_data_accounts_count".into());
+    let mut metric_account_write_count =
+        metrics.register_u64("chain_data_account_write_count".into());
+    let mut metric_newest_processed_slot =
+        metrics.register_u64("chain_data_newest_processed_slot".into());
+    let mut metric_slot_lag = metrics.register_i64("chain_data_slot_lag".into());
+
+    tokio::spawn(async move {
+        loop {
+            interval.tick().await;
+            let newest_processed_slot = {
+                let chain_lock = chain.read().unwrap();
+                metric_slots_count.set(chain_lock.slots_count() as u64);
+                metric_accounts_count.set(chain_lock.accounts_count() as u64);
+                metric_account_write_count.set(chain_lock.account_writes_count() as u64);
+                chain_lock.newest_processed_slot()
+            };
+            metric_newest_processed_slot.set(newest_processed_slot);
+
+            match rpc
+                .get_slot_with_commitment(
+                    solana_sdk::commitment_config::CommitmentConfig::processed(),
+                )
+                .await
+            {
+                Ok(rpc_slot) => metric_slot_lag.set(rpc_slot as i64 - newest_processed_slot as i64),
+                Err(err) => log::debug!("could not get slot for chain_data lag metric: {err:?}"),
+            }
+        }
+    });
+}
@@ -119,3 +119,55 @@ pub async fn fetch_top(
     // return highest abs pnl accounts
     Ok(accounts_pnl.into_iter().take(count).collect::<Vec<_>>())
 }
+
+/// Returns the PerpAdl counterparties for a liqee with `liqee_base_lots`, ordered by
+/// descending ADL score.
+///
+/// Candidates are the `count` accounts with the highest positive pnl that hold a base
+/// position on the opposite side and reach the market's adl_min_score. PerpAdl verifies
+/// the scores and their ordering on-chain.
+pub async fn fetch_adl_candidates(
+    context: &crate::context::MangoGroupContext,
+    account_fetcher: &impl AccountFetcher,
+    perp_market_index: PerpMarketIndex,
+    liqee_base_lots: i64,
+    count: usize,
+) -> anyhow::Result<Vec<(Pubkey, MangoAccountValue, I80F48)>> {
+    let perp = context.perp(perp_market_index);
+    let perp_market =
+        account_fetcher_fetch_anchor_account::<PerpMarket>(account_fetcher, &perp.address).await?;
+    let oracle_acc = account_fetcher
+        .fetch_raw_account(&perp_market.oracle)
+        .await?;
+    let oracle_price = perp_market.oracle_price(
+        &KeyedAccountSharedData::new(perp_market.oracle, oracle_acc),
+        None,
+    )?;
+
+    let top = fetch_top(
+        context,
+        account_fetcher,
+        perp_market_index,
+        Direction::MaxPositive,
+        count,
+    )
+    .await?;
+    let mut candidates = vec![];
+    for (pk, acc, _) in top {
+        let mut perp_pos = acc.perp_position(perp_market_index)?.clone();
+        if perp_pos.base_position_lots().signum() != -liqee_base_lots.signum() {
+            continue;
+        }
+        perp_pos.settle_funding(&perp_market);
+        let equity = crate::health_cache::new(context, account_fetcher, &acc)
+            .await?
+            .equity();
+        let score = perp_pos.adl_score(&perp_market, oracle_price, equity)?;
+        if !score.is_positive() || score < perp_market.adl_min_score {
+            continue;
+        }
+        candidates.push((pk, acc, score));
+    }
+    candidates.sort_by(|a, b| b.2.cmp(&a.2));
+    Ok(candidates)
+}
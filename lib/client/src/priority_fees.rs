@@ -0,0 +1,167 @@
+use std::sync::{atomic, Arc, Mutex};
+use std::time::Duration;
+
+use serde::Deserialize;
+use solana_client::nonblocking::rpc_client::RpcClient as RpcClientAsync;
+use solana_client::rpc_request::RpcRequest;
+use solana_sdk::pubkey::Pubkey;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PriorityFeeMode {
+    /// Always use the base fee
+    Fixed,
+    /// Use a percentile of the prioritization fees paid in recent slots
+    RecentFees,
+    /// Raise the fee while transactions fail to land and lower it while they do
+    LandingRate,
+}
+
+impl std::str::FromStr for PriorityFeeMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "recent-fees" => Ok(Self::RecentFees),
+            "landing-rate" => Ok(Self::LandingRate),
+            _ => anyhow::bail!(
+                "unknown priority fee mode {s}, use fixed, recent-fees or landing-rate"
+            ),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PriorityFeeEstimatorConfig {
+    pub mode: PriorityFeeMode,
+    /// Fee in microlamports per compute unit to use in Fixed mode and the
+    /// lower bound for the other modes
+    pub base_fee: u64,
+    /// Upper bound for the fee in microlamports per compute unit
+    pub max_fee: u64,
+    /// RecentFees: which percentile (0-100) of recent fees to pay
+    pub recent_fees_percentile: u8,
+    /// LandingRate: fraction of transactions that should land, between 0 and 1
+    pub target_landing_rate: f64,
+}
+
+#[derive(Debug)]
+struct LandingState {
+    /// Exponentially weighted average of landed (1.0) and not landed (0.0) transactions
+    landing_rate: f64,
+}
+
+/// Shared source of the compute unit price that transactions should use
+///
+/// One instance is shared by all sends of a Client. Transaction confirmations
+/// are fed back through record_landing().
+#[derive(Debug)]
+pub struct PriorityFeeEstimator {
+    pub config: PriorityFeeEstimatorConfig,
+    current_fee: atomic::AtomicU64,
+    landing: Mutex<LandingState>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct RpcPrioritizationFee {
+    prioritization_fee: u64,
+}
+
+impl PriorityFeeEstimator {
+    pub fn new(config: PriorityFeeEstimatorConfig) -> Self {
+        Self {
+            current_fee: atomic::AtomicU64::new(config.base_fee),
+            landing: Mutex::new(LandingState { landing_rate: 1.0 }),
+            config,
+        }
+    }
+
+    /// The compute unit price in microlamports to use for the next transaction
+    pub fn fee(&self) -> u64 {
+        self.current_fee.load(atomic::Ordering::Acquire)
+    }
+
+    fn set_fee(&self, fee: u64) {
+        let fee = fee.clamp(
+            self.config.base_fee,
+            self.config.max_fee.max(self.config.base_fee),
+        );
+        self.current_fee.store(fee, atomic::Ordering::Release);
+    }
+
+    /// Feed back whether a sent transaction made it into a block
+    ///
+    /// Transactions that were executed but failed count as landed.
+    pub fn record_landing(&self, landed: bool) {
+        if self.config.mode != PriorityFeeMode::LandingRate {
+            return;
+        }
+        let landing_rate = {
+            let mut landing = self.landing.lock().unwrap();
+            let sample = if landed { 1.0 } else { 0.0 };
+            landing.landing_rate = 0.9 * landing.landing_rate + 0.1 * sample;
+            landing.landing_rate
+        };
+        let fee = self.fee();
+        let new_fee = if landing_rate < self.config.target_landing_rate {
+            // increase by 25%, but at least by one
+            fee.saturating_add((fee / 4).max(1))
+        } else {
+            // slowly decay back towards the base fee
+            fee - fee / 20
+        };
+        self.set_fee(new_fee);
+    }
+
+    /// Update the fee from the prioritization fees paid for transactions that
+    /// write-locked any of the accounts in recent slots
+    pub async fn update_from_recent_fees(
+        &self,
+        rpc: &RpcClientAsync,
+        accounts: &[Pubkey],
+    ) -> anyhow::Result<()> {
+        let addresses = accounts.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let mut fees = rpc
+            .send::<Vec<RpcPrioritizationFee>>(
+                RpcRequest::Custom {
+                    method: "getRecentPrioritizationFees",
+                },
+                serde_json::json!([addresses]),
+            )
+            .await?
+            .into_iter()
+            .map(|f| f.prioritization_fee)
+            .collect::<Vec<u64>>();
+        if fees.is_empty() {
+            return Ok(());
+        }
+        fees.sort_unstable();
+        let percentile = self.config.recent_fees_percentile.min(100) as usize;
+        let index = ((fees.len() - 1) * percentile) / 100;
+        self.set_fee(fees[index]);
+        Ok(())
+    }
+
+    /// In RecentFees mode, spawn a job that regularly updates the fee from the rpc node
+    pub fn start_recent_fees_updater(
+        self: &Arc<Self>,
+        rpc: RpcClientAsync,
+        accounts: Vec<Pubkey>,
+        interval: Duration,
+    ) {
+        if self.config.mode != PriorityFeeMode::RecentFees {
+            return;
+        }
+        let estimator = self.clone();
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(interval);
+            loop {
+                interval.tick().await;
+                if let Err(err) = estimator.update_from_recent_fees(&rpc, &accounts).await {
+                    log::warn!("could not update priority fee from recent fees: {err:?}");
+                }
+            }
+        });
+    }
+}
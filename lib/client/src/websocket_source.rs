@@ -18,6 +18,10 @@ use tokio_stream::StreamMap;
 use crate::account_update_stream::{AccountUpdate, Message};
 use crate::AnyhowWrap;
 
+type AccountSubscription = jsonrpc_core_client::TypedSubscriptionStream<
+    solana_client::rpc_response::Response<solana_account_decoder::UiAccount>,
+>;
+
 pub struct Config {
     pub rpc_ws_url: String,
     pub serum_program: Pubkey,
@@ -67,23 +71,7 @@ async fn feed_data(
             Some(all_accounts_config.clone()),
         )
         .map_err_anyhow()?;
-    let mut mango_oracles_sub_map = StreamMap::new();
-    for oracle in mango_oracles.into_iter() {
-        mango_oracles_sub_map.insert(
-            oracle,
-            client
-                .account_subscribe(
-                    oracle.to_string(),
-                    Some(RpcAccountInfoConfig {
-                        encoding: Some(UiAccountEncoding::Base64),
-                        commitment: Some(CommitmentConfig::processed()),
-                        data_slice: None,
-                        min_context_slot: None,
-                    }),
-                )
-                .map_err_anyhow()?,
-        );
-    }
+    let mut mango_oracles_sub_map = subscribe_accounts(&client, mango_oracles)?;
     let mut open_orders_sub = client
         .program_subscribe(
             config.serum_program.to_string(),
@@ -138,6 +126,90 @@ async fn feed_data(
     }
 }
 
+fn subscribe_accounts(
+    client: &RpcSolPubSubClient,
+    accounts: Vec<Pubkey>,
+) -> anyhow::Result<StreamMap<Pubkey, AccountSubscription>> {
+    let mut sub_map = StreamMap::new();
+    for account in accounts.into_iter() {
+        sub_map.insert(
+            account,
+            client
+                .account_subscribe(
+                    account.to_string(),
+                    Some(RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64),
+                        commitment: Some(CommitmentConfig::processed()),
+                        data_slice: None,
+                        min_context_slot: None,
+                    }),
+                )
+                .map_err_anyhow()?,
+        );
+    }
+    Ok(sub_map)
+}
+
+/// Like feed_data(), but only for a fixed list of accounts and slot updates
+async fn feed_account_data(
+    rpc_ws_url: &str,
+    accounts: Vec<Pubkey>,
+    sender: async_channel::Sender<Message>,
+) -> anyhow::Result<()> {
+    let connect = ws::try_connect::<RpcSolPubSubClient>(rpc_ws_url).map_err_anyhow()?;
+    let client = connect.await.map_err_anyhow()?;
+
+    let mut accounts_sub_map = subscribe_accounts(&client, accounts)?;
+    let mut slot_sub = client.slots_updates_subscribe().map_err_anyhow()?;
+
+    loop {
+        tokio::select! {
+            message = accounts_sub_map.next() => {
+                if let Some(data) = message {
+                    let response = data.1.map_err_anyhow()?;
+                    let response = solana_client::rpc_response::Response{ context: RpcResponseContext{ slot: response.context.slot, api_version: None }, value: RpcKeyedAccount{ pubkey: data.0.to_string(), account:  response.value} } ;
+                    sender.send(Message::Account(AccountUpdate::from_rpc(response)?)).await.expect("sending must succeed");
+                } else {
+                    warn!("account stream closed");
+                    return Ok(());
+                }
+            },
+            message = slot_sub.next() => {
+                if let Some(data) = message {
+                    sender.send(Message::Slot(data.map_err_anyhow()?)).await.expect("sending must succeed");
+                } else {
+                    warn!("slot update stream closed");
+                    return Ok(());
+                }
+            },
+            _ = tokio::time::sleep(Duration::from_secs(60)) => {
+                warn!("websocket timeout");
+                return Ok(())
+            }
+        }
+    }
+}
+
+/// Stream updates to the given accounts, reconnecting when needed
+///
+/// Unlike start(), this does not subscribe to all mango and serum accounts.
+pub fn start_account_subscriptions(
+    rpc_ws_url: String,
+    accounts: Vec<Pubkey>,
+    sender: async_channel::Sender<Message>,
+) {
+    tokio::spawn(async move {
+        loop {
+            info!("connecting to solana websocket streams");
+            let out = feed_account_data(&rpc_ws_url, accounts.clone(), sender.clone());
+            let result = out.await;
+            if let Err(err) = result {
+                warn!("websocket stream error: {err}");
+            }
+        }
+    });
+}
+
 pub fn start(config: Config, mango_oracles: Vec<Pubkey>, sender: async_channel::Sender<Message>) {
     tokio::spawn(async move {
         // if the websocket disconnects, we get no data in a while etc, reconnect and try again
@@ -0,0 +1,15 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Emits health and equity snapshots for a batch of mango accounts
+///
+/// remaining_accounts: the mango accounts, followed by the health accounts
+/// (banks, oracles, perp markets, perp oracles, serum3 open orders) of all of them
+#[derive(Accounts)]
+pub struct AccountHealthSnapshot<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::AccountHealthSnapshot) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+}
@@ -64,4 +64,11 @@ pub struct FlashLoanEnd<'info> {
 pub enum FlashLoanType {
     Unknown,
     Swap,
+    /// A swap of collateral for liabilities on a liquidatable account
+    ///
+    /// Allowed while the account is being liquidated, as long as its LiquidationEnd
+    /// health improves. The swap may not create new borrows and only a fraction of
+    /// the liab token's liquidation fee is charged, see
+    /// Group::self_liquidation_fee_fraction.
+    SelfLiquidation,
 }
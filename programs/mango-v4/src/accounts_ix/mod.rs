@@ -3,6 +3,7 @@ pub use account_close::*;
 pub use account_create::*;
 pub use account_edit::*;
 pub use account_expand::*;
+pub use account_health_snapshot::*;
 pub use account_toggle_freeze::*;
 pub use alt_extend::*;
 pub use alt_set::*;
@@ -14,6 +15,7 @@ pub use group_create::*;
 pub use group_edit::*;
 pub use health_region::*;
 pub use ix_gate_set::*;
+pub use perp_adl::*;
 pub use perp_cancel_all_orders::*;
 pub use perp_cancel_all_orders_by_side::*;
 pub use perp_cancel_order::*;
@@ -23,8 +25,10 @@ pub use perp_consume_events::*;
 pub use perp_create_market::*;
 pub use perp_deactivate_position::*;
 pub use perp_edit_market::*;
+pub use perp_isolated_collateral::*;
 pub use perp_liq_base_or_positive_pnl::*;
 pub use perp_liq_force_cancel_orders::*;
+pub use perp_liq_isolated::*;
 pub use perp_liq_negative_pnl_or_bankruptcy::*;
 pub use perp_place_order::*;
 pub use perp_settle_fees::*;
@@ -44,6 +48,7 @@ pub use stub_oracle_close::*;
 pub use stub_oracle_create::*;
 pub use stub_oracle_set::*;
 pub use token_add_bank::*;
+pub use token_claim_rewards::*;
 pub use token_deposit::*;
 pub use token_deregister::*;
 pub use token_edit::*;
@@ -59,6 +64,7 @@ mod account_close;
 mod account_create;
 mod account_edit;
 mod account_expand;
+mod account_health_snapshot;
 mod account_toggle_freeze;
 mod alt_extend;
 mod alt_set;
@@ -70,6 +76,7 @@ mod group_create;
 mod group_edit;
 mod health_region;
 mod ix_gate_set;
+mod perp_adl;
 mod perp_cancel_all_orders;
 mod perp_cancel_all_orders_by_side;
 mod perp_cancel_order;
@@ -79,8 +86,10 @@ mod perp_consume_events;
 mod perp_create_market;
 mod perp_deactivate_position;
 mod perp_edit_market;
+mod perp_isolated_collateral;
 mod perp_liq_base_or_positive_pnl;
 mod perp_liq_force_cancel_orders;
+mod perp_liq_isolated;
 mod perp_liq_negative_pnl_or_bankruptcy;
 mod perp_place_order;
 mod perp_settle_fees;
@@ -100,6 +109,7 @@ mod stub_oracle_close;
 mod stub_oracle_create;
 mod stub_oracle_set;
 mod token_add_bank;
+mod token_claim_rewards;
 mod token_deposit;
 mod token_deregister;
 mod token_edit;
@@ -0,0 +1,42 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Closes the base position of a bankrupt account against an opposite-side
+/// position at the bankruptcy price
+///
+/// remaining_accounts:
+/// - the num_candidates mango accounts competing with the counterparty, see perp_adl()
+/// - health accounts for the liqee, the counterparty and the candidates
+#[derive(Accounts)]
+pub struct PerpAdl<'info> {
+    #[account(
+        has_one = insurance_vault,
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpAdl) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(mut, has_one = group, has_one = oracle)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+
+    /// CHECK: The oracle can be one of several different account types and the pubkey is checked above
+    pub oracle: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = liqee.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub liqee: AccountLoader<'info, MangoAccountFixed>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = counterparty.load()?.is_operational() @ MangoError::AccountIsFrozen
+    )]
+    pub counterparty: AccountLoader<'info, MangoAccountFixed>,
+
+    /// Deleveraging only starts once the group insurance fund is exhausted
+    pub insurance_vault: Account<'info, TokenAccount>,
+}
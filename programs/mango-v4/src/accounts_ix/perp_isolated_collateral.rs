@@ -0,0 +1,32 @@
+use crate::error::*;
+use crate::state::*;
+use anchor_lang::prelude::*;
+
+/// Used by both PerpIsolatedDeposit and PerpIsolatedWithdraw
+#[derive(Accounts)]
+pub struct PerpIsolatedCollateral<'info> {
+    #[account(
+        constraint = group.load()?.is_ix_enabled(IxGate::PerpIsolatedCollateral) @ MangoError::IxIsDisabled,
+    )]
+    pub group: AccountLoader<'info, Group>,
+
+    #[account(
+        mut,
+        has_one = group,
+        constraint = account.load()?.is_operational() @ MangoError::AccountIsFrozen
+        // owner is checked at #1
+    )]
+    pub account: AccountLoader<'info, MangoAccountFixed>,
+    pub owner: Signer<'info>,
+
+    #[account(has_one = group)]
+    pub perp_market: AccountLoader<'info, PerpMarket>,
+
+    // bank correctness is checked at #2
+    #[account(mut, has_one = group)]
+    pub settle_bank: AccountLoader<'info, Bank>,
+
+    /// CHECK: Oracle can have different account types
+    #[account(address = settle_bank.load()?.oracle)]
+    pub settle_oracle: UncheckedAccount<'info>,
+}
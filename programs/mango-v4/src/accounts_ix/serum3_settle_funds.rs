@@ -78,3 +78,28 @@ pub struct Serum3SettleFundsV2<'info> {
     )]
     pub v2: Serum3SettleFundsV2Extra<'info>,
 }
+
+/// Like Serum3SettleFundsV2, but `v1.owner` may be any signer. It must own the `settler`
+/// account, which receives the settle fee.
+///
+/// Needs both the Serum3SettleFunds and the Serum3SettleFundsPermissionless ix gates.
+#[derive(Accounts)]
+pub struct Serum3SettleFundsPermissionless<'info> {
+    #[account(
+        constraint = v1.group.load()?.is_ix_enabled(IxGate::Serum3SettleFundsPermissionless) @ MangoError::IxIsDisabled,
+    )]
+    pub v1: Serum3SettleFunds<'info>,
+    #[account(
+        constraint = v2.quote_oracle.key() == v1.quote_bank.load()?.oracle,
+        constraint = v2.base_oracle.key() == v1.base_bank.load()?.oracle,
+    )]
+    pub v2: Serum3SettleFundsV2Extra<'info>,
+
+    #[account(
+        mut,
+        constraint = settler.load()?.group == v1.group.key(),
+        constraint = settler.load()?.is_operational() @ MangoError::AccountIsFrozen
+        // settler owner is checked at #4
+    )]
+    pub settler: AccountLoader<'info, MangoAccountFixed>,
+}
@@ -68,3 +68,9 @@ pub struct InterestRateParams {
     pub max_rate: f32,
     pub adjustment_factor: f32,
 }
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug)]
+pub struct InterestCurvePointParams {
+    pub util: f32,
+    pub rate: f32,
+}
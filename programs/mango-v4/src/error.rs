@@ -97,6 +97,16 @@ pub enum MangoError {
     PerpOrderIdNotFound,
     #[msg("HealthRegions allow only specific instructions between Begin and End")]
     HealthRegionBadInnerInstruction,
+    #[msg("deposit crosses the token's deposit limit")]
+    BankDepositLimit,
+    #[msg("the liquidation close factor limit for this window has been reached")]
+    LiquidationCloseFactorReached,
+    #[msg("no free isolated perp position index")]
+    NoFreeIsolatedPerpPositionIndex,
+    #[msg("isolated perp position does not exist")]
+    IsolatedPerpPositionDoesNotExist,
+    #[msg("the perp position is margined in isolation")]
+    PerpPositionIsIsolated,
 }
 
 impl MangoError {
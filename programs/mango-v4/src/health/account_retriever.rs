@@ -56,17 +56,23 @@ pub struct FixedOrderAccountRetriever<T: KeyedAccountReader> {
     pub staleness_slot: Option<u64>,
 }
 
+/// Number of account infos a FixedOrderAccountRetriever expects for the account
+pub fn fixed_order_account_count(account: &MangoAccountRef) -> usize {
+    let active_token_len = account.active_token_positions().count();
+    let active_serum3_len = account.active_serum3_orders().count();
+    let active_perp_len = account.active_perp_positions().count();
+    active_token_len * 2 // banks + oracles
+        + active_perp_len * 2 // PerpMarkets + Oracles
+        + active_serum3_len // open_orders
+}
+
 pub fn new_fixed_order_account_retriever<'a, 'info>(
     ais: &'a [AccountInfo<'info>],
     account: &MangoAccountRef,
 ) -> Result<FixedOrderAccountRetriever<AccountInfoRef<'a, 'info>>> {
     let active_token_len = account.active_token_positions().count();
-    let active_serum3_len = account.active_serum3_orders().count();
     let active_perp_len = account.active_perp_positions().count();
-    let expected_ais = active_token_len * 2 // banks + oracles
-        + active_perp_len * 2 // PerpMarkets + Oracles
-        + active_serum3_len; // open_orders
-    require_eq!(ais.len(), expected_ais);
+    require_eq!(ais.len(), fixed_order_account_count(account));
 
     Ok(FixedOrderAccountRetriever {
         ais: AccountInfoRef::borrow_slice(ais)?,
@@ -341,11 +341,31 @@ impl PerpInfo {
     }
 }
 
+/// A perp position that is margined in isolation, with its dedicated collateral.
+///
+/// It does not contribute to the account's health. Instead its own health is
+/// the sum of the collateral and the perp health contributions.
+#[derive(Clone, AnchorDeserialize, AnchorSerialize, Debug)]
+pub struct IsolatedPerpInfo {
+    pub perp: PerpInfo,
+    /// Info for the perp market's settle token, balance_native is the isolated collateral
+    pub collateral: TokenInfo,
+}
+
+impl IsolatedPerpInfo {
+    #[inline(always)]
+    pub fn health(&self, health_type: HealthType) -> I80F48 {
+        self.collateral.health_contribution(health_type)
+            + self.perp.health_contribution(health_type)
+    }
+}
+
 #[derive(Clone, AnchorDeserialize, AnchorSerialize, Debug)]
 pub struct HealthCache {
     pub(crate) token_infos: Vec<TokenInfo>,
     pub(crate) serum3_infos: Vec<Serum3Info>,
     pub(crate) perp_infos: Vec<PerpInfo>,
+    pub(crate) isolated_perp_infos: Vec<IsolatedPerpInfo>,
     pub(crate) being_liquidated: bool,
 }
 
@@ -375,6 +395,46 @@ impl HealthCache {
         (assets, liabs)
     }
 
+    /// Maint health deficit as a fraction of the maint liabilities
+    ///
+    /// Zero if maint health is not negative.
+    pub fn maint_deficit_depth(&self) -> I80F48 {
+        let (assets, liabs) = self.health_assets_and_liabs(HealthType::Maint);
+        if assets >= liabs {
+            return I80F48::ZERO;
+        }
+        (liabs - assets) / liabs
+    }
+
+    /// Unweighted value of the account at oracle prices: assets minus liabilities
+    ///
+    /// Serum3 reserved funds count at their oracle value and perp positions at their
+    /// unsettled pnl. Open perp orders are ignored.
+    pub fn equity(&self) -> I80F48 {
+        let token_value = |ti: &TokenInfo| ti.balance_native * ti.prices.oracle;
+        let perp_value = |pi: &PerpInfo| {
+            pi.quote + I80F48::from(pi.base_lots * pi.base_lot_size) * pi.prices.oracle
+        };
+
+        let mut equity = I80F48::ZERO;
+        for token_info in self.token_infos.iter() {
+            equity += token_value(token_info);
+        }
+        for serum3_info in self.serum3_infos.iter() {
+            let base_info = &self.token_infos[serum3_info.base_index];
+            let quote_info = &self.token_infos[serum3_info.quote_index];
+            equity += serum3_info.reserved_base * base_info.prices.oracle
+                + serum3_info.reserved_quote * quote_info.prices.oracle;
+        }
+        for perp_info in self.perp_infos.iter() {
+            equity += perp_value(perp_info);
+        }
+        for isolated_info in self.isolated_perp_infos.iter() {
+            equity += token_value(&isolated_info.collateral) + perp_value(&isolated_info.perp);
+        }
+        equity
+    }
+
     pub fn token_info(&self, token_index: TokenIndex) -> Result<&TokenInfo> {
         Ok(&self.token_infos[self.token_info_index(token_index)?])
     }
@@ -409,6 +469,50 @@ impl HealthCache {
             })
     }
 
+    pub fn isolated_perp_info(
+        &self,
+        perp_market_index: PerpMarketIndex,
+    ) -> Result<&IsolatedPerpInfo> {
+        Ok(&self.isolated_perp_infos[self.isolated_perp_info_index(perp_market_index)?])
+    }
+
+    fn isolated_perp_info_index(&self, perp_market_index: PerpMarketIndex) -> Result<usize> {
+        self.isolated_perp_infos
+            .iter()
+            .position(|t| t.perp.perp_market_index == perp_market_index)
+            .ok_or_else(|| {
+                error_msg_typed!(
+                    MangoError::IsolatedPerpPositionDoesNotExist,
+                    "isolated perp market index {} not found",
+                    perp_market_index
+                )
+            })
+    }
+
+    /// Health of an isolated perp position and its collateral
+    pub fn isolated_perp_health(
+        &self,
+        perp_market_index: PerpMarketIndex,
+        health_type: HealthType,
+    ) -> Result<I80F48> {
+        Ok(self
+            .isolated_perp_info(perp_market_index)?
+            .health(health_type))
+    }
+
+    /// Changes the cached collateral of an isolated perp position.
+    pub fn adjust_isolated_perp_collateral(
+        &mut self,
+        perp_market_index: PerpMarketIndex,
+        change: I80F48,
+    ) -> Result<()> {
+        let entry_index = self.isolated_perp_info_index(perp_market_index)?;
+        self.isolated_perp_infos[entry_index]
+            .collateral
+            .balance_native += change;
+        Ok(())
+    }
+
     /// Changes the cached user account token balance.
     pub fn adjust_token_balance(&mut self, bank: &Bank, change: I80F48) -> Result<()> {
         let entry_index = self.token_info_index(bank.token_index)?;
@@ -476,6 +580,7 @@ impl HealthCache {
         let perp_entry = self
             .perp_infos
             .iter_mut()
+            .chain(self.isolated_perp_infos.iter_mut().map(|i| &mut i.perp))
             .find(|m| m.perp_market_index == perp_market.perp_market_index)
             .ok_or_else(|| error_msg!("perp market {} not found", perp_market.perp_market_index))?;
         *perp_entry = PerpInfo::new(perp_position, perp_market, perp_entry.prices.clone())?;
@@ -710,6 +815,8 @@ pub fn new_health_cache(
 ) -> Result<HealthCache> {
     // token contribution from token accounts
     let mut token_infos = vec![];
+    // native collateral of isolated perp positions, by perp market index
+    let mut isolated_collateral = vec![];
 
     for (i, position) in account.active_token_positions().enumerate() {
         let (bank, oracle_price) =
@@ -734,6 +841,13 @@ pub fn new_health_cache(
             prices,
             balance_native: native,
         });
+
+        for isolated in account
+            .active_isolated_perp_positions()
+            .filter(|p| p.token_index == bank.token_index)
+        {
+            isolated_collateral.push((isolated.market_index, isolated.native(bank)));
+        }
     }
 
     // Fill the TokenInfo balance with free funds in serum3 oo accounts and build Serum3Infos.
@@ -771,26 +885,54 @@ pub fn new_health_cache(
 
     // health contribution from perp accounts
     let mut perp_infos = Vec::with_capacity(account.active_perp_positions().count());
+    let mut isolated_perp_infos = vec![];
     for (i, perp_position) in account.active_perp_positions().enumerate() {
         let (perp_market, oracle_price) = retriever.perp_market_and_oracle_price(
             &account.fixed.group,
             i,
             perp_position.market_index,
         )?;
-        perp_infos.push(PerpInfo::new(
+        let perp_info = PerpInfo::new(
             perp_position,
             perp_market,
             Prices {
                 oracle: oracle_price,
                 stable: perp_market.stable_price(),
             },
-        )?);
+        )?;
+
+        if !account.is_isolated_perp(perp_position.market_index) {
+            perp_infos.push(perp_info);
+            continue;
+        }
+
+        // isolated positions don't contribute to account health, they are
+        // only backed by their own collateral
+        let collateral_native = isolated_collateral
+            .iter()
+            .find(|(market_index, _)| *market_index == perp_position.market_index)
+            .map(|(_, native)| *native)
+            .ok_or_else(|| {
+                error_msg_typed!(
+                    MangoError::TokenPositionDoesNotExist,
+                    "no settle token position for isolated perp market {}",
+                    perp_position.market_index
+                )
+            })?;
+        let settle_index = find_token_info_index(&token_infos, perp_market.settle_token_index)?;
+        let mut collateral = token_infos[settle_index].clone();
+        collateral.balance_native = collateral_native;
+        isolated_perp_infos.push(IsolatedPerpInfo {
+            perp: perp_info,
+            collateral,
+        });
     }
 
     Ok(HealthCache {
         token_infos,
         serum3_infos,
         perp_infos,
+        isolated_perp_infos,
         being_liquidated: account.fixed.being_liquidated(),
     })
 }
@@ -909,6 +1051,135 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_equity() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 1, 1.0, 0.2, 0.1);
+        let (mut bank2, mut oracle2) = mock_bank_and_oracle(group, 4, 5.0, 0.5, 0.3);
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(1).unwrap().0,
+                I80F48::from(100),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+        bank2
+            .data()
+            .withdraw_without_fee(
+                account.ensure_token_position(4).unwrap().0,
+                I80F48::from(10),
+                DUMMY_NOW_TS,
+                DUMMY_PRICE,
+            )
+            .unwrap();
+
+        let mut perp1 = mock_perp_market(group, oracle2.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+        let perpaccount = account.ensure_perp_position(9, 1).unwrap().0;
+        perpaccount.record_trade(perp1.data(), 3, -I80F48::from(310u16));
+        perpaccount.bids_base_lots = 7;
+
+        let oracle2_ai = oracle2.as_account_info();
+
+        let ais = vec![
+            bank1.as_account_info(),
+            bank2.as_account_info(),
+            oracle1.as_account_info(),
+            oracle2_ai.clone(),
+            perp1.as_account_info(),
+            oracle2_ai,
+        ];
+
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        let health_cache = new_health_cache(&account.borrow(), &retriever).unwrap();
+
+        // weights and the open bids don't matter: 100 - 10 * 5 + (-310 + 3 * 10 * 5)
+        assert!(health_eq(health_cache.equity(), -110.0));
+    }
+
+    #[test]
+    fn test_isolated_perp_health() {
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut account = MangoAccountValue::from_bytes(&buffer).unwrap();
+
+        let group = Pubkey::new_unique();
+
+        let (mut bank1, mut oracle1) = mock_bank_and_oracle(group, 1, 1.0, 0.2, 0.1);
+        bank1
+            .data()
+            .deposit(
+                account.ensure_token_position(1).unwrap().0,
+                I80F48::from(80),
+                DUMMY_NOW_TS,
+            )
+            .unwrap();
+
+        let mut perp_oracle = TestAccount::<StubOracle>::new_zeroed();
+        perp_oracle.data().price = I80F48::from(5);
+        let mut perp1 =
+            mock_perp_market(group, perp_oracle.pubkey, 5.0, 9, (0.2, 0.1), (0.05, 0.02));
+        perp1.data().settle_token_index = 1;
+        let perpaccount = account.ensure_perp_position(9, 1).unwrap().0;
+        perpaccount.record_trade(perp1.data(), 3, -I80F48::from(150));
+
+        bank1
+            .data()
+            .deposit_isolated(
+                account.create_isolated_perp_position(9, 1).unwrap(),
+                I80F48::from(20),
+            )
+            .unwrap();
+
+        let ais = vec![
+            bank1.as_account_info(),
+            oracle1.as_account_info(),
+            perp1.as_account_info(),
+            perp_oracle.as_account_info(),
+        ];
+        let retriever = ScanningAccountRetriever::new_with_staleness(&ais, &group, None).unwrap();
+        let mut health_cache = new_health_cache(&account.borrow(), &retriever).unwrap();
+
+        // the perp position and its collateral don't count toward account health
+        assert!(health_cache.perp_info(9).is_err());
+        assert!(health_eq(health_cache.health(HealthType::Init), 80.0 * 0.8));
+        assert!(health_eq(
+            health_cache.health(HealthType::Maint),
+            80.0 * 0.9
+        ));
+
+        // base: 3 lots * 10 * 5.0, quote: -150
+        assert!(health_eq(
+            health_cache
+                .isolated_perp_health(9, HealthType::Init)
+                .unwrap(),
+            20.0 * 0.8 + 150.0 * 0.8 - 150.0
+        ));
+        assert!(health_eq(
+            health_cache
+                .isolated_perp_health(9, HealthType::Maint)
+                .unwrap(),
+            20.0 * 0.9 + 150.0 * 0.9 - 150.0
+        ));
+
+        health_cache
+            .adjust_isolated_perp_collateral(9, I80F48::from(10))
+            .unwrap();
+        assert!(health_eq(
+            health_cache
+                .isolated_perp_health(9, HealthType::Maint)
+                .unwrap(),
+            30.0 * 0.9 + 150.0 * 0.9 - 150.0
+        ));
+        assert!(health_eq(
+            health_cache.health(HealthType::Maint),
+            80.0 * 0.9
+        ));
+    }
+
     #[derive(Default)]
     struct BankSettings {
         deposits: u64,
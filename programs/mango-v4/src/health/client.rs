@@ -658,6 +658,7 @@ mod tests {
             ],
             serum3_infos: vec![],
             perp_infos: vec![],
+            isolated_perp_infos: vec![],
             being_liquidated: false,
         };
 
@@ -1013,6 +1014,7 @@ mod tests {
                 perp_market_index: 0,
                 ..default_perp_info(0.3)
             }],
+            isolated_perp_infos: vec![],
             being_liquidated: false,
         };
 
@@ -1294,6 +1296,7 @@ mod tests {
             ],
             serum3_infos: vec![],
             perp_infos: vec![],
+            isolated_perp_infos: vec![],
             being_liquidated: false,
         };
 
@@ -7,7 +7,7 @@ use crate::state::*;
 
 use crate::accounts_ix::*;
 
-use crate::logs::{AccountBuybackFeesWithMngoLog, TokenBalanceLog};
+use crate::logs::{emit_token_balance, AccountBuybackFeesWithMngoLog};
 
 pub fn account_buyback_fees_with_mngo(
     ctx: Context<AccountBuybackFeesWithMngo>,
@@ -109,14 +109,13 @@ pub fn account_buyback_fees_with_mngo(
         now_ts,
         mngo_oracle_price,
     )?;
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account.key(),
-        token_index: mngo_bank.token_index,
-        indexed_position: account_mngo_token_position.indexed_position.to_bits(),
-        deposit_index: mngo_bank.deposit_index.to_bits(),
-        borrow_index: mngo_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        account_mngo_token_position,
+        mngo_bank.deposit_index,
+        mngo_bank.borrow_index,
+    );
     if !in_use {
         account.deactivate_token_position_and_log(
             account_mngo_raw_token_index,
@@ -145,14 +144,13 @@ pub fn account_buyback_fees_with_mngo(
         );
     }
     let in_use = fees_bank.deposit(account_fees_token_position, max_buyback_fees, now_ts)?;
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account.key(),
-        token_index: fees_bank.token_index,
-        indexed_position: account_fees_token_position.indexed_position.to_bits(),
-        deposit_index: fees_bank.deposit_index.to_bits(),
-        borrow_index: fees_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        account_fees_token_position,
+        fees_bank.deposit_index,
+        fees_bank.borrow_index,
+    );
     if !in_use {
         account.deactivate_token_position_and_log(
             account_fees_raw_token_index,
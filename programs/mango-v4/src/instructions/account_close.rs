@@ -22,6 +22,9 @@ pub fn account_close(ctx: Context<AccountClose>, force_close: bool) -> Result<()
         for ele in account.all_perp_positions() {
             require_eq!(ele.is_active(), false);
         }
+        for ele in account.all_isolated_perp_positions() {
+            require_eq!(ele.is_active(), false);
+        }
     }
 
     Ok(())
@@ -9,8 +9,26 @@ pub fn account_expand(
     serum3_count: u8,
     perp_count: u8,
     perp_oo_count: u8,
+    isolated_perp_count: Option<u8>,
 ) -> Result<()> {
-    let new_space = MangoAccount::space(token_count, serum3_count, perp_count, perp_oo_count)?;
+    // callers that don't know about isolated perp positions keep the current count
+    let isolated_perp_count = match isolated_perp_count {
+        Some(count) => count,
+        None => ctx
+            .accounts
+            .account
+            .load_full()?
+            .header
+            .isolated_perp_count() as u8,
+    };
+
+    let new_space = MangoAccount::space(
+        token_count,
+        serum3_count,
+        perp_count,
+        perp_oo_count,
+        isolated_perp_count,
+    )?;
     let new_rent_minimum = Rent::get()?.minimum_balance(new_space);
 
     let realloc_account = ctx.accounts.account.as_ref();
@@ -36,7 +54,13 @@ pub fn account_expand(
 
     // expand dynamic content, e.g. to grow token positions, we need to slide serum3orders further later, and so on....
     let mut account = ctx.accounts.account.load_full_mut()?;
-    account.expand_dynamic_content(token_count, serum3_count, perp_count, perp_oo_count)?;
+    account.expand_dynamic_content(
+        token_count,
+        serum3_count,
+        perp_count,
+        perp_oo_count,
+        isolated_perp_count,
+    )?;
 
     Ok(())
 }
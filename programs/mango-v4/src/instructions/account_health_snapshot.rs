@@ -0,0 +1,82 @@
+use anchor_lang::prelude::*;
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::health::*;
+use crate::logs::AccountHealthSnapshotLog;
+use crate::state::*;
+
+/// Emits an AccountHealthSnapshotLog for each mango account passed at the start of
+/// the remaining accounts.
+///
+/// The health accounts that follow must cover all of the mango accounts, in the layout
+/// the ScanningAccountRetriever expects. Accounts whose health can't be computed are
+/// skipped. Nothing is written, anyone can call this.
+pub fn account_health_snapshot(ctx: Context<AccountHealthSnapshot>) -> Result<()> {
+    let group_pk = ctx.accounts.group.key();
+
+    let num_accounts = ctx
+        .remaining_accounts
+        .iter()
+        .take_while(|ai| AccountLoader::<MangoAccountFixed>::try_from(ai).is_ok())
+        .count();
+    require_msg!(num_accounts > 0, "no mango accounts passed");
+    let (account_ais, health_ais) = ctx.remaining_accounts.split_at(num_accounts);
+
+    let retriever = ScanningAccountRetriever::new(health_ais, &group_pk)?;
+
+    for ai in account_ais {
+        let loader = AccountLoader::<MangoAccountFixed>::try_from(ai)?;
+        let account = loader.load_full()?;
+        require_keys_eq!(account.fixed.group, group_pk);
+
+        // An account whose health can't be computed, for example because of a stale
+        // oracle, must not prevent snapshots of the others
+        let snapshot = new_health_cache(&account.borrow(), &retriever).and_then(|health_cache| {
+            let equity = total_equity(&account.borrow(), &retriever)?;
+            Ok((health_cache, equity))
+        });
+        let (health_cache, equity) = match snapshot {
+            Ok(snapshot) => snapshot,
+            Err(e) => {
+                msg!("skipping health snapshot of account {}: {}", ai.key, e);
+                continue;
+            }
+        };
+
+        emit!(AccountHealthSnapshotLog {
+            mango_group: group_pk,
+            mango_account: ai.key(),
+            init_health: health_cache.health(HealthType::Init).to_bits(),
+            maint_health: health_cache.health(HealthType::Maint).to_bits(),
+            liquidation_end_health: health_cache.health(HealthType::LiquidationEnd).to_bits(),
+            equity: equity.to_bits(),
+            being_liquidated: account.fixed.being_liquidated(),
+        });
+    }
+
+    Ok(())
+}
+
+/// Value of the account in native quote at oracle prices: token and serum3 balances,
+/// unsettled perp pnl and isolated perp collateral
+fn total_equity(account: &MangoAccountRef, retriever: &ScanningAccountRetriever) -> Result<I80F48> {
+    let mut equity = compute_equity(account, retriever)?
+        .tokens
+        .iter()
+        .fold(I80F48::ZERO, |sum, token| sum + token.value);
+
+    for perp_position in account.active_perp_positions() {
+        let (perp_market, price) =
+            retriever.scanned_perp_market_and_oracle(perp_position.market_index)?;
+        equity += perp_position.unsettled_pnl(perp_market, price)?
+            - perp_position.unsettled_funding(perp_market);
+    }
+
+    for isolated in account.active_isolated_perp_positions() {
+        let (bank, price) = retriever.scanned_bank_and_oracle(isolated.token_index)?;
+        equity += isolated.native(bank) * price;
+    }
+
+    Ok(equity)
+}
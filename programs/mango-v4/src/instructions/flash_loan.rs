@@ -2,8 +2,12 @@ use crate::accounts_ix::*;
 use crate::accounts_zerocopy::*;
 use crate::error::*;
 use crate::group_seeds;
-use crate::health::{new_fixed_order_account_retriever, new_health_cache, AccountRetriever};
-use crate::logs::{FlashLoanLog, FlashLoanTokenDetail, TokenBalanceLog};
+use crate::health::{
+    new_fixed_order_account_retriever, new_health_cache, AccountRetriever, HealthType,
+};
+use crate::logs::{
+    emit_token_balance, FlashLoanLog, FlashLoanSelfLiquidationLog, FlashLoanTokenDetail,
+};
 use crate::state::*;
 
 use anchor_lang::prelude::*;
@@ -114,6 +118,10 @@ pub fn flash_loan_begin<'key, 'accounts, 'remaining, 'info>(
 
         // The only other mango instruction that must appear before the end of the tx is
         // the FlashLoanEnd instruction. No other mango instructions are allowed.
+        //
+        // Exception: Inside a health region, the instructions after FlashLoanEnd are
+        // validated by HealthRegionBegin instead.
+        let in_health_region = account.fixed.is_in_health_region();
         let mut index = current_index + 1;
         let mut found_end = false;
         loop {
@@ -122,6 +130,9 @@ pub fn flash_loan_begin<'key, 'accounts, 'remaining, 'info>(
                 Err(ProgramError::InvalidArgument) => break, // past the last instruction
                 Err(e) => return Err(e.into()),
             };
+            if found_end && in_health_region {
+                break;
+            }
 
             if account.fixed.is_delegate(ctx.accounts.owner.key()) {
                 require_msg!(
@@ -282,6 +293,8 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
         });
     }
 
+    let self_liquidation = flash_loan_type == FlashLoanType::SelfLiquidation;
+    let mut self_liquidation_fee_fraction = I80F48::ZERO;
     match flash_loan_type {
         FlashLoanType::Unknown => {}
         FlashLoanType::Swap => {
@@ -290,6 +303,23 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
                 "when flash_loan_type is Swap there must be exactly 2 token vault changes"
             )
         }
+        FlashLoanType::SelfLiquidation => {
+            require_msg!(
+                changes.len() == 2,
+                "when flash_loan_type is SelfLiquidation there must be exactly 2 token vault changes"
+            );
+            require_msg!(
+                !account.fixed.is_in_health_region(),
+                "self-liquidation is not allowed inside a health region"
+            );
+            let group_al = AccountLoader::<Group>::try_from(group_ai)?;
+            let group = group_al.load()?;
+            require!(
+                group.is_ix_enabled(IxGate::FlashLoanSelfLiquidation),
+                MangoError::IxIsDisabled
+            );
+            self_liquidation_fee_fraction = I80F48::from_num(group.self_liquidation_fee_fraction);
+        }
     }
 
     // all vaults must have had matching banks
@@ -302,10 +332,27 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
         );
     }
 
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
     // Check health before balance adjustments
+    // Inside a health region, health is only checked in HealthRegionEnd.
+    // Self-liquidation requires a liquidatable account and checks LiquidationEnd health instead.
     let retriever = new_fixed_order_account_retriever(health_ais, &account.borrow())?;
-    let health_cache = new_health_cache(&account.borrow(), &retriever)?;
-    let pre_init_health = account.check_health_pre(&health_cache)?;
+    let mut pre_init_health_opt = None;
+    let mut pre_liq_end_health_opt = None;
+    if self_liquidation {
+        let health_cache = new_health_cache(&account.borrow(), &retriever)?;
+        require_msg!(
+            account.check_liquidatable(&health_cache, now_ts)?,
+            "self-liquidation requires a liquidatable account"
+        );
+        let liq_end_health = health_cache.health(HealthType::LiquidationEnd);
+        msg!("pre_liq_end_health: {}", liq_end_health);
+        pre_liq_end_health_opt = Some(liq_end_health);
+    } else if !account.fixed.is_in_health_region() {
+        let health_cache = new_health_cache(&account.borrow(), &retriever)?;
+        pre_init_health_opt = Some(account.check_health_pre(&health_cache)?);
+    }
 
     // Prices for logging and net borrow checks
     let mut oracle_prices = vec![];
@@ -324,6 +371,16 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
     // Apply the vault diffs to the bank positions
     let mut deactivated_token_positions = vec![];
     let mut token_loan_details = Vec::with_capacity(changes.len());
+    let mut self_liquidation_log = FlashLoanSelfLiquidationLog {
+        mango_group: group.key(),
+        mango_account: ctx.accounts.account.key(),
+        asset_token_index: TokenIndex::MAX,
+        liab_token_index: TokenIndex::MAX,
+        liab_repaid: 0,
+        fee: 0,
+        liq_end_health_before: 0,
+        liq_end_health_after: 0,
+    };
     for (change, oracle_price) in changes.iter().zip(oracle_prices.iter()) {
         let mut bank = health_ais[change.bank_index].load_mut::<Bank>()?;
 
@@ -341,7 +398,34 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
         let loan_origination_fee = loan * bank.loan_origination_fee_rate;
         bank.collected_fees_native += loan_origination_fee;
 
-        let change_amount = change.amount - loan_origination_fee;
+        // Self-liquidation may only repay liabilities with collateral. The repaid
+        // liab is charged the reduced liquidation fee.
+        let mut self_liquidation_fee = I80F48::ZERO;
+        if self_liquidation {
+            if change.amount.is_positive() {
+                require_msg!(
+                    native.is_negative(),
+                    "self-liquidation must repay a liability, token {}",
+                    bank.token_index
+                );
+                let repaid = change.amount.min(-native);
+                self_liquidation_fee =
+                    repaid * bank.liquidation_fee * self_liquidation_fee_fraction;
+                bank.collected_fees_native += self_liquidation_fee;
+                self_liquidation_log.liab_token_index = bank.token_index;
+                self_liquidation_log.liab_repaid = repaid.to_bits();
+                self_liquidation_log.fee = self_liquidation_fee.to_bits();
+            } else {
+                require_msg!(
+                    native + change.amount - loan_origination_fee >= 0,
+                    "self-liquidation must not borrow, token {}",
+                    bank.token_index
+                );
+                self_liquidation_log.asset_token_index = bank.token_index;
+            }
+        }
+
+        let change_amount = change.amount - loan_origination_fee - self_liquidation_fee;
         let native_after_change = native + change_amount;
         if bank.is_reduce_only() {
             require!(
@@ -360,12 +444,9 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
             bank.enforce_min_vault_to_deposits_ratio(vault_ai)?;
         }
 
-        let is_active = bank.change_without_fee(
-            position,
-            change.amount - loan_origination_fee,
-            Clock::get()?.unix_timestamp.try_into().unwrap(),
-            *oracle_price,
-        )?;
+        let indexed_deposits_before = bank.indexed_deposits;
+        let is_active = bank.change_without_fee(position, change_amount, now_ts, *oracle_price)?;
+        bank.check_deposit_limit(indexed_deposits_before)?;
         if !is_active {
             deactivated_token_positions.push(change.raw_token_index);
         }
@@ -383,14 +464,13 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
             price: oracle_price.to_bits(),
         });
 
-        emit!(TokenBalanceLog {
-            mango_group: group.key(),
-            mango_account: ctx.accounts.account.key(),
-            token_index: bank.token_index as u16,
-            indexed_position: position.indexed_position.to_bits(),
-            deposit_index: bank.deposit_index.to_bits(),
-            borrow_index: bank.borrow_index.to_bits(),
-        });
+        emit_token_balance(
+            group.key(),
+            ctx.accounts.account.key(),
+            position,
+            bank.deposit_index,
+            bank.borrow_index,
+        );
     }
 
     emit!(FlashLoanLog {
@@ -401,9 +481,28 @@ pub fn flash_loan_end<'key, 'accounts, 'remaining, 'info>(
     });
 
     // Check health after account position changes
-    let retriever = new_fixed_order_account_retriever(health_ais, &account.borrow())?;
-    let health_cache = new_health_cache(&account.borrow(), &retriever)?;
-    account.check_health_post(&health_cache, pre_init_health)?;
+    if let Some(pre_init_health) = pre_init_health_opt {
+        let retriever = new_fixed_order_account_retriever(health_ais, &account.borrow())?;
+        let health_cache = new_health_cache(&account.borrow(), &retriever)?;
+        account.check_health_post(&health_cache, pre_init_health)?;
+    }
+    if let Some(pre_liq_end_health) = pre_liq_end_health_opt {
+        let retriever = new_fixed_order_account_retriever(health_ais, &account.borrow())?;
+        let health_cache = new_health_cache(&account.borrow(), &retriever)?;
+        let post_liq_end_health = health_cache.health(HealthType::LiquidationEnd);
+        msg!("post_liq_end_health: {}", post_liq_end_health);
+        require!(
+            post_liq_end_health > pre_liq_end_health,
+            MangoError::HealthMustBePositiveOrIncrease
+        );
+        account
+            .fixed
+            .maybe_recover_from_being_liquidated(post_liq_end_health);
+
+        self_liquidation_log.liq_end_health_before = pre_liq_end_health.to_bits();
+        self_liquidation_log.liq_end_health_after = post_liq_end_health.to_bits();
+        emit!(self_liquidation_log);
+    }
 
     // Deactivate inactive token accounts after health check
     for raw_token_index in deactivated_token_positions {
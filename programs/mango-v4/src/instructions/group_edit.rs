@@ -1,6 +1,10 @@
 use anchor_lang::prelude::*;
 
-use crate::{accounts_ix::*, state::TokenIndex};
+use crate::error::*;
+use crate::{
+    accounts_ix::*,
+    state::{PerpFeeTier, TokenIndex, MAX_PERP_FEE_TIERS},
+};
 
 // use case - transfer group ownership to governance, where
 // admin and fast_listing_admin are PDAs
@@ -18,6 +22,13 @@ pub fn group_edit(
     buyback_fees_swap_mango_account_opt: Option<Pubkey>,
     mngo_token_index_opt: Option<TokenIndex>,
     buyback_fees_expiry_interval_opt: Option<u64>,
+    perp_fee_tiers_opt: Option<Vec<PerpFeeTier>>,
+    liquidation_auction_duration_opt: Option<u64>,
+    liquidation_auction_start_fraction_opt: Option<f32>,
+    liquidation_auction_full_fee_depth_opt: Option<f32>,
+    liquidation_close_factor_opt: Option<f32>,
+    liquidation_close_factor_window_opt: Option<u64>,
+    self_liquidation_fee_fraction_opt: Option<f32>,
 ) -> Result<()> {
     let mut group = ctx.accounts.group.load_mut()?;
 
@@ -106,5 +117,94 @@ pub fn group_edit(
         group.buyback_fees_expiry_interval = buyback_fees_expiry_interval;
     }
 
+    if let Some(perp_fee_tiers) = perp_fee_tiers_opt {
+        require_gte!(MAX_PERP_FEE_TIERS, perp_fee_tiers.len());
+        for tier in perp_fee_tiers.iter() {
+            require_gte!(tier.taker_fee, 0.0);
+            require_gte!(tier.maker_fee, -tier.taker_fee);
+        }
+        require!(
+            perp_fee_tiers
+                .windows(2)
+                .all(|w| w[0].min_volume < w[1].min_volume),
+            MangoError::SomeError
+        );
+        msg!(
+            "Perp fee tiers old {:?}, new {:?}",
+            group.perp_fee_tiers(),
+            perp_fee_tiers
+        );
+        group.perp_fee_tiers = Default::default();
+        group.perp_fee_tiers[..perp_fee_tiers.len()].copy_from_slice(&perp_fee_tiers);
+        group.perp_fee_tiers_count = perp_fee_tiers.len() as u8;
+    }
+
+    if let Some(liquidation_auction_duration) = liquidation_auction_duration_opt {
+        msg!(
+            "Liquidation auction duration old {:?}, new {:?}",
+            group.liquidation_auction_duration,
+            liquidation_auction_duration
+        );
+        group.liquidation_auction_duration = liquidation_auction_duration;
+    }
+
+    if let Some(liquidation_auction_start_fraction) = liquidation_auction_start_fraction_opt {
+        require_msg!(
+            (0.0..=1.0).contains(&liquidation_auction_start_fraction),
+            "liquidation auction start fraction must be between 0 and 1"
+        );
+        msg!(
+            "Liquidation auction start fraction old {:?}, new {:?}",
+            group.liquidation_auction_start_fraction,
+            liquidation_auction_start_fraction
+        );
+        group.liquidation_auction_start_fraction = liquidation_auction_start_fraction;
+    }
+
+    if let Some(liquidation_auction_full_fee_depth) = liquidation_auction_full_fee_depth_opt {
+        require_gte!(liquidation_auction_full_fee_depth, 0.0);
+        msg!(
+            "Liquidation auction full fee depth old {:?}, new {:?}",
+            group.liquidation_auction_full_fee_depth,
+            liquidation_auction_full_fee_depth
+        );
+        group.liquidation_auction_full_fee_depth = liquidation_auction_full_fee_depth;
+    }
+
+    if let Some(liquidation_close_factor) = liquidation_close_factor_opt {
+        require_msg!(
+            (0.0..=1.0).contains(&liquidation_close_factor),
+            "liquidation close factor must be between 0 and 1"
+        );
+        msg!(
+            "Liquidation close factor old {:?}, new {:?}",
+            group.liquidation_close_factor,
+            liquidation_close_factor
+        );
+        group.liquidation_close_factor = liquidation_close_factor;
+    }
+
+    if let Some(liquidation_close_factor_window) = liquidation_close_factor_window_opt {
+        msg!(
+            "Liquidation close factor window old {:?}, new {:?}",
+            group.liquidation_close_factor_window,
+            liquidation_close_factor_window
+        );
+        group.liquidation_close_factor_window = liquidation_close_factor_window;
+    }
+
+    if let Some(self_liquidation_fee_fraction) = self_liquidation_fee_fraction_opt {
+        require_msg!(
+            (0.0..=1.0).contains(&self_liquidation_fee_fraction),
+            "self liquidation fee fraction must be between 0 and 1"
+        );
+        msg!(
+            "Self liquidation fee fraction old {:?}, new {:?}",
+            group.self_liquidation_fee_fraction,
+            self_liquidation_fee_fraction
+        );
+        group.self_liquidation_fee_fraction = self_liquidation_fee_fraction;
+    }
+
     Ok(())
 }
@@ -21,6 +21,7 @@ pub fn health_region_begin<'key, 'accounts, 'remaining, 'info>(
         crate::instruction::Serum3CancelAllOrders::discriminator(),
         crate::instruction::Serum3CancelOrder::discriminator(),
         crate::instruction::Serum3PlaceOrder::discriminator(),
+        crate::instruction::Serum3PlaceOrderV2::discriminator(),
         crate::instruction::Serum3SettleFunds::discriminator(),
         crate::instruction::Serum3SettleFundsV2::discriminator(),
     ];
@@ -40,6 +41,10 @@ pub fn health_region_begin<'key, 'accounts, 'remaining, 'info>(
         // There must be a matching HealthRegionEnd instruction
         let mut index = current_index + 1;
         let mut found_end = false;
+        // Flash loans may be used inside a health region. Other programs may only
+        // be called between their begin and end instructions. FlashLoanBegin
+        // validates the instructions up to its FlashLoanEnd itself.
+        let mut in_flash_loan = false;
         loop {
             let ix = match tx_instructions::load_instruction_at_checked(index, ixs) {
                 Ok(ix) => ix,
@@ -48,6 +53,10 @@ pub fn health_region_begin<'key, 'accounts, 'remaining, 'info>(
             };
             index += 1;
 
+            if in_flash_loan && ix.program_id != crate::id() {
+                continue;
+            }
+
             require_keys_eq!(
                 ix.program_id,
                 crate::id(),
@@ -58,8 +67,18 @@ pub fn health_region_begin<'key, 'accounts, 'remaining, 'info>(
             if discriminator == crate::instruction::HealthRegionEnd::discriminator() {
                 // check that it's for the same account
                 require_keys_eq!(ix.accounts[0].pubkey, ctx.accounts.account.key());
+                require_msg!(
+                    !in_flash_loan,
+                    "HealthRegionEnd must not be between FlashLoanBegin and FlashLoanEnd"
+                );
                 found_end = true;
                 break;
+            } else if discriminator == crate::instruction::FlashLoanBegin::discriminator() {
+                require!(!in_flash_loan, MangoError::HealthRegionBadInnerInstruction);
+                in_flash_loan = true;
+            } else if discriminator == crate::instruction::FlashLoanEnd::discriminator() {
+                require!(in_flash_loan, MangoError::HealthRegionBadInnerInstruction);
+                in_flash_loan = false;
             } else {
                 require!(
                     allowed_inner_ix.contains(&discriminator),
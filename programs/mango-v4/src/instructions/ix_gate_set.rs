@@ -65,6 +65,13 @@ pub fn ix_gate_set(ctx: Context<IxGateSet>, ix_gate: u128) -> Result<()> {
     log_if_changed(&group, ix_gate, IxGate::TokenUpdateIndexAndRate);
     log_if_changed(&group, ix_gate, IxGate::TokenWithdraw);
     log_if_changed(&group, ix_gate, IxGate::AccountBuybackFeesWithMngo);
+    log_if_changed(&group, ix_gate, IxGate::TokenClaimRewards);
+    log_if_changed(&group, ix_gate, IxGate::FlashLoanSelfLiquidation);
+    log_if_changed(&group, ix_gate, IxGate::PerpAdl);
+    log_if_changed(&group, ix_gate, IxGate::PerpIsolatedCollateral);
+    log_if_changed(&group, ix_gate, IxGate::PerpLiqIsolated);
+    log_if_changed(&group, ix_gate, IxGate::AccountHealthSnapshot);
+    log_if_changed(&group, ix_gate, IxGate::Serum3SettleFundsPermissionless);
 
     group.ix_gate = ix_gate;
 
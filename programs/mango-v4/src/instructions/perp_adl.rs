@@ -0,0 +1,314 @@
+use anchor_lang::prelude::*;
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::accounts_zerocopy::*;
+use crate::error::*;
+use crate::health::*;
+use crate::logs::{emit_perp_balances, PerpAdlLog};
+use crate::state::*;
+
+/// Auto-deleveraging: close the base position of a bankrupt liqee against an
+/// opposite-side counterparty at the liqee's bankruptcy price.
+///
+/// The bankruptcy price is the price at which closing the liqee's base position
+/// brings its pnl in this market to zero. The counterparty takes the difference to
+/// the oracle price as a loss, limited by its unsettled pnl. Deleveraging only
+/// starts once the group insurance fund can no longer cover the market's losses.
+///
+/// Counterparties are ranked by PerpPosition::adl_score(). The caller passes the
+/// competing candidates as the first num_candidates remaining accounts and the
+/// counterparty's score must reach the market's adl_min_score and be at least the
+/// score of every candidate. The best candidate that wasn't deleveraged is stored
+/// on the market and must be passed again by later calls for the same side.
+pub fn perp_adl(ctx: Context<PerpAdl>, max_base_transfer: u64, num_candidates: u8) -> Result<()> {
+    let group_pk = &ctx.accounts.group.key();
+    let liqee_pk = ctx.accounts.liqee.key();
+    let counterparty_pk = ctx.accounts.counterparty.key();
+    require_keys_neq!(liqee_pk, counterparty_pk);
+
+    let num_candidates = num_candidates as usize;
+    require_gte!(ctx.remaining_accounts.len(), num_candidates);
+    let (candidate_ais, health_ais) = ctx.remaining_accounts.split_at(num_candidates);
+
+    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+    let mut counterparty = ctx.accounts.counterparty.load_full_mut()?;
+
+    let perp_market_index = ctx.accounts.perp_market.load()?.perp_market_index;
+    let liqee_base_lots = liqee.perp_position(perp_market_index)?.base_position_lots();
+
+    let (mut liqee_health_cache, mut counterparty_health_cache, oracle_price, candidate_scores) = {
+        let account_retriever = ScanningAccountRetriever::new(health_ais, group_pk)
+            .context("create account retriever")?;
+        let perp_market = ctx.accounts.perp_market.load()?;
+
+        // Get oracle price for market. Price is validated inside
+        let oracle_price = perp_market.oracle_price(
+            &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?,
+            None, // checked in health
+        )?;
+
+        let candidate_scores = candidate_ais
+            .iter()
+            .map(|ai| {
+                require_keys_neq!(ai.key(), liqee_pk);
+                require_keys_neq!(ai.key(), counterparty_pk);
+                let score = candidate_adl_score(
+                    ai,
+                    group_pk,
+                    &perp_market,
+                    oracle_price,
+                    liqee_base_lots,
+                    &account_retriever,
+                )?;
+                Ok((ai.key(), score))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        (
+            new_health_cache(&liqee.borrow(), &account_retriever)
+                .context("create liqee health cache")?,
+            new_health_cache(&counterparty.borrow(), &account_retriever)
+                .context("create counterparty health cache")?,
+            oracle_price,
+            candidate_scores,
+        )
+    };
+    liqee_health_cache.require_after_phase1_liquidation()?;
+
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    if !liqee.check_liquidatable(&liqee_health_cache, now_ts)? {
+        return Ok(());
+    }
+
+    let liqee_equity = liqee_health_cache.equity();
+    require_msg!(
+        liqee_equity.is_negative(),
+        "liqee is not bankrupt, equity {}",
+        liqee_equity
+    );
+    let counterparty_equity = counterparty_health_cache.equity();
+
+    let mut perp_market = ctx.accounts.perp_market.load_mut()?;
+    require_msg!(
+        perp_market.adl_enabled(),
+        "adl is not enabled for perp market {}",
+        perp_market_index
+    );
+
+    // Bankruptcies in markets covered by the insurance fund are settled from it with
+    // perp_liq_negative_pnl_or_bankruptcy for as long as it has funds
+    if perp_market.elligible_for_group_insurance_fund() {
+        let insurance_vault_amount = ctx.accounts.insurance_vault.amount;
+        require_msg!(
+            insurance_vault_amount == 0,
+            "the insurance fund is not exhausted, {} left",
+            insurance_vault_amount
+        );
+    }
+
+    // Isolated positions are liquidated with perp_liq_isolated
+    require!(
+        !liqee.is_isolated_perp(perp_market_index)
+            && !counterparty.is_isolated_perp(perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
+
+    let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+    let counterparty_perp_position = counterparty.perp_position_mut(perp_market_index)?;
+    require!(
+        !liqee_perp_position.has_open_taker_fills()
+            && !counterparty_perp_position.has_open_taker_fills(),
+        MangoError::HasOpenPerpTakerFills
+    );
+    liqee_perp_position.settle_funding(&perp_market);
+    counterparty_perp_position.settle_funding(&perp_market);
+
+    let counterparty_base_lots = counterparty_perp_position.base_position_lots();
+    require_msg!(
+        liqee_base_lots != 0,
+        "liqee has no base position in perp market {}",
+        perp_market_index
+    );
+    require_msg!(
+        counterparty_base_lots.signum() == -liqee_base_lots.signum(),
+        "counterparty position {} is not on the opposite side of liqee position {}",
+        counterparty_base_lots,
+        liqee_base_lots
+    );
+
+    let score =
+        counterparty_perp_position.adl_score(&perp_market, oracle_price, counterparty_equity)?;
+    require_msg!(
+        score.is_positive() && score >= perp_market.adl_min_score,
+        "counterparty adl score {} is below the minimum {}",
+        score,
+        perp_market.adl_min_score
+    );
+
+    //
+    // Check the ranking against the competing candidates
+    //
+    let next_candidate = if counterparty_base_lots > 0 {
+        perp_market.adl_next_long
+    } else {
+        perp_market.adl_next_short
+    };
+    require_msg!(
+        next_candidate == Pubkey::default()
+            || next_candidate == counterparty_pk
+            || candidate_scores.iter().any(|(pk, _)| *pk == next_candidate),
+        "the market's next adl candidate {} must be passed",
+        next_candidate
+    );
+    for (candidate_pk, candidate_score) in candidate_scores.iter() {
+        require_msg!(
+            *candidate_score <= score,
+            "counterparties must be deleveraged by descending adl score, candidate {} has score {} above {}",
+            candidate_pk,
+            candidate_score,
+            score
+        );
+    }
+
+    //
+    // Determine the bankruptcy price and how much base can be closed
+    //
+    let liqee_pnl = liqee_perp_position.unsettled_pnl(&perp_market, oracle_price)?;
+    require_msg!(
+        liqee_pnl.is_negative(),
+        "liqee has no loss in perp market {}, pnl {}",
+        perp_market_index,
+        liqee_pnl
+    );
+    let base_lot_size = I80F48::from(perp_market.base_lot_size);
+    let liqee_base_native = I80F48::from(liqee_base_lots) * base_lot_size;
+    // Closing the whole position at this price brings its pnl to zero
+    let bankruptcy_price = -liqee_perp_position.quote_position_native() / liqee_base_native;
+    // The counterparty's loss per closed lot, compared to closing at the oracle price
+    let loss_per_lot = (bankruptcy_price - oracle_price).abs() * base_lot_size;
+    let counterparty_pnl = counterparty_perp_position.unsettled_pnl(&perp_market, oracle_price)?;
+    let max_lots = liqee_base_lots
+        .unsigned_abs()
+        .min(counterparty_base_lots.unsigned_abs())
+        .min(max_base_transfer);
+    let base_transfer = I80F48::from(max_lots)
+        .min((counterparty_pnl / loss_per_lot).floor())
+        .to_num::<u64>();
+    if base_transfer == 0 {
+        msg!("no base can be deleveraged");
+        return Ok(());
+    }
+
+    // Remember the best remaining candidate, later calls for this side must pass it
+    let best_candidate = candidate_scores
+        .iter()
+        .filter(|(_, candidate_score)| candidate_score.is_positive())
+        .max_by_key(|(_, candidate_score)| *candidate_score)
+        .map_or(Pubkey::default(), |(pk, _)| *pk);
+    if counterparty_base_lots > 0 {
+        perp_market.adl_next_long = best_candidate;
+    } else {
+        perp_market.adl_next_short = best_candidate;
+    }
+
+    //
+    // Close the positions at the bankruptcy price
+    //
+    let base_transfer_liqee = if liqee_base_lots > 0 {
+        -(base_transfer as i64)
+    } else {
+        base_transfer as i64
+    };
+    let quote_transfer_liqee =
+        -I80F48::from(base_transfer_liqee) * base_lot_size * bankruptcy_price;
+    liqee_perp_position.record_trade(&mut perp_market, base_transfer_liqee, quote_transfer_liqee);
+    counterparty_perp_position.record_trade(
+        &mut perp_market,
+        -base_transfer_liqee,
+        -quote_transfer_liqee,
+    );
+
+    msg!(
+        "deleveraged {} lots at bankruptcy price {}, counterparty score {}",
+        base_transfer,
+        bankruptcy_price,
+        score
+    );
+    emit!(PerpAdlLog {
+        mango_group: *group_pk,
+        liqee: liqee_pk,
+        counterparty: counterparty_pk,
+        perp_market_index,
+        base_transfer_liqee,
+        quote_transfer_liqee: quote_transfer_liqee.to_bits(),
+        bankruptcy_price: bankruptcy_price.to_bits(),
+        adl_score: score.to_bits(),
+        price: oracle_price.to_bits(),
+    });
+    emit_perp_balances(*group_pk, liqee_pk, liqee_perp_position, &perp_market);
+    emit_perp_balances(
+        *group_pk,
+        counterparty_pk,
+        counterparty_perp_position,
+        &perp_market,
+    );
+
+    //
+    // Health checks
+    //
+    liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+    counterparty_health_cache.recompute_perp_info(counterparty_perp_position, &perp_market)?;
+
+    let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
+    liqee
+        .fixed
+        .maybe_recover_from_being_liquidated(liqee_liq_end_health);
+
+    // Deleveraging must not make the counterparty liquidatable
+    let counterparty_maint_health = counterparty_health_cache.health(HealthType::Maint);
+    require!(
+        counterparty_maint_health >= 0,
+        MangoError::HealthMustBePositive
+    );
+
+    Ok(())
+}
+
+/// Adl score of a candidate passed to perp_adl, zero if its position can't be
+/// deleveraged against a liqee with `liqee_base_lots`.
+///
+/// Closed accounts score zero, so a stored next candidate can always be passed.
+fn candidate_adl_score(
+    ai: &AccountInfo,
+    group_pk: &Pubkey,
+    perp_market: &PerpMarket,
+    oracle_price: I80F48,
+    liqee_base_lots: i64,
+    account_retriever: &ScanningAccountRetriever,
+) -> Result<I80F48> {
+    if ai.data_is_empty() {
+        return Ok(I80F48::ZERO);
+    }
+    let loader = AccountLoader::<MangoAccountFixed>::try_from(ai)?;
+    let candidate = loader.load_full()?;
+    require_keys_eq!(candidate.fixed.group, *group_pk);
+
+    let perp_market_index = perp_market.perp_market_index;
+    if candidate.is_isolated_perp(perp_market_index) {
+        return Ok(I80F48::ZERO);
+    }
+    let mut perp_position = match candidate.perp_position(perp_market_index) {
+        Ok(perp_position) => perp_position.clone(),
+        Err(_) => return Ok(I80F48::ZERO),
+    };
+    if perp_position.base_position_lots().signum() != -liqee_base_lots.signum() {
+        return Ok(I80F48::ZERO);
+    }
+    perp_position.settle_funding(perp_market);
+
+    let equity = new_health_cache(&candidate.borrow(), account_retriever)
+        .context("create candidate health cache")?
+        .equity();
+    perp_position.adl_score(perp_market, oracle_price, equity)
+}
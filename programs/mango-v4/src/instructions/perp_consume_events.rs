@@ -1,11 +1,12 @@
 use anchor_lang::prelude::*;
 use bytemuck::cast_ref;
+use fixed::types::I80F48;
 
 use crate::error::MangoError;
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{emit_perp_balances, FillLogV2};
+use crate::logs::{emit_perp_balances, FillLogV3, PerpReferrerRewardLog};
 
 /// Load a mango account by key from the list of account infos.
 ///
@@ -65,6 +66,28 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
             EventType::Fill => {
                 let fill: &FillEvent = cast_ref(event);
 
+                // A missing referrer must not stall the event queue: the fill is consumed
+                // and the reward stays in the market's fees.
+                let referrer_ai_opt = match fill.referrer() {
+                    Some(referrer) if perp_market.referrer_fee_share > 0.0 => {
+                        let ai_opt = mango_account_ais.iter().find(|ai| ai.key == &referrer);
+                        if ai_opt.is_none() {
+                            msg!(
+                                "Unable to find referrer account {}, skipping the reward",
+                                referrer.to_string()
+                            );
+                        }
+                        ai_opt
+                    }
+                    _ => None,
+                };
+
+                let maker_fee;
+                let maker_realized_pnl;
+                let maker_entry_price;
+                let taker_realized_pnl;
+                let taker_entry_price;
+
                 // handle self trade separately because of rust borrow checker
                 if fill.maker == fill.taker {
                     load_mango_account!(
@@ -74,8 +97,23 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
                         group,
                         event_queue
                     );
-                    maker_taker.execute_perp_maker(perp_market_index, &mut perp_market, fill)?;
-                    maker_taker.execute_perp_taker(perp_market_index, &mut perp_market, fill)?;
+                    (maker_fee, maker_realized_pnl) = maker_taker.execute_perp_maker(
+                        perp_market_index,
+                        &mut perp_market,
+                        fill,
+                        group.perp_fee_tiers(),
+                    )?;
+                    maker_entry_price = maker_taker
+                        .perp_position(perp_market_index)?
+                        .avg_entry_price_per_base_lot;
+                    taker_realized_pnl = maker_taker.execute_perp_taker(
+                        perp_market_index,
+                        &mut perp_market,
+                        fill,
+                    )?;
+                    taker_entry_price = maker_taker
+                        .perp_position(perp_market_index)?
+                        .avg_entry_price_per_base_lot;
                     emit_perp_balances(
                         group_key,
                         fill.maker,
@@ -86,8 +124,20 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
                     load_mango_account!(maker, fill.maker, mango_account_ais, group, event_queue);
                     load_mango_account!(taker, fill.taker, mango_account_ais, group, event_queue);
 
-                    maker.execute_perp_maker(perp_market_index, &mut perp_market, fill)?;
-                    taker.execute_perp_taker(perp_market_index, &mut perp_market, fill)?;
+                    (maker_fee, maker_realized_pnl) = maker.execute_perp_maker(
+                        perp_market_index,
+                        &mut perp_market,
+                        fill,
+                        group.perp_fee_tiers(),
+                    )?;
+                    taker_realized_pnl =
+                        taker.execute_perp_taker(perp_market_index, &mut perp_market, fill)?;
+                    maker_entry_price = maker
+                        .perp_position(perp_market_index)?
+                        .avg_entry_price_per_base_lot;
+                    taker_entry_price = taker
+                        .perp_position(perp_market_index)?
+                        .avg_entry_price_per_base_lot;
                     emit_perp_balances(
                         group_key,
                         fill.maker,
@@ -101,7 +151,10 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
                         &perp_market,
                     );
                 }
-                emit!(FillLogV2 {
+                if let Some(referrer_ai) = referrer_ai_opt {
+                    pay_referrer_reward(group_key, &mut perp_market, fill, maker_fee, referrer_ai)?;
+                }
+                emit!(FillLogV3 {
                     mango_group: group_key,
                     market_index: perp_market_index,
                     taker_side: fill.taker_side as u8,
@@ -111,13 +164,17 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
                     seq_num: fill.seq_num,
                     maker: fill.maker,
                     maker_client_order_id: fill.maker_client_order_id,
-                    maker_fee: fill.maker_fee,
+                    maker_fee: maker_fee.to_num::<f32>(),
                     maker_timestamp: fill.maker_timestamp,
                     taker: fill.taker,
                     taker_client_order_id: fill.taker_client_order_id,
                     taker_fee: fill.taker_fee,
                     price: fill.price,
                     quantity: fill.quantity,
+                    maker_realized_pnl: maker_realized_pnl.to_bits(),
+                    maker_entry_price_per_base_lot: maker_entry_price,
+                    taker_realized_pnl: taker_realized_pnl.to_bits(),
+                    taker_entry_price_per_base_lot: taker_entry_price,
                 });
             }
             EventType::Out => {
@@ -135,3 +192,73 @@ pub fn perp_consume_events(ctx: Context<PerpConsumeEvents>, limit: usize) -> Res
     }
     Ok(())
 }
+
+/// Credit the referrer's share of the taker fee of a fill to its perp position.
+///
+/// The fee share comes out of the market's fees_accrued and never exceeds the net
+/// fees of the fill. The referrer's perp position is reopened if it was closed since
+/// the order was placed. If the referrer can't be credited at all, for example because
+/// the account was closed, the reward is skipped instead of blocking the event queue.
+fn pay_referrer_reward(
+    group_key: Pubkey,
+    perp_market: &mut PerpMarket,
+    fill: &FillEvent,
+    maker_fee: I80F48,
+    referrer_ai: &AccountInfo,
+) -> Result<()> {
+    let referrer_pk = referrer_ai.key();
+    let fee_share = I80F48::from_num(perp_market.referrer_fee_share);
+
+    let referrer_loader = match AccountLoader::<MangoAccountFixed>::try_from(referrer_ai) {
+        Ok(loader) => loader,
+        Err(_) => {
+            msg!("Referrer account {} is not a mango account", referrer_pk);
+            return Ok(());
+        }
+    };
+    let mut referrer = match referrer_loader.load_full_mut() {
+        Ok(referrer) => referrer,
+        Err(_) => {
+            msg!("Referrer account {} can't be written", referrer_pk);
+            return Ok(());
+        }
+    };
+    if referrer.fixed.group != group_key {
+        msg!("Referrer account {} is in a different group", referrer_pk);
+        return Ok(());
+    }
+
+    let quote_native =
+        I80F48::from(perp_market.quote_lot_size) * I80F48::from(fill.price * fill.quantity);
+    let taker_fee = I80F48::from_num(fill.taker_fee);
+    let reward = (quote_native * taker_fee * fee_share)
+        .min(quote_native * (taker_fee + maker_fee))
+        .max(I80F48::ZERO);
+    if reward <= 0 {
+        return Ok(());
+    }
+
+    let perp_market_index = perp_market.perp_market_index;
+    let pp = match referrer.ensure_perp_position(perp_market_index, perp_market.settle_token_index)
+    {
+        Ok((pp, _)) => pp,
+        Err(_) => {
+            msg!("Referrer account {} has no free perp position", referrer_pk);
+            return Ok(());
+        }
+    };
+    pp.record_referrer_reward(reward);
+    perp_market.fees_accrued -= reward;
+
+    emit!(PerpReferrerRewardLog {
+        mango_group: group_key,
+        market_index: perp_market_index,
+        referrer: referrer_pk,
+        taker: fill.taker,
+        seq_num: fill.seq_num,
+        reward: reward.to_bits(),
+    });
+    emit_perp_balances(group_key, referrer_pk, pp, perp_market);
+
+    Ok(())
+}
@@ -97,11 +97,17 @@ pub fn perp_create_market(
         padding3: Default::default(),
         settle_pnl_limit_window_size_ts,
         reduce_only: 0,
+        adl_enabled: 0,
         padding4: Default::default(),
         maint_overall_asset_weight: I80F48::from_num(maint_overall_asset_weight),
         init_overall_asset_weight: I80F48::from_num(init_overall_asset_weight),
         positive_pnl_liquidation_fee: I80F48::from_num(positive_pnl_liquidation_fee),
-        reserved: [0; 1888],
+        referrer_fee_share: 0.0,
+        padding5: Default::default(),
+        adl_min_score: I80F48::ZERO,
+        adl_next_long: Pubkey::default(),
+        adl_next_short: Pubkey::default(),
+        reserved: [0; 1800],
     };
 
     let oracle_price =
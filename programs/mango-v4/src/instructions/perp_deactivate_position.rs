@@ -13,6 +13,10 @@ pub fn perp_deactivate_position(ctx: Context<PerpDeactivatePosition>) -> Result<
     );
 
     let perp_market = ctx.accounts.perp_market.load()?;
+    require!(
+        !account.is_isolated_perp(perp_market.perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
     let perp_position = account.perp_position_mut(perp_market.perp_market_index)?;
 
     // Is the perp position closable?
@@ -38,6 +38,9 @@ pub fn perp_edit_market(
     reset_stable_price: bool,
     positive_pnl_liquidation_fee_opt: Option<f32>,
     name_opt: Option<String>,
+    referrer_fee_share_opt: Option<f32>,
+    adl_enabled_opt: Option<bool>,
+    adl_min_score_opt: Option<f32>,
 ) -> Result<()> {
     let group = ctx.accounts.group.load()?;
 
@@ -330,6 +333,39 @@ pub fn perp_edit_market(
         require_group_admin = true;
     };
 
+    if let Some(referrer_fee_share) = referrer_fee_share_opt {
+        require_gte!(referrer_fee_share, 0.0);
+        require_gte!(1.0, referrer_fee_share);
+        msg!(
+            "Referrer fee share: old - {:?}, new - {:?}",
+            perp_market.referrer_fee_share,
+            referrer_fee_share
+        );
+        perp_market.referrer_fee_share = referrer_fee_share;
+        require_group_admin = true;
+    }
+
+    if let Some(adl_enabled) = adl_enabled_opt {
+        msg!(
+            "ADL enabled: old - {:?}, new - {:?}",
+            perp_market.adl_enabled,
+            u8::from(adl_enabled)
+        );
+        perp_market.adl_enabled = u8::from(adl_enabled);
+        require_group_admin = true;
+    }
+
+    if let Some(adl_min_score) = adl_min_score_opt {
+        require_gte!(adl_min_score, 0.0);
+        msg!(
+            "ADL min score: old - {:?}, new - {:?}",
+            perp_market.adl_min_score,
+            adl_min_score
+        );
+        perp_market.adl_min_score = I80F48::from_num(adl_min_score);
+        require_group_admin = true;
+    }
+
     // account constraint #1
     if require_group_admin {
         require!(
@@ -0,0 +1,209 @@
+use anchor_lang::prelude::*;
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::accounts_zerocopy::*;
+use crate::error::*;
+use crate::health::*;
+use crate::logs::{emit_token_balance, PerpIsolatedCollateralLog};
+use crate::state::*;
+
+/// Moves settle token deposits into the dedicated collateral of an isolated perp position.
+///
+/// If the account has no isolated position in the market yet, one is created. That is only
+/// possible if the existing perp position, if any, is empty.
+pub fn perp_isolated_deposit(ctx: Context<PerpIsolatedCollateral>, amount: u64) -> Result<()> {
+    require_msg!(amount > 0, "deposit amount must be positive");
+
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    // account constraint #1
+    require!(
+        account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+        MangoError::SomeError
+    );
+
+    let (perp_market_index, settle_token_index) = {
+        let perp_market = ctx.accounts.perp_market.load()?;
+        if !account.is_isolated_perp(perp_market.perp_market_index) {
+            // Only empty positions may become isolated, cross margined risk can't be moved
+            if let Ok(perp_position) = account.perp_position_mut(perp_market.perp_market_index) {
+                perp_position.settle_funding(&perp_market);
+                require_msg!(
+                    perp_position.base_position_lots() == 0
+                        && perp_position.quote_position_native() == 0
+                        && !perp_position.has_open_orders_or_fills(),
+                    "perp position for market {} must be empty to become isolated",
+                    perp_market.perp_market_index
+                );
+            }
+            account.create_isolated_perp_position(
+                perp_market.perp_market_index,
+                perp_market.settle_token_index,
+            )?;
+        }
+        (
+            perp_market.perp_market_index,
+            perp_market.settle_token_index,
+        )
+    };
+    account.ensure_perp_position(perp_market_index, settle_token_index)?;
+
+    // Health check _after_ the perp position and isolated collateral are guaranteed to exist
+    let pre_health_opt = if !account.fixed.is_in_health_region() {
+        let retriever =
+            new_fixed_order_account_retriever(ctx.remaining_accounts, &account.borrow())?;
+        let health_cache =
+            new_health_cache(&account.borrow(), &retriever).context("pre-deposit init health")?;
+        let pre_init_health = account.check_health_pre(&health_cache)?;
+        Some((health_cache, pre_init_health))
+    } else {
+        None
+    };
+
+    let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
+    // account constraint #2
+    require!(
+        settle_bank.token_index == settle_token_index,
+        MangoError::InvalidBank
+    );
+
+    let now_slot = Clock::get()?.slot;
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    let oracle_price = settle_bank.oracle_price(
+        &AccountInfoRef::borrow(ctx.accounts.settle_oracle.as_ref())?,
+        Some(now_slot),
+    )?;
+
+    // Move the funds: isolated collateral can't be borrowed
+    let amount_i80f48 = I80F48::from(amount);
+    let token_position = account.token_position_mut(settle_token_index)?.0;
+    require_msg!(
+        token_position.native(&settle_bank) >= amount_i80f48,
+        "not enough settle token deposits, can't borrow into isolated collateral"
+    );
+    settle_bank.withdraw_without_fee(token_position, amount_i80f48, now_ts, oracle_price)?;
+    let token_position_after = *token_position;
+
+    let isolated = account.isolated_perp_position_mut(perp_market_index)?;
+    settle_bank.deposit_isolated(isolated, amount_i80f48)?;
+
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        &token_position_after,
+        settle_bank.deposit_index,
+        settle_bank.borrow_index,
+    );
+    emit!(PerpIsolatedCollateralLog {
+        mango_group: ctx.accounts.group.key(),
+        mango_account: ctx.accounts.account.key(),
+        perp_market_index,
+        token_index: settle_token_index,
+        change: amount_i80f48.to_bits(),
+        indexed_collateral: isolated.indexed_collateral.to_bits(),
+        deposit_index: settle_bank.deposit_index.to_bits(),
+    });
+
+    //
+    // Health check
+    //
+    if let Some((mut health_cache, pre_init_health)) = pre_health_opt {
+        health_cache.adjust_token_balance(&settle_bank, -amount_i80f48)?;
+        health_cache.adjust_isolated_perp_collateral(perp_market_index, amount_i80f48)?;
+        account.check_health_post(&health_cache, pre_init_health)?;
+    }
+
+    Ok(())
+}
+
+/// Moves isolated perp collateral back into the account's settle token position.
+///
+/// The isolated position must keep a non-negative init health. Withdrawing all
+/// collateral of a flat position ends the isolation.
+///
+/// An amount of u64::MAX withdraws all collateral.
+pub fn perp_isolated_withdraw(ctx: Context<PerpIsolatedCollateral>, amount: u64) -> Result<()> {
+    require_msg!(amount > 0, "withdraw amount must be positive");
+
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    // account constraint #1
+    require!(
+        account.fixed.is_owner_or_delegate(ctx.accounts.owner.key()),
+        MangoError::SomeError
+    );
+
+    let (perp_market_index, settle_token_index) = {
+        let perp_market = ctx.accounts.perp_market.load()?;
+        (
+            perp_market.perp_market_index,
+            perp_market.settle_token_index,
+        )
+    };
+    account.isolated_perp_position(perp_market_index)?;
+
+    // The isolated health must always be checked, even in health regions
+    let mut health_cache = {
+        let retriever =
+            new_fixed_order_account_retriever(ctx.remaining_accounts, &account.borrow())?;
+        new_health_cache(&account.borrow(), &retriever).context("pre-withdraw health")?
+    };
+
+    let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
+    // account constraint #2
+    require!(
+        settle_bank.token_index == settle_token_index,
+        MangoError::InvalidBank
+    );
+
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+    let isolated = account.isolated_perp_position_mut(perp_market_index)?;
+    let amount_i80f48 = if amount == u64::MAX {
+        isolated.native(&settle_bank)
+    } else {
+        I80F48::from(amount)
+    };
+    settle_bank.withdraw_isolated(isolated, amount_i80f48)?;
+    let indexed_collateral = isolated.indexed_collateral;
+
+    let token_position = account.token_position_mut(settle_token_index)?.0;
+    settle_bank.deposit(token_position, amount_i80f48, now_ts)?;
+
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        token_position,
+        settle_bank.deposit_index,
+        settle_bank.borrow_index,
+    );
+    emit!(PerpIsolatedCollateralLog {
+        mango_group: ctx.accounts.group.key(),
+        mango_account: ctx.accounts.account.key(),
+        perp_market_index,
+        token_index: settle_token_index,
+        change: (-amount_i80f48).to_bits(),
+        indexed_collateral: indexed_collateral.to_bits(),
+        deposit_index: settle_bank.deposit_index.to_bits(),
+    });
+
+    //
+    // Health check on the isolated position only: the account's health can only increase
+    //
+    health_cache.adjust_isolated_perp_collateral(perp_market_index, -amount_i80f48)?;
+    let isolated_init_health =
+        health_cache.isolated_perp_health(perp_market_index, HealthType::Init)?;
+    msg!("post_isolated_init_health: {}", isolated_init_health);
+    require!(isolated_init_health >= 0, MangoError::HealthMustBePositive);
+
+    // Once all collateral is gone from a flat position, it stops being isolated
+    let perp_position = account.perp_position(perp_market_index)?;
+    if indexed_collateral.is_zero()
+        && perp_position.base_position_lots() == 0
+        && perp_position.quote_position_native() == 0
+        && !perp_position.has_open_orders_or_fills()
+    {
+        account.deactivate_isolated_perp_position(perp_market_index)?;
+    }
+
+    Ok(())
+}
@@ -8,7 +8,7 @@ use crate::health::*;
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{emit_perp_balances, PerpLiqBaseOrPositivePnlLog, TokenBalanceLog};
+use crate::logs::{emit_perp_balances, emit_token_balance, PerpLiqBaseOrPositivePnlLog};
 
 /// This instruction deals with increasing health by:
 /// - reducing the liqee's base position
@@ -58,10 +58,19 @@ pub fn perp_liq_base_or_positive_pnl(
     let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
     liqee_health_cache.require_after_phase1_liquidation()?;
 
-    if !liqee.check_liquidatable(&liqee_health_cache)? {
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    if !liqee.check_liquidatable(&liqee_health_cache, now_ts)? {
         return Ok(());
     }
 
+    let group = ctx.accounts.group.load()?;
+    let liquidation_fee_fraction = group.liquidation_fee_fraction(
+        liqee.fixed.being_liquidated_duration(now_ts),
+        liqee_health_cache.maint_deficit_depth(),
+    );
+    msg!("liquidation fee fraction {}", liquidation_fee_fraction);
+    let close_factor = group.liquidation_close_factor();
+
     let mut perp_market = ctx.accounts.perp_market.load_mut()?;
     let perp_market_index = perp_market.perp_market_index;
     let settle_token_index = perp_market.settle_token_index;
@@ -79,6 +88,12 @@ pub fn perp_liq_base_or_positive_pnl(
         None, // checked in health
     )?;
 
+    // Isolated positions are liquidated with perp_liq_isolated
+    require!(
+        !liqee.is_isolated_perp(perp_market_index) && !liqor.is_isolated_perp(perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
+
     // Fetch perp positions for accounts, creating for the liqor if needed
     let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
     require!(
@@ -93,7 +108,6 @@ pub fn perp_liq_base_or_positive_pnl(
     // Settle funding, update limit
     liqee_perp_position.settle_funding(&perp_market);
     liqor_perp_position.settle_funding(&perp_market);
-    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
     liqee_perp_position.update_settle_limit(&perp_market, now_ts);
 
     //
@@ -110,6 +124,8 @@ pub fn perp_liq_base_or_positive_pnl(
             now_ts,
             max_base_transfer,
             max_pnl_transfer,
+            liquidation_fee_fraction,
+            close_factor,
         )?;
 
     //
@@ -137,23 +153,21 @@ pub fn perp_liq_base_or_positive_pnl(
         let liqee_token_position = liqee.token_position(settle_token_index)?;
         let liqor_token_position = liqor.token_position(settle_token_index)?;
 
-        emit!(TokenBalanceLog {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.liqee.key(),
-            token_index: settle_token_index,
-            indexed_position: liqee_token_position.indexed_position.to_bits(),
-            deposit_index: settle_bank.deposit_index.to_bits(),
-            borrow_index: settle_bank.borrow_index.to_bits(),
-        });
+        emit_token_balance(
+            ctx.accounts.group.key(),
+            ctx.accounts.liqee.key(),
+            liqee_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
 
-        emit!(TokenBalanceLog {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.liqor.key(),
-            token_index: settle_token_index,
-            indexed_position: liqor_token_position.indexed_position.to_bits(),
-            deposit_index: settle_bank.deposit_index.to_bits(),
-            borrow_index: settle_bank.borrow_index.to_bits(),
-        });
+        emit_token_balance(
+            ctx.accounts.group.key(),
+            ctx.accounts.liqor.key(),
+            liqor_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
     }
 
     if base_transfer != 0 || pnl_transfer != 0 {
@@ -207,6 +221,8 @@ pub(crate) fn liquidation_action(
     now_ts: u64,
     max_base_transfer: i64,
     max_pnl_transfer: u64,
+    liquidation_fee_fraction: I80F48,
+    close_factor: Option<LiquidationCloseFactor>,
 ) -> Result<(i64, I80F48, I80F48, I80F48)> {
     let perp_market_index = perp_market.perp_market_index;
     let settle_token_index = perp_market.settle_token_index;
@@ -216,6 +232,7 @@ pub(crate) fn liquidation_action(
 
     let perp_info = liqee_health_cache.perp_info(perp_market_index)?;
     let oracle_price = perp_info.prices.oracle;
+    let base_liquidation_fee = perp_market.base_liquidation_fee * liquidation_fee_fraction;
     let base_lot_size = I80F48::from(perp_market.base_lot_size);
     let oracle_price_per_lot = base_lot_size * oracle_price;
 
@@ -245,7 +262,7 @@ pub(crate) fn liquidation_action(
         // and increased by `base * price * (1 - liq_fee) * quote_init_asset_weight`
         let quote_init_asset_weight = I80F48::ONE;
         direction = -1;
-        fee_factor = I80F48::ONE - perp_market.base_liquidation_fee;
+        fee_factor = I80F48::ONE - base_liquidation_fee;
         let asset_price = perp_info.prices.asset(HealthType::LiquidationEnd);
         unweighted_health_per_lot =
             -asset_price * base_lot_size * perp_market.init_base_asset_weight
@@ -261,13 +278,29 @@ pub(crate) fn liquidation_action(
         // and reduced by `base * price * (1 + liq_fee) * quote_init_liab_weight`
         let quote_init_liab_weight = I80F48::ONE;
         direction = 1;
-        fee_factor = I80F48::ONE + perp_market.base_liquidation_fee;
+        fee_factor = I80F48::ONE + base_liquidation_fee;
         let liab_price = perp_info.prices.liab(HealthType::LiquidationEnd);
         unweighted_health_per_lot = liab_price * base_lot_size * perp_market.init_base_liab_weight
             - oracle_price_per_lot * quote_init_liab_weight * fee_factor;
     };
     assert!(unweighted_health_per_lot > 0);
 
+    // The close factor limits how many base lots may be taken over in the current window.
+    // Taking over positive pnl is not limited by it. Like in token liquidation, it's an
+    // error when nothing may be liquidated at all.
+    let max_base_lots = match close_factor.as_ref() {
+        Some(close_factor) if liqee_base_lots != 0 => {
+            let remaining =
+                liqee_perp_position.liquidation_close_factor_remaining(close_factor, now_ts);
+            require!(
+                remaining > 0 || max_pnl_transfer > 0,
+                MangoError::LiquidationCloseFactorReached
+            );
+            max_base_transfer.abs().min(remaining)
+        }
+        _ => max_base_transfer.abs(),
+    };
+
     // Amount of settle token received for each token that is settled
     let spot_gain_per_settled = I80F48::ONE - perp_market.positive_pnl_liquidation_fee;
 
@@ -313,7 +346,7 @@ pub(crate) fn liquidation_action(
             .ceil() // overshoot to aim for init_health >= 0
             .to_num::<i64>()
             .min(liqee_base_lots.abs() - base_reduction)
-            .min(max_base_transfer.abs() - base_reduction)
+            .min(max_base_lots - base_reduction)
             .max(0);
         let unweighted_change = I80F48::from(base_lots) * unweighted_health_per_lot;
         let current_unweighted = *current_unweighted_perp_health;
@@ -391,6 +424,9 @@ pub(crate) fn liquidation_action(
         );
         liqee_perp_position.record_trade(perp_market, base_transfer, quote_transfer);
         liqor_perp_position.record_trade(perp_market, -base_transfer, -quote_transfer);
+        if close_factor.is_some() {
+            liqee_perp_position.record_liquidation(base_transfer);
+        }
     }
 
     //
@@ -487,6 +523,8 @@ mod tests {
         perp_oracle: TestAccount<StubOracle>,
         liqee: MangoAccountValue,
         liqor: MangoAccountValue,
+        close_factor: Option<LiquidationCloseFactor>,
+        now_ts: u64,
     }
 
     impl TestSetup {
@@ -520,6 +558,8 @@ mod tests {
                 perp_oracle,
                 liqee,
                 liqor,
+                close_factor: None,
+                now_ts: 0,
             }
         }
 
@@ -551,9 +591,11 @@ mod tests {
                 &mut setup.liqee.borrow_mut(),
                 &mut liqee_health_cache,
                 liqee_liq_end_health,
-                0,
+                setup.now_ts,
                 max_base,
                 max_pnl,
+                I80F48::ONE,
+                setup.close_factor,
             )?;
 
             Ok(setup)
@@ -859,4 +901,68 @@ mod tests {
             0.1
         );
     }
+
+    #[test]
+    fn test_liq_base_or_positive_pnl_close_factor() {
+        let mut setup = TestSetup::new();
+        {
+            let pm = setup.perp_market.data();
+            pm.init_base_asset_weight = I80F48::from_num(0.5);
+            pm.init_base_liab_weight = I80F48::from_num(1.5);
+            pm.init_overall_asset_weight = I80F48::from_num(0.5);
+        }
+        {
+            perp_p(&mut setup.liqee).record_trade(
+                setup.perp_market.data(),
+                20,
+                I80F48::from_num(-20.0),
+            );
+
+            let settle_bank = setup.settle_bank.data();
+            settle_bank
+                .change_without_fee(
+                    token_p(&mut setup.liqee),
+                    I80F48::from_num(5.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+            settle_bank
+                .change_without_fee(
+                    token_p(&mut setup.liqor),
+                    I80F48::from_num(1000.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+        }
+
+        // without the close factor, the base position is reduced to 10 lots
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 10);
+
+        // a quarter of the position per 100s window, rounded up
+        setup.close_factor = Some(LiquidationCloseFactor {
+            fraction: I80F48::from_num(0.25),
+            window: 100,
+        });
+        setup.now_ts = 1000;
+        let mut result = setup.run(100, 0).unwrap();
+        assert_eq!(perp_p(&mut result.liqee).base_position_lots(), 15);
+
+        // the window's limit is used up
+        result.now_ts = 1050;
+        assert!(result
+            .run(100, 0)
+            .is_anchor_error_with_code(MangoError::LiquidationCloseFactorReached.into()));
+
+        // a new window starts with the reduced position
+        result.now_ts = 1100;
+        let mut result = result.run(100, 0).unwrap();
+        let liqee_perp = perp_p(&mut result.liqee);
+        assert_eq!(liqee_perp.base_position_lots(), 11);
+        assert_eq!(liqee_perp.liq_window_start_ts, 1100);
+        assert_eq!(liqee_perp.liq_window_start_base_lots, 15);
+        assert_eq!(liqee_perp.liq_window_liquidated_base_lots, 4);
+    }
 }
@@ -10,6 +10,8 @@ pub fn perp_liq_force_cancel_orders(
     limit: u8,
 ) -> Result<()> {
     let mut account = ctx.accounts.account.load_full_mut()?;
+    let perp_market_index = ctx.accounts.perp_market.load()?.perp_market_index;
+    let is_isolated = account.is_isolated_perp(perp_market_index);
 
     //
     // Check liqee health if liquidation is allowed
@@ -20,8 +22,17 @@ pub fn perp_liq_force_cancel_orders(
         let health_cache =
             new_health_cache(&account.borrow(), &retriever).context("create health cache")?;
 
-        {
-            let result = account.check_liquidatable(&health_cache);
+        if is_isolated {
+            // Isolated positions are liquidatable based on their own health only
+            let maint_health =
+                health_cache.isolated_perp_health(perp_market_index, HealthType::Maint)?;
+            require!(
+                maint_health < 0 || !account.fixed.is_operational(),
+                MangoError::HealthMustBeNegative
+            );
+        } else {
+            let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+            let result = account.check_liquidatable(&health_cache, now_ts);
             if account.fixed.is_operational() {
                 if !result? {
                     return Ok(());
@@ -57,10 +68,12 @@ pub fn perp_liq_force_cancel_orders(
     //
     // Health check at the end
     //
-    let init_health = health_cache.health(HealthType::LiquidationEnd);
-    account
-        .fixed
-        .maybe_recover_from_being_liquidated(init_health);
+    if !is_isolated {
+        let init_health = health_cache.health(HealthType::LiquidationEnd);
+        account
+            .fixed
+            .maybe_recover_from_being_liquidated(init_health);
+    }
 
     Ok(())
 }
@@ -0,0 +1,352 @@
+use anchor_lang::prelude::*;
+
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::accounts_zerocopy::*;
+use crate::error::*;
+use crate::health::*;
+use crate::logs::{
+    emit_perp_balances, emit_token_balance, PerpIsolatedCollateralLog, PerpLiqBankruptcyLog,
+    PerpLiqIsolatedLog,
+};
+use crate::state::*;
+
+/// Liquidates an isolated perp position once its own maint health is negative.
+///
+/// Only the position and its dedicated collateral are touched, the rest of the
+/// liqee account is unaffected:
+/// 1. the liqor takes over base position at a discount until the isolated health is restored
+/// 2. without base position, negative pnl is paid from the collateral to the liqor,
+///    who takes over the perp loss
+/// 3. losses that exceed the collateral are socialized, or left for auto-deleveraging
+///    if the market has it enabled
+pub fn perp_liq_isolated(
+    ctx: Context<PerpLiqIsolated>,
+    mut max_base_transfer: i64,
+    max_pnl_transfer: u64,
+) -> Result<()> {
+    // Ensure max_base_transfer can be negated
+    max_base_transfer = max_base_transfer.max(i64::MIN + 1);
+
+    let mango_group = ctx.accounts.group.key();
+
+    let (perp_market_index, settle_token_index) = {
+        let perp_market = ctx.accounts.perp_market.load()?;
+        (
+            perp_market.perp_market_index,
+            perp_market.settle_token_index,
+        )
+    };
+
+    require_keys_neq!(ctx.accounts.liqor.key(), ctx.accounts.liqee.key());
+    let mut liqor = ctx.accounts.liqor.load_full_mut()?;
+    // account constraint #1
+    require!(
+        liqor
+            .fixed
+            .is_owner_or_delegate(ctx.accounts.liqor_owner.key()),
+        MangoError::SomeError
+    );
+    require_msg_typed!(
+        !liqor.fixed.being_liquidated(),
+        MangoError::BeingLiquidated,
+        "liqor account"
+    );
+    // the liqor's position is checked with cross margin health
+    require!(
+        !liqor.is_isolated_perp(perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
+
+    let mut liqee = ctx.accounts.liqee.load_full_mut()?;
+
+    // Initial liqee isolated health check
+    let mut liqee_health_cache = {
+        let account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, &mango_group)
+            .context("create account retriever")?;
+        new_health_cache(&liqee.borrow(), &account_retriever)
+            .context("create liqee health cache")?
+    };
+    let liqee_maint_health =
+        liqee_health_cache.isolated_perp_health(perp_market_index, HealthType::Maint)?;
+    require_msg_typed!(
+        liqee_maint_health < 0,
+        MangoError::HealthMustBeNegative,
+        "isolated maint health is {}",
+        liqee_maint_health
+    );
+    let liqee_liq_end_health =
+        liqee_health_cache.isolated_perp_health(perp_market_index, HealthType::LiquidationEnd)?;
+    let asset_price = liqee_health_cache
+        .isolated_perp_info(perp_market_index)?
+        .perp
+        .prices
+        .asset(HealthType::LiquidationEnd);
+    let liab_price = liqee_health_cache
+        .isolated_perp_info(perp_market_index)?
+        .perp
+        .prices
+        .liab(HealthType::LiquidationEnd);
+
+    liqor.ensure_perp_position(perp_market_index, settle_token_index)?;
+
+    let mut perp_market = ctx.accounts.perp_market.load_mut()?;
+    let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
+    // account constraint #2
+    require!(
+        settle_bank.token_index == settle_token_index,
+        MangoError::InvalidBank
+    );
+
+    // Get oracle price for market. Price is validated inside
+    let oracle_price = perp_market.oracle_price(
+        &AccountInfoRef::borrow(ctx.accounts.oracle.as_ref())?,
+        None, // checked in health
+    )?;
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+
+    //
+    // Step 1: Take over base position until the isolated health is restored
+    //
+    let base_transfer;
+    let quote_transfer;
+    {
+        let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+        require!(
+            !liqee_perp_position.has_open_orders(),
+            MangoError::HasOpenPerpOrders
+        );
+        require!(
+            !liqee_perp_position.has_open_taker_fills(),
+            MangoError::HasOpenPerpTakerFills
+        );
+        let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
+        liqee_perp_position.settle_funding(&perp_market);
+        liqor_perp_position.settle_funding(&perp_market);
+
+        let base_lot_size = I80F48::from(perp_market.base_lot_size);
+        let oracle_price_per_lot = base_lot_size * oracle_price;
+        let liqee_base_lots = liqee_perp_position.base_position_lots();
+
+        // Each lot the base position gets closer to 0, the isolated health increases by
+        // health_per_lot as long as the perp health contribution is negative.
+        let direction: i64;
+        let fee_factor;
+        let health_per_lot;
+        if liqee_base_lots > 0 {
+            require_msg!(
+                max_base_transfer >= 0,
+                "max_base_transfer can't be negative when liqee's base_position is positive"
+            );
+            direction = -1;
+            fee_factor = I80F48::ONE - perp_market.base_liquidation_fee;
+            health_per_lot = -asset_price * base_lot_size * perp_market.init_base_asset_weight
+                + oracle_price_per_lot * fee_factor;
+        } else {
+            require_msg!(
+                max_base_transfer <= 0,
+                "max_base_transfer can't be positive when liqee's base_position is negative"
+            );
+            direction = 1;
+            fee_factor = I80F48::ONE + perp_market.base_liquidation_fee;
+            health_per_lot = liab_price * base_lot_size * perp_market.init_base_liab_weight
+                - oracle_price_per_lot * fee_factor;
+        }
+
+        let base_reduction = if liqee_base_lots != 0 {
+            require_msg!(
+                health_per_lot > 0,
+                "base liquidation doesn't increase health, health per lot {}",
+                health_per_lot
+            );
+            (-liqee_liq_end_health / health_per_lot)
+                .ceil() // overshoot to aim for liquidation end health >= 0
+                .max(I80F48::ZERO)
+                .to_num::<i64>()
+                .min(liqee_base_lots.abs())
+                .min(max_base_transfer.abs())
+        } else {
+            0
+        };
+
+        base_transfer = direction * base_reduction;
+        quote_transfer = -I80F48::from(base_transfer) * oracle_price_per_lot * fee_factor;
+        if base_transfer != 0 {
+            msg!(
+                "transfering: {} base lots and {} quote",
+                base_transfer,
+                quote_transfer
+            );
+            liqee_perp_position.record_trade(&mut perp_market, base_transfer, quote_transfer);
+            liqor_perp_position.record_trade(&mut perp_market, -base_transfer, -quote_transfer);
+        }
+    }
+
+    //
+    // Step 2: Without base position, the liqor takes over negative pnl in exchange
+    // for the isolated collateral
+    //
+    let liqee_pnl = {
+        let liqee_perp_position = liqee.perp_position(perp_market_index)?;
+        if liqee_perp_position.base_position_lots() == 0 {
+            liqee_perp_position.unsettled_pnl(&perp_market, oracle_price)?
+        } else {
+            I80F48::ZERO
+        }
+    };
+    let mut collateral_transfer = I80F48::ZERO;
+    if liqee_pnl < 0 {
+        let isolated = liqee.isolated_perp_position_mut(perp_market_index)?;
+        collateral_transfer = (-liqee_pnl)
+            .min(isolated.native(&settle_bank))
+            .min(I80F48::from(max_pnl_transfer))
+            .max(I80F48::ZERO);
+        if collateral_transfer > 0 {
+            settle_bank.withdraw_isolated(isolated, collateral_transfer)?;
+            let liqor_token_position = liqor.token_position_mut(settle_token_index)?.0;
+            settle_bank.deposit(liqor_token_position, collateral_transfer, now_ts)?;
+
+            let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+            let liqor_perp_position = liqor.perp_position_mut(perp_market_index)?;
+            liqee_perp_position.record_settle(-collateral_transfer);
+            liqor_perp_position.record_liquidation_quote_change(-collateral_transfer);
+
+            // Update the accounts' perp_spot_transfer statistics.
+            let transfer_i64 = collateral_transfer.round_to_zero().to_num::<i64>();
+            liqor_perp_position.perp_spot_transfers += transfer_i64;
+            liqee_perp_position.perp_spot_transfers -= transfer_i64;
+            liqor.fixed.perp_spot_transfers += transfer_i64;
+            liqee.fixed.perp_spot_transfers -= transfer_i64;
+
+            liqee_health_cache
+                .adjust_isolated_perp_collateral(perp_market_index, -collateral_transfer)?;
+
+            msg!("liquidated pnl = {}", collateral_transfer);
+        }
+    }
+
+    //
+    // Step 3: bankruptcy
+    //
+    // Once the collateral is exhausted, remaining losses are socialized.
+    //
+    let mut bankruptcy_loss = I80F48::ZERO;
+    let collateral_exhausted = liqee
+        .isolated_perp_position(perp_market_index)?
+        .indexed_collateral
+        .is_zero();
+    if collateral_exhausted && liqee_pnl < 0 {
+        let liqee_perp_position = liqee.perp_position_mut(perp_market_index)?;
+        bankruptcy_loss =
+            (-liqee_perp_position.unsettled_pnl(&perp_market, oracle_price)?).max(I80F48::ZERO);
+        let mut socialized_loss = I80F48::ZERO;
+        let (starting_long_funding, starting_short_funding) =
+            (perp_market.long_funding, perp_market.short_funding);
+        if bankruptcy_loss > 0 {
+            perp_market.socialize_loss(-bankruptcy_loss)?;
+            socialized_loss = bankruptcy_loss;
+            liqee_perp_position.record_settle(-bankruptcy_loss);
+
+            emit!(PerpLiqBankruptcyLog {
+                mango_group,
+                liqee: ctx.accounts.liqee.key(),
+                liqor: ctx.accounts.liqor.key(),
+                perp_market_index,
+                insurance_transfer: I80F48::ZERO.to_bits(),
+                socialized_loss: socialized_loss.to_bits(),
+                starting_long_funding: starting_long_funding.to_bits(),
+                starting_short_funding: starting_short_funding.to_bits(),
+                ending_long_funding: perp_market.long_funding.to_bits(),
+                ending_short_funding: perp_market.short_funding.to_bits(),
+            });
+        }
+    }
+
+    //
+    // Log positions aftewards
+    //
+    let liqee_perp_position = liqee.perp_position(perp_market_index)?;
+    let liqor_perp_position = liqor.perp_position(perp_market_index)?;
+    emit_perp_balances(
+        mango_group,
+        ctx.accounts.liqor.key(),
+        liqor_perp_position,
+        &perp_market,
+    );
+    emit_perp_balances(
+        mango_group,
+        ctx.accounts.liqee.key(),
+        liqee_perp_position,
+        &perp_market,
+    );
+
+    if collateral_transfer > 0 {
+        let liqor_token_position = liqor.token_position(settle_token_index)?;
+        emit_token_balance(
+            mango_group,
+            ctx.accounts.liqor.key(),
+            liqor_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
+
+        let isolated = liqee.isolated_perp_position(perp_market_index)?;
+        emit!(PerpIsolatedCollateralLog {
+            mango_group,
+            mango_account: ctx.accounts.liqee.key(),
+            perp_market_index,
+            token_index: settle_token_index,
+            change: (-collateral_transfer).to_bits(),
+            indexed_collateral: isolated.indexed_collateral.to_bits(),
+            deposit_index: settle_bank.deposit_index.to_bits(),
+        });
+    }
+
+    if base_transfer != 0 || collateral_transfer > 0 || bankruptcy_loss > 0 {
+        emit!(PerpLiqIsolatedLog {
+            mango_group,
+            perp_market_index,
+            liqor: ctx.accounts.liqor.key(),
+            liqee: ctx.accounts.liqee.key(),
+            base_transfer,
+            quote_transfer: quote_transfer.to_bits(),
+            collateral_transfer: collateral_transfer.to_bits(),
+            bankruptcy_loss: bankruptcy_loss.to_bits(),
+            price: oracle_price.to_bits(),
+        });
+    }
+
+    // Check liqee isolated health again
+    liqee_health_cache.recompute_perp_info(liqee_perp_position, &perp_market)?;
+    let liqee_liq_end_health_after =
+        liqee_health_cache.isolated_perp_health(perp_market_index, HealthType::LiquidationEnd)?;
+    require_gte!(liqee_liq_end_health_after, liqee_liq_end_health);
+    msg!(
+        "liqee isolated liq end health: {} -> {}",
+        liqee_liq_end_health,
+        liqee_liq_end_health_after
+    );
+
+    // A bankrupt position ends up flat and without collateral, it stops being isolated
+    if collateral_exhausted
+        && liqee_perp_position.base_position_lots() == 0
+        && liqee_perp_position.quote_position_native() == 0
+    {
+        liqee.deactivate_isolated_perp_position(perp_market_index)?;
+    }
+
+    drop(settle_bank);
+    drop(perp_market);
+
+    // Check liqor's health
+    if !liqor.fixed.is_in_health_region() {
+        let account_retriever = ScanningAccountRetriever::new(ctx.remaining_accounts, &mango_group)
+            .context("create account retriever end")?;
+        let liqor_health = compute_health(&liqor.borrow(), HealthType::Init, &account_retriever)
+            .context("compute liqor health")?;
+        require!(liqor_health >= 0, MangoError::HealthMustBePositive);
+    }
+
+    Ok(())
+}
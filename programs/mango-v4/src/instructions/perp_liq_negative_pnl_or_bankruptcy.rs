@@ -8,7 +8,7 @@ use crate::accounts_zerocopy::*;
 use crate::error::*;
 use crate::health::{compute_health, new_health_cache, HealthType, ScanningAccountRetriever};
 use crate::logs::{
-    emit_perp_balances, PerpLiqBankruptcyLog, PerpLiqNegativePnlOrBankruptcyLog, TokenBalanceLog,
+    emit_perp_balances, emit_token_balance, PerpLiqBankruptcyLog, PerpLiqNegativePnlOrBankruptcyLog,
 };
 use crate::state::*;
 
@@ -51,10 +51,17 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
     let liqee_settle_health = liqee_health_cache.perp_settle_health();
     liqee_health_cache.require_after_phase2_liquidation()?;
 
-    if !liqee.check_liquidatable(&liqee_health_cache)? {
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    if !liqee.check_liquidatable(&liqee_health_cache, now_ts)? {
         return Ok(());
     }
 
+    // Isolated positions are liquidated with perp_liq_isolated
+    require!(
+        !liqee.is_isolated_perp(perp_market_index) && !liqor.is_isolated_perp(perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
+
     // check positions exist/create them, done early for nicer error messages
     {
         liqee.perp_position(perp_market_index)?;
@@ -77,8 +84,6 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
         None, // staleness checked in health
     )?;
 
-    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
-
     //
     // Step 1: Allow the liqor to take over ("settle") negative liqee pnl.
     //
@@ -239,26 +244,24 @@ pub fn perp_liq_negative_pnl_or_bankruptcy(
     //
     if settlement > 0 || insurance_transfer > 0 {
         let liqor_token_position = liqor.token_position(settle_token_index)?;
-        emit!(TokenBalanceLog {
+        emit_token_balance(
             mango_group,
-            mango_account: ctx.accounts.liqor.key(),
-            token_index: settle_token_index,
-            indexed_position: liqor_token_position.indexed_position.to_bits(),
-            deposit_index: settle_bank.deposit_index.to_bits(),
-            borrow_index: settle_bank.borrow_index.to_bits(),
-        });
+            ctx.accounts.liqor.key(),
+            liqor_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
     }
 
     if settlement > 0 {
         let liqee_token_position = liqee.token_position(settle_token_index)?;
-        emit!(TokenBalanceLog {
+        emit_token_balance(
             mango_group,
-            mango_account: ctx.accounts.liqee.key(),
-            token_index: settle_token_index,
-            indexed_position: liqee_token_position.indexed_position.to_bits(),
-            deposit_index: settle_bank.deposit_index.to_bits(),
-            borrow_index: settle_bank.borrow_index.to_bits(),
-        });
+            ctx.accounts.liqee.key(),
+            liqee_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
     }
 
     let liqee_perp_position = liqee.perp_position(perp_market_index)?;
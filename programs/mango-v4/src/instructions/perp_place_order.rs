@@ -3,7 +3,9 @@ use anchor_lang::prelude::*;
 use crate::accounts_ix::*;
 use crate::accounts_zerocopy::*;
 use crate::error::*;
-use crate::health::{new_fixed_order_account_retriever, new_health_cache};
+use crate::health::{
+    fixed_order_account_count, new_fixed_order_account_retriever, new_health_cache, HealthType,
+};
 use crate::state::*;
 
 // TODO
@@ -60,15 +62,51 @@ pub fn perp_place_order(
     //
     account.ensure_perp_position(perp_market_index, settle_token_index)?;
 
+    //
+    // An optional referrer MangoAccount may be passed after the health accounts
+    //
+    let health_ais_len = fixed_order_account_count(&account.borrow());
+    let (health_ais, referrer_opt) = if ctx.remaining_accounts.len() == health_ais_len + 1 {
+        let referrer_ai = &ctx.remaining_accounts[health_ais_len];
+        require_keys_neq!(referrer_ai.key(), account_pk);
+        let referrer_loader = AccountLoader::<MangoAccountFixed>::try_from(referrer_ai)?;
+        let referrer = referrer_loader.load_full()?;
+        require_keys_eq!(referrer.fixed.group, account.fixed.group);
+        // the taker's owner must not get part of its own taker fee back
+        require_msg!(
+            !referrer.fixed.is_owner_or_delegate(account.fixed.owner)
+                && !referrer
+                    .fixed
+                    .is_owner_or_delegate(ctx.accounts.owner.key()),
+            "referrer {} is controlled by the taker's owner",
+            referrer_ai.key()
+        );
+        // the referrer is credited in its perp position for this market
+        referrer.perp_position(perp_market_index)?;
+        (
+            &ctx.remaining_accounts[..health_ais_len],
+            Some(referrer_ai.key()),
+        )
+    } else {
+        (ctx.remaining_accounts, None)
+    };
+
     //
     // Pre-health computation, _after_ perp position is created
     //
-    let pre_health_opt = if !account.fixed.is_in_health_region() {
-        let retriever =
-            new_fixed_order_account_retriever(ctx.remaining_accounts, &account.borrow())?;
+    // Isolated positions are only backed by their own collateral, their health is checked
+    // even in health regions.
+    //
+    let is_isolated = account.is_isolated_perp(perp_market_index);
+    let pre_health_opt = if !account.fixed.is_in_health_region() || is_isolated {
+        let retriever = new_fixed_order_account_retriever(health_ais, &account.borrow())?;
         let health_cache =
             new_health_cache(&account.borrow(), &retriever).context("pre-withdraw init health")?;
-        let pre_init_health = account.check_health_pre(&health_cache)?;
+        let pre_init_health = if is_isolated {
+            health_cache.isolated_perp_health(perp_market_index, HealthType::Init)?
+        } else {
+            account.check_health_pre(&health_cache)?
+        };
         Some((health_cache, pre_init_health))
     } else {
         None
@@ -111,6 +149,8 @@ pub fn perp_place_order(
         &mut account.borrow_mut(),
         &account_pk,
         now_ts,
+        group.perp_fee_tiers(),
+        referrer_opt,
         limit,
     )?;
 
@@ -120,7 +160,15 @@ pub fn perp_place_order(
     if let Some((mut health_cache, pre_init_health)) = pre_health_opt {
         let perp_position = account.perp_position(perp_market_index)?;
         health_cache.recompute_perp_info(perp_position, &perp_market)?;
-        account.check_health_post(&health_cache, pre_init_health)?;
+        if is_isolated {
+            account.check_isolated_perp_health_post(
+                &health_cache,
+                perp_market_index,
+                pre_init_health,
+            )?;
+        } else {
+            account.check_health_post(&health_cache, pre_init_health)?;
+        }
     }
 
     Ok(order_id_opt)
@@ -8,7 +8,7 @@ use crate::health::{compute_health, new_fixed_order_account_retriever, HealthTyp
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{emit_perp_balances, PerpSettleFeesLog, TokenBalanceLog};
+use crate::logs::{emit_perp_balances, emit_token_balance, PerpSettleFeesLog};
 
 pub fn perp_settle_fees(ctx: Context<PerpSettleFees>, max_settle_amount: u64) -> Result<()> {
     // max_settle_amount must greater than zero
@@ -34,6 +34,12 @@ pub fn perp_settle_fees(ctx: Context<PerpSettleFees>, max_settle_amount: u64) ->
         None, // staleness checked in health
     )?;
 
+    // Isolated positions can only settle against their collateral with perp_settle_pnl
+    require!(
+        !account.is_isolated_perp(perp_market.perp_market_index),
+        MangoError::PerpPositionIsIsolated
+    );
+
     // Fetch perp positions for accounts
     let perp_position = account.perp_position_mut(perp_market.perp_market_index)?;
 
@@ -98,14 +104,13 @@ pub fn perp_settle_fees(ctx: Context<PerpSettleFees>, max_settle_amount: u64) ->
     // Update the settled balance on the market itself
     perp_market.fees_settled += settlement;
 
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account.key(),
-        token_index: perp_market.settle_token_index,
-        indexed_position: token_position.indexed_position.to_bits(),
-        deposit_index: settle_bank.deposit_index.to_bits(),
-        borrow_index: settle_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        token_position,
+        settle_bank.deposit_index,
+        settle_bank.borrow_index,
+    );
 
     emit!(PerpSettleFeesLog {
         mango_group: ctx.accounts.group.key(),
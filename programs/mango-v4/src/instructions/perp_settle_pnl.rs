@@ -6,7 +6,9 @@ use crate::accounts_ix::*;
 use crate::accounts_zerocopy::*;
 use crate::error::*;
 use crate::health::{new_health_cache, HealthType, ScanningAccountRetriever};
-use crate::logs::{emit_perp_balances, PerpSettlePnlLog, TokenBalanceLog};
+use crate::logs::{
+    emit_perp_balances, emit_token_balance, PerpIsolatedCollateralLog, PerpSettlePnlLog,
+};
 use crate::state::*;
 
 pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
@@ -36,6 +38,10 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
         account_b.token_position(settle_token_index)?;
     }
 
+    // Isolated positions settle into and out of their dedicated collateral
+    let a_is_isolated = account_a.is_isolated_perp(perp_market_index);
+    let b_is_isolated = account_b.is_isolated_perp(perp_market_index);
+
     let a_liq_end_health;
     let a_maint_health;
     let b_settle_health;
@@ -43,10 +49,24 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
         let retriever =
             ScanningAccountRetriever::new(ctx.remaining_accounts, &ctx.accounts.group.key())
                 .context("create account retriever")?;
-        b_settle_health = new_health_cache(&account_b.borrow(), &retriever)?.perp_settle_health();
+        let b_cache = new_health_cache(&account_b.borrow(), &retriever)?;
+        b_settle_health = if b_is_isolated {
+            b_cache
+                .isolated_perp_info(perp_market_index)?
+                .collateral
+                .balance_native
+        } else {
+            b_cache.perp_settle_health()
+        };
         let a_cache = new_health_cache(&account_a.borrow(), &retriever)?;
-        a_liq_end_health = a_cache.health(HealthType::LiquidationEnd);
-        a_maint_health = a_cache.health(HealthType::Maint);
+        if a_is_isolated {
+            a_liq_end_health =
+                a_cache.isolated_perp_health(perp_market_index, HealthType::LiquidationEnd)?;
+            a_maint_health = a_cache.isolated_perp_health(perp_market_index, HealthType::Maint)?;
+        } else {
+            a_liq_end_health = a_cache.health(HealthType::LiquidationEnd);
+            a_maint_health = a_cache.health(HealthType::Maint);
+        }
     };
 
     let mut settle_bank = ctx.accounts.settle_bank.load_mut()?;
@@ -166,31 +186,60 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
 
     // Transfer token balances
     // The fee is paid by the account with positive unsettled pnl
-    let a_token_position = account_a.token_position_mut(settle_token_index)?.0;
-    let b_token_position = account_b.token_position_mut(settle_token_index)?.0;
-    settle_bank.deposit(a_token_position, settlement - fee, now_ts)?;
-    // Don't charge loan origination fees on borrows created via settling:
-    // Even small loan origination fees could accumulate if a perp position is
-    // settled back and forth repeatedly.
-    settle_bank.withdraw_without_fee(b_token_position, settlement, now_ts, oracle_price)?;
-
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account_a.key(),
-        token_index: settle_token_index,
-        indexed_position: a_token_position.indexed_position.to_bits(),
-        deposit_index: settle_bank.deposit_index.to_bits(),
-        borrow_index: settle_bank.borrow_index.to_bits(),
-    });
+    if a_is_isolated {
+        let a_isolated = account_a.isolated_perp_position_mut(perp_market_index)?;
+        settle_bank.deposit_isolated(a_isolated, settlement - fee)?;
 
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account_b.key(),
-        token_index: settle_token_index,
-        indexed_position: b_token_position.indexed_position.to_bits(),
-        deposit_index: settle_bank.deposit_index.to_bits(),
-        borrow_index: settle_bank.borrow_index.to_bits(),
-    });
+        emit!(PerpIsolatedCollateralLog {
+            mango_group: ctx.accounts.group.key(),
+            mango_account: ctx.accounts.account_a.key(),
+            perp_market_index,
+            token_index: settle_token_index,
+            change: (settlement - fee).to_bits(),
+            indexed_collateral: a_isolated.indexed_collateral.to_bits(),
+            deposit_index: settle_bank.deposit_index.to_bits(),
+        });
+    } else {
+        let a_token_position = account_a.token_position_mut(settle_token_index)?.0;
+        settle_bank.deposit(a_token_position, settlement - fee, now_ts)?;
+
+        emit_token_balance(
+            ctx.accounts.group.key(),
+            ctx.accounts.account_a.key(),
+            a_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
+    }
+
+    if b_is_isolated {
+        let b_isolated = account_b.isolated_perp_position_mut(perp_market_index)?;
+        settle_bank.withdraw_isolated(b_isolated, settlement)?;
+
+        emit!(PerpIsolatedCollateralLog {
+            mango_group: ctx.accounts.group.key(),
+            mango_account: ctx.accounts.account_b.key(),
+            perp_market_index,
+            token_index: settle_token_index,
+            change: (-settlement).to_bits(),
+            indexed_collateral: b_isolated.indexed_collateral.to_bits(),
+            deposit_index: settle_bank.deposit_index.to_bits(),
+        });
+    } else {
+        let b_token_position = account_b.token_position_mut(settle_token_index)?.0;
+        // Don't charge loan origination fees on borrows created via settling:
+        // Even small loan origination fees could accumulate if a perp position is
+        // settled back and forth repeatedly.
+        settle_bank.withdraw_without_fee(b_token_position, settlement, now_ts, oracle_price)?;
+
+        emit_token_balance(
+            ctx.accounts.group.key(),
+            ctx.accounts.account_b.key(),
+            b_token_position,
+            settle_bank.deposit_index,
+            settle_bank.borrow_index,
+        );
+    }
 
     // settler might be the same as account a or b
     drop(account_a);
@@ -209,14 +258,13 @@ pub fn perp_settle_pnl(ctx: Context<PerpSettlePnl>) -> Result<()> {
         settler.ensure_token_position(settle_token_index)?;
     let settler_token_position_active = settle_bank.deposit(settler_token_position, fee, now_ts)?;
 
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.settler.key(),
-        token_index: settler_token_position.token_index,
-        indexed_position: settler_token_position.indexed_position.to_bits(),
-        deposit_index: settle_bank.deposit_index.to_bits(),
-        borrow_index: settle_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.settler.key(),
+        settler_token_position,
+        settle_bank.deposit_index,
+        settle_bank.borrow_index,
+    );
 
     if !settler_token_position_active {
         settler
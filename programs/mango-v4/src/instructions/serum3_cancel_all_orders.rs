@@ -1,9 +1,8 @@
 use anchor_lang::prelude::*;
 
-use super::{OpenOrdersAmounts, OpenOrdersSlim};
+use super::{emit_serum3_open_orders_balance, OpenOrdersSlim};
 use crate::accounts_ix::*;
 use crate::error::*;
-use crate::logs::Serum3OpenOrdersBalanceLogV2;
 use crate::serum3_cpi::load_open_orders_ref;
 use crate::state::*;
 
@@ -40,18 +39,16 @@ pub fn serum3_cancel_all_orders(ctx: Context<Serum3CancelAllOrders>, limit: u8)
     let oo_ai = &ctx.accounts.open_orders.as_ref();
     let open_orders = load_open_orders_ref(oo_ai)?;
     let after_oo = OpenOrdersSlim::from_oo(&open_orders);
-    emit!(Serum3OpenOrdersBalanceLogV2 {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account.key(),
-        market_index: serum_market.market_index,
-        base_token_index: serum_market.base_token_index,
-        quote_token_index: serum_market.quote_token_index,
-        base_total: after_oo.native_base_total(),
-        base_free: after_oo.native_base_free(),
-        quote_total: after_oo.native_quote_total(),
-        quote_free: after_oo.native_quote_free(),
-        referrer_rebates_accrued: after_oo.native_rebates(),
-    });
+    // cancelling doesn't change the open orders totals, fills are recorded on settle
+    let account = ctx.accounts.account.load_full()?;
+    let serum3_orders = account.serum3_orders(serum_market.market_index)?;
+    emit_serum3_open_orders_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        &serum_market,
+        &after_oo,
+        serum3_orders,
+    );
 
     Ok(())
 }
@@ -4,6 +4,9 @@ use anchor_lang::prelude::*;
 pub fn serum3_edit_market(
     ctx: Context<Serum3EditMarket>,
     reduce_only_opt: Option<bool>,
+    settle_fee_flat_opt: Option<f32>,
+    settle_fee_amount_threshold_opt: Option<f32>,
+    settle_rebates_to_account_opt: Option<bool>,
 ) -> Result<()> {
     let mut serum3_market = ctx.accounts.market.load_mut()?;
 
@@ -24,6 +27,38 @@ pub fn serum3_edit_market(
         }
     };
 
+    if let Some(settle_fee_flat) = settle_fee_flat_opt {
+        msg!(
+            "Settle fee flat: old - {:?}, new - {:?}",
+            serum3_market.settle_fee_flat,
+            settle_fee_flat
+        );
+        require_gte!(settle_fee_flat, 0.0);
+        serum3_market.settle_fee_flat = settle_fee_flat;
+        require_group_admin = true;
+    };
+
+    if let Some(settle_fee_amount_threshold) = settle_fee_amount_threshold_opt {
+        msg!(
+            "Settle fee amount threshold: old - {:?}, new - {:?}",
+            serum3_market.settle_fee_amount_threshold,
+            settle_fee_amount_threshold
+        );
+        require_gte!(settle_fee_amount_threshold, 0.0);
+        serum3_market.settle_fee_amount_threshold = settle_fee_amount_threshold;
+        require_group_admin = true;
+    };
+
+    if let Some(settle_rebates_to_account) = settle_rebates_to_account_opt {
+        msg!(
+            "Settle rebates to account: old - {:?}, new - {:?}",
+            serum3_market.settle_rebates_to_account,
+            u8::from(settle_rebates_to_account)
+        );
+        serum3_market.settle_rebates_to_account = u8::from(settle_rebates_to_account);
+        require_group_admin = true;
+    };
+
     if require_group_admin {
         require!(
             group.admin == ctx.accounts.admin.key(),
@@ -4,10 +4,12 @@ use crate::accounts_ix::*;
 use crate::error::*;
 use crate::health::*;
 use crate::instructions::apply_settle_changes;
-use crate::instructions::{charge_loan_origination_fees, OpenOrdersAmounts, OpenOrdersSlim};
-use crate::logs::Serum3OpenOrdersBalanceLogV2;
+use crate::instructions::{
+    charge_loan_origination_fees, emit_serum3_open_orders_balance, OpenOrdersSlim,
+};
 use crate::serum3_cpi::load_open_orders_ref;
 use crate::state::*;
+use serum_dex::state::OpenOrders;
 
 pub fn serum3_liq_force_cancel_orders(
     ctx: Context<Serum3LiqForceCancelOrders>,
@@ -53,15 +55,23 @@ pub fn serum3_liq_force_cancel_orders(
     //
     // Check liqee health if liquidation is allowed
     //
-    let mut health_cache = {
+    let (mut health_cache, all_open_orders_count) = {
         let mut account = ctx.accounts.account.load_full_mut()?;
         let retriever =
             new_fixed_order_account_retriever(ctx.remaining_accounts, &account.borrow())?;
         let health_cache =
             new_health_cache(&account.borrow(), &retriever).context("create health cache")?;
 
+        // The close factor window is based on the orders on all serum3 markets
+        let mut all_open_orders_count = 0;
+        for (i, serum3_orders) in account.active_serum3_orders().enumerate() {
+            let oo = retriever.serum_oo(i, &serum3_orders.open_orders)?;
+            all_open_orders_count += open_orders_count(oo);
+        }
+
         {
-            let result = account.check_liquidatable(&health_cache);
+            let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+            let result = account.check_liquidatable(&health_cache, now_ts);
             if account.fixed.is_operational() {
                 if !result? {
                     return Ok(());
@@ -75,16 +85,37 @@ pub fn serum3_liq_force_cancel_orders(
             }
         }
 
-        health_cache
+        (health_cache, all_open_orders_count)
     };
 
     //
     // Charge any open loan origination fees
     //
-    let before_oo = {
+    let (before_oo, before_orders_count, limit, close_factor_applies) = {
         let open_orders = load_open_orders_ref(ctx.accounts.open_orders.as_ref())?;
         let before_oo = OpenOrdersSlim::from_oo(&open_orders);
+        let before_orders_count = open_orders_count(&open_orders);
         let mut account = ctx.accounts.account.load_full_mut()?;
+
+        // The close factor limits the fraction of the account's serum3 orders that can be
+        // cancelled per window, like for token and perp liquidation.
+        // Frozen accounts can always have all their orders cancelled.
+        let mut limit = limit;
+        let close_factor = ctx.accounts.group.load()?.liquidation_close_factor();
+        let close_factor_applies =
+            close_factor.is_some() && account.fixed.is_operational() && before_orders_count > 0;
+        if close_factor_applies {
+            let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+            let remaining = account.fixed.serum3_liquidation_close_factor_remaining(
+                close_factor.as_ref().unwrap(),
+                all_open_orders_count,
+                now_ts,
+            );
+            require!(remaining > 0, MangoError::LiquidationCloseFactorReached);
+            limit = limit.min(remaining.min(u8::MAX as u32) as u8);
+            msg!("close factor limits cancelled orders to {}", limit);
+        }
+
         let mut base_bank = ctx.accounts.base_bank.load_mut()?;
         let mut quote_bank = ctx.accounts.quote_bank.load_mut()?;
         charge_loan_origination_fees(
@@ -97,7 +128,7 @@ pub fn serum3_liq_force_cancel_orders(
             &before_oo,
         )?;
 
-        before_oo
+        (before_oo, before_orders_count, limit, close_factor_applies)
     };
 
     //
@@ -115,24 +146,13 @@ pub fn serum3_liq_force_cancel_orders(
     //
     // After-settle tracking
     //
-    let after_oo;
-    {
+    let (after_oo, after_orders_count) = {
         let oo_ai = &ctx.accounts.open_orders.as_ref();
         let open_orders = load_open_orders_ref(oo_ai)?;
-        after_oo = OpenOrdersSlim::from_oo(&open_orders);
-
-        emit!(Serum3OpenOrdersBalanceLogV2 {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.account.key(),
-            market_index: serum_market.market_index,
-            base_token_index: serum_market.base_token_index,
-            quote_token_index: serum_market.quote_token_index,
-            base_total: after_oo.native_base_total(),
-            base_free: after_oo.native_base_free(),
-            quote_total: after_oo.native_quote_total(),
-            quote_free: after_oo.native_quote_free(),
-            referrer_rebates_accrued: after_oo.native_rebates(),
-        });
+        (
+            OpenOrdersSlim::from_oo(&open_orders),
+            open_orders_count(&open_orders),
+        )
     };
 
     ctx.accounts.base_vault.reload()?;
@@ -162,6 +182,21 @@ pub fn serum3_liq_force_cancel_orders(
         None,
     )?;
 
+    if close_factor_applies {
+        account
+            .fixed
+            .record_serum3_liquidation(before_orders_count.saturating_sub(after_orders_count));
+    }
+
+    let serum3_orders = account.serum3_orders(serum_market.market_index)?;
+    emit_serum3_open_orders_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        &serum_market,
+        &after_oo,
+        serum3_orders,
+    );
+
     //
     // Health check at the end
     //
@@ -173,6 +208,11 @@ pub fn serum3_liq_force_cancel_orders(
     Ok(())
 }
 
+/// Number of orders on the book for an open orders account
+fn open_orders_count(open_orders: &OpenOrders) -> u32 {
+    open_orders.free_slot_bits.count_zeros()
+}
+
 fn cpi_cancel_all_orders(ctx: &Serum3LiqForceCancelOrders, limit: u8) -> Result<()> {
     use crate::serum3_cpi;
     let group = ctx.group.load()?;
@@ -5,7 +5,7 @@ use crate::i80f48::ClampToInt;
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{Serum3OpenOrdersBalanceLogV2, TokenBalanceLog};
+use crate::logs::{emit_token_balance, Serum3OpenOrdersBalanceLogV2, Serum3OpenOrdersBalanceLogV3};
 use crate::serum3_cpi::{load_market_state, load_open_orders_ref};
 use anchor_lang::prelude::*;
 
@@ -92,6 +92,48 @@ impl OpenOrdersAmounts for OpenOrders {
     }
 }
 
+/// Emits Serum3OpenOrdersBalanceLogV2 and Serum3OpenOrdersBalanceLogV3
+///
+/// The older layout keeps being emitted for existing log consumers.
+pub fn emit_serum3_open_orders_balance(
+    mango_group: Pubkey,
+    mango_account: Pubkey,
+    serum_market: &Serum3Market,
+    oo: &impl OpenOrdersAmounts,
+    serum3_orders: &Serum3Orders,
+) {
+    emit!(Serum3OpenOrdersBalanceLogV2 {
+        mango_group,
+        mango_account,
+        market_index: serum_market.market_index,
+        base_token_index: serum_market.base_token_index,
+        quote_token_index: serum_market.quote_token_index,
+        base_total: oo.native_base_total(),
+        base_free: oo.native_base_free(),
+        quote_total: oo.native_quote_total(),
+        quote_free: oo.native_quote_free(),
+        referrer_rebates_accrued: oo.native_rebates(),
+    });
+    emit!(Serum3OpenOrdersBalanceLogV3 {
+        mango_group,
+        mango_account,
+        market_index: serum_market.market_index,
+        base_token_index: serum_market.base_token_index,
+        quote_token_index: serum_market.quote_token_index,
+        base_total: oo.native_base_total(),
+        base_free: oo.native_base_free(),
+        quote_total: oo.native_quote_total(),
+        quote_free: oo.native_quote_free(),
+        referrer_rebates_accrued: oo.native_rebates(),
+        base_bought: serum3_orders.base_bought_native,
+        quote_paid: serum3_orders.quote_paid_native,
+        base_sold: serum3_orders.base_sold_native,
+        quote_received: serum3_orders.quote_received_native,
+        estimated_net_fees: serum3_orders.estimated_net_fees_native,
+        referrer_rebates_settled: serum3_orders.referrer_rebates_settled_native,
+    });
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn serum3_place_order(
     ctx: Context<Serum3PlaceOrder>,
@@ -103,10 +145,11 @@ pub fn serum3_place_order(
     order_type: Serum3OrderType,
     client_order_id: u64,
     limit: u16,
+    reduce_only: bool,
 ) -> Result<()> {
     let serum_market = ctx.accounts.serum_market.load()?;
     require!(
-        !serum_market.is_reduce_only(),
+        reduce_only || !serum_market.is_reduce_only(),
         MangoError::MarketInReduceOnlyMode
     );
 
@@ -167,14 +210,43 @@ pub fn serum3_place_order(
         OpenOrdersSlim::from_oo(&open_orders)
     };
 
+    let base_lot_size = load_market_state(
+        &ctx.accounts.serum_market_external,
+        &ctx.accounts.serum_program.key(),
+    )?
+    .coin_lot_size;
+
+    //
+    // Reduce-only: limit the order size
+    //
+    let max_base_qty = if reduce_only {
+        let base_position_native = if matches!(side, Serum3Side::Ask) {
+            // the payer bank is the base bank
+            let payer_bank = ctx.accounts.payer_bank.load()?;
+            account
+                .token_position(payer_bank.token_index)?
+                .native(&payer_bank)
+        } else {
+            base_position_native_from_health_accounts(
+                &ctx.accounts.group.key(),
+                &account.borrow(),
+                ctx.remaining_accounts,
+                serum_market.base_token_index,
+            )?
+        };
+        let allowed_base_qty =
+            reduce_only_max_base_qty(side, base_position_native, &before_oo, base_lot_size);
+        allowed_base_qty.min(max_base_qty)
+    } else {
+        max_base_qty
+    };
+    if reduce_only && max_base_qty == 0 {
+        msg!("reduce only: no order placed");
+        return Ok(());
+    }
+
     // Provide a readable error message in case the vault doesn't have enough tokens
     {
-        let base_lot_size = load_market_state(
-            &ctx.accounts.serum_market_external,
-            &ctx.accounts.serum_program.key(),
-        )?
-        .coin_lot_size;
-
         let needed_amount = match side {
             Serum3Side::Ask => {
                 (max_base_qty * base_lot_size).saturating_sub(before_oo.native_base_free())
@@ -212,26 +284,12 @@ pub fn serum3_place_order(
     };
     cpi_place_order(ctx.accounts, order)?;
 
-    let oo_difference = {
+    let after_oo = {
         let oo_ai = &ctx.accounts.open_orders.as_ref();
         let open_orders = load_open_orders_ref(oo_ai)?;
-        let after_oo = OpenOrdersSlim::from_oo(&open_orders);
-
-        emit!(Serum3OpenOrdersBalanceLogV2 {
-            mango_group: ctx.accounts.group.key(),
-            mango_account: ctx.accounts.account.key(),
-            market_index: serum_market.market_index,
-            base_token_index: serum_market.base_token_index,
-            quote_token_index: serum_market.quote_token_index,
-            base_total: after_oo.native_coin_total,
-            base_free: after_oo.native_coin_free,
-            quote_total: after_oo.native_pc_total,
-            quote_free: after_oo.native_pc_free,
-            referrer_rebates_accrued: after_oo.referrer_rebates_accrued,
-        });
-
-        OODifference::new(&before_oo, &after_oo)
+        OpenOrdersSlim::from_oo(&open_orders)
     };
+    let oo_difference = OODifference::new(&before_oo, &after_oo);
 
     //
     // After-order tracking
@@ -242,6 +300,35 @@ pub fn serum3_place_order(
     // Placing an order cannot increase vault balance
     require_gte!(before_vault, after_vault);
 
+    // Record fills since the totals were last seen and fills of the new order.
+    // The tokens taken from the vault were deposited into the open orders account.
+    {
+        let deposited = before_vault - after_vault;
+        let (base_deposited, quote_deposited) = match side {
+            Serum3Side::Ask => (deposited, 0),
+            Serum3Side::Bid => (0, deposited),
+        };
+        let serum3_orders = account.serum3_orders_mut(serum_market.market_index)?;
+        serum3_orders.record_maker_fills(
+            before_oo.native_base_total(),
+            before_oo.native_quote_total(),
+        );
+        serum3_orders.set_last_seen_totals(
+            before_oo.native_base_total() + base_deposited,
+            before_oo.native_quote_total() + quote_deposited,
+        );
+        serum3_orders
+            .record_taker_fills(after_oo.native_base_total(), after_oo.native_quote_total());
+
+        emit_serum3_open_orders_balance(
+            ctx.accounts.group.key(),
+            ctx.accounts.account.key(),
+            &serum_market,
+            &after_oo,
+            serum3_orders,
+        );
+    }
+
     let mut payer_bank = ctx.accounts.payer_bank.load_mut()?;
 
     // Enforce min vault to deposits ratio
@@ -283,6 +370,61 @@ pub fn serum3_place_order(
     Ok(())
 }
 
+/// Native base token position of the account, using the base bank from the health accounts
+fn base_position_native_from_health_accounts(
+    group: &Pubkey,
+    account: &MangoAccountRef,
+    health_ais: &[AccountInfo],
+    base_token_index: TokenIndex,
+) -> Result<I80F48> {
+    let active_index = match account
+        .active_token_positions()
+        .position(|p| p.token_index == base_token_index)
+    {
+        Some(index) => index,
+        None => return Ok(I80F48::ZERO),
+    };
+    let retriever = new_fixed_order_account_retriever(health_ais, account)?;
+    let (base_bank, _) = retriever.bank_and_oracle(group, active_index, base_token_index)?;
+    Ok(account.token_position(base_token_index)?.native(base_bank))
+}
+
+/// Max base lots a reduce-only order may have, such that the account's net base position
+/// can't grow in magnitude.
+///
+/// The net base position is the token position plus the base tokens in the open orders
+/// account. Base tokens reserved for existing asks are assumed to be sold. The base
+/// amount that existing bids would buy is not known, so no reduce-only bids are allowed
+/// while quote is reserved for bids.
+fn reduce_only_max_base_qty(
+    side: Serum3Side,
+    base_position_native: I80F48,
+    oo: &impl OpenOrdersAmounts,
+    base_lot_size: u64,
+) -> u64 {
+    if matches!(side, Serum3Side::Bid) && oo.native_quote_reserved() > 0 {
+        msg!("reduce only: bids are already open");
+        return 0;
+    }
+
+    let allowed_native = match side {
+        Serum3Side::Ask => base_position_native + I80F48::from(oo.native_base_free()),
+        Serum3Side::Bid => -(base_position_native + I80F48::from(oo.native_base_total())),
+    };
+    msg!(
+        "reduce only: allowed {} of {} native base",
+        if matches!(side, Serum3Side::Ask) {
+            "sale"
+        } else {
+            "purchase"
+        },
+        allowed_native
+    );
+    (allowed_native.max(I80F48::ZERO) / I80F48::from(base_lot_size))
+        .floor()
+        .to_num::<u64>()
+}
+
 pub struct OODifference {
     reserved_base_change: I80F48,
     reserved_quote_change: I80F48,
@@ -373,7 +515,7 @@ fn apply_vault_difference(
         .abs()
         .to_num::<u64>();
 
-    let indexed_position = position.indexed_position;
+    let position_after = *position;
     let market = account.serum3_orders_mut(serum_market_index).unwrap();
     let borrows_without_fee = if bank.token_index == market.base_token_index {
         &mut market.base_borrows_without_fee
@@ -394,14 +536,13 @@ fn apply_vault_difference(
         *borrows_without_fee = (*borrows_without_fee).saturating_sub(needed_change.to_num::<u64>());
     }
 
-    emit!(TokenBalanceLog {
-        mango_group: bank.group,
-        mango_account: account_pk,
-        token_index: bank.token_index,
-        indexed_position: indexed_position.to_bits(),
-        deposit_index: bank.deposit_index.to_bits(),
-        borrow_index: bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        bank.group,
+        account_pk,
+        &position_after,
+        bank.deposit_index,
+        bank.borrow_index,
+    );
 
     Ok(VaultDifference {
         token_index: bank.token_index,
@@ -428,6 +569,20 @@ pub fn apply_settle_changes(
     fees_to_dao: bool,
     quote_oracle: Option<&AccountInfo>,
 ) -> Result<()> {
+    // Record fills since the totals were last seen, settling withdraws the free funds
+    {
+        let serum3_orders = account.serum3_orders_mut(serum_market.market_index)?;
+        serum3_orders.record_maker_fills(
+            before_oo.native_base_total(),
+            before_oo.native_quote_total(),
+        );
+        serum3_orders.referrer_rebates_settled_native += before_oo
+            .native_rebates()
+            .saturating_sub(after_oo.native_rebates());
+        serum3_orders
+            .set_last_seen_totals(after_oo.native_base_total(), after_oo.native_quote_total());
+    }
+
     let mut received_fees = 0;
     if fees_to_dao {
         // Example: rebates go from 100 -> 10. That means we credit 90 in fees.
@@ -514,3 +669,60 @@ fn cpi_place_order(ctx: &Serum3PlaceOrder, order: NewOrderInstructionV3) -> Resu
     }
     .call(&group, order)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serum3_reduce_only_max_base_qty() {
+        let oo = |base_free: u64, base_total: u64, quote_reserved: u64| OpenOrdersSlim {
+            native_coin_free: base_free,
+            native_coin_total: base_total,
+            native_pc_free: 0,
+            native_pc_total: quote_reserved,
+            referrer_rebates_accrued: 0,
+        };
+        let lot = 100;
+
+        // (side, position, oo base free, oo base total, oo quote reserved, expected lots)
+        let test_cases = vec![
+            // no position, nothing allowed
+            (Serum3Side::Ask, 0, 0, 0, 0, 0),
+            (Serum3Side::Bid, 0, 0, 0, 0, 0),
+            // long: can sell, but not buy
+            (Serum3Side::Ask, 1000, 0, 0, 0, 10),
+            (Serum3Side::Ask, 1099, 0, 0, 0, 10),
+            (Serum3Side::Bid, 1000, 0, 0, 0, 0),
+            // free base in open orders counts
+            (Serum3Side::Ask, 1000, 500, 500, 0, 15),
+            // base reserved for asks is assumed sold
+            (Serum3Side::Ask, 0, 0, 1000, 0, 0),
+            (Serum3Side::Ask, 1000, 200, 700, 0, 12),
+            // short: can buy, but not sell
+            (Serum3Side::Bid, -1000, 0, 0, 0, 10),
+            (Serum3Side::Ask, -1000, 0, 0, 0, 0),
+            // base in open orders offsets the short
+            (Serum3Side::Bid, -1000, 0, 400, 0, 6),
+            (Serum3Side::Bid, -1000, 300, 1000, 0, 0),
+            // selling from free base while short in position
+            (Serum3Side::Ask, -1000, 1500, 1500, 0, 5),
+            // stacked bids could flip the short, so none are allowed while bids are open
+            (Serum3Side::Bid, -1000, 0, 0, 500, 0),
+            (Serum3Side::Ask, 1000, 0, 0, 500, 10),
+        ];
+
+        for (i, (side, position, base_free, base_total, quote_reserved, expected)) in
+            test_cases.into_iter().enumerate()
+        {
+            println!("test case {}", i);
+            let result = reduce_only_max_base_qty(
+                side,
+                I80F48::from(position),
+                &oo(base_free, base_total, quote_reserved),
+                lot,
+            );
+            assert_eq!(result, expected);
+        }
+    }
+}
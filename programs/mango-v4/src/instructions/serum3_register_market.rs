@@ -44,7 +44,10 @@ pub fn serum3_register_market(
         bump: *ctx.bumps.get("serum_market").ok_or(MangoError::SomeError)?,
         padding2: Default::default(),
         registration_time: Clock::get()?.unix_timestamp.try_into().unwrap(),
-        reserved: [0; 128],
+        settle_fee_flat: 0.0,
+        settle_fee_amount_threshold: 0.0,
+        settle_rebates_to_account: 0,
+        reserved: [0; 119],
     };
 
     let mut serum_index_reservation = ctx.accounts.index_reservation.load_init()?;
@@ -6,9 +6,12 @@ use crate::error::*;
 use crate::serum3_cpi::load_open_orders_ref;
 use crate::state::*;
 
-use super::{apply_settle_changes, OpenOrdersAmounts, OpenOrdersSlim};
+use super::{
+    apply_settle_changes, emit_serum3_open_orders_balance, OpenOrdersAmounts, OpenOrdersSlim,
+};
 use crate::accounts_ix::*;
-use crate::logs::Serum3OpenOrdersBalanceLogV2;
+use crate::accounts_zerocopy::AccountInfoRef;
+use crate::logs::{emit_token_balance, Serum3SettleFundsPermissionlessLog};
 use crate::logs::{LoanOriginationFeeInstruction, WithdrawLoanOriginationFeeLog};
 
 /// Settling means moving free funds from the serum3 open orders account
@@ -21,11 +24,6 @@ pub fn serum3_settle_funds<'info>(
     v2: Option<&mut Serum3SettleFundsV2Extra<'info>>,
     fees_to_dao: bool,
 ) -> Result<()> {
-    let serum_market = accounts.serum_market.load()?;
-
-    //
-    // Validation
-    //
     {
         let account = accounts.account.load_full()?;
         // account constraint #1
@@ -33,6 +31,33 @@ pub fn serum3_settle_funds<'info>(
             account.fixed.is_owner_or_delegate(accounts.owner.key()),
             MangoError::SomeError
         );
+    }
+
+    settle_funds(accounts, v2.map(|d| d.quote_oracle.as_ref()), fees_to_dao)?;
+    Ok(())
+}
+
+/// Native base and quote amounts that were credited to the account's token positions
+pub struct SettledAmounts {
+    pub base: u64,
+    pub quote: u64,
+}
+
+/// Settle the free funds of the account's open orders into its token positions.
+///
+/// Does not check the owner: callers must make sure it's fine to settle for this account.
+pub fn settle_funds<'info>(
+    accounts: &mut Serum3SettleFunds<'info>,
+    quote_oracle: Option<&AccountInfo<'info>>,
+    fees_to_dao: bool,
+) -> Result<SettledAmounts> {
+    let serum_market = accounts.serum_market.load()?;
+
+    //
+    // Validation
+    //
+    {
+        let account = accounts.account.load_full()?;
 
         // Validate open_orders #2
         require!(
@@ -111,6 +136,8 @@ pub fn serum3_settle_funds<'info>(
     let mut base_bank = accounts.base_bank.load_mut()?;
     let mut quote_bank = accounts.quote_bank.load_mut()?;
     let group = accounts.group.load()?;
+    let base_indexed_deposits_before = base_bank.indexed_deposits;
+    let quote_indexed_deposits_before = quote_bank.indexed_deposits;
     apply_settle_changes(
         &group,
         accounts.account.key(),
@@ -126,20 +153,141 @@ pub fn serum3_settle_funds<'info>(
         &after_oo,
         None,
         fees_to_dao,
-        v2.map(|d| d.quote_oracle.as_ref()),
+        quote_oracle,
+    )?;
+    base_bank.check_deposit_limit(base_indexed_deposits_before)?;
+    quote_bank.check_deposit_limit(quote_indexed_deposits_before)?;
+
+    let serum3_orders = account.serum3_orders(serum_market.market_index)?;
+    emit_serum3_open_orders_balance(
+        accounts.group.key(),
+        accounts.account.key(),
+        &serum_market,
+        &after_oo,
+        serum3_orders,
+    );
+
+    // referrer rebates that went to the fees are not credited to the account
+    let quote_fees = if fees_to_dao {
+        before_oo
+            .native_rebates()
+            .saturating_sub(after_oo.native_rebates())
+    } else {
+        0
+    };
+    Ok(SettledAmounts {
+        base: after_base_vault - before_base_vault,
+        quote: after_quote_vault - before_quote_vault - quote_fees,
+    })
+}
+
+/// Settle funds for any account, optionally paying the caller a fee.
+///
+/// Funds only move into the account's own token positions. The fee is paid from the
+/// quote the settle credited to the account, so the account's health can't end up
+/// lower than before the settle and no borrow is created.
+pub fn serum3_settle_funds_permissionless(
+    ctx: Context<Serum3SettleFundsPermissionless>,
+) -> Result<()> {
+    let accounts = ctx.accounts;
+
+    let fees_to_dao = accounts.v1.serum_market.load()?.settle_fees_to_dao();
+    let settled = settle_funds(
+        &mut accounts.v1,
+        Some(accounts.v2.quote_oracle.as_ref()),
+        fees_to_dao,
     )?;
 
-    emit!(Serum3OpenOrdersBalanceLogV2 {
-        mango_group: accounts.group.key(),
-        mango_account: accounts.account.key(),
+    let serum_market = accounts.v1.serum_market.load()?;
+    let base_price = accounts.v1.base_bank.load()?.oracle_price(
+        &AccountInfoRef::borrow(accounts.v2.base_oracle.as_ref())?,
+        None,
+    )?;
+    let mut quote_bank = accounts.v1.quote_bank.load_mut()?;
+    let quote_price = quote_bank.oracle_price(
+        &AccountInfoRef::borrow(accounts.v2.quote_oracle.as_ref())?,
+        None,
+    )?;
+
+    let settled_value =
+        I80F48::from(settled.base) * base_price + I80F48::from(settled.quote) * quote_price;
+    let fee_value = serum_market.permissionless_settle_fee(settled_value);
+    if fee_value <= 0 {
+        return Ok(());
+    }
+
+    let now_ts = Clock::get()?.unix_timestamp.try_into().unwrap();
+    let group_key = accounts.v1.group.key();
+    let account_key = accounts.v1.account.key();
+    let settler_key = accounts.settler.key();
+    let quote_token_index = quote_bank.token_index;
+
+    {
+        let settler = accounts.settler.load_full()?;
+        // settler constraint #4
+        require!(
+            settler.fixed.is_owner_or_delegate(accounts.v1.owner.key()),
+            MangoError::SomeError
+        );
+
+        // Settling is more important than the fee: skip it if the settler can't receive it
+        let can_receive = settler.token_position(quote_token_index).is_ok()
+            || settler.all_token_positions().any(|p| !p.is_active());
+        if !can_receive {
+            msg!("settler has no free token position, skipping the fee");
+            return Ok(());
+        }
+    }
+
+    let fee = {
+        let mut account = accounts.v1.account.load_full_mut()?;
+        let (position, raw_index) = account.token_position_mut(quote_token_index)?;
+        let fee = (fee_value / quote_price)
+            .min(I80F48::from(settled.quote))
+            .min(position.native(&quote_bank))
+            .max(I80F48::ZERO);
+        if fee <= 0 {
+            return Ok(());
+        }
+        let position_active =
+            quote_bank.withdraw_without_fee(position, fee, now_ts, quote_price)?;
+        emit_token_balance(
+            group_key,
+            account_key,
+            position,
+            quote_bank.deposit_index,
+            quote_bank.borrow_index,
+        );
+        if !position_active {
+            account.deactivate_token_position_and_log(raw_index, account_key);
+        }
+        fee
+    };
+
+    // the settler might be the same account
+    let mut settler = accounts.settler.load_full_mut()?;
+    let (settler_position, settler_raw_index, _) =
+        settler.ensure_token_position(quote_token_index)?;
+    let settler_position_active = quote_bank.deposit(settler_position, fee, now_ts)?;
+    emit_token_balance(
+        group_key,
+        settler_key,
+        settler_position,
+        quote_bank.deposit_index,
+        quote_bank.borrow_index,
+    );
+    if !settler_position_active {
+        settler.deactivate_token_position_and_log(settler_raw_index, settler_key);
+    }
+
+    emit!(Serum3SettleFundsPermissionlessLog {
+        mango_group: group_key,
+        mango_account: account_key,
         market_index: serum_market.market_index,
-        base_token_index: serum_market.base_token_index,
-        quote_token_index: serum_market.quote_token_index,
-        base_total: after_oo.native_base_total(),
-        base_free: after_oo.native_base_free(),
-        quote_total: after_oo.native_quote_total(),
-        quote_free: after_oo.native_quote_free(),
-        referrer_rebates_accrued: after_oo.native_rebates(),
+        settler: settler_key,
+        settled_base: settled.base,
+        settled_quote: settled.quote,
+        fee: fee.to_bits(),
     });
 
     Ok(())
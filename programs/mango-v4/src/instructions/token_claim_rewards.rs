@@ -0,0 +1,51 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token;
+use fixed::types::I80F48;
+
+use crate::accounts_ix::*;
+use crate::error::*;
+use crate::logs::TokenClaimRewardsLog;
+use crate::state::*;
+
+/// Transfers the liquidity mining rewards earned by the account's position
+/// in the bank's token from the bank's rewards vault to token_account.
+///
+/// If the rewards vault can't cover everything, the remainder stays claimable.
+pub fn token_claim_rewards(ctx: Context<TokenClaimRewards>) -> Result<()> {
+    let group = ctx.accounts.group.load()?;
+    let bank = ctx.accounts.bank.load()?;
+    require_msg!(bank.has_rewards(), "no rewards configured for this bank");
+
+    let mut account = ctx.accounts.account.load_full_mut()?;
+    let (position, _) = account.token_position_mut(bank.token_index)?;
+
+    // credit rewards earned since the last position change
+    let indexed_position = position.indexed_position;
+    bank.update_rewards(position, indexed_position);
+
+    let amount = position
+        .unclaimed_rewards
+        .floor()
+        .to_num::<u64>()
+        .min(ctx.accounts.rewards_vault.amount);
+    if amount == 0 {
+        return Ok(());
+    }
+    position.unclaimed_rewards -= I80F48::from(amount);
+
+    let group_seeds = group_seeds!(group);
+    token::transfer(
+        ctx.accounts.transfer_ctx().with_signer(&[group_seeds]),
+        amount,
+    )?;
+
+    emit!(TokenClaimRewardsLog {
+        mango_group: ctx.accounts.group.key(),
+        mango_account: ctx.accounts.account.key(),
+        token_index: bank.token_index,
+        rewards_mint: bank.rewards_mint,
+        amount,
+    });
+
+    Ok(())
+}
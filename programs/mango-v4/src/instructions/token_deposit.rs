@@ -10,7 +10,7 @@ use crate::health::*;
 use crate::state::*;
 
 use crate::accounts_ix::*;
-use crate::logs::{DepositLog, TokenBalanceLog};
+use crate::logs::{emit_token_balance, DepositLog};
 
 struct DepositCommon<'a, 'info> {
     pub group: &'a AccountLoader<'info, Group>,
@@ -75,6 +75,7 @@ impl<'a, 'info> DepositCommon<'a, 'info> {
 
         let (position, raw_token_index) = account.token_position_mut(token_index)?;
 
+        let indexed_deposits_before = bank.indexed_deposits;
         let position_is_active = {
             bank.deposit(
                 position,
@@ -82,11 +83,13 @@ impl<'a, 'info> DepositCommon<'a, 'info> {
                 Clock::get()?.unix_timestamp.try_into().unwrap(),
             )?
         };
+        bank.check_deposit_limit(indexed_deposits_before)?;
+        position.cumulative_deposits += amount_i80f48.to_num::<f64>();
 
         // Transfer the actual tokens
         token::transfer(self.transfer_ctx(), amount_i80f48.to_num::<u64>())?;
 
-        let indexed_position = position.indexed_position;
+        let position_after = *position;
         let oracle_price = bank.oracle_price(
             &AccountInfoRef::borrow(self.oracle.as_ref())?,
             None, // staleness checked in health
@@ -96,14 +99,13 @@ impl<'a, 'info> DepositCommon<'a, 'info> {
         let amount_usd = (amount_i80f48 * oracle_price).to_num::<i64>();
         account.fixed.net_deposits += amount_usd;
 
-        emit!(TokenBalanceLog {
-            mango_group: self.group.key(),
-            mango_account: self.account.key(),
-            token_index,
-            indexed_position: indexed_position.to_bits(),
-            deposit_index: bank.deposit_index.to_bits(),
-            borrow_index: bank.borrow_index.to_bits(),
-        });
+        emit_token_balance(
+            self.group.key(),
+            self.account.key(),
+            &position_after,
+            bank.deposit_index,
+            bank.borrow_index,
+        );
         drop(bank);
 
         //
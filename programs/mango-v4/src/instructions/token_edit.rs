@@ -38,6 +38,12 @@ pub fn token_edit(
     reset_net_borrow_limit: bool,
     reduce_only_opt: Option<bool>,
     name_opt: Option<String>,
+    interest_curve_opt: Option<Vec<InterestCurvePointParams>>,
+    rewards_mint_opt: Option<Pubkey>,
+    rewards_vault_opt: Option<Pubkey>,
+    rewards_per_second_opt: Option<u64>,
+    rewards_deposit_share_opt: Option<f32>,
+    deposit_limit_opt: Option<u64>,
 ) -> Result<()> {
     let group = ctx.accounts.group.load()?;
 
@@ -289,6 +295,76 @@ pub fn token_edit(
             bank.name = fill_from_str(&name)?;
             require_group_admin = true;
         };
+
+        if let Some(interest_curve) = interest_curve_opt.as_ref() {
+            msg!(
+                "Interest curve: old - {:?}, new - {:?}",
+                bank.interest_curve(),
+                interest_curve
+            );
+            let curve = interest_curve
+                .iter()
+                .map(|p| InterestCurvePoint {
+                    util: I80F48::from_num(p.util),
+                    rate: I80F48::from_num(p.rate),
+                })
+                .collect::<Vec<_>>();
+            bank.set_interest_curve(&curve)?;
+            require_group_admin = true;
+        }
+
+        if let Some(rewards_mint) = rewards_mint_opt {
+            msg!(
+                "Rewards mint: old - {:?}, new - {:?}",
+                bank.rewards_mint,
+                rewards_mint
+            );
+            // unclaimed rewards are denominated in the rewards mint, it can't be changed later
+            require_msg!(!bank.has_rewards(), "the rewards mint can only be set once");
+            bank.rewards_mint = rewards_mint;
+            require_group_admin = true;
+        }
+        if let Some(rewards_vault) = rewards_vault_opt {
+            msg!(
+                "Rewards vault: old - {:?}, new - {:?}",
+                bank.rewards_vault,
+                rewards_vault
+            );
+            bank.rewards_vault = rewards_vault;
+            require_group_admin = true;
+        }
+        if let Some(rewards_per_second) = rewards_per_second_opt {
+            msg!(
+                "Rewards per second: old - {:?}, new - {:?}",
+                bank.rewards_per_second,
+                rewards_per_second
+            );
+            bank.rewards_per_second = rewards_per_second;
+            require_group_admin = true;
+        }
+        if let Some(rewards_deposit_share) = rewards_deposit_share_opt {
+            msg!(
+                "Rewards deposit share: old - {:?}, new - {:?}",
+                bank.rewards_deposit_share,
+                rewards_deposit_share
+            );
+            require_msg!(
+                (0.0..=1.0).contains(&rewards_deposit_share),
+                "rewards deposit share must be between 0 and 1"
+            );
+            bank.rewards_deposit_share = rewards_deposit_share;
+            require_group_admin = true;
+        }
+
+        if let Some(deposit_limit) = deposit_limit_opt {
+            msg!(
+                "Deposit limit: old - {:?}, new - {:?}",
+                bank.deposit_limit,
+                deposit_limit
+            );
+            bank.deposit_limit = deposit_limit;
+            require_group_admin = true;
+        }
     }
 
     // account constraint #1
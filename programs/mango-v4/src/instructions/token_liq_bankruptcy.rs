@@ -9,7 +9,7 @@ use crate::state::*;
 
 use crate::accounts_ix::*;
 use crate::logs::{
-    LoanOriginationFeeInstruction, TokenBalanceLog, TokenLiqBankruptcyLog,
+    emit_token_balance, LoanOriginationFeeInstruction, TokenLiqBankruptcyLog,
     WithdrawLoanOriginationFeeLog,
 };
 
@@ -48,7 +48,8 @@ pub fn token_liq_bankruptcy(
     let mut liqee_health_cache = new_health_cache(&liqee.borrow(), &account_retriever)
         .context("create liqee health cache")?;
     liqee_health_cache.require_after_phase2_liquidation()?;
-    liqee.fixed.set_being_liquidated(true);
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    liqee.fixed.start_being_liquidated(now_ts);
 
     let liab_is_insurance_token = liab_token_index == INSURANCE_TOKEN_INDEX;
     let (liab_bank, liab_oracle_price, opt_quote_bank_and_price) =
@@ -93,8 +94,6 @@ pub fn token_liq_bankruptcy(
     // liquidators to exploit the insurance fund for 1 native token each call.
     let liab_transfer = insurance_transfer_i80f48 / liab_to_quote_with_fee;
 
-    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
-
     let mut liqee_liab_active = true;
     if insurance_transfer > 0 {
         // liqee gets liab assets (enable dusting to prevent a case where the position is brought
@@ -125,14 +124,13 @@ pub fn token_liq_bankruptcy(
                 quote_bank.deposit(liqor_quote, insurance_transfer_i80f48, now_ts)?;
 
             // liqor quote
-            emit!(TokenBalanceLog {
-                mango_group: ctx.accounts.group.key(),
-                mango_account: ctx.accounts.liqor.key(),
-                token_index: INSURANCE_TOKEN_INDEX,
-                indexed_position: liqor_quote.indexed_position.to_bits(),
-                deposit_index: quote_deposit_index.to_bits(),
-                borrow_index: quote_borrow_index.to_bits(),
-            });
+            emit_token_balance(
+                ctx.accounts.group.key(),
+                ctx.accounts.liqor.key(),
+                liqor_quote,
+                quote_deposit_index,
+                quote_borrow_index,
+            );
 
             // transfer liab from liqee to liqor
             let (liqor_liab, liqor_liab_raw_token_index, _) =
@@ -145,14 +143,13 @@ pub fn token_liq_bankruptcy(
             )?;
 
             // liqor liab
-            emit!(TokenBalanceLog {
-                mango_group: ctx.accounts.group.key(),
-                mango_account: ctx.accounts.liqor.key(),
-                token_index: liab_token_index,
-                indexed_position: liqor_liab.indexed_position.to_bits(),
-                deposit_index: liab_deposit_index.to_bits(),
-                borrow_index: liab_borrow_index.to_bits(),
-            });
+            emit_token_balance(
+                ctx.accounts.group.key(),
+                ctx.accounts.liqor.key(),
+                liqor_liab,
+                liab_deposit_index,
+                liab_borrow_index,
+            );
 
             // Check liqor's health
             if !liqor.fixed.is_in_health_region() {
@@ -239,14 +236,13 @@ pub fn token_liq_bankruptcy(
     }
 
     // liqee liab
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.liqee.key(),
-        token_index: liab_token_index,
-        indexed_position: liqee_liab.indexed_position.to_bits(),
-        deposit_index: liab_deposit_index.to_bits(),
-        borrow_index: liab_borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.liqee.key(),
+        liqee_liab,
+        liab_deposit_index,
+        liab_borrow_index,
+    );
 
     let liab_bank = bank_ais[0].load::<Bank>()?;
     let end_liab_native = liqee_liab.native(&liab_bank);
@@ -6,7 +6,7 @@ use crate::accounts_ix::*;
 use crate::error::*;
 use crate::health::*;
 use crate::logs::{
-    LoanOriginationFeeInstruction, TokenBalanceLog, TokenLiqWithTokenLog,
+    emit_token_balance, LoanOriginationFeeInstruction, TokenLiqWithTokenLog,
     WithdrawLoanOriginationFeeLog,
 };
 use crate::state::*;
@@ -46,15 +46,23 @@ pub fn token_liq_with_token(
     let liqee_liq_end_health = liqee_health_cache.health(HealthType::LiquidationEnd);
     liqee_health_cache.require_after_phase1_liquidation()?;
 
-    if !liqee.check_liquidatable(&liqee_health_cache)? {
+    let now_ts: u64 = Clock::get()?.unix_timestamp.try_into().unwrap();
+    if !liqee.check_liquidatable(&liqee_health_cache, now_ts)? {
         return Ok(());
     }
 
+    let group = ctx.accounts.group.load()?;
+    let liquidation_fee_fraction = group.liquidation_fee_fraction(
+        liqee.fixed.being_liquidated_duration(now_ts),
+        liqee_health_cache.maint_deficit_depth(),
+    );
+    msg!("liquidation fee fraction {}", liquidation_fee_fraction);
+    let close_factor = group.liquidation_close_factor();
+
     //
     // Transfer some liab_token from liqor to liqee and
     // transfer some asset_token from liqee to liqor.
     //
-    let now_ts = Clock::get()?.unix_timestamp.try_into().unwrap();
     liquidation_action(
         &mut account_retriever,
         liab_token_index,
@@ -67,6 +75,8 @@ pub fn token_liq_with_token(
         liqee_liq_end_health,
         now_ts,
         max_liab_transfer,
+        liquidation_fee_fraction,
+        close_factor,
     )?;
 
     // Check liqor's health
@@ -91,6 +101,8 @@ pub(crate) fn liquidation_action(
     liqee_liq_end_health: I80F48,
     now_ts: u64,
     max_liab_transfer: I80F48,
+    liquidation_fee_fraction: I80F48,
+    close_factor: Option<LiquidationCloseFactor>,
 ) -> Result<()> {
     // Get the mut banks and oracle prices
     //
@@ -120,11 +132,13 @@ pub(crate) fn liquidation_action(
     // the conversion.
     // It would be more fully correct to use (1+liab_liq_fee)*(1+asset_liq_fee), but for small
     // fee amounts that is nearly identical.
+    // With a liquidation fee auction, only liquidation_fee_fraction of the fees apply.
     // For simplicity we write
     //   assets = liabs * liab_oracle_price / asset_oracle_price * fee_factor
     //   assets = liabs * liab_oracle_price_adjusted / asset_oracle_price
     //          = liabs * lopa / aop
-    let fee_factor = I80F48::ONE + asset_bank.liquidation_fee + liab_bank.liquidation_fee;
+    let fee_factor = I80F48::ONE
+        + (asset_bank.liquidation_fee + liab_bank.liquidation_fee) * liquidation_fee_fraction;
     let liab_oracle_price_adjusted = liab_oracle_price * fee_factor;
 
     let init_asset_weight = asset_bank.init_asset_weight;
@@ -171,10 +185,24 @@ pub(crate) fn liquidation_action(
     // How much liab can we get at most for the asset balance?
     let liab_possible = liqee_asset_native * asset_oracle_price / liab_oracle_price_adjusted;
 
+    // How much liab may still be liquidated in the current close factor window?
+    let liab_close_factor_remaining = if let Some(close_factor) = close_factor.as_ref() {
+        let remaining = liqee
+            .token_position_mut_by_raw_index(liqee_liab_raw_index)
+            .liquidation_close_factor_remaining(close_factor, liqee_liab_native, now_ts);
+        require!(
+            remaining.is_positive(),
+            MangoError::LiquidationCloseFactorReached
+        );
+        remaining
+    } else {
+        I80F48::MAX
+    };
+
     // The amount of liab native tokens we will transfer
     let liab_transfer = min(
         min(min(liab_needed, -liqee_liab_native), liab_possible),
-        max_liab_transfer,
+        min(max_liab_transfer, liab_close_factor_remaining),
     );
 
     // The amount of asset native tokens we will give up for them
@@ -186,9 +214,12 @@ pub(crate) fn liquidation_action(
 
     // Apply the balance changes to the liqor and liqee accounts
     let liqee_liab_position = liqee.token_position_mut_by_raw_index(liqee_liab_raw_index);
+    if close_factor.is_some() {
+        liqee_liab_position.record_liquidation(liab_transfer);
+    }
     let liqee_liab_active =
         liab_bank.deposit_with_dusting(liqee_liab_position, liab_transfer, now_ts)?;
-    let liqee_liab_indexed_position = liqee_liab_position.indexed_position;
+    let liqee_liab_position_after = *liqee_liab_position;
 
     let (liqor_liab_position, liqor_liab_raw_index, _) =
         liqor.ensure_token_position(liab_token_index)?;
@@ -198,13 +229,13 @@ pub(crate) fn liquidation_action(
         now_ts,
         liab_oracle_price,
     )?;
-    let liqor_liab_indexed_position = liqor_liab_position.indexed_position;
+    let liqor_liab_position_after = *liqor_liab_position;
     let liqee_liab_native_after = liqee_liab_position.native(liab_bank);
 
     let (liqor_asset_position, liqor_asset_raw_index, _) =
         liqor.ensure_token_position(asset_token_index)?;
     let liqor_asset_active = asset_bank.deposit(liqor_asset_position, asset_transfer, now_ts)?;
-    let liqor_asset_indexed_position = liqor_asset_position.indexed_position;
+    let liqor_asset_position_after = *liqor_asset_position;
 
     let liqee_asset_position = liqee.token_position_mut_by_raw_index(liqee_asset_raw_index);
     let liqee_asset_active = asset_bank.withdraw_without_fee_with_dusting(
@@ -213,7 +244,7 @@ pub(crate) fn liquidation_action(
         now_ts,
         asset_oracle_price,
     )?;
-    let liqee_asset_indexed_position = liqee_asset_position.indexed_position;
+    let liqee_asset_position_after = *liqee_asset_position;
     let liqee_assets_native_after = liqee_asset_position.native(asset_bank);
 
     // Update the health cache
@@ -229,41 +260,37 @@ pub(crate) fn liquidation_action(
     );
 
     // liqee asset
-    emit!(TokenBalanceLog {
-        mango_group: liqee.fixed.group,
-        mango_account: liqee_key,
-        token_index: asset_token_index,
-        indexed_position: liqee_asset_indexed_position.to_bits(),
-        deposit_index: asset_bank.deposit_index.to_bits(),
-        borrow_index: asset_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        liqee.fixed.group,
+        liqee_key,
+        &liqee_asset_position_after,
+        asset_bank.deposit_index,
+        asset_bank.borrow_index,
+    );
     // liqee liab
-    emit!(TokenBalanceLog {
-        mango_group: liqee.fixed.group,
-        mango_account: liqee_key,
-        token_index: liab_token_index,
-        indexed_position: liqee_liab_indexed_position.to_bits(),
-        deposit_index: liab_bank.deposit_index.to_bits(),
-        borrow_index: liab_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        liqee.fixed.group,
+        liqee_key,
+        &liqee_liab_position_after,
+        liab_bank.deposit_index,
+        liab_bank.borrow_index,
+    );
     // liqor asset
-    emit!(TokenBalanceLog {
-        mango_group: liqee.fixed.group,
-        mango_account: liqor_key,
-        token_index: asset_token_index,
-        indexed_position: liqor_asset_indexed_position.to_bits(),
-        deposit_index: asset_bank.deposit_index.to_bits(),
-        borrow_index: asset_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        liqee.fixed.group,
+        liqor_key,
+        &liqor_asset_position_after,
+        asset_bank.deposit_index,
+        asset_bank.borrow_index,
+    );
     // liqor liab
-    emit!(TokenBalanceLog {
-        mango_group: liqee.fixed.group,
-        mango_account: liqor_key,
-        token_index: liab_token_index,
-        indexed_position: liqor_liab_indexed_position.to_bits(),
-        deposit_index: liab_bank.deposit_index.to_bits(),
-        borrow_index: liab_bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        liqee.fixed.group,
+        liqor_key,
+        &liqor_liab_position_after,
+        liab_bank.deposit_index,
+        liab_bank.borrow_index,
+    );
 
     if loan_origination_fee.is_positive() {
         emit!(WithdrawLoanOriginationFeeLog {
@@ -326,6 +353,9 @@ mod tests {
         liab_oracle: TestAccount<StubOracle>,
         liqee: MangoAccountValue,
         liqor: MangoAccountValue,
+        liquidation_fee_fraction: I80F48,
+        close_factor: Option<LiquidationCloseFactor>,
+        now_ts: u64,
     }
 
     impl TestSetup {
@@ -356,6 +386,9 @@ mod tests {
                 liab_oracle,
                 liqee,
                 liqor,
+                liquidation_fee_fraction: I80F48::ONE,
+                close_factor: None,
+                now_ts: 0,
             }
         }
 
@@ -400,8 +433,10 @@ mod tests {
                 Pubkey::new_unique(),
                 &mut liqee_health_cache,
                 liqee_liq_end_health,
-                0,
+                setup.now_ts,
                 max_liab_transfer,
+                setup.liquidation_fee_fraction,
+                setup.close_factor,
             )?;
 
             drop(retriever);
@@ -500,4 +535,172 @@ mod tests {
         let hc = result.liqee_health_cache();
         assert_eq_f!(hc.health(HealthType::LiquidationEnd), 0.0, 0.01);
     }
+
+    #[test]
+    fn test_liq_with_token_fee_auction() {
+        let mut group: Group = bytemuck::Zeroable::zeroed();
+        assert_eq!(group.liquidation_fee_fraction(0, I80F48::ZERO), 1);
+
+        group.liquidation_auction_duration = 100;
+        group.liquidation_auction_start_fraction = 0.2;
+        group.liquidation_auction_full_fee_depth = 0.1;
+        let fraction = |duration: u64, depth: f64| {
+            group
+                .liquidation_fee_fraction(duration, I80F48::from_num(depth))
+                .to_num::<f64>()
+        };
+        assert!((fraction(0, 0.0) - 0.2).abs() < 0.0001);
+        assert!((fraction(50, 0.0) - 0.6).abs() < 0.0001);
+        assert!((fraction(0, 0.05) - 0.6).abs() < 0.0001);
+        assert!((fraction(25, 0.025) - 0.6).abs() < 0.0001);
+        assert!((fraction(100, 0.0) - 1.0).abs() < 0.0001);
+        assert!((fraction(50, 0.1) - 1.0).abs() < 0.0001);
+
+        let mut setup = TestSetup::new();
+        {
+            setup.asset_bank.data().liquidation_fee = I80F48::from_num(0.1);
+            let lb = setup.liab_bank.data();
+            lb.liquidation_fee = I80F48::from_num(0.1);
+            lb.init_liab_weight = I80F48::from_num(1.4);
+            lb.maint_liab_weight = I80F48::from_num(1.2);
+        }
+        {
+            let asset_bank = setup.asset_bank.data();
+            asset_bank
+                .change_without_fee(
+                    asset_p(&mut setup.liqee),
+                    I80F48::from_num(10.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+            asset_bank
+                .change_without_fee(
+                    asset_p(&mut setup.liqor),
+                    I80F48::from_num(1000.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+
+            let liab_bank = setup.liab_bank.data();
+            liab_bank
+                .change_without_fee(
+                    liab_p(&mut setup.liqor),
+                    I80F48::from_num(1000.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+            liab_bank
+                .change_without_fee(
+                    liab_p(&mut setup.liqee),
+                    I80F48::from_num(-9.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+        }
+        let hc = setup.liqee_health_cache();
+        assert_eq_f!(hc.maint_deficit_depth(), (10.8 - 10.0) / 10.8, 0.001);
+
+        // full fee: liab and asset fees add up to 20%
+        let mut result = setup.run(I80F48::from(1)).unwrap();
+        let liqee_asset = asset_p(&mut result.liqee);
+        assert_eq_f!(liqee_asset.native(&result.asset_bank.data()), 8.8, 0.01);
+
+        // half the fees early in the auction
+        setup.liquidation_fee_fraction = I80F48::from_num(0.5);
+        let mut result = setup.run(I80F48::from(1)).unwrap();
+        let liqee_asset = asset_p(&mut result.liqee);
+        assert_eq_f!(liqee_asset.native(&result.asset_bank.data()), 8.9, 0.01);
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -8.0, 0.01);
+    }
+
+    #[test]
+    fn test_liq_with_token_close_factor() {
+        let mut setup = TestSetup::new();
+        {
+            let lb = setup.liab_bank.data();
+            lb.init_liab_weight = I80F48::from_num(1.4);
+            lb.maint_liab_weight = I80F48::from_num(1.2);
+        }
+        {
+            let asset_bank = setup.asset_bank.data();
+            asset_bank
+                .change_without_fee(
+                    asset_p(&mut setup.liqee),
+                    I80F48::from_num(10.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+            asset_bank
+                .change_without_fee(
+                    asset_p(&mut setup.liqor),
+                    I80F48::from_num(1000.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+
+            let liab_bank = setup.liab_bank.data();
+            liab_bank
+                .change_without_fee(
+                    liab_p(&mut setup.liqor),
+                    I80F48::from_num(1000.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+            liab_bank
+                .change_without_fee(
+                    liab_p(&mut setup.liqee),
+                    I80F48::from_num(-9.0),
+                    0,
+                    I80F48::from(1),
+                )
+                .unwrap();
+        }
+
+        // without the close factor, 6.5 liab would be liquidated in one go
+        let mut result = setup.run(I80F48::from(100)).unwrap();
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -2.5, 0.01);
+
+        // a quarter of the liab per 100s window, rounded up
+        setup.close_factor = Some(LiquidationCloseFactor {
+            fraction: I80F48::from_num(0.25),
+            window: 100,
+        });
+        setup.now_ts = 1000;
+        let mut result = setup.run(I80F48::from(100)).unwrap();
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -6.0, 0.01);
+        assert_eq!(liqee_liab.liq_window_start_ts, 1000);
+        assert_eq_f!(liqee_liab.liq_window_start_liab, 9.0, 0.01);
+        assert_eq_f!(liqee_liab.liq_window_liquidated, 3.0, 0.01);
+
+        // the window's limit is used up
+        result.now_ts = 1099;
+        assert!(result
+            .run(I80F48::from(100))
+            .is_anchor_error_with_code(MangoError::LiquidationCloseFactorReached.into()));
+
+        // a new window starts with the reduced liab
+        result.now_ts = 1100;
+        let mut result = result.run(I80F48::from(100)).unwrap();
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -4.0, 0.01);
+
+        // partial use of the window leaves the rest available
+        result.now_ts = 1200;
+        let mut result = result.run(I80F48::from_num(0.5)).unwrap();
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -3.5, 0.01);
+        let mut result = result.run(I80F48::from(100)).unwrap();
+        let liqee_liab = liab_p(&mut result.liqee);
+        assert_eq_f!(liqee_liab.native(&result.liab_bank.data()), -3.0, 0.01);
+    }
 }
@@ -88,7 +88,18 @@ pub fn token_register(
         borrow_weight_scale_start_quote: f64::MAX,
         deposit_weight_scale_start_quote: f64::MAX,
         reduce_only: 0,
-        reserved: [0; 2119],
+        interest_curve_len: 0,
+        padding: Default::default(),
+        interest_curve: bytemuck::Zeroable::zeroed(),
+        rewards_mint: Pubkey::default(),
+        rewards_vault: Pubkey::default(),
+        deposit_rewards_index: I80F48::ZERO,
+        borrow_rewards_index: I80F48::ZERO,
+        rewards_per_second: 0,
+        rewards_deposit_share: 0.0,
+        padding2: Default::default(),
+        deposit_limit: 0,
+        reserved: [0; 1728],
     };
     require_gt!(bank.max_rate, MINIMUM_MAX_RATE);
 
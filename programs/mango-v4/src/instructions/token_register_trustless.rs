@@ -3,6 +3,7 @@ use fixed::types::I80F48;
 
 use crate::accounts_zerocopy::AccountInfoRef;
 use crate::error::*;
+use crate::i80f48::ClampToInt;
 use crate::instructions::INDEX_START;
 use crate::state::*;
 use crate::util::fill_from_str;
@@ -74,7 +75,18 @@ pub fn token_register_trustless(
         borrow_weight_scale_start_quote: f64::MAX,
         deposit_weight_scale_start_quote: f64::MAX,
         reduce_only: 0,
-        reserved: [0; 2119],
+        interest_curve_len: 0,
+        padding: Default::default(),
+        interest_curve: bytemuck::Zeroable::zeroed(),
+        rewards_mint: Pubkey::default(),
+        rewards_vault: Pubkey::default(),
+        deposit_rewards_index: I80F48::ZERO,
+        borrow_rewards_index: I80F48::ZERO,
+        rewards_per_second: 0,
+        rewards_deposit_share: 0.0,
+        padding2: Default::default(),
+        deposit_limit: 0,
+        reserved: [0; 1728],
     };
     require_gt!(bank.max_rate, MINIMUM_MAX_RATE);
 
@@ -83,6 +95,14 @@ pub fn token_register_trustless(
     bank.stable_price_model
         .reset_to_price(oracle_price.to_num(), now_ts);
 
+    // Start with deposits worth at most 100k USD at the listing price
+    bank.deposit_limit = I80F48::from(100_000_000_000u64)
+        .checked_div(oracle_price)
+        .unwrap_or(I80F48::ZERO)
+        .floor()
+        .clamp_to_u64()
+        .max(1);
+
     let mut mint_info = ctx.accounts.mint_info.load_init()?;
     *mint_info = MintInfo {
         group: ctx.accounts.group.key(),
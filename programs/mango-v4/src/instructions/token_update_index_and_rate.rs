@@ -78,6 +78,9 @@ pub fn token_update_index_and_rate(ctx: Context<TokenUpdateIndexAndRate>) -> Res
 
         some_bank.collected_fees_native += borrow_fees;
 
+        let (deposit_rewards_index, borrow_rewards_index) =
+            some_bank.compute_rewards_index(indexed_total_deposits, indexed_total_borrows, diff_ts);
+
         let new_avg_utilization = some_bank.compute_new_avg_utilization(
             indexed_total_deposits,
             indexed_total_borrows,
@@ -118,6 +121,8 @@ pub fn token_update_index_and_rate(ctx: Context<TokenUpdateIndexAndRate>) -> Res
         msg!("deposit_index {}", deposit_index);
         msg!("borrow_index {}", borrow_index);
         msg!("avg_utilization {}", new_avg_utilization);
+        msg!("deposit_rewards_index {}", deposit_rewards_index);
+        msg!("borrow_rewards_index {}", borrow_rewards_index);
 
         for ai in ctx.remaining_accounts.iter() {
             let mut bank = ai.load_mut::<Bank>()?;
@@ -127,6 +132,9 @@ pub fn token_update_index_and_rate(ctx: Context<TokenUpdateIndexAndRate>) -> Res
             bank.deposit_index = deposit_index;
             bank.borrow_index = borrow_index;
 
+            bank.deposit_rewards_index = deposit_rewards_index;
+            bank.borrow_rewards_index = borrow_rewards_index;
+
             bank.avg_utilization = new_avg_utilization;
 
             bank.stable_price_model = stable_price_model;
@@ -141,6 +149,7 @@ pub fn token_update_index_and_rate(ctx: Context<TokenUpdateIndexAndRate>) -> Res
 
         // update each hour
         if diff_ts > HOUR {
+            let adjustment = some_bank.compute_rate_adjustment();
             let (rate0, rate1, max_rate) = some_bank.compute_rates();
 
             emit!(UpdateRateLog {
@@ -164,6 +173,7 @@ pub fn token_update_index_and_rate(ctx: Context<TokenUpdateIndexAndRate>) -> Res
                 bank.rate0 = rate0;
                 bank.rate1 = rate1;
                 bank.max_rate = max_rate;
+                bank.adjust_interest_curve_rates(adjustment);
             }
         }
     }
@@ -8,7 +8,7 @@ use fixed::types::I80F48;
 
 use crate::accounts_ix::*;
 use crate::logs::{
-    LoanOriginationFeeInstruction, TokenBalanceLog, WithdrawLoanOriginationFeeLog, WithdrawLog,
+    emit_token_balance, LoanOriginationFeeInstruction, WithdrawLoanOriginationFeeLog, WithdrawLog,
 };
 
 pub fn token_withdraw(ctx: Context<TokenWithdraw>, amount: u64, allow_borrow: bool) -> Result<()> {
@@ -71,6 +71,7 @@ pub fn token_withdraw(ctx: Context<TokenWithdraw>, amount: u64, allow_borrow: bo
         Clock::get()?.unix_timestamp.try_into().unwrap(),
         oracle_price,
     )?;
+    position.cumulative_withdraws += amount_i80f48.to_num::<f64>();
 
     // Provide a readable error message in case the vault doesn't have enough tokens
     if ctx.accounts.vault.amount < amount {
@@ -91,14 +92,13 @@ pub fn token_withdraw(ctx: Context<TokenWithdraw>, amount: u64, allow_borrow: bo
 
     let native_position_after = position.native(&bank);
 
-    emit!(TokenBalanceLog {
-        mango_group: ctx.accounts.group.key(),
-        mango_account: ctx.accounts.account.key(),
-        token_index,
-        indexed_position: position.indexed_position.to_bits(),
-        deposit_index: bank.deposit_index.to_bits(),
-        borrow_index: bank.borrow_index.to_bits(),
-    });
+    emit_token_balance(
+        ctx.accounts.group.key(),
+        ctx.accounts.account.key(),
+        position,
+        bank.deposit_index,
+        bank.borrow_index,
+    );
 
     // Update the net deposits - adjust by price so different tokens are on the same basis (in USD terms)
     let amount_usd = (amount_i80f48 * oracle_price).to_num::<i64>();
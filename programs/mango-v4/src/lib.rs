@@ -31,7 +31,8 @@ pub mod instructions;
 compile_error!("compiling the program entrypoint without 'enable-gpl' makes no sense, enable it or use the 'cpi' or 'client' features");
 
 use state::{
-    OracleConfigParams, PerpMarketIndex, PlaceOrderType, Serum3MarketIndex, Side, TokenIndex,
+    OracleConfigParams, PerpFeeTier, PerpMarketIndex, PlaceOrderType, Serum3MarketIndex, Side,
+    TokenIndex,
 };
 
 declare_id!("4MangoMjqJ2firMokCjjGgoK8d4MXcrgL7XJaL3w6fVg");
@@ -65,6 +66,13 @@ pub mod mango_v4 {
         buyback_fees_swap_mango_account_opt: Option<Pubkey>,
         mngo_token_index_opt: Option<TokenIndex>,
         buyback_fees_expiry_interval_opt: Option<u64>,
+        perp_fee_tiers_opt: Option<Vec<PerpFeeTier>>,
+        liquidation_auction_duration_opt: Option<u64>,
+        liquidation_auction_start_fraction_opt: Option<f32>,
+        liquidation_auction_full_fee_depth_opt: Option<f32>,
+        liquidation_close_factor_opt: Option<f32>,
+        liquidation_close_factor_window_opt: Option<u64>,
+        self_liquidation_fee_fraction_opt: Option<f32>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::group_edit(
@@ -80,6 +88,13 @@ pub mod mango_v4 {
             buyback_fees_swap_mango_account_opt,
             mngo_token_index_opt,
             buyback_fees_expiry_interval_opt,
+            perp_fee_tiers_opt,
+            liquidation_auction_duration_opt,
+            liquidation_auction_start_fraction_opt,
+            liquidation_auction_full_fee_depth_opt,
+            liquidation_close_factor_opt,
+            liquidation_close_factor_window_opt,
+            self_liquidation_fee_fraction_opt,
         )?;
         Ok(())
     }
@@ -171,6 +186,12 @@ pub mod mango_v4 {
         reset_net_borrow_limit: bool,
         reduce_only_opt: Option<bool>,
         name_opt: Option<String>,
+        interest_curve_opt: Option<Vec<InterestCurvePointParams>>,
+        rewards_mint_opt: Option<Pubkey>,
+        rewards_vault_opt: Option<Pubkey>,
+        rewards_per_second_opt: Option<u64>,
+        rewards_deposit_share_opt: Option<f32>,
+        deposit_limit_opt: Option<u64>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::token_edit(
@@ -198,6 +219,12 @@ pub mod mango_v4 {
             reset_net_borrow_limit,
             reduce_only_opt,
             name_opt,
+            interest_curve_opt,
+            rewards_mint_opt,
+            rewards_vault_opt,
+            rewards_per_second_opt,
+            rewards_deposit_share_opt,
+            deposit_limit_opt,
         )?;
         Ok(())
     }
@@ -257,7 +284,34 @@ pub mod mango_v4 {
         perp_oo_count: u8,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
-        instructions::account_expand(ctx, token_count, serum3_count, perp_count, perp_oo_count)?;
+        instructions::account_expand(
+            ctx,
+            token_count,
+            serum3_count,
+            perp_count,
+            perp_oo_count,
+            None,
+        )?;
+        Ok(())
+    }
+
+    pub fn account_expand_v2(
+        ctx: Context<AccountExpand>,
+        token_count: u8,
+        serum3_count: u8,
+        perp_count: u8,
+        perp_oo_count: u8,
+        isolated_perp_count: u8,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_expand(
+            ctx,
+            token_count,
+            serum3_count,
+            perp_count,
+            perp_oo_count,
+            Some(isolated_perp_count),
+        )?;
         Ok(())
     }
 
@@ -341,6 +395,12 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    pub fn token_claim_rewards(ctx: Context<TokenClaimRewards>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::token_claim_rewards(ctx)?;
+        Ok(())
+    }
+
     pub fn flash_loan_begin<'key, 'accounts, 'remaining, 'info>(
         ctx: Context<'key, 'accounts, 'remaining, 'info, FlashLoanBegin<'info>>,
         loan_amounts: Vec<u64>,
@@ -392,9 +452,18 @@ pub mod mango_v4 {
     pub fn serum3_edit_market(
         ctx: Context<Serum3EditMarket>,
         reduce_only_opt: Option<bool>,
+        settle_fee_flat_opt: Option<f32>,
+        settle_fee_amount_threshold_opt: Option<f32>,
+        settle_rebates_to_account_opt: Option<bool>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
-        instructions::serum3_edit_market(ctx, reduce_only_opt)?;
+        instructions::serum3_edit_market(
+            ctx,
+            reduce_only_opt,
+            settle_fee_flat_opt,
+            settle_fee_amount_threshold_opt,
+            settle_rebates_to_account_opt,
+        )?;
         Ok(())
     }
 
@@ -444,6 +513,38 @@ pub mod mango_v4 {
             order_type,
             client_order_id,
             limit,
+            false,
+        )?;
+        Ok(())
+    }
+
+    /// Like Serum3PlaceOrder, but with `reduce_only` the order is reduced such that
+    /// the account's net base position can't grow in magnitude.
+    #[allow(clippy::too_many_arguments)]
+    pub fn serum3_place_order_v2(
+        ctx: Context<Serum3PlaceOrder>,
+        side: Serum3Side,
+        limit_price: u64,
+        max_base_qty: u64,
+        max_native_quote_qty_including_fees: u64,
+        self_trade_behavior: Serum3SelfTradeBehavior,
+        order_type: Serum3OrderType,
+        client_order_id: u64,
+        limit: u16,
+        reduce_only: bool,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::serum3_place_order(
+            ctx,
+            side,
+            limit_price,
+            max_base_qty,
+            max_native_quote_qty_including_fees,
+            self_trade_behavior,
+            order_type,
+            client_order_id,
+            limit,
+            reduce_only,
         )?;
         Ok(())
     }
@@ -488,6 +589,16 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    /// Settles free funds into the MangoAccount's bank positions. Anyone may call this,
+    /// the caller's `settler` account may receive a fee.
+    pub fn serum3_settle_funds_permissionless(
+        ctx: Context<Serum3SettleFundsPermissionless>,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::serum3_settle_funds_permissionless(ctx)?;
+        Ok(())
+    }
+
     pub fn serum3_liq_force_cancel_orders(
         ctx: Context<Serum3LiqForceCancelOrders>,
         limit: u8,
@@ -650,6 +761,9 @@ pub mod mango_v4 {
         reset_stable_price: bool,
         positive_pnl_liquidation_fee_opt: Option<f32>,
         name_opt: Option<String>,
+        referrer_fee_share_opt: Option<f32>,
+        adl_enabled_opt: Option<bool>,
+        adl_min_score_opt: Option<f32>,
     ) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::perp_edit_market(
@@ -683,6 +797,9 @@ pub mod mango_v4 {
             reset_stable_price,
             positive_pnl_liquidation_fee_opt,
             name_opt,
+            referrer_fee_share_opt,
+            adl_enabled_opt,
+            adl_min_score_opt,
         )?;
         Ok(())
     }
@@ -916,6 +1033,38 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    pub fn perp_adl(
+        ctx: Context<PerpAdl>,
+        max_base_transfer: u64,
+        num_candidates: u8,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_adl(ctx, max_base_transfer, num_candidates)?;
+        Ok(())
+    }
+
+    pub fn perp_isolated_deposit(ctx: Context<PerpIsolatedCollateral>, amount: u64) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_isolated_deposit(ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn perp_isolated_withdraw(ctx: Context<PerpIsolatedCollateral>, amount: u64) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_isolated_withdraw(ctx, amount)?;
+        Ok(())
+    }
+
+    pub fn perp_liq_isolated(
+        ctx: Context<PerpLiqIsolated>,
+        max_base_transfer: i64,
+        max_pnl_transfer: u64,
+    ) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::perp_liq_isolated(ctx, max_base_transfer, max_pnl_transfer)?;
+        Ok(())
+    }
+
     pub fn alt_set(ctx: Context<AltSet>, index: u8) -> Result<()> {
         #[cfg(feature = "enable-gpl")]
         instructions::alt_set(ctx, index)?;
@@ -938,6 +1087,12 @@ pub mod mango_v4 {
         Ok(())
     }
 
+    pub fn account_health_snapshot(ctx: Context<AccountHealthSnapshot>) -> Result<()> {
+        #[cfg(feature = "enable-gpl")]
+        instructions::account_health_snapshot(ctx)?;
+        Ok(())
+    }
+
     ///
     /// benchmark
     ///
@@ -1,9 +1,10 @@
 use crate::{
     accounts_ix::FlashLoanType,
-    state::{PerpMarket, PerpPosition},
+    state::{PerpMarket, PerpPosition, TokenPosition},
 };
 use anchor_lang::prelude::*;
 use borsh::BorshSerialize;
+use fixed::types::I80F48;
 
 pub fn emit_perp_balances(
     mango_group: Pubkey,
@@ -24,6 +25,38 @@ pub fn emit_perp_balances(
     });
 }
 
+/// Emits TokenBalanceLog and TokenBalanceLogV2 for a token position
+///
+/// The original layout keeps being emitted for existing log consumers.
+pub fn emit_token_balance(
+    mango_group: Pubkey,
+    mango_account: Pubkey,
+    tp: &TokenPosition,
+    deposit_index: I80F48,
+    borrow_index: I80F48,
+) {
+    emit!(TokenBalanceLog {
+        mango_group,
+        mango_account,
+        token_index: tp.token_index,
+        indexed_position: tp.indexed_position.to_bits(),
+        deposit_index: deposit_index.to_bits(),
+        borrow_index: borrow_index.to_bits(),
+    });
+    emit!(TokenBalanceLogV2 {
+        mango_group,
+        mango_account,
+        token_index: tp.token_index,
+        indexed_position: tp.indexed_position.to_bits(),
+        deposit_index: deposit_index.to_bits(),
+        borrow_index: borrow_index.to_bits(),
+        cumulative_deposit_interest: tp.cumulative_deposit_interest,
+        cumulative_borrow_interest: tp.cumulative_borrow_interest,
+        cumulative_deposits: tp.cumulative_deposits,
+        cumulative_withdraws: tp.cumulative_withdraws,
+    });
+}
+
 #[event]
 pub struct PerpBalanceLog {
     pub mango_group: Pubkey,
@@ -47,6 +80,21 @@ pub struct TokenBalanceLog {
     pub borrow_index: i128,     // I80F48
 }
 
+#[event]
+pub struct TokenBalanceLogV2 {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub token_index: u16,
+    pub indexed_position: i128, // I80F48
+    pub deposit_index: i128,    // I80F48
+    pub borrow_index: i128,     // I80F48
+    // the position's display-only totals, in token native units
+    pub cumulative_deposit_interest: f64,
+    pub cumulative_borrow_interest: f64,
+    pub cumulative_deposits: f64,
+    pub cumulative_withdraws: f64,
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize)]
 pub struct FlashLoanTokenDetail {
     pub token_index: u16,
@@ -66,6 +114,18 @@ pub struct FlashLoanLog {
     pub flash_loan_type: FlashLoanType,
 }
 
+#[event]
+pub struct FlashLoanSelfLiquidationLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub asset_token_index: u16,
+    pub liab_token_index: u16,
+    pub liab_repaid: i128,           // I80F48
+    pub fee: i128,                   // I80F48
+    pub liq_end_health_before: i128, // I80F48
+    pub liq_end_health_after: i128,  // I80F48
+}
+
 #[event]
 pub struct WithdrawLog {
     pub mango_group: Pubkey,
@@ -137,6 +197,48 @@ pub struct FillLogV2 {
     pub quantity: i64, // number of base lots
 }
 
+#[event]
+pub struct FillLogV3 {
+    pub mango_group: Pubkey,
+    pub market_index: u16,
+    pub taker_side: u8, // side from the taker's POV
+    pub maker_slot: u8,
+    pub maker_out: bool, // true if maker order quantity == 0
+    pub timestamp: u64,
+    pub seq_num: u64, // note: usize same as u64
+
+    pub maker: Pubkey,
+    pub maker_client_order_id: u64,
+    pub maker_fee: f32,
+
+    // Timestamp of when the maker order was placed; copied over from the LeafNode
+    pub maker_timestamp: u64,
+
+    pub taker: Pubkey,
+    pub taker_client_order_id: u64,
+    pub taker_fee: f32,
+
+    pub price: i64,
+    pub quantity: i64, // number of base lots
+
+    // Trade pnl realized by this fill, without fees, and the position's
+    // avg_entry_price_per_base_lot after it
+    pub maker_realized_pnl: i128, // I80F48
+    pub maker_entry_price_per_base_lot: f64,
+    pub taker_realized_pnl: i128, // I80F48
+    pub taker_entry_price_per_base_lot: f64,
+}
+
+#[event]
+pub struct PerpReferrerRewardLog {
+    pub mango_group: Pubkey,
+    pub market_index: u16,
+    pub referrer: Pubkey,
+    pub taker: Pubkey,
+    pub seq_num: u64, // of the fill event
+    pub reward: i128, // I80F48
+}
+
 #[event]
 pub struct PerpUpdateFundingLog {
     pub mango_group: Pubkey,
@@ -217,6 +319,38 @@ pub struct Serum3OpenOrdersBalanceLogV2 {
     pub referrer_rebates_accrued: u64,
 }
 
+#[event]
+pub struct Serum3OpenOrdersBalanceLogV3 {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub market_index: u16,
+    pub base_token_index: u16,
+    pub quote_token_index: u16,
+    pub base_total: u64,
+    pub base_free: u64,
+    pub quote_total: u64,
+    pub quote_free: u64,
+    pub referrer_rebates_accrued: u64,
+    // cumulative fills of the account's orders on the market, see Serum3Orders
+    pub base_bought: u64,
+    pub quote_paid: u64,
+    pub base_sold: u64,
+    pub quote_received: u64,
+    pub estimated_net_fees: i64,
+    pub referrer_rebates_settled: u64,
+}
+
+#[event]
+pub struct Serum3SettleFundsPermissionlessLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub market_index: u16,
+    pub settler: Pubkey,
+    pub settled_base: u64,
+    pub settled_quote: u64,
+    pub fee: i128,
+}
+
 #[derive(PartialEq, Copy, Clone, Debug, AnchorSerialize, AnchorDeserialize)]
 #[repr(u8)]
 pub enum LoanOriginationFeeInstruction {
@@ -333,6 +467,19 @@ pub struct PerpLiqBankruptcyLog {
     pub ending_short_funding: i128,
 }
 
+#[event]
+pub struct PerpAdlLog {
+    pub mango_group: Pubkey,
+    pub liqee: Pubkey,
+    pub counterparty: Pubkey,
+    pub perp_market_index: u16,
+    pub base_transfer_liqee: i64,
+    pub quote_transfer_liqee: i128, // I80F48
+    pub bankruptcy_price: i128,     // I80F48
+    pub adl_score: i128,            // I80F48
+    pub price: i128,                // I80F48
+}
+
 #[event]
 pub struct PerpLiqNegativePnlOrBankruptcyLog {
     pub mango_group: Pubkey,
@@ -370,3 +517,47 @@ pub struct AccountBuybackFeesWithMngoLog {
     pub mngo_buyback_price: i128,
     pub oracle_price: i128,
 }
+
+#[event]
+pub struct TokenClaimRewardsLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub token_index: u16,
+    pub rewards_mint: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct PerpIsolatedCollateralLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub perp_market_index: u16,
+    pub token_index: u16,
+    pub change: i128,             // I80F48, positive for deposits
+    pub indexed_collateral: i128, // I80F48
+    pub deposit_index: i128,      // I80F48
+}
+
+#[event]
+pub struct PerpLiqIsolatedLog {
+    pub mango_group: Pubkey,
+    pub perp_market_index: u16,
+    pub liqor: Pubkey,
+    pub liqee: Pubkey,
+    pub base_transfer: i64,
+    pub quote_transfer: i128,
+    pub collateral_transfer: i128,
+    pub bankruptcy_loss: i128,
+    pub price: i128,
+}
+
+#[event]
+pub struct AccountHealthSnapshotLog {
+    pub mango_group: Pubkey,
+    pub mango_account: Pubkey,
+    pub init_health: i128,            // I80F48
+    pub maint_health: i128,           // I80F48
+    pub liquidation_end_health: i128, // I80F48
+    pub equity: i128,                 // I80F48, in native quote
+    pub being_liquidated: bool,
+}
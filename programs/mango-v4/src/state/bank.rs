@@ -1,4 +1,4 @@
-use super::{OracleConfig, TokenIndex, TokenPosition};
+use super::{IsolatedPerpPosition, OracleConfig, TokenIndex, TokenPosition};
 use crate::accounts_zerocopy::KeyedAccountReader;
 use crate::error::*;
 use crate::i80f48::ClampToInt;
@@ -129,8 +129,45 @@ pub struct Bank {
 
     pub reduce_only: u8,
 
+    /// Number of points in interest_curve that are in use.
+    ///
+    /// If zero, the interest rate curve is defined by util0, rate0, util1, rate1 and max_rate.
+    pub interest_curve_len: u8,
+
+    #[derivative(Debug = "ignore")]
+    pub padding: [u8; 14],
+
+    /// Points of a piecewise-linear interest rate curve, starting from (0, 0)
+    ///
+    /// The utilization of the first point is the optimal utilization used for
+    /// rate adjustments, the last point is at 100% utilization.
+    pub interest_curve: [InterestCurvePoint; MAX_INTEREST_CURVE_POINTS],
+
+    /// Mint of the liquidity mining rewards, zero if no rewards are configured
+    pub rewards_mint: Pubkey,
+    /// Token account of rewards_mint owned by the group, rewards are claimed from here
+    pub rewards_vault: Pubkey,
+
+    /// Accumulated rewards (in rewards_mint native) per indexed deposit
+    pub deposit_rewards_index: I80F48,
+    /// Accumulated rewards (in rewards_mint native) per indexed borrow
+    pub borrow_rewards_index: I80F48,
+
+    /// Rewards emitted per second, in rewards_mint native units
+    pub rewards_per_second: u64,
+    /// Fraction of the emissions that goes to depositors, the rest goes to borrowers
+    pub rewards_deposit_share: f32,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 2119],
+    pub padding2: [u8; 4],
+
+    /// Limit for the total native deposits of the token, set to 0 to disable
+    ///
+    /// Deposits that would make native_deposits() exceed this fail.
+    pub deposit_limit: u64,
+
+    #[derivative(Debug = "ignore")]
+    pub reserved: [u8; 1728],
 }
 const_assert_eq!(
     size_of::<Bank>(),
@@ -158,11 +195,34 @@ const_assert_eq!(
         + 8
         + 8
         + 1
-        + 2119
+        + 1
+        + 14
+        + 32 * MAX_INTEREST_CURVE_POINTS
+        + 32 * 2
+        + 16 * 2
+        + 8
+        + 4
+        + 4
+        + 8
+        + 1728
 );
 const_assert_eq!(size_of::<Bank>(), 3064);
 const_assert_eq!(size_of::<Bank>() % 8, 0);
 
+pub const MAX_INTEREST_CURVE_POINTS: usize = 8;
+
+/// A point on a Bank's interest rate curve
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InterestCurvePoint {
+    /// Utilization, between 0 and 1
+    pub util: I80F48,
+    /// Borrow interest rate in APR at this utilization
+    pub rate: I80F48,
+}
+const_assert_eq!(size_of::<InterestCurvePoint>(), 32);
+const_assert_eq!(size_of::<InterestCurvePoint>() % 8, 0);
+
 impl Bank {
     pub fn from_existing_bank(
         existing_bank: &Bank,
@@ -219,7 +279,18 @@ impl Bank {
             borrow_weight_scale_start_quote: f64::MAX,
             deposit_weight_scale_start_quote: f64::MAX,
             reduce_only: 0,
-            reserved: [0; 2119],
+            interest_curve_len: existing_bank.interest_curve_len,
+            padding: Default::default(),
+            interest_curve: existing_bank.interest_curve,
+            rewards_mint: existing_bank.rewards_mint,
+            rewards_vault: existing_bank.rewards_vault,
+            deposit_rewards_index: existing_bank.deposit_rewards_index,
+            borrow_rewards_index: existing_bank.borrow_rewards_index,
+            rewards_per_second: existing_bank.rewards_per_second,
+            rewards_deposit_share: existing_bank.rewards_deposit_share,
+            padding2: Default::default(),
+            deposit_limit: existing_bank.deposit_limit,
+            reserved: [0; 1728],
         }
     }
 
@@ -243,6 +314,27 @@ impl Bank {
         self.deposit_index * self.indexed_deposits
     }
 
+    /// Error if deposits grew since `indexed_deposits_before` and now exceed deposit_limit.
+    ///
+    /// Withdraws and borrow repayments stay possible when the limit was lowered below
+    /// the current deposits.
+    pub fn check_deposit_limit(&self, indexed_deposits_before: I80F48) -> Result<()> {
+        if self.deposit_limit == 0 || self.indexed_deposits <= indexed_deposits_before {
+            return Ok(());
+        }
+
+        let native_deposits = self.native_deposits();
+        require_msg_typed!(
+            native_deposits <= I80F48::from(self.deposit_limit),
+            MangoError::BankDepositLimit,
+            "native deposits ({}) exceed the deposit limit ({}) of token {}",
+            native_deposits,
+            self.deposit_limit,
+            self.token_index,
+        );
+        Ok(())
+    }
+
     /// Prevent borrowing away the full bank vault.
     /// Keep some in reserve to satisfy non-borrow withdraws.
     pub fn enforce_min_vault_to_deposits_ratio(&self, vault_ai: &AccountInfo) -> Result<()> {
@@ -306,6 +398,7 @@ impl Bank {
         let opening_indexed_position = position.indexed_position;
         let result = self.deposit_internal(position, native_amount, allow_dusting, now_ts)?;
         self.update_cumulative_interest(position, opening_indexed_position);
+        self.update_rewards(position, opening_indexed_position);
         Ok(result)
     }
 
@@ -446,6 +539,42 @@ impl Bank {
         )
     }
 
+    /// Deposits `native_amount` into the collateral of an isolated perp position.
+    ///
+    /// Isolated collateral is held as deposit shares, it earns deposit interest but
+    /// never becomes a borrow.
+    pub fn deposit_isolated(
+        &mut self,
+        position: &mut IsolatedPerpPosition,
+        native_amount: I80F48,
+    ) -> Result<()> {
+        require_gte!(native_amount, 0);
+        let indexed_change = native_amount / self.deposit_index;
+        self.indexed_deposits += indexed_change;
+        position.indexed_collateral += indexed_change;
+        Ok(())
+    }
+
+    /// Withdraws `native_amount` from the collateral of an isolated perp position.
+    ///
+    /// The amount must not exceed the available collateral.
+    pub fn withdraw_isolated(
+        &mut self,
+        position: &mut IsolatedPerpPosition,
+        native_amount: I80F48,
+    ) -> Result<()> {
+        require_gte!(native_amount, 0);
+        require_gte!(position.native(self), native_amount);
+        let mut indexed_change = native_amount / self.deposit_index;
+        if indexed_change * self.deposit_index < native_amount {
+            indexed_change += I80F48::DELTA;
+        }
+        let indexed_change = indexed_change.min(position.indexed_collateral);
+        self.indexed_deposits -= indexed_change;
+        position.indexed_collateral -= indexed_change;
+        Ok(())
+    }
+
     /// Internal function to withdraw funds
     fn withdraw_internal_wrapper(
         &mut self,
@@ -466,6 +595,7 @@ impl Bank {
             oracle_price,
         );
         self.update_cumulative_interest(position, opening_indexed_position);
+        self.update_rewards(position, opening_indexed_position);
         res
     }
 
@@ -645,6 +775,62 @@ impl Bank {
         }
     }
 
+    /// Credit the rewards earned by `opening_indexed_position` since the last change
+    /// to unclaimed_rewards and remember the current rewards index for the new position.
+    ///
+    /// Unclaimed rewards are forfeited when the token position is deactivated.
+    pub fn update_rewards(&self, position: &mut TokenPosition, opening_indexed_position: I80F48) {
+        if opening_indexed_position.is_positive() {
+            position.unclaimed_rewards += (self.deposit_rewards_index
+                - position.previous_rewards_index)
+                * opening_indexed_position;
+        } else if opening_indexed_position.is_negative() {
+            position.unclaimed_rewards -= (self.borrow_rewards_index
+                - position.previous_rewards_index)
+                * opening_indexed_position;
+        }
+
+        if position.indexed_position.is_positive() {
+            position.previous_rewards_index = self.deposit_rewards_index
+        } else {
+            position.previous_rewards_index = self.borrow_rewards_index
+        }
+    }
+
+    pub fn has_rewards(&self) -> bool {
+        self.rewards_mint != Pubkey::default()
+    }
+
+    /// Compute new deposit and borrow rewards indexes after `diff_ts` seconds of emissions
+    pub fn compute_rewards_index(
+        &self,
+        indexed_total_deposits: I80F48,
+        indexed_total_borrows: I80F48,
+        diff_ts: I80F48,
+    ) -> (I80F48, I80F48) {
+        if !self.has_rewards() || self.rewards_per_second == 0 {
+            return (self.deposit_rewards_index, self.borrow_rewards_index);
+        }
+
+        let emissions = I80F48::from(self.rewards_per_second) * diff_ts;
+        let deposit_share = I80F48::from_num(self.rewards_deposit_share);
+        let deposit_emissions = emissions * deposit_share;
+        let borrow_emissions = emissions - deposit_emissions;
+
+        // emissions for a side without positions are not distributed
+        let deposit_rewards_index = if indexed_total_deposits.is_positive() {
+            self.deposit_rewards_index + deposit_emissions / indexed_total_deposits
+        } else {
+            self.deposit_rewards_index
+        };
+        let borrow_rewards_index = if indexed_total_borrows.is_positive() {
+            self.borrow_rewards_index + borrow_emissions / indexed_total_borrows
+        } else {
+            self.borrow_rewards_index
+        };
+        (deposit_rewards_index, borrow_rewards_index)
+    }
+
     pub fn compute_index(
         &self,
         indexed_total_deposits: I80F48,
@@ -694,9 +880,42 @@ impl Bank {
         ))
     }
 
+    pub fn interest_curve(&self) -> &[InterestCurvePoint] {
+        &self.interest_curve[..self.interest_curve_len as usize]
+    }
+
+    /// Replace the interest curve, an empty curve means util0, rate0, util1, rate1
+    /// and max_rate are used instead.
+    ///
+    /// Utilizations must be increasing, with the last point at 100% utilization.
+    /// Rates may not be negative or decrease.
+    pub fn set_interest_curve(&mut self, curve: &[InterestCurvePoint]) -> Result<()> {
+        require_gte!(MAX_INTEREST_CURVE_POINTS, curve.len());
+        if let Some(last) = curve.last() {
+            require_eq!(last.util, I80F48::ONE);
+        }
+        let mut prev_util = I80F48::ZERO;
+        let mut prev_rate = I80F48::ZERO;
+        for point in curve {
+            require_gt!(point.util, prev_util);
+            require_gte!(point.rate, prev_rate);
+            prev_util = point.util;
+            prev_rate = point.rate;
+        }
+
+        self.interest_curve = bytemuck::Zeroable::zeroed();
+        self.interest_curve[..curve.len()].copy_from_slice(curve);
+        self.interest_curve_len = curve.len() as u8;
+        Ok(())
+    }
+
     /// returns the current interest rate in APR
     #[inline(always)]
     pub fn compute_interest_rate(&self, utilization: I80F48) -> I80F48 {
+        let curve = self.interest_curve();
+        if !curve.is_empty() {
+            return Bank::interest_curve_calculator(utilization, curve);
+        }
         Bank::interest_rate_curve_calculator(
             utilization,
             self.util0,
@@ -707,6 +926,23 @@ impl Bank {
         )
     }
 
+    /// Interpolate the interest rate between the points of a curve that starts at (0, 0).
+    ///
+    /// Utilization beyond the last point continues the slope of the last segment.
+    pub fn interest_curve_calculator(utilization: I80F48, curve: &[InterestCurvePoint]) -> I80F48 {
+        let mut prev_util = I80F48::ZERO;
+        let mut prev_rate = I80F48::ZERO;
+        for (i, point) in curve.iter().enumerate() {
+            if utilization <= point.util || i == curve.len() - 1 {
+                let slope = (point.rate - prev_rate) / (point.util - prev_util);
+                return prev_rate + slope * (utilization - prev_util);
+            }
+            prev_util = point.util;
+            prev_rate = point.rate;
+        }
+        I80F48::ZERO
+    }
+
     /// calcualtor function that can be used to compute an interest
     /// rate based on the given parameters
     #[inline(always)]
@@ -765,9 +1001,24 @@ impl Bank {
 
     // computes new optimal rates and max rate
     pub fn compute_rates(&self) -> (I80F48, I80F48, I80F48) {
+        let adjustment = self.compute_rate_adjustment();
+        (
+            self.rate0 * adjustment,
+            self.rate1 * adjustment,
+            self.max_rate * adjustment,
+        )
+    }
+
+    /// Factor by which all rates of the interest curve get multiplied in a rate update
+    pub fn compute_rate_adjustment(&self) -> I80F48 {
         // interest rate legs 2 and 3 are seen as punitive legs, encouraging utilization to move towards optimal utilization
         // lets choose util0 as optimal utilization and 0 to utli0 as the leg where we want the utlization to preferably be
-        let optimal_util = self.util0;
+        // (for a custom interest curve, that's the first point)
+        let curve = self.interest_curve();
+        let (optimal_util, max_rate) = match (curve.first(), curve.last()) {
+            (Some(first), Some(last)) => (first.util, last.rate),
+            _ => (self.util0, self.max_rate),
+        };
         // use avg_utilization and not instantaneous_utilization so that rates cannot be manipulated easily
         let avg_util = self.avg_utilization;
         // move rates up when utilization is above optimal utilization, and vice versa
@@ -783,14 +1034,18 @@ impl Bank {
         // 2. only update rates as long as new adjusted rates are above MINIMUM_MAX_RATE,
         //  since we don't want to fall to such low rates that it would take a long time to
         //  recover to high rates if utilization suddently increases to a high value
-        if (self.max_rate * adjustment) > MINIMUM_MAX_RATE {
-            (
-                (self.rate0 * adjustment),
-                (self.rate1 * adjustment),
-                (self.max_rate * adjustment),
-            )
+        if (max_rate * adjustment) > MINIMUM_MAX_RATE {
+            adjustment
         } else {
-            (self.rate0, self.rate1, self.max_rate)
+            I80F48::ONE
+        }
+    }
+
+    /// Multiply the rates of the interest curve points by the adjustment
+    pub fn adjust_interest_curve_rates(&mut self, adjustment: I80F48) {
+        let len = self.interest_curve_len as usize;
+        for point in self.interest_curve[..len].iter_mut() {
+            point.rate *= adjustment;
         }
     }
 
@@ -936,8 +1191,15 @@ mod tests {
                     cumulative_deposit_interest: 0.0,
                     cumulative_borrow_interest: 0.0,
                     previous_index: I80F48::ZERO,
+                    previous_rewards_index: I80F48::ZERO,
+                    unclaimed_rewards: I80F48::ZERO,
+                    liq_window_start_ts: 0,
+                    liq_window_start_liab: I80F48::ZERO,
+                    liq_window_liquidated: I80F48::ZERO,
+                    cumulative_deposits: 0.0,
+                    cumulative_withdraws: 0.0,
                     padding: Default::default(),
-                    reserved: [0; 128],
+                    reserved: [0; 40],
                 };
 
                 account.indexed_position = indexed(I80F48::from_num(start), &bank);
@@ -1072,4 +1334,235 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_interest_curve() {
+        let point = |util: f64, rate: f64| InterestCurvePoint {
+            util: I80F48::from_num(util),
+            rate: I80F48::from_num(rate),
+        };
+        // a stablecoin-like curve: flat until a steep kink close to full utilization
+        let curve = [
+            point(0.5, 0.02),
+            point(0.8, 0.05),
+            point(0.9, 0.1),
+            point(0.95, 0.5),
+            point(1.0, 2.0),
+        ];
+
+        let mut bank = Bank::zeroed();
+        bank.set_interest_curve(&curve).unwrap();
+        assert_eq!(bank.interest_curve().len(), 5);
+
+        // passes through all points
+        assert_eq!(bank.compute_interest_rate(I80F48::ZERO), I80F48::ZERO);
+        for p in curve.iter() {
+            assert!((bank.compute_interest_rate(p.util) - p.rate).abs() < 0.000001);
+        }
+        assert!(
+            (bank.compute_interest_rate(I80F48::from_num(0.25)) - I80F48::from_num(0.01)).abs()
+                < 0.000001
+        );
+
+        // continuous and monotonic
+        let epsilon = I80F48::from_num(0.000001);
+        let mut prev_rate = I80F48::ZERO;
+        for i in 0..=1100 {
+            let util = I80F48::from_num(i) / 1000;
+            let rate = bank.compute_interest_rate(util);
+            assert!(rate >= prev_rate);
+            if i > 0 {
+                // max slope is (2.0 - 0.5) / 0.05 = 30
+                assert!(rate - prev_rate <= I80F48::from_num(0.03) + epsilon);
+            }
+            prev_rate = rate;
+        }
+        for p in curve.iter() {
+            let left = bank.compute_interest_rate(p.util - epsilon);
+            let right = bank.compute_interest_rate(p.util + epsilon);
+            assert!((right - left).abs() < 0.0001);
+        }
+
+        // same results as the two kink curve with the same points
+        bank.util0 = I80F48::from_num(0.5);
+        bank.rate0 = I80F48::from_num(0.02);
+        bank.util1 = I80F48::from_num(0.8);
+        bank.rate1 = I80F48::from_num(0.05);
+        bank.max_rate = I80F48::from_num(1.0);
+        bank.set_interest_curve(&[point(0.5, 0.02), point(0.8, 0.05), point(1.0, 1.0)])
+            .unwrap();
+        let mut legacy_bank = bank;
+        legacy_bank.set_interest_curve(&[]).unwrap();
+        for i in 0..=100 {
+            let util = I80F48::from_num(i) / 100;
+            assert!(
+                (bank.compute_interest_rate(util) - legacy_bank.compute_interest_rate(util)).abs()
+                    < 0.000001
+            );
+        }
+
+        // rate adjustments scale the whole curve
+        bank.adjustment_factor = I80F48::from_num(0.1);
+        bank.avg_utilization = I80F48::ONE;
+        let adjustment = bank.compute_rate_adjustment();
+        assert!((adjustment - I80F48::from_num(1.1)).abs() < 0.000001);
+        bank.adjust_interest_curve_rates(adjustment);
+        assert!((bank.interest_curve()[2].rate - I80F48::from_num(1.1)).abs() < 0.000001);
+
+        // invalid curves
+        let mut bank = Bank::zeroed();
+        assert!(bank
+            .set_interest_curve(&[point(0.5, 0.1), point(0.9, 1.0)])
+            .is_err());
+        assert!(bank
+            .set_interest_curve(&[point(0.5, 0.1), point(0.5, 0.2), point(1.0, 1.0)])
+            .is_err());
+        assert!(bank
+            .set_interest_curve(&[point(0.5, 0.2), point(0.8, 0.1), point(1.0, 1.0)])
+            .is_err());
+        assert!(bank
+            .set_interest_curve(&[point(0.0, 0.0), point(1.0, 1.0)])
+            .is_err());
+        assert!(bank.set_interest_curve(&[point(1.0, -0.1)]).is_err());
+        assert!(bank.set_interest_curve(&[point(0.1, 0.1); 9]).is_err());
+        assert_eq!(bank.interest_curve_len, 0);
+    }
+
+    #[test]
+    fn test_rewards() {
+        let mut bank = Bank::zeroed();
+        bank.deposit_index = I80F48::ONE;
+        bank.borrow_index = I80F48::ONE;
+        bank.net_borrow_limit_window_size_ts = 1;
+        bank.net_borrow_limit_per_window_quote = -1;
+        bank.rewards_mint = Pubkey::new_unique();
+        bank.rewards_per_second = 10;
+        bank.rewards_deposit_share = 0.75;
+        let price = I80F48::ONE;
+
+        let mut depositor = TokenPosition::default();
+        let mut borrower = TokenPosition::default();
+        bank.change_without_fee(&mut depositor, I80F48::from(300), 0, price)
+            .unwrap();
+        bank.change_without_fee(&mut borrower, I80F48::from(-100), 0, price)
+            .unwrap();
+
+        // 100 rewards, 75 to the depositors and 25 to the borrowers
+        let (deposit_rewards_index, borrow_rewards_index) = bank.compute_rewards_index(
+            bank.indexed_deposits,
+            bank.indexed_borrows,
+            I80F48::from(10),
+        );
+        assert_eq!(deposit_rewards_index, I80F48::from_num(0.25));
+        assert_eq!(borrow_rewards_index, I80F48::from_num(0.25));
+        bank.deposit_rewards_index = deposit_rewards_index;
+        bank.borrow_rewards_index = borrow_rewards_index;
+
+        // rewards are credited when the position changes
+        bank.change_without_fee(&mut depositor, I80F48::from(100), 0, price)
+            .unwrap();
+        assert_eq!(depositor.unclaimed_rewards, I80F48::from(75));
+        assert_eq!(depositor.previous_rewards_index, deposit_rewards_index);
+        bank.change_without_fee(&mut borrower, I80F48::from(100), 0, price)
+            .unwrap();
+        assert_eq!(borrower.unclaimed_rewards, I80F48::from(25));
+        assert_eq!(borrower.indexed_position, I80F48::ZERO);
+
+        // closed positions don't earn
+        let (deposit_rewards_index, borrow_rewards_index) = bank.compute_rewards_index(
+            bank.indexed_deposits,
+            bank.indexed_borrows,
+            I80F48::from(10),
+        );
+        assert_eq!(deposit_rewards_index, I80F48::from_num(0.25 + 75.0 / 400.0));
+        // no borrowers: borrow emissions are not distributed
+        assert_eq!(borrow_rewards_index, I80F48::from_num(0.25));
+        bank.deposit_rewards_index = deposit_rewards_index;
+        bank.borrow_rewards_index = borrow_rewards_index;
+
+        bank.change_without_fee(&mut borrower, I80F48::from(10), 0, price)
+            .unwrap();
+        assert_eq!(borrower.unclaimed_rewards, I80F48::from(25));
+        let indexed_position = depositor.indexed_position;
+        bank.update_rewards(&mut depositor, indexed_position);
+        assert_eq!(depositor.unclaimed_rewards, I80F48::from(150));
+
+        // no emissions without a rewards mint
+        bank.rewards_mint = Pubkey::default();
+        assert_eq!(
+            bank.compute_rewards_index(
+                bank.indexed_deposits,
+                bank.indexed_borrows,
+                I80F48::from(10)
+            ),
+            (bank.deposit_rewards_index, bank.borrow_rewards_index)
+        );
+    }
+
+    #[test]
+    fn test_cumulative_interest() {
+        let mut bank = Bank::zeroed();
+        bank.deposit_index = I80F48::ONE;
+        bank.borrow_index = I80F48::ONE;
+        bank.net_borrow_limit_window_size_ts = 1;
+        bank.net_borrow_limit_per_window_quote = -1;
+        let price = I80F48::ONE;
+
+        let mut position = TokenPosition::default();
+        bank.change_without_fee(&mut position, I80F48::from(100), 0, price)
+            .unwrap();
+
+        // interest is credited on the next change
+        bank.deposit_index = I80F48::from_num(1.25);
+        bank.change_without_fee(&mut position, I80F48::from(-50), 0, price)
+            .unwrap();
+        assert!((position.cumulative_deposit_interest - 25.0).abs() < 1e-6);
+
+        // withdrawing into a borrow
+        bank.change_without_fee(&mut position, I80F48::from(-100), 0, price)
+            .unwrap();
+        assert!(position.indexed_position.is_negative());
+
+        bank.borrow_index = I80F48::from_num(1.5);
+        bank.change_without_fee(&mut position, I80F48::from(40), 0, price)
+            .unwrap();
+        assert!((position.cumulative_borrow_interest - 12.5).abs() < 1e-6);
+
+        // bank changes alone are not user deposits or withdraws
+        assert_eq!(position.cumulative_deposits, 0.0);
+        assert_eq!(position.cumulative_withdraws, 0.0);
+    }
+
+    #[test]
+    fn test_deposit_limit() {
+        let mut bank = Bank::zeroed();
+        bank.deposit_index = I80F48::ONE;
+        bank.borrow_index = I80F48::ONE;
+        bank.net_borrow_limit_window_size_ts = 1;
+        bank.net_borrow_limit_per_window_quote = -1;
+        bank.deposit_limit = 1000;
+        let price = I80F48::ONE;
+
+        let mut account = TokenPosition::default();
+        let mut change = |bank: &mut Bank, amount: i32| {
+            let before = bank.indexed_deposits;
+            bank.change_without_fee(&mut account, I80F48::from(amount), 0, price)
+                .unwrap();
+            bank.check_deposit_limit(before)
+        };
+
+        assert!(change(&mut bank, 1000).is_ok());
+        assert!(change(&mut bank, 1).is_err());
+
+        // withdraws and repays are always fine, even above the limit
+        bank.deposit_limit = 500;
+        assert!(change(&mut bank, -100).is_ok());
+        assert!(change(&mut bank, 1).is_err());
+        assert!(change(&mut bank, -1000).is_ok());
+        assert!(change(&mut bank, 100).is_ok());
+
+        // disabled
+        bank.deposit_limit = 0;
+        assert!(change(&mut bank, 10000).is_ok());
+    }
 }
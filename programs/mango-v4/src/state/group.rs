@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use fixed::types::I80F48;
 use static_assertions::const_assert_eq;
 use std::mem::size_of;
 
@@ -88,11 +89,65 @@ pub struct Group {
     /// When set to 0, there's no expiry of buyback fees.
     pub buyback_fees_expiry_interval: u64,
 
-    pub reserved: [u8; 1824],
+    /// Perp fee tiers by the account's perp volume over the last 30 days.
+    ///
+    /// Only the first perp_fee_tiers_count entries are in use, ordered by min_volume.
+    pub perp_fee_tiers: [PerpFeeTier; MAX_PERP_FEE_TIERS],
+    pub perp_fee_tiers_count: u8,
+    pub padding2: [u8; 7],
+
+    /// Liquidation fee auction duration in seconds, 0 disables the auction.
+    ///
+    /// When enabled, liquidators of token and perp base positions only get a fraction
+    /// of the configured liquidation fees. See liquidation_fee_fraction().
+    pub liquidation_auction_duration: u64,
+    /// Fraction of the liquidation fees that applies when an account starts being liquidated
+    pub liquidation_auction_start_fraction: f32,
+    /// Maint health deficit, as a fraction of maint liabilities, at which the full
+    /// liquidation fees apply. 0 disables the depth component.
+    pub liquidation_auction_full_fee_depth: f32,
+
+    /// Maximum fraction of a liab token or perp base position that liquidators may
+    /// transfer within one liquidation_close_factor_window, 0 disables the limit.
+    ///
+    /// The fraction is relative to the position size at the start of the window.
+    pub liquidation_close_factor: f32,
+    pub padding3: [u8; 4],
+    /// Length of the close factor window in seconds
+    pub liquidation_close_factor_window: u64,
+
+    /// Fraction of the liab token's liquidation fee that accounts pay when they
+    /// liquidate themselves with a FlashLoanType::SelfLiquidation flash loan
+    pub self_liquidation_fee_fraction: f32,
+    pub padding4: [u8; 4],
+
+    pub reserved: [u8; 1648],
 }
 const_assert_eq!(
     size_of::<Group>(),
-    32 + 4 + 32 * 2 + 4 + 32 * 2 + 4 + 4 + 20 * 32 + 32 + 8 + 16 + 32 + 8 + 1824
+    32 + 4
+        + 32 * 2
+        + 4
+        + 32 * 2
+        + 4
+        + 4
+        + 20 * 32
+        + 32
+        + 8
+        + 16
+        + 32
+        + 8
+        + 16 * MAX_PERP_FEE_TIERS
+        + 8
+        + 8
+        + 4
+        + 4
+        + 4
+        + 4
+        + 8
+        + 4
+        + 4
+        + 1648
 );
 const_assert_eq!(size_of::<Group>(), 2736);
 const_assert_eq!(size_of::<Group>() % 8, 0);
@@ -121,12 +176,116 @@ impl Group {
     pub fn is_ix_enabled(&self, ix: IxGate) -> bool {
         self.ix_gate & (1 << ix as u128) == 0
     }
+
+    pub fn perp_fee_tiers(&self) -> &[PerpFeeTier] {
+        &self.perp_fee_tiers[..self.perp_fee_tiers_count as usize]
+    }
+
+    /// Fraction of the configured liquidation fees that liquidators receive
+    ///
+    /// Without a liquidation auction that's always one. Otherwise it starts at
+    /// liquidation_auction_start_fraction when the liqee starts being liquidated and
+    /// rises linearly with the time since then and with the depth of the liqee's maint
+    /// health deficit, until it reaches one.
+    pub fn liquidation_fee_fraction(
+        &self,
+        being_liquidated_duration: u64,
+        maint_deficit_depth: I80F48,
+    ) -> I80F48 {
+        if self.liquidation_auction_duration == 0 {
+            return I80F48::ONE;
+        }
+
+        let time_progress = I80F48::from(being_liquidated_duration)
+            / I80F48::from(self.liquidation_auction_duration);
+        let depth_progress = if self.liquidation_auction_full_fee_depth > 0.0 {
+            maint_deficit_depth.max(I80F48::ZERO)
+                / I80F48::from_num(self.liquidation_auction_full_fee_depth)
+        } else {
+            I80F48::ZERO
+        };
+        let progress = (time_progress + depth_progress).min(I80F48::ONE);
+
+        let start = I80F48::from_num(self.liquidation_auction_start_fraction);
+        start + (I80F48::ONE - start) * progress
+    }
+
+    /// The liquidation close factor limit, if enabled
+    pub fn liquidation_close_factor(&self) -> Option<LiquidationCloseFactor> {
+        if self.liquidation_close_factor <= 0.0 || self.liquidation_close_factor >= 1.0 {
+            return None;
+        }
+        Some(LiquidationCloseFactor {
+            fraction: I80F48::from_num(self.liquidation_close_factor),
+            window: self.liquidation_close_factor_window,
+        })
+    }
+}
+
+/// Limits the fraction of a position that can be liquidated per time window
+///
+/// See Group::liquidation_close_factor.
+#[derive(Clone, Copy, Debug)]
+pub struct LiquidationCloseFactor {
+    pub fraction: I80F48,
+    pub window: u64,
+}
+
+impl LiquidationCloseFactor {
+    /// Maximum amount that may be liquidated in a window, given the position size at its start
+    pub fn allowed(&self, window_start_size: I80F48) -> I80F48 {
+        (self.fraction * window_start_size.abs()).ceil()
+    }
+}
+
+pub const MAX_PERP_FEE_TIERS: usize = 8;
+
+/// Perp fees for accounts that traded at least min_volume in the last 30 days
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Debug, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PerpFeeTier {
+    /// Minimum perp volume of the account, in quote native (like USDC native)
+    pub min_volume: u64,
+
+    /// Fees for accounts in this tier. They only apply on markets where they
+    /// are lower than the market's own maker_fee and taker_fee.
+    pub maker_fee: f32,
+    pub taker_fee: f32,
+}
+const_assert_eq!(size_of::<PerpFeeTier>(), 16);
+const_assert_eq!(size_of::<PerpFeeTier>() % 8, 0);
+
+impl PerpFeeTier {
+    /// The highest tier an account with the given perp volume qualifies for
+    pub fn for_volume(fee_tiers: &[PerpFeeTier], volume: u64) -> Option<&PerpFeeTier> {
+        fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| volume >= tier.min_volume)
+    }
+
+    /// Maker and taker fees for an account with the given perp volume on a market
+    /// that charges maker_fee and taker_fee
+    pub fn fees_for_volume(
+        fee_tiers: &[PerpFeeTier],
+        volume: u64,
+        maker_fee: I80F48,
+        taker_fee: I80F48,
+    ) -> (I80F48, I80F48) {
+        match Self::for_volume(fee_tiers, volume) {
+            Some(tier) => (
+                maker_fee.min(I80F48::from_num(tier.maker_fee)),
+                taker_fee.min(I80F48::from_num(tier.taker_fee)),
+            ),
+            None => (maker_fee, taker_fee),
+        }
+    }
 }
 
 /// Enum for lookup into ix gate
 /// note:
-/// total ix files 56,
-/// ix files included 48,
+/// total ix files 61,
+/// ix files included 53,
 /// ix files not included 8,
 /// - Benchmark,
 /// - ComputeAccountData,
@@ -187,6 +346,15 @@ pub enum IxGate {
     TokenUpdateIndexAndRate = 46,
     TokenWithdraw = 47,
     AccountBuybackFeesWithMngo = 48,
+    TokenClaimRewards = 49,
+    /// FlashLoanEnd with FlashLoanType::SelfLiquidation
+    FlashLoanSelfLiquidation = 50,
+    PerpAdl = 51,
+    /// PerpIsolatedDeposit and PerpIsolatedWithdraw
+    PerpIsolatedCollateral = 52,
+    PerpLiqIsolated = 53,
+    AccountHealthSnapshot = 54,
+    Serum3SettleFundsPermissionless = 55,
     // NOTE: Adding new variants requires matching changes in ts and the ix_gate_set instruction.
 }
 
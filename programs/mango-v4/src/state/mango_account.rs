@@ -17,7 +17,10 @@ use crate::logs::{DeactivatePerpPositionLog, DeactivateTokenPositionLog};
 use super::dynamic_account::*;
 use super::BookSideOrderTree;
 use super::FillEvent;
+use super::IsolatedPerpPosition;
 use super::LeafNode;
+use super::LiquidationCloseFactor;
+use super::PerpFeeTier;
 use super::PerpMarket;
 use super::PerpMarketIndex;
 use super::PerpOpenOrder;
@@ -32,6 +35,9 @@ const BORSH_VEC_PADDING_BYTES: usize = 4;
 const BORSH_VEC_SIZE_BYTES: usize = 4;
 const DEFAULT_MANGO_ACCOUNT_VERSION: u8 = 1;
 
+/// Length of the window in seconds over which perp volume counts for perp fee tiers
+pub const PERP_VOLUME_WINDOW: u64 = 30 * 24 * 60 * 60;
+
 // Mango Account
 // This struct definition is only for clients e.g. typescript, so that they can easily use out of the box
 // deserialization and not have to do custom deserialization
@@ -95,7 +101,23 @@ pub struct MangoAccount {
     /// End timestamp of the current expiry interval of the buyback fees amount.
     pub buyback_fees_expiry_timestamp: u64,
 
-    pub reserved: [u8; 208],
+    /// Perp volume in quote native of the current and the previous window of
+    /// PERP_VOLUME_WINDOW seconds, for perp fee tiers.
+    pub perp_volume_current: u64,
+    pub perp_volume_previous: u64,
+    /// End timestamp of the current perp volume window.
+    pub perp_volume_window_end: u64,
+
+    /// Timestamp at which being_liquidated was last set, for liquidation fee auctions.
+    pub being_liquidated_since: u64,
+
+    /// Liquidation close factor window for cancelling serum3 orders: start of the window,
+    /// the number of open serum3 orders at that time and the number cancelled since.
+    pub serum3_liq_window_start_ts: u64,
+    pub serum3_liq_window_start_orders: u32,
+    pub serum3_liq_window_cancelled_orders: u32,
+
+    pub reserved: [u8; 160],
 
     // dynamic
     pub header_version: u8,
@@ -113,6 +135,10 @@ pub struct MangoAccount {
     pub perps: Vec<PerpPosition>,
     pub padding7: u32,
     pub perp_open_orders: Vec<PerpOpenOrder>,
+    pub padding8: u32,
+    // Dedicated collateral for perp positions that are margined in isolation.
+    // Accounts created before this section existed don't have it, see MangoAccountDynamicHeader.
+    pub isolated_perps: Vec<IsolatedPerpPosition>,
 }
 
 impl MangoAccount {
@@ -133,7 +159,14 @@ impl MangoAccount {
             buyback_fees_accrued_current: 0,
             buyback_fees_accrued_previous: 0,
             buyback_fees_expiry_timestamp: 0,
-            reserved: [0; 208],
+            perp_volume_current: 0,
+            perp_volume_previous: 0,
+            perp_volume_window_end: 0,
+            being_liquidated_since: 0,
+            serum3_liq_window_start_ts: 0,
+            serum3_liq_window_start_orders: 0,
+            serum3_liq_window_cancelled_orders: 0,
+            reserved: [0; 160],
             header_version: DEFAULT_MANGO_ACCOUNT_VERSION,
             padding3: Default::default(),
             padding4: Default::default(),
@@ -144,6 +177,8 @@ impl MangoAccount {
             perps: vec![PerpPosition::default(); 4],
             padding7: Default::default(),
             perp_open_orders: vec![PerpOpenOrder::default(); 6],
+            padding8: Default::default(),
+            isolated_perps: vec![IsolatedPerpPosition::default(); 2],
             perp_spot_transfers: 0,
         }
     }
@@ -154,14 +189,22 @@ impl MangoAccount {
         serum3_count: u8,
         perp_count: u8,
         perp_oo_count: u8,
+        isolated_perp_count: u8,
     ) -> Result<usize> {
         require_gte!(16, token_count);
         require_gte!(8, serum3_count);
         require_gte!(8, perp_count);
         require_gte!(64, perp_oo_count);
+        require_gte!(perp_count, isolated_perp_count);
 
         Ok(8 + size_of::<MangoAccountFixed>()
-            + Self::dynamic_size(token_count, serum3_count, perp_count, perp_oo_count))
+            + Self::dynamic_size(
+                token_count,
+                serum3_count,
+                perp_count,
+                perp_oo_count,
+                isolated_perp_count,
+            ))
     }
 
     pub fn dynamic_token_vec_offset() -> usize {
@@ -187,7 +230,7 @@ impl MangoAccount {
             + BORSH_VEC_PADDING_BYTES
     }
 
-    pub fn dynamic_size(
+    pub fn dynamic_isolated_perp_vec_offset(
         token_count: u8,
         serum3_count: u8,
         perp_count: u8,
@@ -195,6 +238,19 @@ impl MangoAccount {
     ) -> usize {
         Self::dynamic_perp_oo_vec_offset(token_count, serum3_count, perp_count)
             + (BORSH_VEC_SIZE_BYTES + size_of::<PerpOpenOrder>() * usize::from(perp_oo_count))
+            + BORSH_VEC_PADDING_BYTES
+    }
+
+    pub fn dynamic_size(
+        token_count: u8,
+        serum3_count: u8,
+        perp_count: u8,
+        perp_oo_count: u8,
+        isolated_perp_count: u8,
+    ) -> usize {
+        Self::dynamic_isolated_perp_vec_offset(token_count, serum3_count, perp_count, perp_oo_count)
+            + (BORSH_VEC_SIZE_BYTES
+                + size_of::<IsolatedPerpPosition>() * usize::from(isolated_perp_count))
     }
 }
 
@@ -218,9 +274,19 @@ pub struct MangoAccountFixed {
     pub buyback_fees_accrued_current: u64,
     pub buyback_fees_accrued_previous: u64,
     pub buyback_fees_expiry_timestamp: u64,
-    pub reserved: [u8; 208],
+    pub perp_volume_current: u64,
+    pub perp_volume_previous: u64,
+    pub perp_volume_window_end: u64,
+    pub being_liquidated_since: u64,
+    pub serum3_liq_window_start_ts: u64,
+    pub serum3_liq_window_start_orders: u32,
+    pub serum3_liq_window_cancelled_orders: u32,
+    pub reserved: [u8; 160],
 }
-const_assert_eq!(size_of::<MangoAccountFixed>(), 32 * 4 + 8 + 7 * 8 + 208);
+const_assert_eq!(
+    size_of::<MangoAccountFixed>(),
+    32 * 4 + 8 + 7 * 8 + 3 * 8 + 8 + 8 + 4 * 2 + 160
+);
 const_assert_eq!(size_of::<MangoAccountFixed>(), 400);
 const_assert_eq!(size_of::<MangoAccountFixed>() % 8, 0);
 
@@ -252,6 +318,47 @@ impl MangoAccountFixed {
         self.being_liquidated = u8::from(b);
     }
 
+    /// Sets being_liquidated, remembering the time if it wasn't set before
+    pub fn start_being_liquidated(&mut self, now_ts: u64) {
+        if !self.being_liquidated() {
+            self.being_liquidated_since = now_ts;
+        }
+        self.set_being_liquidated(true);
+    }
+
+    /// Seconds since the account started being liquidated
+    pub fn being_liquidated_duration(&self, now_ts: u64) -> u64 {
+        now_ts.saturating_sub(self.being_liquidated_since)
+    }
+
+    /// Serum3 orders that may still be cancelled in the current close factor window.
+    /// Starts a new window with `open_orders` orders if the previous one has ended.
+    pub fn serum3_liquidation_close_factor_remaining(
+        &mut self,
+        close_factor: &LiquidationCloseFactor,
+        open_orders: u32,
+        now_ts: u64,
+    ) -> u32 {
+        if now_ts
+            >= self
+                .serum3_liq_window_start_ts
+                .saturating_add(close_factor.window)
+        {
+            self.serum3_liq_window_start_ts = now_ts;
+            self.serum3_liq_window_start_orders = open_orders;
+            self.serum3_liq_window_cancelled_orders = 0;
+        }
+        let allowed = close_factor
+            .allowed(I80F48::from(self.serum3_liq_window_start_orders))
+            .to_num::<u32>();
+        allowed.saturating_sub(self.serum3_liq_window_cancelled_orders)
+    }
+
+    /// Record serum3 orders cancelled in the current close factor window
+    pub fn record_serum3_liquidation(&mut self, cancelled_orders: u32) {
+        self.serum3_liq_window_cancelled_orders += cancelled_orders;
+    }
+
     pub fn is_in_health_region(&self) -> bool {
         self.in_health_region == 1
     }
@@ -296,6 +403,51 @@ impl MangoAccountFixed {
             self.buyback_fees_accrued_current.saturating_add(amount);
     }
 
+    /// Moves the perp volume windows forward such that the current one contains now_ts.
+    pub fn expire_perp_volume(&mut self, now_ts: u64) {
+        if now_ts < self.perp_volume_window_end {
+            return;
+        } else if now_ts < self.perp_volume_window_end + PERP_VOLUME_WINDOW {
+            self.perp_volume_previous = self.perp_volume_current;
+        } else {
+            self.perp_volume_previous = 0;
+        }
+        self.perp_volume_current = 0;
+        self.perp_volume_window_end = (now_ts / PERP_VOLUME_WINDOW + 1) * PERP_VOLUME_WINDOW;
+    }
+
+    /// Add perp trading volume, in quote native.
+    pub fn accrue_perp_volume(&mut self, now_ts: u64, volume: u64) {
+        self.expire_perp_volume(now_ts);
+        self.perp_volume_current = self.perp_volume_current.saturating_add(volume);
+    }
+
+    /// Estimate of the perp volume over the last PERP_VOLUME_WINDOW seconds.
+    ///
+    /// The volume of the previous window is assumed to be spread evenly over it.
+    pub fn perp_volume(&self, now_ts: u64) -> u64 {
+        let (current, previous, window_end) = if now_ts < self.perp_volume_window_end {
+            (
+                self.perp_volume_current,
+                self.perp_volume_previous,
+                self.perp_volume_window_end,
+            )
+        } else if now_ts < self.perp_volume_window_end + PERP_VOLUME_WINDOW {
+            (
+                0,
+                self.perp_volume_current,
+                self.perp_volume_window_end + PERP_VOLUME_WINDOW,
+            )
+        } else {
+            return 0;
+        };
+        // the part of the previous window that is still within the last PERP_VOLUME_WINDOW
+        let previous_remaining = window_end - now_ts;
+        let previous_part =
+            (previous as u128 * previous_remaining as u128 / PERP_VOLUME_WINDOW as u128) as u64;
+        current.saturating_add(previous_part)
+    }
+
     /// Reduce the available buyback fees amount because it was used up.
     pub fn reduce_buyback_fees_accrued(&mut self, amount: u64) {
         if amount > self.buyback_fees_accrued_previous {
@@ -329,6 +481,7 @@ pub struct MangoAccountDynamicHeader {
     pub serum3_count: u8,
     pub perp_count: u8,
     pub perp_oo_count: u8,
+    pub isolated_perp_count: u8,
 }
 
 impl DynamicHeader for MangoAccountDynamicHeader {
@@ -365,11 +518,32 @@ impl DynamicHeader for MangoAccountDynamicHeader {
                 ]))
                 .unwrap();
 
+                // Accounts created before isolated perp positions existed end after the
+                // perp open orders. They behave as if they had zero isolated perp positions.
+                let isolated_perp_vec_offset = MangoAccount::dynamic_isolated_perp_vec_offset(
+                    token_count,
+                    serum3_count,
+                    perp_count,
+                    perp_oo_count,
+                );
+                let isolated_perp_count =
+                    if dynamic_data.len() >= isolated_perp_vec_offset + BORSH_VEC_SIZE_BYTES {
+                        u8::try_from(BorshVecLength::from_le_bytes(*array_ref![
+                            dynamic_data,
+                            isolated_perp_vec_offset,
+                            BORSH_VEC_SIZE_BYTES
+                        ]))
+                        .unwrap()
+                    } else {
+                        0
+                    };
+
                 Ok(Self {
                     token_count,
                     serum3_count,
                     perp_count,
                     perp_oo_count,
+                    isolated_perp_count,
                 })
             }
             _ => err!(MangoError::NotImplementedError).context("unexpected header version number"),
@@ -424,6 +598,16 @@ impl MangoAccountDynamicHeader {
             + raw_index * size_of::<PerpOpenOrder>()
     }
 
+    fn isolated_perp_offset(&self, raw_index: usize) -> usize {
+        MangoAccount::dynamic_isolated_perp_vec_offset(
+            self.token_count,
+            self.serum3_count,
+            self.perp_count,
+            self.perp_oo_count,
+        ) + BORSH_VEC_SIZE_BYTES
+            + raw_index * size_of::<IsolatedPerpPosition>()
+    }
+
     pub fn token_count(&self) -> usize {
         self.token_count.into()
     }
@@ -436,6 +620,9 @@ impl MangoAccountDynamicHeader {
     pub fn perp_oo_count(&self) -> usize {
         self.perp_oo_count.into()
     }
+    pub fn isolated_perp_count(&self) -> usize {
+        self.isolated_perp_count.into()
+    }
 }
 
 /// Fully owned MangoAccount, useful for tests
@@ -613,6 +800,37 @@ impl<
             .find(|&oo| oo.is_active_for_market(market_index) && oo.id == order_id)
     }
 
+    pub fn isolated_perp_position(
+        &self,
+        market_index: PerpMarketIndex,
+    ) -> Result<&IsolatedPerpPosition> {
+        self.all_isolated_perp_positions()
+            .find(|p| p.is_active_for_market(market_index))
+            .ok_or_else(|| error!(MangoError::IsolatedPerpPositionDoesNotExist))
+    }
+
+    pub fn isolated_perp_position_by_raw_index(&self, raw_index: usize) -> &IsolatedPerpPosition {
+        get_helper(
+            self.dynamic(),
+            self.header().isolated_perp_offset(raw_index),
+        )
+    }
+
+    pub fn all_isolated_perp_positions(&self) -> impl Iterator<Item = &IsolatedPerpPosition> {
+        (0..self.header().isolated_perp_count())
+            .map(|i| self.isolated_perp_position_by_raw_index(i))
+    }
+
+    pub fn active_isolated_perp_positions(&self) -> impl Iterator<Item = &IsolatedPerpPosition> {
+        self.all_isolated_perp_positions().filter(|p| p.is_active())
+    }
+
+    /// Is the perp position for the market margined in isolation?
+    pub fn is_isolated_perp(&self, market_index: PerpMarketIndex) -> bool {
+        self.all_isolated_perp_positions()
+            .any(|p| p.is_active_for_market(market_index))
+    }
+
     pub fn being_liquidated(&self) -> bool {
         self.fixed().being_liquidated()
     }
@@ -710,8 +928,15 @@ impl<
                     cumulative_deposit_interest: 0.0,
                     cumulative_borrow_interest: 0.0,
                     previous_index: I80F48::ZERO,
+                    previous_rewards_index: I80F48::ZERO,
+                    unclaimed_rewards: I80F48::ZERO,
+                    liq_window_start_ts: 0,
+                    liq_window_start_liab: I80F48::ZERO,
+                    liq_window_liquidated: I80F48::ZERO,
+                    cumulative_deposits: 0.0,
+                    cumulative_withdraws: 0.0,
                     padding: Default::default(),
-                    reserved: [0; 128],
+                    reserved: [0; 40],
                 };
             }
             Ok((v, raw_index, bank_index))
@@ -762,6 +987,8 @@ impl<
         if let Some(raw_index) = raw_index_opt {
             *(self.serum3_orders_mut_by_raw_index(raw_index)) = Serum3Orders {
                 market_index: market_index as Serum3MarketIndex,
+                // new open orders accounts start out empty
+                fills_tracked: 1,
                 ..Serum3Orders::default()
             };
             Ok(self.serum3_orders_mut_by_raw_index(raw_index))
@@ -881,6 +1108,70 @@ impl<
         Ok(())
     }
 
+    pub fn isolated_perp_position_mut_by_raw_index(
+        &mut self,
+        raw_index: usize,
+    ) -> &mut IsolatedPerpPosition {
+        let offset = self.header().isolated_perp_offset(raw_index);
+        get_helper_mut(self.dynamic_mut(), offset)
+    }
+
+    pub fn isolated_perp_position_mut(
+        &mut self,
+        market_index: PerpMarketIndex,
+    ) -> Result<&mut IsolatedPerpPosition> {
+        let raw_index_opt = self
+            .all_isolated_perp_positions()
+            .position(|p| p.is_active_for_market(market_index));
+        raw_index_opt
+            .map(|raw_index| self.isolated_perp_position_mut_by_raw_index(raw_index))
+            .ok_or_else(|| error!(MangoError::IsolatedPerpPositionDoesNotExist))
+    }
+
+    /// Creates the isolated collateral for a perp market.
+    ///
+    /// The perp position itself must be created separately, with ensure_perp_position().
+    pub fn create_isolated_perp_position(
+        &mut self,
+        market_index: PerpMarketIndex,
+        settle_token_index: TokenIndex,
+    ) -> Result<&mut IsolatedPerpPosition> {
+        require_msg!(
+            !self.is_isolated_perp(market_index),
+            "isolated perp position for market {} exists already",
+            market_index
+        );
+
+        let raw_index_opt = self
+            .all_isolated_perp_positions()
+            .position(|p| !p.is_active());
+        if let Some(raw_index) = raw_index_opt {
+            *(self.isolated_perp_position_mut_by_raw_index(raw_index)) = IsolatedPerpPosition {
+                market_index,
+                token_index: settle_token_index,
+                ..IsolatedPerpPosition::default()
+            };
+            Ok(self.isolated_perp_position_mut_by_raw_index(raw_index))
+        } else {
+            err!(MangoError::NoFreeIsolatedPerpPositionIndex)
+        }
+    }
+
+    /// Frees the isolated collateral slot, the collateral must have been withdrawn.
+    pub fn deactivate_isolated_perp_position(
+        &mut self,
+        market_index: PerpMarketIndex,
+    ) -> Result<()> {
+        let isolated = self.isolated_perp_position_mut(market_index)?;
+        require_msg!(
+            isolated.indexed_collateral.is_zero(),
+            "isolated perp position for market {} still has collateral",
+            market_index
+        );
+        isolated.market_index = PerpMarketIndex::MAX;
+        Ok(())
+    }
+
     pub fn add_perp_order(
         &mut self,
         perp_market_index: PerpMarketIndex,
@@ -936,16 +1227,36 @@ impl<
         Ok(())
     }
 
+    /// Apply a fill to the maker's perp position
+    ///
+    /// Returns the maker fee rate and the trade pnl the fill realized.
     pub fn execute_perp_maker(
         &mut self,
         perp_market_index: PerpMarketIndex,
         perp_market: &mut PerpMarket,
         fill: &FillEvent,
-    ) -> Result<()> {
+        fee_tiers: &[PerpFeeTier],
+    ) -> Result<(I80F48, I80F48)> {
         let side = fill.taker_side().invert_side();
         let (base_change, quote_change) = fill.base_quote_change(side);
         let quote = I80F48::from(perp_market.quote_lot_size) * I80F48::from(quote_change);
-        let fees = quote.abs() * I80F48::from_num(fill.maker_fee);
+
+        // The market's fees_accrued already includes the maker fee from the fill event,
+        // adjust it for the maker's fee tier. Rebates never exceed the taker fee.
+        let fill_maker_fee = I80F48::from_num(fill.maker_fee);
+        let (maker_fee, _) = PerpFeeTier::fees_for_volume(
+            fee_tiers,
+            self.fixed().perp_volume(fill.timestamp),
+            fill_maker_fee,
+            I80F48::ZERO,
+        );
+        let max_rebate = I80F48::from_num(fill.taker_fee).max(-fill_maker_fee);
+        let maker_fee = maker_fee.max(-max_rebate);
+        perp_market.fees_accrued += quote.abs() * (maker_fee - fill_maker_fee);
+        self.fixed_mut()
+            .accrue_perp_volume(fill.timestamp, quote.abs().to_num::<u64>());
+
+        let fees = quote.abs() * maker_fee;
         if fees.is_positive() {
             self.fixed_mut()
                 .accrue_buyback_fees(fees.floor().to_num::<u64>());
@@ -953,12 +1264,12 @@ impl<
         let pa = self.perp_position_mut(perp_market_index)?;
         pa.settle_funding(perp_market);
         pa.record_trading_fee(fees);
-        pa.record_trade(perp_market, base_change, quote);
+        let realized_pnl = pa.record_trade(perp_market, base_change, quote);
 
         pa.maker_volume += quote.abs().to_num::<u64>();
 
         if fill.maker_out() {
-            self.remove_perp_order(fill.maker_slot as usize, base_change.abs())
+            self.remove_perp_order(fill.maker_slot as usize, base_change.abs())?;
         } else {
             match side {
                 Side::Bid => {
@@ -968,16 +1279,19 @@ impl<
                     pa.asks_base_lots -= base_change.abs();
                 }
             }
-            Ok(())
         }
+        Ok((maker_fee, realized_pnl))
     }
 
+    /// Apply a fill to the taker's perp position
+    ///
+    /// Returns the trade pnl the fill realized.
     pub fn execute_perp_taker(
         &mut self,
         perp_market_index: PerpMarketIndex,
         perp_market: &mut PerpMarket,
         fill: &FillEvent,
-    ) -> Result<()> {
+    ) -> Result<I80F48> {
         let pa = self.perp_position_mut(perp_market_index)?;
         pa.settle_funding(perp_market);
 
@@ -986,11 +1300,13 @@ impl<
         // fees are assessed at time of trade; no need to assess fees here
         let quote_change_native =
             I80F48::from(perp_market.quote_lot_size) * I80F48::from(quote_change);
-        pa.record_trade(perp_market, base_change, quote_change_native);
+        let realized_pnl = pa.record_trade(perp_market, base_change, quote_change_native);
 
         pa.taker_volume += quote_change_native.abs().to_num::<u64>();
+        self.fixed_mut()
+            .accrue_perp_volume(fill.timestamp, quote_change_native.abs().to_num::<u64>());
 
-        Ok(())
+        Ok(realized_pnl)
     }
 
     pub fn check_health_pre(&mut self, health_cache: &HealthCache) -> Result<I80F48> {
@@ -1028,7 +1344,24 @@ impl<
         Ok(())
     }
 
-    pub fn check_liquidatable(&mut self, health_cache: &HealthCache) -> Result<bool> {
+    /// Like check_health_post(), but for the health of an isolated perp position
+    pub fn check_isolated_perp_health_post(
+        &self,
+        health_cache: &HealthCache,
+        perp_market_index: PerpMarketIndex,
+        pre_init_health: I80F48,
+    ) -> Result<()> {
+        let post_init_health =
+            health_cache.isolated_perp_health(perp_market_index, HealthType::Init)?;
+        msg!("post_isolated_init_health: {}", post_init_health);
+        require!(
+            post_init_health >= 0 || post_init_health > pre_init_health,
+            MangoError::HealthMustBePositiveOrIncrease
+        );
+        Ok(())
+    }
+
+    pub fn check_liquidatable(&mut self, health_cache: &HealthCache, now_ts: u64) -> Result<bool> {
         // Once maint_health falls below 0, we want to start liquidating,
         // we want to allow liquidation to continue until init_health is positive,
         // to prevent constant oscillation between the two states
@@ -1047,7 +1380,7 @@ impl<
                 maint_health < I80F48::ZERO,
                 MangoError::HealthMustBeNegative
             );
-            self.fixed_mut().set_being_liquidated(true);
+            self.fixed_mut().start_being_liquidated(now_ts);
         }
         Ok(true)
     }
@@ -1102,17 +1435,27 @@ impl<
         dst.copy_from_slice(&BorshVecLength::from(count).to_le_bytes());
     }
 
+    fn write_isolated_perp_length(&mut self) {
+        let isolated_perp_offset = self.header().isolated_perp_offset(0);
+        let count = self.header().isolated_perp_count;
+        let dst: &mut [u8] = &mut self.dynamic_mut()
+            [isolated_perp_offset - BORSH_VEC_SIZE_BYTES..isolated_perp_offset];
+        dst.copy_from_slice(&BorshVecLength::from(count).to_le_bytes());
+    }
+
     pub fn expand_dynamic_content(
         &mut self,
         new_token_count: u8,
         new_serum3_count: u8,
         new_perp_count: u8,
         new_perp_oo_count: u8,
+        new_isolated_perp_count: u8,
     ) -> Result<()> {
         require_gte!(new_token_count, self.header().token_count);
         require_gte!(new_serum3_count, self.header().serum3_count);
         require_gte!(new_perp_count, self.header().perp_count);
         require_gte!(new_perp_oo_count, self.header().perp_oo_count);
+        require_gte!(new_isolated_perp_count, self.header().isolated_perp_count);
 
         // create a temp copy to compute new starting offsets
         let new_header = MangoAccountDynamicHeader {
@@ -1120,12 +1463,28 @@ impl<
             serum3_count: new_serum3_count,
             perp_count: new_perp_count,
             perp_oo_count: new_perp_oo_count,
+            isolated_perp_count: new_isolated_perp_count,
         };
         let old_header = self.header().clone();
         let dynamic = self.dynamic_mut();
 
         // expand dynamic components by first moving existing positions, and then setting new ones to defaults
 
+        // isolated perp positions
+        if old_header.isolated_perp_count() > 0 {
+            unsafe {
+                sol_memmove(
+                    &mut dynamic[new_header.isolated_perp_offset(0)],
+                    &mut dynamic[old_header.isolated_perp_offset(0)],
+                    size_of::<IsolatedPerpPosition>() * old_header.isolated_perp_count(),
+                );
+            }
+        }
+        for i in old_header.isolated_perp_count..new_isolated_perp_count {
+            *get_helper_mut(dynamic, new_header.isolated_perp_offset(i.into())) =
+                IsolatedPerpPosition::default();
+        }
+
         // perp oo
         if old_header.perp_oo_count() > 0 {
             unsafe {
@@ -1191,6 +1550,7 @@ impl<
         self.write_serum3_length();
         self.write_perp_length();
         self.write_perp_oo_length();
+        self.write_isolated_perp_length();
 
         Ok(())
     }
@@ -1286,11 +1646,15 @@ mod tests {
         account.perps.resize(8, PerpPosition::default());
         account.perps[0].market_index = 9;
         account.perp_open_orders.resize(8, PerpOpenOrder::default());
+        account
+            .isolated_perps
+            .resize(8, IsolatedPerpPosition::default());
+        account.isolated_perps[0].market_index = 10;
 
         let account_bytes = AnchorSerialize::try_to_vec(&account).unwrap();
         assert_eq!(
             8 + account_bytes.len(),
-            MangoAccount::space(8, 8, 8, 8).unwrap()
+            MangoAccount::space(8, 8, 8, 8, 8).unwrap()
         );
 
         let account2 = MangoAccountValue::from_bytes(&account_bytes).unwrap();
@@ -1335,6 +1699,10 @@ mod tests {
             account.perps[0].market_index,
             account2.perp_position_by_raw_index(0).market_index
         );
+        assert_eq!(
+            account.isolated_perps[0].market_index,
+            account2.isolated_perp_position_by_raw_index(0).market_index
+        );
     }
 
     #[test]
@@ -1406,6 +1774,45 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_serum3_liquidation_close_factor() {
+        let mut account = make_test_account();
+        let close_factor = LiquidationCloseFactor {
+            fraction: I80F48::from_num(0.25),
+            window: 100,
+        };
+
+        // a quarter of the 10 orders at the start of the window, rounded up
+        let remaining =
+            account
+                .fixed
+                .serum3_liquidation_close_factor_remaining(&close_factor, 10, 1000);
+        assert_eq!(remaining, 3);
+        account.fixed.record_serum3_liquidation(2);
+
+        // the window doesn't restart with the new order count
+        let remaining =
+            account
+                .fixed
+                .serum3_liquidation_close_factor_remaining(&close_factor, 8, 1099);
+        assert_eq!(remaining, 1);
+        account.fixed.record_serum3_liquidation(1);
+        let remaining =
+            account
+                .fixed
+                .serum3_liquidation_close_factor_remaining(&close_factor, 7, 1099);
+        assert_eq!(remaining, 0);
+
+        // a new window
+        let remaining =
+            account
+                .fixed
+                .serum3_liquidation_close_factor_remaining(&close_factor, 7, 1100);
+        assert_eq!(remaining, 2);
+        assert_eq!(account.fixed.serum3_liq_window_start_orders, 7);
+        assert_eq!(account.fixed.serum3_liq_window_cancelled_orders, 0);
+    }
+
     #[test]
     fn test_serum3_orders() {
         let mut account = make_test_account();
@@ -1564,4 +1971,101 @@ mod tests {
         fixed.reduce_buyback_fees_accrued(100);
         assert_eq!(fixed.buyback_fees_accrued(), 0);
     }
+
+    #[test]
+    fn test_perp_volume() {
+        let mut account = make_test_account();
+        let fixed = account.fixed_mut();
+        let window = PERP_VOLUME_WINDOW;
+        let start = 10 * window;
+        assert_eq!(fixed.perp_volume(start), 0);
+
+        fixed.accrue_perp_volume(start, 100);
+        fixed.accrue_perp_volume(start + 10, 50);
+        assert_eq!(fixed.perp_volume_window_end, start + window);
+        assert_eq!(fixed.perp_volume(start + 10), 150);
+
+        // previous window volume fades out linearly
+        fixed.accrue_perp_volume(start + window, 10);
+        assert_eq!(fixed.perp_volume_previous, 150);
+        assert_eq!(fixed.perp_volume_current, 10);
+        assert_eq!(fixed.perp_volume(start + window), 160);
+        assert_eq!(fixed.perp_volume(start + window + window / 3), 110);
+        assert_eq!(fixed.perp_volume(start + 2 * window - 1), 10);
+
+        // without accruing, reading a later time still expires old volume
+        assert_eq!(fixed.perp_volume(start + 2 * window + window / 2), 5);
+        assert_eq!(fixed.perp_volume(start + 3 * window), 0);
+
+        fixed.accrue_perp_volume(start + 5 * window, 7);
+        assert_eq!(fixed.perp_volume_previous, 0);
+        assert_eq!(fixed.perp_volume(start + 5 * window), 7);
+    }
+
+    #[test]
+    fn test_isolated_perp_positions() {
+        let mut account = make_test_account();
+        assert!(!account.is_isolated_perp(1));
+        assert!(account.isolated_perp_position(1).is_err());
+
+        assert_eq!(
+            account
+                .create_isolated_perp_position(1, 0)
+                .unwrap()
+                .market_index,
+            1
+        );
+        assert!(account.create_isolated_perp_position(1, 0).is_err());
+        assert!(account.create_isolated_perp_position(7, 0).is_ok());
+        assert!(account.create_isolated_perp_position(8, 0).is_err());
+        assert!(account.is_isolated_perp(1));
+        assert!(account.is_isolated_perp(7));
+        assert_eq!(account.active_isolated_perp_positions().count(), 2);
+
+        account
+            .isolated_perp_position_mut(7)
+            .unwrap()
+            .indexed_collateral = I80F48::from(10);
+        assert!(account.deactivate_isolated_perp_position(7).is_err());
+        account
+            .isolated_perp_position_mut(7)
+            .unwrap()
+            .indexed_collateral = I80F48::ZERO;
+        assert!(account.deactivate_isolated_perp_position(7).is_ok());
+        assert!(!account.is_isolated_perp(7));
+        assert!(account.create_isolated_perp_position(8, 0).is_ok());
+        assert_eq!(account.isolated_perp_position(8).unwrap().token_index, 0);
+    }
+
+    #[test]
+    fn test_account_without_isolated_perp_section() {
+        let mut account = MangoAccount::default_for_tests();
+        account.perps[0].market_index = 9;
+        account.perp_open_orders[5].market = 9;
+        account.isolated_perps.clear();
+        let mut bytes = AnchorSerialize::try_to_vec(&account).unwrap();
+        // drop padding8 and the isolated_perps length, like on old accounts
+        bytes.truncate(bytes.len() - BORSH_VEC_PADDING_BYTES - BORSH_VEC_SIZE_BYTES);
+
+        let mut account2 = MangoAccountValue::from_bytes(&bytes).unwrap();
+        assert_eq!(account2.header.isolated_perp_count(), 0);
+        assert_eq!(account2.active_isolated_perp_positions().count(), 0);
+        assert_eq!(account2.perp_position_by_raw_index(0).market_index, 9);
+        assert_eq!(account2.perp_order_by_raw_index(5).market, 9);
+
+        // expanding adds the section while keeping the other positions
+        let new_dynamic_len = MangoAccount::dynamic_size(3, 5, 4, 6, 2);
+        account2.dynamic.resize(new_dynamic_len, 0);
+        account2.expand_dynamic_content(3, 5, 4, 6, 2).unwrap();
+        assert_eq!(account2.header.isolated_perp_count(), 2);
+        assert_eq!(account2.active_isolated_perp_positions().count(), 0);
+        assert_eq!(account2.perp_position_by_raw_index(0).market_index, 9);
+        assert_eq!(account2.perp_order_by_raw_index(5).market, 9);
+        assert!(account2.create_isolated_perp_position(9, 0).is_ok());
+
+        let mut bytes = bytemuck::bytes_of(&account2.fixed).to_vec();
+        bytes.extend_from_slice(&account2.dynamic);
+        let account3 = MangoAccountValue::from_bytes(&bytes).unwrap();
+        assert!(account3.is_isolated_perp(9));
+    }
 }
@@ -42,13 +42,33 @@ pub struct TokenPosition {
     // Cumulative borrow interest in token native units
     pub cumulative_borrow_interest: f64,
 
+    // bookkeeping variable for liquidity mining rewards
+    // either deposit_rewards_index or borrow_rewards_index at last indexed_position change
+    pub previous_rewards_index: I80F48,
+    // Rewards earned but not yet claimed, in rewards mint native units
+    pub unclaimed_rewards: I80F48,
+
+    // bookkeeping variables for the liquidation close factor
+    // start of the current window, the liab at that time and the liab liquidated since,
+    // in token native units
+    pub liq_window_start_ts: u64,
+    pub liq_window_start_liab: I80F48,
+    pub liq_window_liquidated: I80F48,
+
+    // (Display only)
+    // Cumulative amounts deposited into and withdrawn from this position through
+    // TokenDeposit and TokenWithdraw, in token native units. Fees, liquidations,
+    // settlements and trades are not counted.
+    pub cumulative_deposits: f64,
+    pub cumulative_withdraws: f64,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 128],
+    pub reserved: [u8; 40],
 }
 
 const_assert_eq!(
     size_of::<TokenPosition>(),
-    16 + 2 + 1 + 5 + 16 + 8 + 8 + 128
+    16 + 2 + 1 + 5 + 16 + 8 + 8 + 16 + 16 + 8 + 16 * 2 + 8 * 2 + 40
 );
 const_assert_eq!(size_of::<TokenPosition>(), 184);
 const_assert_eq!(size_of::<TokenPosition>() % 8, 0);
@@ -62,8 +82,15 @@ impl Default for TokenPosition {
             cumulative_deposit_interest: 0.0,
             cumulative_borrow_interest: 0.0,
             previous_index: I80F48::ZERO,
+            previous_rewards_index: I80F48::ZERO,
+            unclaimed_rewards: I80F48::ZERO,
+            liq_window_start_ts: 0,
+            liq_window_start_liab: I80F48::ZERO,
+            liq_window_liquidated: I80F48::ZERO,
+            cumulative_deposits: 0.0,
+            cumulative_withdraws: 0.0,
             padding: Default::default(),
-            reserved: [0; 128],
+            reserved: [0; 40],
         }
     }
 }
@@ -99,6 +126,28 @@ impl TokenPosition {
     pub fn is_in_use(&self) -> bool {
         self.in_use_count > 0
     }
+
+    /// Liab amount (native, positive) that may still be liquidated in the current
+    /// close factor window. Starts a new window if the previous one has ended.
+    pub fn liquidation_close_factor_remaining(
+        &mut self,
+        close_factor: &LiquidationCloseFactor,
+        liab_native: I80F48,
+        now_ts: u64,
+    ) -> I80F48 {
+        if now_ts >= self.liq_window_start_ts.saturating_add(close_factor.window) {
+            self.liq_window_start_ts = now_ts;
+            self.liq_window_start_liab = liab_native.abs();
+            self.liq_window_liquidated = I80F48::ZERO;
+        }
+        let allowed = close_factor.allowed(self.liq_window_start_liab);
+        (allowed - self.liq_window_liquidated).max(I80F48::ZERO)
+    }
+
+    /// Record liab liquidated in the current close factor window
+    pub fn record_liquidation(&mut self, liab_native: I80F48) {
+        self.liq_window_liquidated += liab_native.abs();
+    }
 }
 
 #[zero_copy]
@@ -122,13 +171,44 @@ pub struct Serum3Orders {
     pub base_token_index: TokenIndex,
     pub quote_token_index: TokenIndex,
 
-    #[derivative(Debug = "ignore")]
-    pub padding: [u8; 2],
+    /// Set once last_seen_base_total and last_seen_quote_total are valid.
+    /// Older orders start tracking fills at the first settle or place order.
+    pub fills_tracked: u8,
 
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 64],
+    pub padding: [u8; 1],
+
+    /// Cumulative native base bought in fills and the native quote paid for it,
+    /// including taker fees and net of maker rebates
+    pub base_bought_native: u64,
+    pub quote_paid_native: u64,
+
+    /// Cumulative native base sold in fills and the native quote received for it,
+    /// net of taker fees and including maker rebates
+    pub base_sold_native: u64,
+    pub quote_received_native: u64,
+
+    /// Cumulative native quote referrer rebates that were settled out of the open orders
+    /// account, either to the account or to the DAO as fees
+    pub referrer_rebates_settled_native: u64,
+
+    /// Open orders base and quote totals when they were last seen. Changes of the totals
+    /// that are not caused by deposits or withdrawals are fills.
+    pub last_seen_base_total: u64,
+    pub last_seen_quote_total: u64,
+
+    /// Estimate of the cumulative native quote taker fees paid minus maker rebates
+    /// received in fills.
+    ///
+    /// The open orders account doesn't report fees. They are derived from the quote
+    /// paid and received with the serum base fee tier rates, so the estimate is off
+    /// on markets with other rates, like stable markets.
+    pub estimated_net_fees_native: i64,
 }
-const_assert_eq!(size_of::<Serum3Orders>(), 32 + 8 * 2 + 2 * 3 + 2 + 64);
+const_assert_eq!(
+    size_of::<Serum3Orders>(),
+    32 + 8 * 2 + 2 * 3 + 1 + 1 + 8 * 7 + 8
+);
 const_assert_eq!(size_of::<Serum3Orders>(), 120);
 const_assert_eq!(size_of::<Serum3Orders>() % 8, 0);
 
@@ -140,6 +220,67 @@ impl Serum3Orders {
     pub fn is_active_for_market(&self, market_index: Serum3MarketIndex) -> bool {
         self.market_index == market_index
     }
+
+    /// Record the change of the open orders totals since they were last seen as fills
+    /// of resting orders.
+    pub fn record_maker_fills(&mut self, base_total: u64, quote_total: u64) {
+        self.record_fills(base_total, quote_total, false);
+    }
+
+    /// Record the change of the open orders totals since they were last seen as fills
+    /// of a newly placed order against the book.
+    pub fn record_taker_fills(&mut self, base_total: u64, quote_total: u64) {
+        self.record_fills(base_total, quote_total, true);
+    }
+
+    /// The changes of the totals since they were last seen can be a mix of buys and
+    /// sells. Each side is recorded on its own, which keeps the net amounts exact.
+    ///
+    /// Fees are estimated from the quote change with the serum base fee tier rates.
+    fn record_fills(&mut self, base_total: u64, quote_total: u64, taker: bool) {
+        if self.fills_tracked == 1 {
+            let base_change = base_total as i128 - self.last_seen_base_total as i128;
+            if base_change > 0 {
+                self.base_bought_native += base_change as u64;
+            } else {
+                self.base_sold_native += (-base_change) as u64;
+            }
+
+            let quote_change = quote_total as i128 - self.last_seen_quote_total as i128;
+            if quote_change < 0 {
+                // paid for buys: including taker fees, excluding maker rebates
+                let paid = (-quote_change) as u64;
+                let fee_rate_bps = if taker {
+                    SERUM3_TAKER_FEE_BPS
+                } else {
+                    -SERUM3_MAKER_REBATE_BPS
+                };
+                let value = serum3_value_without_fees(paid, fee_rate_bps);
+                self.quote_paid_native += paid;
+                self.estimated_net_fees_native += paid as i64 - value as i64;
+            } else if quote_change > 0 {
+                // received for sells: excluding taker fees, including maker rebates
+                let received = quote_change as u64;
+                let fee_rate_bps = if taker {
+                    -SERUM3_TAKER_FEE_BPS
+                } else {
+                    SERUM3_MAKER_REBATE_BPS
+                };
+                let value = serum3_value_without_fees(received, fee_rate_bps);
+                self.quote_received_native += received;
+                self.estimated_net_fees_native += value as i64 - received as i64;
+            }
+        }
+        self.set_last_seen_totals(base_total, quote_total);
+    }
+
+    /// Set the open orders totals without recording fills, like after deposits
+    /// or withdrawals
+    pub fn set_last_seen_totals(&mut self, base_total: u64, quote_total: u64) {
+        self.last_seen_base_total = base_total;
+        self.last_seen_quote_total = quote_total;
+        self.fills_tracked = 1;
+    }
 }
 
 impl Default for Serum3Orders {
@@ -149,14 +290,35 @@ impl Default for Serum3Orders {
             market_index: Serum3MarketIndex::MAX,
             base_token_index: TokenIndex::MAX,
             quote_token_index: TokenIndex::MAX,
-            reserved: [0; 64],
+            fills_tracked: 0,
             padding: Default::default(),
             base_borrows_without_fee: 0,
             quote_borrows_without_fee: 0,
+            base_bought_native: 0,
+            quote_paid_native: 0,
+            base_sold_native: 0,
+            quote_received_native: 0,
+            referrer_rebates_settled_native: 0,
+            last_seen_base_total: 0,
+            last_seen_quote_total: 0,
+            estimated_net_fees_native: 0,
         }
     }
 }
 
+/// Serum taker fee and maker rebate of the base fee tier, in basis points, used to
+/// estimate fees. Mango never passes a fee discount account when placing orders, but
+/// markets can have other rates.
+const SERUM3_TAKER_FEE_BPS: i64 = 4;
+const SERUM3_MAKER_REBATE_BPS: i64 = 2;
+
+/// The quote value of a fill whose quote amount `native` had `fee_rate_bps` of the value
+/// added to it, rounded to the nearest native unit
+fn serum3_value_without_fees(native: u64, fee_rate_bps: i64) -> u64 {
+    let denominator = (10_000 + fee_rate_bps) as u128;
+    ((native as u128 * 10_000 + denominator / 2) / denominator) as u64
+}
+
 #[zero_copy]
 #[derive(AnchorSerialize, AnchorDeserialize, Derivative, bytemuck::Pod, bytemuck::Zeroable)]
 #[derivative(Debug)]
@@ -263,12 +425,40 @@ pub struct PerpPosition {
     /// price and current price of the base position is the overall pnl.
     pub realized_pnl_for_position_native: I80F48,
 
+    /// Cumulative share of taker fees received as the referrer of other accounts'
+    /// orders, in quote native units
+    ///
+    /// (Display only)
+    pub cumulative_referrer_rewards: f64,
+
+    /// Liquidation close factor bookkeeping: start of the current window, the base
+    /// position at that time and the base lots liquidated since
+    pub liq_window_start_ts: u64,
+    pub liq_window_start_base_lots: i64,
+    pub liq_window_liquidated_base_lots: i64,
+
     #[derivative(Debug = "ignore")]
-    pub reserved: [u8; 88],
+    pub reserved: [u8; 56],
 }
 const_assert_eq!(
     size_of::<PerpPosition>(),
-    2 + 2 + 4 + 8 + 8 + 16 + 8 + 16 * 2 + 8 * 2 + 8 * 2 + 8 * 5 + 8 + 2 * 16 + 8 + 16 + 88
+    2 + 2
+        + 4
+        + 8
+        + 8
+        + 16
+        + 8
+        + 16 * 2
+        + 8 * 2
+        + 8 * 2
+        + 8 * 5
+        + 8
+        + 2 * 16
+        + 8
+        + 16
+        + 8
+        + 8 * 3
+        + 56
 );
 const_assert_eq!(size_of::<PerpPosition>(), 304);
 const_assert_eq!(size_of::<PerpPosition>() % 8, 0);
@@ -299,7 +489,11 @@ impl Default for PerpPosition {
             settle_pnl_limit_settled_in_current_window_native: 0,
             settle_pnl_limit_realized_trade: 0,
             realized_pnl_for_position_native: I80F48::ZERO,
-            reserved: [0; 88],
+            cumulative_referrer_rewards: 0.0,
+            liq_window_start_ts: 0,
+            liq_window_start_base_lots: 0,
+            liq_window_liquidated_base_lots: 0,
+            reserved: [0; 56],
         }
     }
 }
@@ -390,14 +584,16 @@ impl PerpPosition {
     }
 
     /// Updates avg entry price, breakeven price, realized pnl, realized pnl limit
+    ///
+    /// Returns the newly realized trade pnl.
     fn update_trade_stats(
         &mut self,
         base_change: i64,
         quote_change_native: I80F48,
         perp_market: &PerpMarket,
-    ) {
+    ) -> I80F48 {
         if base_change == 0 {
-            return;
+            return I80F48::ZERO;
         }
 
         let old_position = self.base_position_lots;
@@ -502,6 +698,8 @@ impl PerpPosition {
 
         // Ensure the realized limit doesn't exceed the realized pnl
         self.apply_realized_trade_pnl_settle_limit_constraint(newly_realized_pnl);
+
+        newly_realized_pnl
     }
 
     /// The abs(realized pnl settle limit) should be roughly < abs(realized pnl).
@@ -569,16 +767,19 @@ impl PerpPosition {
     }
 
     /// Change the base and quote positions as the result of a trade
+    ///
+    /// Returns the trade pnl realized by reducing the position, in quote native units.
     pub fn record_trade(
         &mut self,
         perp_market: &mut PerpMarket,
         base_change: i64,
         quote_change_native: I80F48,
-    ) {
+    ) -> I80F48 {
         assert_eq!(perp_market.perp_market_index, self.market_index);
-        self.update_trade_stats(base_change, quote_change_native, perp_market);
+        let realized_pnl = self.update_trade_stats(base_change, quote_change_native, perp_market);
         self.change_base_position(perp_market, base_change);
         self.change_quote_position(quote_change_native);
+        realized_pnl
     }
 
     fn change_quote_position(&mut self, quote_change_native: I80F48) {
@@ -627,6 +828,32 @@ impl PerpPosition {
         Ok(pnl)
     }
 
+    /// Ranking score for auto-deleveraging: unsettled pnl relative to the entry notional
+    /// of the base position, times the leverage of the position (base notional over
+    /// account equity). Higher scores are deleveraged first.
+    ///
+    /// Positions without base position or without positive pnl can't be deleveraged
+    /// and score zero. Without positive equity the leverage is unbounded.
+    pub fn adl_score(
+        &self,
+        perp_market: &PerpMarket,
+        price: I80F48,
+        account_equity: I80F48,
+    ) -> Result<I80F48> {
+        let pnl = self.unsettled_pnl(perp_market, price)?;
+        let entry_notional = I80F48::from_num(
+            self.base_position_lots.unsigned_abs() as f64 * self.avg_entry_price_per_base_lot,
+        );
+        if !pnl.is_positive() || !entry_notional.is_positive() {
+            return Ok(I80F48::ZERO);
+        }
+        if !account_equity.is_positive() {
+            return Ok(I80F48::MAX);
+        }
+        let base_notional = self.base_position_native(perp_market).abs() * price;
+        Ok(pnl / entry_notional * base_notional / account_equity)
+    }
+
     /// Updates the perp pnl limit time windowing, resetting the amount
     /// of used settle-pnl budget if necessary
     pub fn update_settle_limit(&mut self, market: &PerpMarket, now_ts: u64) {
@@ -765,6 +992,12 @@ impl PerpPosition {
         self.realized_pnl_for_position_native -= fee;
     }
 
+    /// Credits the referrer's share of a taker fee, it is immediately settleable
+    pub fn record_referrer_reward(&mut self, reward: I80F48) {
+        self.record_trading_fee(-reward);
+        self.cumulative_referrer_rewards += reward.to_num::<f64>();
+    }
+
     /// Adds immediately-settleable realized pnl when a liqor takes over pnl during liquidation
     pub fn record_liquidation_quote_change(&mut self, change: I80F48) {
         self.change_quote_position(change);
@@ -776,6 +1009,29 @@ impl PerpPosition {
         self.change_quote_position(change);
         self.realized_trade_pnl_native += recurring_limit;
     }
+
+    /// Base lots (positive) that may still be liquidated in the current close factor
+    /// window. Starts a new window if the previous one has ended.
+    pub fn liquidation_close_factor_remaining(
+        &mut self,
+        close_factor: &LiquidationCloseFactor,
+        now_ts: u64,
+    ) -> i64 {
+        if now_ts >= self.liq_window_start_ts.saturating_add(close_factor.window) {
+            self.liq_window_start_ts = now_ts;
+            self.liq_window_start_base_lots = self.base_position_lots;
+            self.liq_window_liquidated_base_lots = 0;
+        }
+        let allowed = close_factor
+            .allowed(I80F48::from(self.liq_window_start_base_lots))
+            .clamp_to_i64();
+        (allowed - self.liq_window_liquidated_base_lots).max(0)
+    }
+
+    /// Record base lots liquidated in the current close factor window
+    pub fn record_liquidation(&mut self, base_lots: i64) {
+        self.liq_window_liquidated_base_lots += base_lots.abs();
+    }
 }
 
 #[zero_copy]
@@ -817,6 +1073,63 @@ impl PerpOpenOrder {
     }
 }
 
+/// Dedicated collateral for a perp position that is margined in isolation.
+///
+/// The PerpPosition for the market stays in the account's perp positions, but it
+/// doesn't count toward the account's health. Instead its health is computed from
+/// the position and this collateral alone, and liquidating it only touches this
+/// collateral.
+#[zero_copy]
+#[derive(AnchorSerialize, AnchorDeserialize, Derivative, bytemuck::Pod, bytemuck::Zeroable)]
+#[derivative(Debug)]
+pub struct IsolatedPerpPosition {
+    /// The perp market the collateral is dedicated to, PerpMarketIndex::MAX if unused
+    pub market_index: PerpMarketIndex,
+
+    /// The collateral token, always the perp market's settle token
+    pub token_index: TokenIndex,
+
+    #[derivative(Debug = "ignore")]
+    pub padding: [u8; 4],
+
+    /// The collateral in deposit_index scaled units of the settle token bank.
+    /// Like a positive TokenPosition it earns deposit interest, but never rewards.
+    pub indexed_collateral: I80F48,
+
+    #[derivative(Debug = "ignore")]
+    pub reserved: [u8; 40],
+}
+const_assert_eq!(size_of::<IsolatedPerpPosition>(), 2 + 2 + 4 + 16 + 40);
+const_assert_eq!(size_of::<IsolatedPerpPosition>(), 64);
+const_assert_eq!(size_of::<IsolatedPerpPosition>() % 8, 0);
+
+impl Default for IsolatedPerpPosition {
+    fn default() -> Self {
+        Self {
+            market_index: PerpMarketIndex::MAX,
+            token_index: TokenIndex::MAX,
+            padding: Default::default(),
+            indexed_collateral: I80F48::ZERO,
+            reserved: [0; 40],
+        }
+    }
+}
+
+impl IsolatedPerpPosition {
+    pub fn is_active(&self) -> bool {
+        self.market_index != PerpMarketIndex::MAX
+    }
+
+    pub fn is_active_for_market(&self, market_index: PerpMarketIndex) -> bool {
+        self.market_index == market_index
+    }
+
+    /// The collateral in native settle token units
+    pub fn native(&self, bank: &Bank) -> I80F48 {
+        self.indexed_collateral * bank.deposit_index
+    }
+}
+
 #[macro_export]
 macro_rules! account_seeds {
     ( $account:expr ) => {
@@ -1196,6 +1509,51 @@ mod tests {
         assert_eq!(pnl, I80F48::from(50 * 10 * 1), "short profitable");
     }
 
+    #[test]
+    fn test_perp_position_adl_score() {
+        let mut market = test_perp_market(10.0);
+        market.base_lot_size = 10;
+        let price = I80F48::from_num(12.5);
+        let equity = I80F48::from(6250);
+
+        // pnl 1250 on entry notional 5000, position notional 6250: leverage 1
+        let large_pos = create_perp_position(&market, 50, 100);
+        let large_score = large_pos.adl_score(&market, price, equity).unwrap();
+        assert_eq!(large_score, I80F48::from_num(0.25));
+
+        // same position with half the equity backing it: twice the leverage
+        let leveraged_score = large_pos.adl_score(&market, price, equity / 2).unwrap();
+        assert_eq!(leveraged_score, I80F48::from_num(0.5));
+
+        // same price move, but a better entry: more profitable relative to size
+        let cheap_pos = create_perp_position(&market, 10, 50);
+        let cheap_score = cheap_pos
+            .adl_score(&market, price, I80F48::from(1250))
+            .unwrap();
+        assert_eq!(cheap_score, I80F48::from_num(1.5));
+        assert!(cheap_score > large_score);
+
+        let losing_pos = create_perp_position(&market, -50, 100);
+        assert_eq!(
+            losing_pos.adl_score(&market, price, equity).unwrap(),
+            I80F48::ZERO
+        );
+
+        // no base position to close
+        let mut pnl_only = create_perp_position(&market, 0, 100);
+        pnl_only.quote_position_native = I80F48::from(10);
+        assert_eq!(
+            pnl_only.adl_score(&market, price, equity).unwrap(),
+            I80F48::ZERO
+        );
+
+        // no equity backing the position
+        assert_eq!(
+            large_pos.adl_score(&market, price, I80F48::ZERO).unwrap(),
+            I80F48::MAX
+        );
+    }
+
     #[test]
     fn test_perp_realized_pnl_consumption() {
         let market = test_perp_market(10.0);
@@ -1410,4 +1768,213 @@ mod tests {
             );
         }
     }
+
+    /// A random fill for a position: (base lots, quote native), at an integer price per lot
+    fn random_fill(rng: &mut impl Rng, base_change: i64) -> (i64, I80F48, f64) {
+        let price: i64 = rng.gen_range(1..=10_000);
+        (
+            base_change,
+            I80F48::from(-base_change * price),
+            price as f64,
+        )
+    }
+
+    fn assert_approx(actual: f64, expected: f64, scale: f64, what: &str) {
+        let tolerance = 1e-9 * (1.0 + scale.abs());
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{what}: {actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn test_perp_record_trade_random_realized_pnl() {
+        let mut market = test_perp_market(10.0);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let mut pos = create_perp_position(&market, 0, 0);
+            let mut total_realized = I80F48::ZERO;
+            let mut volume = 0.0;
+            for _ in 0..100 {
+                let lots = rng.gen_range(1..=1000) * if rng.gen_bool(0.5) { 1 } else { -1 };
+                let (base_change, quote_change, _) = random_fill(&mut rng, lots);
+                volume += quote_change.abs().to_num::<f64>();
+
+                let realized_before = pos.realized_trade_pnl_native;
+                let realized = pos.record_trade(&mut market, base_change, quote_change);
+                total_realized += realized;
+
+                // the returned pnl is exactly what was added to the realized trade pnl
+                assert_eq!(pos.realized_trade_pnl_native - realized_before, realized);
+                assert_eq!(pos.realized_trade_pnl_native, total_realized);
+
+                // at the entry price nothing is unrealized
+                let pnl_at_entry = pos.quote_position_native.to_num::<f64>()
+                    + pos.base_position_lots as f64 * pos.avg_entry_price_per_base_lot;
+                assert_approx(
+                    pnl_at_entry,
+                    total_realized.to_num::<f64>(),
+                    volume,
+                    "pnl at entry price",
+                );
+
+                if pos.base_position_lots == 0 {
+                    assert_eq!(pos.quote_position_native, total_realized);
+                    assert_eq!(pos.avg_entry_price_per_base_lot, 0.0);
+                } else {
+                    assert!(pos.avg_entry_price_per_base_lot > 0.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_perp_record_trade_random_partial_close() {
+        let mut market = test_perp_market(10.0);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..1000 {
+            let old_lots = rng.gen_range(2..=1000) * if rng.gen_bool(0.5) { 1 } else { -1 };
+            let entry: i64 = rng.gen_range(1..=10_000);
+            let mut pos = create_perp_position(&market, old_lots, entry);
+            pos.realized_pnl_for_position_native = I80F48::from(rng.gen_range(-1000..=1000));
+            let realized_for_position_before = pos.realized_pnl_for_position_native;
+
+            let reduce = rng.gen_range(1..old_lots.abs()) * -old_lots.signum();
+            let (base_change, quote_change, price) = random_fill(&mut rng, reduce);
+            let realized = pos.record_trade(&mut market, base_change, quote_change);
+
+            let expected = -base_change as f64 * (price - entry as f64);
+            assert_approx(
+                realized.to_num::<f64>(),
+                expected,
+                quote_change.to_num::<f64>(),
+                "realized pnl",
+            );
+            assert_eq!(pos.base_position_lots, old_lots + reduce);
+            assert_eq!(pos.avg_entry_price_per_base_lot, entry as f64);
+            assert_eq!(
+                pos.realized_pnl_for_position_native,
+                realized_for_position_before + realized
+            );
+        }
+    }
+
+    #[test]
+    fn test_perp_record_trade_random_side_flip() {
+        let mut market = test_perp_market(10.0);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..1000 {
+            let old_lots = rng.gen_range(1..=1000) * if rng.gen_bool(0.5) { 1 } else { -1 };
+            let entry: i64 = rng.gen_range(1..=10_000);
+            let mut pos = create_perp_position(&market, old_lots, entry);
+            pos.realized_pnl_for_position_native = I80F48::from(rng.gen_range(-1000..=1000));
+
+            // a single fill that closes the position and opens one on the other side
+            let new_lots = rng.gen_range(1..=1000) * -old_lots.signum();
+            let (base_change, quote_change, price) = random_fill(&mut rng, new_lots - old_lots);
+            let realized = pos.record_trade(&mut market, base_change, quote_change);
+
+            let expected = old_lots as f64 * (price - entry as f64);
+            assert_approx(
+                realized.to_num::<f64>(),
+                expected,
+                quote_change.to_num::<f64>(),
+                "realized pnl",
+            );
+            assert_eq!(pos.base_position_lots, new_lots);
+            assert_eq!(pos.avg_entry_price_per_base_lot, price);
+            assert_eq!(pos.quote_running_native, (-new_lots as f64 * price) as i64);
+            assert_eq!(pos.realized_pnl_for_position_native, I80F48::ZERO);
+            assert_eq!(pos.realized_trade_pnl_native, realized);
+        }
+    }
+
+    #[test]
+    fn test_perp_record_trade_random_settle_limits() {
+        let mut market = test_perp_market(10.0);
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..100 {
+            let mut pos = create_perp_position(&market, 0, 0);
+            for _ in 0..100 {
+                if rng.gen_bool(0.7) {
+                    let lots = rng.gen_range(1..=100) * if rng.gen_bool(0.5) { 1 } else { -1 };
+                    let (base_change, quote_change, _) = random_fill(&mut rng, lots);
+                    pos.record_trade(&mut market, base_change, quote_change);
+                } else {
+                    // settle as much of the pnl at a random price as the limits allow
+                    let price = I80F48::from(rng.gen_range(1..=10_000));
+                    let pnl = pos.unsettled_pnl(&market, price).unwrap();
+                    let (min_pnl, max_pnl) = pos.available_settle_limit(&market);
+                    let settleable = pos.apply_pnl_settle_limit(&market, pnl);
+                    assert!(settleable >= min_pnl && settleable <= max_pnl);
+                    assert!(settleable.abs() <= pnl.abs());
+                    pos.record_settle(settleable);
+                }
+
+                // the realized settle limit never exceeds or opposes the realized pnl
+                let limit = pos.settle_pnl_limit_realized_trade;
+                let realized = pos.realized_trade_pnl_native;
+                if realized > 0 {
+                    assert!(limit >= 0 && limit <= realized.ceil().to_num::<i64>());
+                } else {
+                    assert!(limit <= 0 && limit >= realized.floor().to_num::<i64>());
+                }
+
+                let (min_pnl, max_pnl) = pos.available_settle_limit(&market);
+                assert!(min_pnl <= 0 && max_pnl >= 0);
+            }
+
+            // a new window frees up the whole settle limit again
+            pos.update_settle_limit(
+                &market,
+                (pos.settle_pnl_limit_window as u64 + 1) * market.settle_pnl_limit_window_size_ts,
+            );
+            assert_eq!(
+                pos.available_settle_limit(&market),
+                pos.settle_limit(&market)
+            );
+        }
+    }
+
+    #[test]
+    fn test_serum3_orders_record_fills() {
+        let mut orders = Serum3Orders::default();
+
+        // untracked orders only remember the totals
+        orders.record_maker_fills(100_000, 50_000);
+        assert_eq!(orders.base_bought_native, 0);
+        assert_eq!(orders.base_sold_native, 0);
+        assert_eq!(orders.fills_tracked, 1);
+
+        // a resting bid bought 10_000 base for 20_000 quote, minus the maker rebate
+        orders.record_maker_fills(110_000, 30_004);
+        assert_eq!(orders.base_bought_native, 10_000);
+        assert_eq!(orders.quote_paid_native, 19_996);
+        assert_eq!(orders.estimated_net_fees_native, -4);
+
+        // deposits and withdrawals are not fills
+        orders.set_last_seen_totals(0, 0);
+        assert_eq!(orders.base_bought_native, 10_000);
+
+        // a new ask sold 5_000 base for 10_000 quote, minus the taker fee
+        orders.set_last_seen_totals(5_000, 0);
+        orders.record_taker_fills(0, 9_996);
+        assert_eq!(orders.base_sold_native, 5_000);
+        assert_eq!(orders.quote_received_native, 9_996);
+        assert_eq!(orders.estimated_net_fees_native, 0);
+
+        // a mix of buys and sells keeps the net changes
+        orders.record_maker_fills(1_000, 30_000);
+        assert_eq!(orders.base_bought_native, 11_000);
+        assert_eq!(orders.quote_paid_native, 19_996);
+        assert_eq!(orders.base_sold_native, 5_000);
+        assert_eq!(orders.quote_received_native, 30_000);
+        assert_eq!(orders.estimated_net_fees_native, -4);
+        assert_eq!(orders.last_seen_base_total, 1_000);
+        assert_eq!(orders.last_seen_quote_total, 30_000);
+    }
 }
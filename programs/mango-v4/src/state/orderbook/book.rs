@@ -1,7 +1,7 @@
 use crate::state::MangoAccountRefMut;
 use crate::{
     error::*,
-    state::{orderbook::bookside::*, EventQueue, PerpMarket},
+    state::{orderbook::bookside::*, EventQueue, PerpFeeTier, PerpMarket},
 };
 use anchor_lang::prelude::*;
 use bytemuck::cast;
@@ -49,6 +49,8 @@ impl<'a> Orderbook<'a> {
         mango_account: &mut MangoAccountRefMut,
         mango_account_pk: &Pubkey,
         now_ts: u64,
+        fee_tiers: &[PerpFeeTier],
+        referrer: Option<Pubkey>,
         mut limit: u8,
     ) -> std::result::Result<Option<u128>, Error> {
         let side = order.side;
@@ -59,6 +61,16 @@ impl<'a> Orderbook<'a> {
         let mut post_target = order.post_target();
         let (price_lots, price_data) = order.price(now_ts, oracle_price_lots, self)?;
 
+        // The taker's fee tier is decided here, the maker's when the fill is consumed.
+        // The taker fee always covers the market's maker rebate.
+        let (_, taker_fee) = PerpFeeTier::fees_for_volume(
+            fee_tiers,
+            mango_account.fixed.perp_volume(now_ts),
+            market.maker_fee,
+            market.taker_fee,
+        );
+        let taker_fee = taker_fee.max(-market.maker_fee);
+
         // generate new order id
         let order_id = market.gen_order_id(side, price_data);
 
@@ -149,7 +161,8 @@ impl<'a> Orderbook<'a> {
                 best_opposing.node.timestamp,
                 *mango_account_pk,
                 order.client_order_id,
-                market.taker_fee,
+                taker_fee,
+                referrer,
                 best_opposing_price,
                 match_base_lots,
             );
@@ -165,7 +178,7 @@ impl<'a> Orderbook<'a> {
         // realized when the fill event gets executed
         if total_quote_lots_taken > 0 || total_base_lots_taken > 0 {
             perp_position.add_taker_trade(side, total_base_lots_taken, total_quote_lots_taken);
-            apply_fees(market, mango_account, total_quote_lots_taken)?;
+            apply_fees(market, mango_account, total_quote_lots_taken, taker_fee)?;
         }
 
         // Apply changes to matched asks (handles invalidate on delete!)
@@ -353,13 +366,15 @@ fn apply_fees(
     market: &mut PerpMarket,
     account: &mut MangoAccountRefMut,
     quote_lots: i64,
+    taker_fee: I80F48,
 ) -> Result<()> {
     let quote_native = I80F48::from_num(market.quote_lot_size * quote_lots);
 
     // The maker fees apply to the maker's account only when the fill event is consumed.
+    // If the maker's fee tier changes them, fees_accrued is adjusted then.
     let maker_fees = quote_native * market.maker_fee;
 
-    let taker_fees = quote_native * market.taker_fee;
+    let taker_fees = quote_native * taker_fee;
 
     // taker fees should never be negative
     require_gte!(taker_fees, 0);
@@ -21,7 +21,7 @@ mod queue;
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::state::{MangoAccount, MangoAccountValue, PerpMarket, FREE_ORDER_SLOT};
+    use crate::state::{MangoAccount, MangoAccountValue, PerpFeeTier, PerpMarket, FREE_ORDER_SLOT};
     use anchor_lang::prelude::*;
     use bytemuck::Zeroable;
     use fixed::types::I80F48;
@@ -137,6 +137,8 @@ mod tests {
                 &mut account.borrow_mut(),
                 &Pubkey::default(),
                 now_ts,
+                &[],
+                None,
                 u8::MAX,
             )
             .unwrap();
@@ -285,6 +287,8 @@ mod tests {
             &mut maker.borrow_mut(),
             &maker_pk,
             now_ts,
+            &[],
+            None,
             u8::MAX,
         )
         .unwrap();
@@ -346,6 +350,8 @@ mod tests {
             &mut taker.borrow_mut(),
             &taker_pk,
             now_ts,
+            &[],
+            None,
             u8::MAX,
         )
         .unwrap();
@@ -393,7 +399,7 @@ mod tests {
 
         // simulate event queue processing
         maker
-            .execute_perp_maker(market.perp_market_index, &mut market, fill)
+            .execute_perp_maker(market.perp_market_index, &mut market, fill, &[])
             .unwrap();
         taker
             .execute_perp_taker(market.perp_market_index, &mut market, fill)
@@ -465,6 +471,8 @@ mod tests {
             &mut account.borrow_mut(),
             &taker_pk,
             now_ts,
+            &[],
+            None,
             u8::MAX,
         )
         .unwrap();
@@ -489,6 +497,8 @@ mod tests {
             &mut account.borrow_mut(),
             &taker_pk,
             now_ts,
+            &[],
+            None,
             u8::MAX,
         )
         .unwrap();
@@ -524,6 +534,8 @@ mod tests {
             &mut account.borrow_mut(),
             &taker_pk,
             now_ts,
+            &[],
+            None,
             u8::MAX,
         )
         .unwrap();
@@ -544,4 +556,89 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_perp_fee_tiers() -> Result<()> {
+        let (mut market, oracle_price, mut event_queue, book_accs) = test_setup(1000.0);
+        let mut book = book_accs.orderbook();
+
+        let buffer = MangoAccount::default_for_tests().try_to_vec().unwrap();
+        let mut maker = MangoAccountValue::from_bytes(&buffer).unwrap();
+        let mut taker = MangoAccountValue::from_bytes(&buffer).unwrap();
+        let maker_pk = Pubkey::new_unique();
+        let taker_pk = Pubkey::new_unique();
+        let now_ts = 1000000;
+
+        market.maker_fee = I80F48::ZERO;
+        market.taker_fee = I80F48::from_num(0.01);
+        maker.ensure_perp_position(market.perp_market_index, 0)?;
+        taker.ensure_perp_position(market.perp_market_index, 0)?;
+
+        let fee_tiers = [PerpFeeTier {
+            min_volume: 1000,
+            maker_fee: -0.001,
+            taker_fee: 0.005,
+        }];
+        for account in [&mut maker, &mut taker] {
+            account.fixed.perp_volume_current = 1000;
+            account.fixed.perp_volume_window_end = now_ts + 1;
+        }
+
+        let mut place = |account: &mut MangoAccountValue, pk: &Pubkey, side: Side| {
+            book.new_order(
+                Order {
+                    side,
+                    max_base_lots: 1,
+                    max_quote_lots: i64::MAX,
+                    client_order_id: 0,
+                    time_in_force: 0,
+                    reduce_only: false,
+                    params: OrderParams::Fixed {
+                        price_lots: 1000,
+                        order_type: PostOrderType::Limit,
+                    },
+                },
+                &mut market,
+                &mut event_queue,
+                oracle_price,
+                &mut account.borrow_mut(),
+                pk,
+                now_ts,
+                &fee_tiers,
+                None,
+                u8::MAX,
+            )
+            .unwrap();
+        };
+        place(&mut maker, &maker_pk, Side::Ask);
+        place(&mut taker, &taker_pk, Side::Bid);
+
+        // the taker pays the tier's fee
+        let pos = taker.perp_position(market.perp_market_index)?;
+        assert_eq!(pos.quote_position_native().round(), I80F48::from_num(-5));
+        assert_eq!(market.fees_accrued.round(), I80F48::from_num(5));
+
+        let event = event_queue.peek_front().unwrap();
+        let fill: &FillEvent = bytemuck::cast_ref(event);
+        assert_eq!(fill.taker_fee, 0.005);
+        assert_eq!(fill.maker_fee, 0.0);
+
+        // the maker gets the tier's rebate, paid from the market's fees
+        let (maker_fee, _) = maker
+            .execute_perp_maker(market.perp_market_index, &mut market, fill, &fee_tiers)
+            .unwrap();
+        taker
+            .execute_perp_taker(market.perp_market_index, &mut market, fill)
+            .unwrap();
+        assert_eq!(maker_fee, I80F48::from_num(-0.001f32));
+        let pos = maker.perp_position(market.perp_market_index)?;
+        assert_eq!(pos.quote_position_native().round(), I80F48::from_num(1001));
+        assert_eq!(market.fees_accrued.round(), I80F48::from_num(4));
+
+        // volume is tracked for both sides
+        assert_eq!(maker.fixed.perp_volume(now_ts), 2000);
+        assert_eq!(taker.fixed.perp_volume(now_ts), 2000);
+
+        Ok(())
+    }
 }
@@ -186,7 +186,9 @@ pub struct FillEvent {
     pub seq_num: u64,
 
     pub maker: Pubkey,
-    pub padding2: [u8; 32],
+
+    /// MangoAccount that receives a share of the taker fee, or the default pubkey
+    pub referrer: Pubkey,
 
     // Timestamp of when the maker order was placed; copied over from the LeafNode
     pub maker_timestamp: u64,
@@ -221,6 +223,7 @@ impl FillEvent {
         taker: Pubkey,
         taker_client_order_id: u64,
         taker_fee: I80F48,
+        referrer: Option<Pubkey>,
         price: i64,
         quantity: i64,
     ) -> FillEvent {
@@ -238,10 +241,10 @@ impl FillEvent {
             taker,
             taker_client_order_id,
             taker_fee: taker_fee.to_num::<f32>(),
+            referrer: referrer.unwrap_or_default(),
             price,
             quantity,
             padding: Default::default(),
-            padding2: Default::default(),
             padding3: Default::default(),
             padding4: Default::default(),
             reserved: [0; 8],
@@ -258,6 +261,14 @@ impl FillEvent {
     pub fn taker_side(&self) -> Side {
         self.taker_side.try_into().unwrap()
     }
+
+    pub fn referrer(&self) -> Option<Pubkey> {
+        if self.referrer == Pubkey::default() {
+            None
+        } else {
+            Some(self.referrer)
+        }
+    }
     pub fn maker_out(&self) -> bool {
         self.maker_out == 1
     }
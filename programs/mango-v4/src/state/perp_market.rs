@@ -158,7 +158,11 @@ pub struct PerpMarket {
     /// that reduce their position are still allowed.
     pub reduce_only: u8,
 
-    pub padding4: [u8; 7],
+    /// If true, PerpAdl may close the base position of bankrupt accounts against the
+    /// most profitable and leveraged opposite-side positions at the bankruptcy price.
+    pub adl_enabled: u8,
+
+    pub padding4: [u8; 6],
 
     /// Weights for full perp market health, if positive
     pub maint_overall_asset_weight: I80F48,
@@ -166,7 +170,21 @@ pub struct PerpMarket {
 
     pub positive_pnl_liquidation_fee: I80F48,
 
-    pub reserved: [u8; 1888],
+    /// Fraction of the taker fee that goes to the referrer of a taker order, if any.
+    pub referrer_fee_share: f32,
+    pub padding5: [u8; 4],
+
+    /// Counterparties of PerpAdl need at least this adl score, see PerpPosition::adl_score()
+    pub adl_min_score: I80F48,
+
+    /// The best-ranked long position that the last PerpAdl against longs passed as a
+    /// candidate but didn't deleverage. Later PerpAdl calls against longs must pass it
+    /// as a candidate again, so the ranking can't be skipped by leaving it out.
+    pub adl_next_long: Pubkey,
+    /// Like adl_next_long, for PerpAdl calls against shorts
+    pub adl_next_short: Pubkey,
+
+    pub reserved: [u8; 1800],
 }
 
 const_assert_eq!(
@@ -200,9 +218,14 @@ const_assert_eq!(
         + 8
         + 8
         + 1
-        + 7
+        + 1
+        + 6
         + 3 * 16
-        + 1888
+        + 4
+        + 4
+        + 16
+        + 32 * 2
+        + 1800
 );
 const_assert_eq!(size_of::<PerpMarket>(), 2808);
 const_assert_eq!(size_of::<PerpMarket>() % 8, 0);
@@ -218,6 +241,10 @@ impl PerpMarket {
         self.reduce_only == 1
     }
 
+    pub fn adl_enabled(&self) -> bool {
+        self.adl_enabled == 1
+    }
+
     pub fn elligible_for_group_insurance_fund(&self) -> bool {
         self.group_insurance_fund == 1
     }
@@ -452,11 +479,17 @@ impl PerpMarket {
             padding3: Default::default(),
             settle_pnl_limit_window_size_ts: 24 * 60 * 60,
             reduce_only: 0,
+            adl_enabled: 0,
             padding4: Default::default(),
             maint_overall_asset_weight: I80F48::ONE,
             init_overall_asset_weight: I80F48::ONE,
             positive_pnl_liquidation_fee: I80F48::ZERO,
-            reserved: [0; 1888],
+            referrer_fee_share: 0.0,
+            padding5: Default::default(),
+            adl_min_score: I80F48::ZERO,
+            adl_next_long: Pubkey::default(),
+            adl_next_short: Pubkey::default(),
+            reserved: [0; 1800],
         }
     }
 }
@@ -1,4 +1,5 @@
 use anchor_lang::prelude::*;
+use fixed::types::I80F48;
 use static_assertions::const_assert_eq;
 use std::mem::size_of;
 
@@ -29,11 +30,23 @@ pub struct Serum3Market {
 
     pub registration_time: u64,
 
-    pub reserved: [u8; 128],
+    /// Fee paid by the account to the caller of Serum3SettleFundsPermissionless,
+    /// in $ native units (like USDC native)
+    pub settle_fee_flat: f32,
+
+    /// The fee is only charged when settling funds worth at least this amount,
+    /// in $ native units (like USDC native)
+    pub settle_fee_amount_threshold: f32,
+
+    /// Whether Serum3SettleFundsPermissionless credits serum referrer rebates to the
+    /// settled account instead of collecting them as fees, see Serum3SettleFundsV2
+    pub settle_rebates_to_account: u8,
+
+    pub reserved: [u8; 119],
 }
 const_assert_eq!(
     size_of::<Serum3Market>(),
-    32 + 2 + 2 + 1 + 3 + 16 + 2 * 32 + 2 + 1 + 5 + 8 + 128
+    32 + 2 + 2 + 1 + 3 + 16 + 2 * 32 + 2 + 1 + 5 + 8 + 4 + 4 + 1 + 119
 );
 const_assert_eq!(size_of::<Serum3Market>(), 264);
 const_assert_eq!(size_of::<Serum3Market>() % 8, 0);
@@ -48,6 +61,21 @@ impl Serum3Market {
     pub fn is_reduce_only(&self) -> bool {
         self.reduce_only == 1
     }
+
+    /// The fees_to_dao setting used by Serum3SettleFundsPermissionless
+    pub fn settle_fees_to_dao(&self) -> bool {
+        self.settle_rebates_to_account == 0
+    }
+
+    /// Fee in $ native for a permissionless settle of funds worth `settled_value` $ native
+    pub fn permissionless_settle_fee(&self, settled_value: I80F48) -> I80F48 {
+        if settled_value < I80F48::from_num(self.settle_fee_amount_threshold) {
+            return I80F48::ZERO;
+        }
+        I80F48::from_num(self.settle_fee_flat)
+            .min(settled_value)
+            .max(I80F48::ZERO)
+    }
 }
 
 #[account(zero_copy)]
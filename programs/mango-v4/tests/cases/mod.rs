@@ -20,6 +20,7 @@ mod test_delegate;
 mod test_fees_buyback_with_mngo;
 mod test_health_compute;
 mod test_health_region;
+mod test_health_snapshot;
 mod test_ix_gate_set;
 mod test_liq_perps_bankruptcy;
 mod test_liq_perps_base_and_bankruptcy;
@@ -28,6 +29,7 @@ mod test_liq_perps_positive_pnl;
 mod test_liq_tokens;
 mod test_margin_trade;
 mod test_perp;
+mod test_perp_isolated;
 mod test_perp_settle;
 mod test_perp_settle_fees;
 mod test_position_lifetime;
@@ -218,3 +218,220 @@ async fn test_health_wrap() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_health_wrap_flash_loan() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(250000);
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+
+    //
+    // SETUP: Create a group, accounts, tokens
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let bank = tokens[0].bank;
+    let vault = tokens[0].vault;
+
+    // SETUP: Create an account with deposits, so the second account can borrow more than it has
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        mints,
+        200000000,
+        0,
+    )
+    .await;
+
+    // SETUP: Make a second account
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..=1],
+        100,
+        0,
+    )
+    .await;
+
+    let target_token_account = context.users[0].token_accounts[0];
+    let margin_account = context.users[1].token_accounts[0];
+
+    let flash_loan_begin = |withdraw_amount| FlashLoanBeginInstruction {
+        account,
+        owner,
+        group,
+        mango_token_bank: bank,
+        mango_token_vault: vault,
+        target_token_account,
+        withdraw_amount,
+    };
+    let flash_loan_end = || FlashLoanEndInstruction {
+        account,
+        owner,
+        mango_token_bank: bank,
+        mango_token_vault: vault,
+        target_token_account,
+        flash_loan_type: mango_v4::accounts_ix::FlashLoanType::Unknown,
+    };
+    // moves the withdrawn tokens away, so the account ends up with less
+    let transfer_out = |amount| {
+        spl_token::instruction::transfer(
+            &spl_token::ID,
+            &target_token_account,
+            &margin_account,
+            &owner.pubkey(),
+            &[&owner.pubkey()],
+            amount,
+        )
+        .unwrap()
+    };
+    let health_region_end = || HealthRegionEndInstruction {
+        account,
+        affected_bank: None,
+    };
+
+    //
+    // TEST: A flash loan inside a health region works, health is only checked at the end
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(flash_loan_begin(10)).await;
+        tx.add_instruction_direct(transfer_out(10));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap();
+
+        let logs = solana.program_log();
+        assert_eq!(
+            logs.iter()
+                .filter(|line| line.contains("post_init_health"))
+                .count(),
+            1
+        );
+        assert_eq!(account_position(solana, account, bank).await, 90);
+    }
+
+    //
+    // TEST: Several flash loans in one health region
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(flash_loan_begin(5)).await;
+        tx.add_instruction_direct(transfer_out(5));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(flash_loan_begin(5)).await;
+        tx.add_instruction_direct(transfer_out(5));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap();
+
+        assert_eq!(account_position(solana, account, bank).await, 80);
+    }
+
+    //
+    // TEST: A flash loan that makes health negative fails in HealthRegionEnd
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(flash_loan_begin(1000)).await;
+        tx.add_instruction_direct(transfer_out(1000));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap_err();
+
+        let logs = solana.program_log();
+        // reaches the End instruction
+        assert!(logs
+            .iter()
+            .any(|line| line.contains("Instruction: HealthRegionEnd")));
+        // errors due to health
+        assert!(logs
+            .iter()
+            .any(|line| line.contains("Error Code: HealthMustBePositiveOrIncrease")));
+    }
+
+    //
+    // TEST: A flash loan can't begin inside and end outside a health region
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(flash_loan_begin(10)).await;
+        tx.add_instruction_direct(transfer_out(10));
+        tx.add_instruction(health_region_end()).await;
+        tx.add_instruction(flash_loan_end()).await;
+        tx.send().await.unwrap_err();
+    }
+
+    //
+    // TEST: A flash loan can't begin outside and end inside a health region
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(flash_loan_begin(10)).await;
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction_direct(transfer_out(10));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap_err();
+    }
+
+    //
+    // TEST: Other programs are still forbidden in the health region outside of flash loans
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(flash_loan_begin(10)).await;
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction_direct(transfer_out(10));
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap_err();
+    }
+
+    //
+    // TEST: A flash loan without a health region still forbids later mango instructions
+    //
+    {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(flash_loan_begin(10)).await;
+        tx.add_instruction_direct(transfer_out(10));
+        tx.add_instruction(flash_loan_end()).await;
+        tx.add_instruction(HealthRegionBeginInstruction { account })
+            .await;
+        tx.add_instruction(health_region_end()).await;
+        tx.send().await.unwrap_err();
+    }
+
+    assert_eq!(account_position(solana, account, bank).await, 80);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use super::*;
+use solana_sdk::instruction::AccountMeta;
+
+#[tokio::test]
+async fn test_account_health_snapshot() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+
+    //
+    // SETUP: Create a group and accounts with different tokens
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let account_0 = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    let account_1 =
+        create_funded_account(&solana, group, owner, 1, &context.users[1], mints, 1000, 0).await;
+
+    set_bank_stub_oracle_price(solana, group, &tokens[1], admin, 2.0).await;
+
+    //
+    // TEST: One instruction snapshots both accounts
+    //
+    send_tx(
+        solana,
+        AccountHealthSnapshotInstruction {
+            accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    let snapshots = solana.program_log_events::<mango_v4::logs::AccountHealthSnapshotLog>();
+    assert_eq!(snapshots.len(), 2);
+    assert_eq!(snapshots[0].mango_account, account_0);
+    assert_eq!(snapshots[1].mango_account, account_1);
+
+    let equity_0 = I80F48::from_bits(snapshots[0].equity);
+    let equity_1 = I80F48::from_bits(snapshots[1].equity);
+    assert!(assert_equal(equity_0, 1000.0, 0.01));
+    assert!(assert_equal(equity_1, 3000.0, 0.01));
+    assert!(snapshots.iter().all(|s| !s.being_liquidated));
+
+    // healths match what ComputeAccountData reports
+    for (snapshot, account) in snapshots.iter().zip([account_0, account_1]) {
+        send_tx(solana, ComputeAccountDataInstruction { account })
+            .await
+            .unwrap();
+        let data = solana
+            .program_log_events::<mango_v4::events::MangoAccountData>()
+            .pop()
+            .unwrap();
+        assert_eq!(I80F48::from_bits(snapshot.init_health), data.init_health);
+        assert_eq!(I80F48::from_bits(snapshot.maint_health), data.maint_health);
+        assert_eq!(
+            I80F48::from_bits(snapshot.liquidation_end_health),
+            data.health_cache
+                .health(mango_v4::health::HealthType::LiquidationEnd)
+        );
+    }
+
+    //
+    // TEST: An account whose health can't be computed is skipped
+    //
+    // The health accounts only cover account_0, account_1 lacks the bank for tokens[1]
+    let (_, mut instruction) = AccountHealthSnapshotInstruction {
+        accounts: vec![account_0],
+    }
+    .to_instruction(solana)
+    .await;
+    instruction.accounts.insert(
+        2,
+        AccountMeta {
+            pubkey: account_1,
+            is_writable: false,
+            is_signer: false,
+        },
+    );
+    solana
+        .process_transaction(&[instruction], None)
+        .await
+        .unwrap();
+
+    let snapshots = solana.program_log_events::<mango_v4::logs::AccountHealthSnapshotLog>();
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0].mango_account, account_0);
+
+    Ok(())
+}
@@ -244,7 +244,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
             health as f64
         );
 
-        (perp_market, account)
+        (perp_market, account, helper_account)
     };
     let mut setup_perp = |health: i64, pnl: i64, settle_limit: i64| {
         let out = setup_perp_inner(perp_market_index, health, pnl, settle_limit);
@@ -286,7 +286,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
     };
 
     {
-        let (perp_market, account) = setup_perp(-28, -50, -10).await;
+        let (perp_market, account, _) = setup_perp(-28, -50, -10).await;
         let liqor_quote_before = account_position(solana, liqor, quote_token.bank).await;
 
         send_tx(
@@ -319,7 +319,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
     }
 
     {
-        let (perp_market, account) = setup_perp(-28, -50, -10).await;
+        let (perp_market, account, _) = setup_perp(-28, -50, -10).await;
         fund_insurance(2).await;
         let liqor_quote_before = account_position(solana, liqor, quote_token.bank).await;
 
@@ -357,7 +357,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
     }
 
     {
-        let (perp_market, account) = setup_perp(-28, -50, -10).await;
+        let (perp_market, account, _) = setup_perp(-28, -50, -10).await;
         fund_insurance(5).await;
 
         send_tx(
@@ -380,7 +380,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
 
     // no insurance
     {
-        let (perp_market, account) = setup_perp(-28, -50, -10).await;
+        let (perp_market, account, _) = setup_perp(-28, -50, -10).await;
 
         send_tx(
             solana,
@@ -399,7 +399,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
 
     // no settlement: no settle health
     {
-        let (perp_market, account) = setup_perp(-200, -50, -10).await;
+        let (perp_market, account, _) = setup_perp(-200, -50, -10).await;
         fund_insurance(5).await;
 
         send_tx(
@@ -419,7 +419,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
 
     // no settlement: no settle limit
     {
-        let (perp_market, account) = setup_perp(-40, -50, 0).await;
+        let (perp_market, account, _) = setup_perp(-40, -50, 0).await;
         // no insurance
 
         send_tx(
@@ -439,7 +439,7 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
 
     // no socialized loss: fully covered by insurance fund
     {
-        let (perp_market, account) = setup_perp(-40, -50, -5).await;
+        let (perp_market, account, _) = setup_perp(-40, -50, -5).await;
         fund_insurance(42).await;
 
         send_tx(
@@ -457,5 +457,226 @@ async fn test_liq_perps_bankruptcy() -> Result<(), TransportError> {
         assert_eq!(liq_event_amounts(), (5.0, 42, 0.0));
     }
 
+    // adl: the bankrupt account's base position is closed against profitable,
+    // leveraged opposite-side positions at the bankruptcy price
+    {
+        let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+            solana,
+            PerpCreateMarketInstruction {
+                group,
+                admin,
+                payer,
+                perp_market_index,
+                quote_lot_size: 1,
+                base_lot_size: 100,
+                maint_base_asset_weight: 0.8,
+                init_base_asset_weight: 0.6,
+                maint_base_liab_weight: 1.2,
+                init_base_liab_weight: 1.4,
+                base_liquidation_fee: 0.05,
+                maker_fee: 0.0,
+                taker_fee: 0.0,
+                group_insurance_fund: true,
+                ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+            },
+        )
+        .await
+        .unwrap();
+        set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.0).await;
+        set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 1.0).await;
+
+        let new_account = |account_num: u32, amount: u64| {
+            create_funded_account(
+                &solana,
+                group,
+                owner,
+                account_num,
+                &context.users[1],
+                &mints[2..3],
+                amount,
+                0,
+            )
+        };
+        let account = new_account(100, 1000).await;
+        // less collateral means more leverage and a higher adl score
+        let helper1 = new_account(101, 1000).await;
+        let helper2 = new_account(102, 3000).await;
+        let helper3 = new_account(103, 500).await;
+
+        // the account shorts 3 lots, each helper is long 1 lot
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(PerpPlaceOrderInstruction {
+            account,
+            perp_market,
+            owner,
+            side: Side::Ask,
+            price_lots,
+            max_base_lots: 3,
+            max_quote_lots: i64::MAX,
+            client_order_id: 0,
+            reduce_only: false,
+        })
+        .await;
+        for helper in [helper1, helper2, helper3] {
+            tx.add_instruction(PerpPlaceOrderInstruction {
+                account: helper,
+                perp_market,
+                owner,
+                side: Side::Bid,
+                price_lots,
+                max_base_lots: 1,
+                max_quote_lots: i64::MAX,
+                client_order_id: 0,
+                reduce_only: false,
+            })
+            .await;
+        }
+        tx.add_instruction(PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account, helper1, helper2, helper3],
+        })
+        .await;
+        tx.send().await.unwrap();
+
+        // The account equity becomes 10 - 150 = -140. Its perp pnl is zero at the entry
+        // price of 1, which is the bankruptcy price.
+        // The helpers have 50 pnl each and equity of 60, 80 and 55, for adl scores of
+        // 0.5 * 150 / equity: 1.25, 0.9375 and 1.36.
+        set_perp_stub_oracle_price(solana, group, perp_market, &base_token, admin, 1.5).await;
+        set_bank_stub_oracle_price(solana, group, &collateral_token, admin, 0.01).await;
+        let bankruptcy_price = 1.0;
+
+        let adl = |counterparty: Pubkey, candidates: Vec<Pubkey>| PerpAdlInstruction {
+            perp_market,
+            liqee: account,
+            counterparty,
+            candidates,
+            max_base_transfer: u64::MAX,
+        };
+
+        // adl is not enabled yet
+        assert!(send_tx(solana, adl(helper3, vec![])).await.is_err());
+
+        send_tx(
+            solana,
+            PerpEditAdl {
+                group,
+                admin,
+                perp_market,
+                adl_enabled_opt: Some(true),
+                adl_min_score_opt: Some(1.0),
+            },
+        )
+        .await
+        .unwrap();
+
+        // below the minimum score
+        assert!(send_tx(solana, adl(helper2, vec![])).await.is_err());
+
+        // a position on the same side can't be used
+        let result = send_tx(
+            solana,
+            PerpAdlInstruction {
+                perp_market,
+                liqee: helper1,
+                counterparty: helper3,
+                candidates: vec![],
+                max_base_transfer: u64::MAX,
+            },
+        )
+        .await;
+        assert!(result.is_err());
+
+        // helper3 has a higher score than helper1, it must go first
+        assert!(send_tx(solana, adl(helper1, vec![helper3])).await.is_err());
+
+        send_tx(solana, adl(helper3, vec![helper1, helper2]))
+            .await
+            .unwrap();
+        let log = solana
+            .program_log_events::<mango_v4::logs::PerpAdlLog>()
+            .pop()
+            .unwrap();
+        assert_eq!(log.base_transfer_liqee, 1);
+        assert!(assert_equal(
+            I80F48::from_bits(log.bankruptcy_price),
+            bankruptcy_price,
+            0.001
+        ));
+        assert!(assert_equal(
+            I80F48::from_bits(log.adl_score),
+            75.0 / 55.0,
+            0.001
+        ));
+
+        let account_data = solana.get_account::<MangoAccount>(account).await;
+        assert_eq!(account_data.perps[0].base_position_lots(), -2);
+        let helper3_data = solana.get_account::<MangoAccount>(helper3).await;
+        assert_eq!(helper3_data.perps[0].base_position_lots(), 0);
+        // the helper gave up all of its 50 pnl
+        assert!(assert_equal(
+            helper3_data.perps[0].quote_position_native(),
+            -100.0 + 100.0 * bankruptcy_price,
+            0.01
+        ));
+
+        // the best remaining candidate is remembered
+        let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+        assert_eq!(perp_market_data.adl_next_long, helper1);
+
+        // nothing left to deleverage on helper3
+        assert!(send_tx(solana, adl(helper3, vec![helper1])).await.is_err());
+
+        send_tx(
+            solana,
+            PerpEditAdl {
+                group,
+                admin,
+                perp_market,
+                adl_enabled_opt: None,
+                adl_min_score_opt: Some(0.0),
+            },
+        )
+        .await
+        .unwrap();
+
+        // helper1 can't be skipped by leaving it out
+        assert!(send_tx(solana, adl(helper2, vec![])).await.is_err());
+
+        // adl waits for the insurance fund to be exhausted
+        fund_insurance(1).await;
+        assert!(send_tx(solana, adl(helper1, vec![helper2])).await.is_err());
+
+        // unless the market isn't covered by it
+        send_tx(
+            solana,
+            PerpEditGroupInsuranceFund {
+                group,
+                admin,
+                perp_market,
+                group_insurance_fund: false,
+            },
+        )
+        .await
+        .unwrap();
+
+        send_tx(solana, adl(helper1, vec![helper2])).await.unwrap();
+        let account_data = solana.get_account::<MangoAccount>(account).await;
+        assert_eq!(account_data.perps[0].base_position_lots(), -1);
+        let helper1_data = solana.get_account::<MangoAccount>(helper1).await;
+        assert_eq!(helper1_data.perps[0].base_position_lots(), 0);
+
+        send_tx(solana, adl(helper2, vec![])).await.unwrap();
+        let account_data = solana.get_account::<MangoAccount>(account).await;
+        assert_eq!(account_data.perps[0].base_position_lots(), 0);
+        let helper2_data = solana.get_account::<MangoAccount>(helper2).await;
+        assert_eq!(helper2_data.perps[0].base_position_lots(), 0);
+
+        // no socialized loss
+        let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+        assert_eq!(perp_market_data.long_funding, 0);
+        assert_eq!(perp_market_data.short_funding, 0);
+    }
+
     Ok(())
 }
@@ -483,3 +483,305 @@ async fn test_liq_tokens_with_token() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_liq_tokens_close_factor() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(85_000); // LiqTokenWithToken needs 79k
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let borrow_token = &tokens[0];
+    let collateral_token = &tokens[1];
+
+    let vault_account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    //
+    // SETUP: Make an account with some collateral and some borrows
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[1..2],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 350,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_mint_accounts[0],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Limit liquidation to 10% of the liab per 10s and make health go negative
+    //
+    send_tx(
+        solana,
+        GroupEditLiquidationCloseFactor {
+            group,
+            admin,
+            close_factor: 0.1,
+            window: 10,
+        },
+    )
+    .await
+    .unwrap();
+
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 2.0).await;
+
+    let liq_ix = || TokenLiqWithTokenInstruction {
+        liqee: account,
+        liqor: vault_account,
+        liqor_owner: owner,
+        asset_token_index: collateral_token.index,
+        liab_token_index: borrow_token.index,
+        asset_bank_index: 0,
+        liab_bank_index: 0,
+        max_liab_transfer: I80F48::from_num(10000.0),
+    };
+
+    //
+    // TEST: the first liquidation only transfers 10% of the liab
+    //
+    send_tx(solana, liq_ix()).await.unwrap();
+
+    // the liab is 350.175 including the loan origination fee, 10% of it rounded up is 36
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -350 + 36
+    );
+    let liqee = get_mango_account(solana, account).await;
+    assert!(liqee.being_liquidated());
+
+    //
+    // TEST: further liquidation in the same window fails
+    //
+    let result = send_tx(solana, liq_ix()).await;
+    assert_mango_error(
+        &result,
+        MangoError::LiquidationCloseFactorReached.into(),
+        "close factor reached".to_string(),
+    );
+
+    //
+    // TEST: after the window ends, another 10% may be liquidated
+    //
+    let now = solana.get_clock().await.unix_timestamp;
+    solana.advance_clock_to(now + 10).await;
+    send_tx(solana, liq_ix()).await.unwrap();
+
+    // 10% of 314.175 rounded up is 32
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -350 + 36 + 32
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_liq_tokens_self_liquidation() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let owner_token_accounts = &context.users[0].token_accounts[0..2];
+    let payer_mint_accounts = &context.users[1].token_accounts[0..2];
+
+    //
+    // SETUP: Create a group and an account to fill the vaults
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+    let borrow_token = &tokens[0];
+    let collateral_token = &tokens[1];
+
+    create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        100000,
+        0,
+    )
+    .await;
+
+    send_tx(
+        solana,
+        GroupEditSelfLiquidationFee {
+            group,
+            admin,
+            fee_fraction: 0.5,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Make an account with some collateral and some borrows
+    //
+    let account = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[1..2],
+        1000,
+        0,
+    )
+    .await;
+    send_tx(
+        solana,
+        TokenWithdrawInstruction {
+            amount: 350,
+            allow_borrow: true,
+            account,
+            owner,
+            token_account: payer_mint_accounts[0],
+            bank_index: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // SETUP: Change the oracle to make health go negative
+    //
+    set_bank_stub_oracle_price(solana, group, borrow_token, admin, 2.0).await;
+
+    // The owner sells collateral_amount collateral for liab_amount of the borrowed token.
+    // The swap is simulated by transfers to and from the payer's token accounts.
+    let send_self_liquidation_tx = |solana, collateral_amount, liab_amount| async move {
+        let mut tx = ClientTransaction::new(solana);
+        tx.add_instruction(FlashLoanSwapBeginInstruction {
+            account,
+            group,
+            owner,
+            input_bank: collateral_token.bank,
+            input_vault: collateral_token.vault,
+            input_token_account: owner_token_accounts[1],
+            output_bank: borrow_token.bank,
+            output_vault: borrow_token.vault,
+            output_token_account: owner_token_accounts[0],
+            withdraw_amount: collateral_amount,
+        })
+        .await;
+        tx.add_instruction_direct(
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &owner_token_accounts[1],
+                &payer_mint_accounts[1],
+                &owner.pubkey(),
+                &[&owner.pubkey()],
+                collateral_amount,
+            )
+            .unwrap(),
+        );
+        tx.add_instruction_direct(
+            spl_token::instruction::transfer(
+                &spl_token::ID,
+                &payer_mint_accounts[0],
+                &owner_token_accounts[0],
+                &payer.pubkey(),
+                &[&payer.pubkey()],
+                liab_amount,
+            )
+            .unwrap(),
+        );
+        tx.add_signer(payer);
+        tx.add_instruction(FlashLoanSwapEndInstruction {
+            account,
+            owner,
+            input_bank: collateral_token.bank,
+            input_vault: collateral_token.vault,
+            input_token_account: owner_token_accounts[1],
+            output_vault: borrow_token.vault,
+            output_token_account: owner_token_accounts[0],
+            flash_loan_type: mango_v4::accounts_ix::FlashLoanType::SelfLiquidation,
+        })
+        .await;
+        tx.send().await.map_err(TransportError::from)
+    };
+
+    //
+    // TEST: A self-liquidation that reduces health fails
+    //
+    let result = send_self_liquidation_tx(solana, 200, 10).await;
+    assert_mango_error(
+        &result,
+        MangoError::HealthMustBePositiveOrIncrease.into(),
+        "self-liquidation must improve health".to_string(),
+    );
+
+    //
+    // TEST: Selling 200 collateral for 100 liab improves health
+    //
+    send_self_liquidation_tx(solana, 200, 100).await.unwrap();
+
+    // the fee is 100 * 2% liquidation fee * 0.5 fee fraction = 1
+    assert_eq!(
+        account_position(solana, account, borrow_token.bank).await,
+        -350 + 99
+    );
+    assert_eq!(
+        account_position(solana, account, collateral_token.bank).await,
+        1000 - 200
+    );
+    let liqee = get_mango_account(solana, account).await;
+    assert!(liqee.being_liquidated());
+
+    Ok(())
+}
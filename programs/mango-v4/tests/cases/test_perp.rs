@@ -1055,6 +1055,263 @@ async fn test_perp_realize_partially() -> Result<(), TransportError> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_perp_referrer() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+
+    //
+    // SETUP: Create a group, accounts and a perp market
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let deposit_amount = 1000;
+    let maker = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        mints,
+        deposit_amount,
+        0,
+    )
+    .await;
+    let taker = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        mints,
+        deposit_amount,
+        0,
+    )
+    .await;
+    let own_referrer = create_funded_account(
+        &solana,
+        group,
+        owner,
+        2,
+        &context.users[1],
+        mints,
+        deposit_amount,
+        0,
+    )
+    .await;
+    let referrer_owner = context.users[1].key;
+    let referrer = create_funded_account(
+        &solana,
+        group,
+        referrer_owner,
+        0,
+        &context.users[1],
+        mints,
+        deposit_amount,
+        0,
+    )
+    .await;
+
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.975,
+            init_base_asset_weight: 0.95,
+            maint_base_liab_weight: 1.025,
+            init_base_liab_weight: 1.05,
+            base_liquidation_fee: 0.012,
+            maker_fee: 0.0000,
+            taker_fee: 0.01,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, &tokens[1]).await
+        },
+    )
+    .await
+    .unwrap();
+
+    send_tx(
+        solana,
+        PerpSetReferrerFeeShare {
+            group,
+            admin,
+            perp_market,
+            referrer_fee_share: 0.25,
+        },
+    )
+    .await
+    .unwrap();
+
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    let price_lots = perp_market_data.native_price_to_lot(I80F48::from(1000));
+    set_perp_stub_oracle_price(solana, group, perp_market, &tokens[1], admin, 1000.0).await;
+
+    let place_order = |account, side, client_order_id| PerpPlaceOrderInstruction {
+        account,
+        perp_market,
+        owner,
+        side,
+        price_lots,
+        max_base_lots: 1,
+        max_quote_lots: i64::MAX,
+        reduce_only: false,
+        client_order_id,
+    };
+
+    send_tx(solana, place_order(maker, Side::Bid, 1))
+        .await
+        .unwrap();
+
+    //
+    // TEST: The referrer needs a perp position in the market
+    //
+    assert!(send_tx(
+        solana,
+        PerpPlaceOrderWithReferrerInstruction {
+            place_order: place_order(taker, Side::Ask, 2),
+            referrer,
+        },
+    )
+    .await
+    .is_err());
+
+    // far away bids create the referrers' perp positions
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            price_lots: price_lots / 2,
+            owner: referrer_owner,
+            ..place_order(referrer, Side::Bid, 3)
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            price_lots: price_lots / 2,
+            ..place_order(own_referrer, Side::Bid, 3)
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: The referrer can't be controlled by the taker's owner
+    //
+    assert!(send_tx(
+        solana,
+        PerpPlaceOrderWithReferrerInstruction {
+            place_order: place_order(taker, Side::Ask, 2),
+            referrer: own_referrer,
+        },
+    )
+    .await
+    .is_err());
+
+    //
+    // TEST: Take the maker's bid with a referrer
+    //
+    send_tx(
+        solana,
+        PerpPlaceOrderWithReferrerInstruction {
+            place_order: place_order(taker, Side::Ask, 2),
+            referrer,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Without the referrer account the fill is consumed and the reward skipped
+    //
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![maker, taker],
+        },
+    )
+    .await
+    .unwrap();
+    let taker_perp = solana.get_account::<MangoAccount>(taker).await.perps[0];
+    assert_eq!(taker_perp.base_position_lots(), -1);
+    assert_eq!(taker_perp.taker_base_lots, 0);
+
+    let referrer_perp = solana.get_account::<MangoAccount>(referrer).await.perps[0];
+    assert_eq!(referrer_perp.quote_position_native(), 0);
+
+    // one lot at 1000 is 100_000 quote native, the taker fee is 1000
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    assert!(assert_equal(perp_market_data.fees_accrued, 1000.0, 0.001));
+
+    //
+    // TEST: With the referrer account the referrer receives its share of the taker fee
+    //
+    send_tx(solana, place_order(maker, Side::Bid, 4))
+        .await
+        .unwrap();
+    send_tx(
+        solana,
+        PerpPlaceOrderWithReferrerInstruction {
+            place_order: place_order(taker, Side::Ask, 5),
+            referrer,
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![maker, taker, referrer],
+        },
+    )
+    .await
+    .unwrap();
+
+    let taker_perp = solana.get_account::<MangoAccount>(taker).await.perps[0];
+    assert!(assert_equal(
+        taker_perp.quote_position_native(),
+        2.0 * (100_000.0 - 1000.0),
+        0.001
+    ));
+
+    let referrer_perp = solana.get_account::<MangoAccount>(referrer).await.perps[0];
+    assert!(assert_equal(
+        referrer_perp.quote_position_native(),
+        250.0,
+        0.001
+    ));
+    assert!((referrer_perp.cumulative_referrer_rewards - 250.0).abs() < 0.001);
+
+    let perp_market_data = solana.get_account::<PerpMarket>(perp_market).await;
+    assert!(assert_equal(
+        perp_market_data.fees_accrued,
+        1000.0 + 750.0,
+        0.001
+    ));
+
+    Ok(())
+}
+
 async fn assert_no_perp_orders(solana: &SolanaCookie, account_0: Pubkey) {
     let mango_account_0 = solana.get_account::<MangoAccount>(account_0).await;
 
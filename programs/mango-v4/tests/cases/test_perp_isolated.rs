@@ -0,0 +1,362 @@
+use super::*;
+
+#[tokio::test]
+async fn test_perp_isolated() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+
+    //
+    // SETUP: Create a group, perp market and accounts
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        zero_token_is_quote: true,
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let quote_token = &tokens[0];
+    let base_token = &tokens[1];
+
+    let liqor = create_funded_account(
+        &solana,
+        group,
+        owner,
+        250,
+        &context.users[1],
+        mints,
+        10000,
+        0,
+    )
+    .await;
+    let account_0 = create_funded_account(
+        &solana,
+        group,
+        owner,
+        0,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+    let account_1 = create_funded_account(
+        &solana,
+        group,
+        owner,
+        1,
+        &context.users[1],
+        &mints[0..1],
+        1000,
+        0,
+    )
+    .await;
+
+    let mango_v4::accounts::PerpCreateMarket { perp_market, .. } = send_tx(
+        solana,
+        PerpCreateMarketInstruction {
+            group,
+            admin,
+            payer,
+            perp_market_index: 0,
+            quote_lot_size: 10,
+            base_lot_size: 100,
+            maint_base_asset_weight: 0.8,
+            init_base_asset_weight: 0.6,
+            maint_base_liab_weight: 1.2,
+            init_base_liab_weight: 1.4,
+            base_liquidation_fee: 0.05,
+            maker_fee: 0.0,
+            taker_fee: 0.0,
+            settle_pnl_limit_factor: -1.0,
+            ..PerpCreateMarketInstruction::with_new_book_and_queue(&solana, base_token).await
+        },
+    )
+    .await
+    .unwrap();
+
+    let price_lots = {
+        let perp_market = solana.get_account::<PerpMarket>(perp_market).await;
+        perp_market.native_price_to_lot(I80F48::ONE)
+    };
+
+    //
+    // TEST: Accounts without the isolated perp section can't isolate
+    //
+    let result = send_tx(
+        solana,
+        PerpIsolatedDepositInstruction {
+            account: account_0,
+            owner,
+            perp_market,
+            settle_bank: quote_token.bank,
+            amount: 500,
+        },
+    )
+    .await;
+    assert_mango_error(
+        &result,
+        MangoError::NoFreeIsolatedPerpPositionIndex.into(),
+        "no isolated perp slots".to_string(),
+    );
+
+    send_tx(
+        solana,
+        AccountExpandV2Instruction {
+            account_num: 0,
+            group,
+            owner,
+            payer,
+            token_count: 16,
+            serum3_count: 8,
+            perp_count: 8,
+            perp_oo_count: 8,
+            isolated_perp_count: 2,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: Move collateral into the isolated position
+    //
+    send_tx(
+        solana,
+        PerpIsolatedDepositInstruction {
+            account: account_0,
+            owner,
+            perp_market,
+            settle_bank: quote_token.bank,
+            amount: 500,
+        },
+    )
+    .await
+    .unwrap();
+
+    let isolated_collateral = || async {
+        let account_data = get_mango_account(solana, account_0).await;
+        let bank_data: Bank = solana.get_account(quote_token.bank).await;
+        account_data
+            .isolated_perp_position(0)
+            .map(|p| p.native(&bank_data).to_num::<f64>())
+    };
+    assert_eq!(
+        account_position(solana, account_0, quote_token.bank).await,
+        500
+    );
+    assert!((isolated_collateral().await.unwrap() - 500.0).abs() < 0.01);
+    assert_eq!(account_init_health(solana, account_0).await.round(), 500.0);
+
+    //
+    // TEST: Trading is limited by the isolated collateral, not the account health
+    //
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: account_0,
+            perp_market,
+            owner,
+            side: Side::Bid,
+            price_lots,
+            max_base_lots: 10,
+            max_quote_lots: i64::MAX,
+            reduce_only: false,
+            client_order_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: account_1,
+            perp_market,
+            owner,
+            side: Side::Ask,
+            price_lots,
+            max_base_lots: 10,
+            max_quote_lots: i64::MAX,
+            reduce_only: false,
+            client_order_id: 0,
+        },
+    )
+    .await
+    .unwrap();
+    send_tx(
+        solana,
+        PerpConsumeEventsInstruction {
+            perp_market,
+            mango_accounts: vec![account_0, account_1],
+        },
+    )
+    .await
+    .unwrap();
+
+    // isolated init health: 500 + 10*100*(0.6-1) = 100, account health is unaffected
+    assert_eq!(account_init_health(solana, account_0).await.round(), 500.0);
+
+    // another 10 lots would bring the isolated init health to -300
+    let result = send_tx(
+        solana,
+        PerpPlaceOrderInstruction {
+            account: account_0,
+            perp_market,
+            owner,
+            side: Side::Bid,
+            price_lots,
+            max_base_lots: 10,
+            max_quote_lots: i64::MAX,
+            reduce_only: false,
+            client_order_id: 1,
+        },
+    )
+    .await;
+    assert_mango_error(
+        &result,
+        MangoError::HealthMustBePositiveOrIncrease.into(),
+        "isolated health limits trading".to_string(),
+    );
+
+    //
+    // TEST: Withdrawals must keep the isolated init health positive
+    //
+    let result = send_tx(
+        solana,
+        PerpIsolatedWithdrawInstruction {
+            account: account_0,
+            owner,
+            perp_market,
+            settle_bank: quote_token.bank,
+            amount: 200,
+        },
+    )
+    .await;
+    assert_mango_error(
+        &result,
+        MangoError::HealthMustBePositive.into(),
+        "isolated health after withdraw".to_string(),
+    );
+
+    send_tx(
+        solana,
+        PerpIsolatedWithdrawInstruction {
+            account: account_0,
+            owner,
+            perp_market,
+            settle_bank: quote_token.bank,
+            amount: 50,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        account_position(solana, account_0, quote_token.bank).await,
+        550
+    );
+    assert!((isolated_collateral().await.unwrap() - 450.0).abs() < 0.01);
+
+    //
+    // TEST: A price drop makes only the isolated position liquidatable
+    // isolated maint health: 450 + 600*0.8 - 1000 = -70
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.6).await;
+    assert_eq!(account_init_health(solana, account_0).await.round(), 550.0);
+
+    let result = send_tx(
+        solana,
+        PerpLiqBaseOrPositivePnlInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            max_base_transfer: i64::MAX,
+            max_pnl_transfer: 0,
+        },
+    )
+    .await;
+    assert!(result.is_err());
+
+    send_tx(
+        solana,
+        PerpLiqIsolatedInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            settle_bank: quote_token.bank,
+            max_base_transfer: 5,
+            max_pnl_transfer: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let liq_amount = 5.0 * 100.0 * 0.6 * (1.0 - 0.05);
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 5);
+    assert!(assert_equal(
+        liqor_data.perps[0].quote_position_native(),
+        -liq_amount,
+        0.1
+    ));
+    let liqee_data = solana.get_account::<MangoAccount>(account_0).await;
+    assert_eq!(liqee_data.perps[0].base_position_lots(), 5);
+    assert!(assert_equal(
+        liqee_data.perps[0].quote_position_native(),
+        -1000.0 + liq_amount,
+        0.1
+    ));
+    assert!(!liqee_data.being_liquidated());
+
+    //
+    // TEST: Liquidation beyond the collateral leads to isolated bankruptcy
+    // isolated maint health: 450 + 50*0.8 - 715 = -225
+    //
+    set_bank_stub_oracle_price(solana, group, base_token, admin, 0.1).await;
+
+    let liqor_quote_before = account_position(solana, liqor, quote_token.bank).await;
+    send_tx(
+        solana,
+        PerpLiqIsolatedInstruction {
+            liqor,
+            liqor_owner: owner,
+            liqee: account_0,
+            perp_market,
+            settle_bank: quote_token.bank,
+            max_base_transfer: i64::MAX,
+            max_pnl_transfer: u64::MAX,
+        },
+    )
+    .await
+    .unwrap();
+
+    let liqor_data = solana.get_account::<MangoAccount>(liqor).await;
+    assert_eq!(liqor_data.perps[0].base_position_lots(), 10);
+    assert_eq!(
+        account_position(solana, liqor, quote_token.bank).await,
+        liqor_quote_before + 450
+    );
+
+    // the isolated position was wiped, the rest of the account is untouched
+    let liqee_data = get_mango_account(solana, account_0).await;
+    let liqee_perp = liqee_data.perp_position(0).unwrap();
+    assert_eq!(liqee_perp.base_position_lots(), 0);
+    assert!(assert_equal(liqee_perp.quote_position_native(), 0.0, 0.1));
+    assert!(isolated_collateral().await.is_err());
+    assert_eq!(
+        account_position(solana, account_0, quote_token.bank).await,
+        550
+    );
+    assert_eq!(account_init_health(solana, account_0).await.round(), 550.0);
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use super::*;
 
 use mango_v4::accounts_ix::{Serum3OrderType, Serum3SelfTradeBehavior, Serum3Side};
-use mango_v4::{instructions::OpenOrdersSlim, serum3_cpi::load_open_orders_bytes};
+use mango_v4::instructions::{OpenOrdersAmounts, OpenOrdersSlim};
+use mango_v4::serum3_cpi::load_open_orders_bytes;
 use std::sync::Arc;
 
 struct SerumOrderPlacer {
@@ -82,6 +83,38 @@ impl SerumOrderPlacer {
             .await
     }
 
+    async fn reduce_only_order(
+        &mut self,
+        side: Serum3Side,
+        limit_price: f64,
+        max_base: u64,
+    ) -> Option<(u128, u64)> {
+        let client_order_id = self.inc_client_order_id();
+        send_tx(
+            &self.solana,
+            Serum3PlaceOrderV2Instruction {
+                v1: Serum3PlaceOrderInstruction {
+                    side,
+                    limit_price: (limit_price * 100.0 / 10.0) as u64, // in quote_lot (10) per base lot (100)
+                    max_base_qty: max_base / 100,                     // in base lot (100)
+                    max_native_quote_qty_including_fees: (limit_price * (max_base as f64)) as u64,
+                    self_trade_behavior: Serum3SelfTradeBehavior::AbortTransaction,
+                    order_type: Serum3OrderType::Limit,
+                    client_order_id,
+                    limit: 10,
+                    account: self.account,
+                    owner: self.owner,
+                    serum_market: self.serum_market,
+                },
+                reduce_only: true,
+            },
+        )
+        .await
+        .unwrap();
+        self.find_order_id_for_client_order_id(client_order_id)
+            .await
+    }
+
     async fn cancel(&self, order_id: u128) {
         let side = {
             let open_orders = self.serum.load_open_orders(self.open_orders).await;
@@ -802,6 +835,375 @@ async fn test_serum_settle_v2_to_account() -> Result<(), TransportError> {
     let account2_data = solana.get_account::<MangoAccount>(account2).await;
     assert_eq!(account2_data.buyback_fees_accrued_current, 0);
 
+    //
+    // TEST: Fills, fees and rebates are tracked on the serum3 orders
+    //
+    let serum3_orders = get_mango_account(solana, account)
+        .await
+        .serum3_orders(0)
+        .unwrap()
+        .clone();
+    assert_eq!(serum3_orders.base_sold_native, amount as u64);
+    assert_eq!(
+        serum3_orders.quote_received_native,
+        (amount - serum_taker_fee(amount)) as u64
+    );
+    assert_eq!(
+        serum3_orders.estimated_net_fees_native,
+        serum_taker_fee(amount)
+    );
+    assert_eq!(
+        serum3_orders.referrer_rebates_settled_native,
+        serum_referrer_fee(amount) as u64
+    );
+    assert_eq!(serum3_orders.base_bought_native, 0);
+
+    let serum3_orders2 = get_mango_account(solana, account2)
+        .await
+        .serum3_orders(0)
+        .unwrap()
+        .clone();
+    assert_eq!(serum3_orders2.base_bought_native, amount as u64);
+    assert_eq!(
+        serum3_orders2.quote_paid_native,
+        (amount - serum_maker_rebate(amount)) as u64
+    );
+    assert_eq!(
+        serum3_orders2.estimated_net_fees_native,
+        -serum_maker_rebate(amount)
+    );
+    assert_eq!(serum3_orders2.base_sold_native, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serum_settle_permissionless() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(120_000); // Serum3PlaceOrder needs 92.8k
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    //
+    // SETUP: Create a group, accounts, market etc
+    //
+    let deposit_amount = 160000;
+    let CommonSetup {
+        group_with_tokens,
+        serum_market_cookie,
+        quote_token,
+        base_token,
+        mut order_placer,
+        mut order_placer2,
+        ..
+    } = common_setup(&context, deposit_amount).await;
+    let quote_bank = quote_token.bank;
+    let base_bank = base_token.bank;
+    let account = order_placer.account;
+    let serum_market = order_placer.serum_market;
+
+    let settler_owner = context.users[1].key;
+    let settler = create_funded_account(
+        &solana,
+        group_with_tokens.group,
+        settler_owner,
+        0,
+        &context.users[1],
+        &context.mints[0..1],
+        deposit_amount,
+        0,
+    )
+    .await;
+
+    let settle_fee = 100;
+    send_tx(
+        solana,
+        Serum3SetSettleFeeInstruction {
+            group: group_with_tokens.group,
+            admin: group_with_tokens.admin,
+            serum_market,
+            settle_fee_flat: settle_fee as f32,
+            settle_fee_amount_threshold: 1000.0,
+            settle_rebates_to_account: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let serum_taker_fee = |amount: i64| (amount as f64 * 0.0004).trunc() as i64;
+    let serum_maker_rebate = |amount: i64| (amount as f64 * 0.0002).floor() as i64;
+    let serum_referrer_fee = |amount: i64| (amount as f64 * 0.0002).trunc() as i64;
+
+    let amount = 200000;
+    let quote_start = account_position(solana, account, quote_bank).await;
+    let base_start = account_position(solana, account, base_bank).await;
+    let settler_quote_start = account_position(solana, settler, quote_bank).await;
+
+    // account2 has an order on the book, account takes
+    order_placer2.bid(1.0, amount as u64).await.unwrap();
+    order_placer.ask(1.0, amount as u64).await.unwrap();
+
+    context
+        .serum
+        .consume_spot_events(
+            &serum_market_cookie,
+            &[order_placer.open_orders, order_placer2.open_orders],
+        )
+        .await;
+
+    //
+    // TEST: Permissionless settling has its own ix gate
+    //
+    send_tx(
+        solana,
+        IxGateSetInstruction {
+            group: group_with_tokens.group,
+            admin: group_with_tokens.admin,
+            ix_gate: 1 << IxGate::Serum3SettleFundsPermissionless as u128,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner,
+        },
+    )
+    .await
+    .is_err());
+    send_tx(
+        solana,
+        IxGateSetInstruction {
+            group: group_with_tokens.group,
+            admin: group_with_tokens.admin,
+            ix_gate: 0,
+        },
+    )
+    .await
+    .unwrap();
+
+    //
+    // TEST: The settler must be owned by the signer
+    //
+    assert!(send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner: order_placer.owner,
+        },
+    )
+    .await
+    .is_err());
+
+    //
+    // TEST: Settling moves the funds into the account and pays the settler
+    //
+    send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner,
+        },
+    )
+    .await
+    .unwrap();
+
+    let quote_end = account_position(solana, account, quote_bank).await;
+    let base_end = account_position(solana, account, base_bank).await;
+    let settler_quote_end = account_position(solana, settler, quote_bank).await;
+    assert!(base_end < base_start);
+    assert_eq!(
+        quote_start + amount - serum_taker_fee(amount) - settle_fee,
+        quote_end
+    );
+    assert_eq!(settler_quote_start + settle_fee, settler_quote_end);
+
+    let open_orders = order_placer._open_orders().await;
+    assert_eq!(open_orders.native_quote_free(), 0);
+    assert_eq!(open_orders.native_base_free(), 0);
+
+    //
+    // TEST: No fee when the settled amount is below the threshold
+    //
+    send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        account_position(solana, account, quote_bank).await,
+        quote_end
+    );
+    assert_eq!(
+        account_position(solana, settler, quote_bank).await,
+        settler_quote_end
+    );
+
+    //
+    // TEST: The fee is limited to the quote the settle credited to the account
+    //
+    // account buys base as the maker, only its rebate is settled in quote
+    let bid_amount = 10000;
+    order_placer.bid(1.0, bid_amount as u64).await.unwrap();
+    order_placer2.ask(1.0, bid_amount as u64).await.unwrap();
+    context
+        .serum
+        .consume_spot_events(
+            &serum_market_cookie,
+            &[order_placer.open_orders, order_placer2.open_orders],
+        )
+        .await;
+
+    send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner,
+        },
+    )
+    .await
+    .unwrap();
+    assert!(serum_maker_rebate(bid_amount) < settle_fee);
+    let quote_end = account_position(solana, account, quote_bank).await;
+    assert_eq!(
+        quote_end,
+        quote_start + amount - serum_taker_fee(amount) - settle_fee - bid_amount
+    );
+    let settler_quote_capped = account_position(solana, settler, quote_bank).await;
+    assert_eq!(
+        settler_quote_capped,
+        settler_quote_end + serum_maker_rebate(bid_amount)
+    );
+
+    //
+    // TEST: Referrer rebates can be credited to the settled account
+    //
+    send_tx(
+        solana,
+        Serum3SetSettleFeeInstruction {
+            group: group_with_tokens.group,
+            admin: group_with_tokens.admin,
+            serum_market,
+            settle_fee_flat: settle_fee as f32,
+            settle_fee_amount_threshold: 1000.0,
+            settle_rebates_to_account: true,
+        },
+    )
+    .await
+    .unwrap();
+
+    let ask_amount = 20000;
+    order_placer2.bid(1.0, ask_amount as u64).await.unwrap();
+    order_placer.ask(1.0, ask_amount as u64).await.unwrap();
+    context
+        .serum
+        .consume_spot_events(
+            &serum_market_cookie,
+            &[order_placer.open_orders, order_placer2.open_orders],
+        )
+        .await;
+
+    send_tx(
+        solana,
+        Serum3SettleFundsPermissionlessInstruction {
+            account,
+            serum_market,
+            settler,
+            settler_owner,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        account_position(solana, account, quote_bank).await,
+        quote_end + ask_amount - serum_taker_fee(ask_amount) + serum_referrer_fee(ask_amount)
+            - settle_fee
+    );
+    assert_eq!(
+        account_position(solana, settler, quote_bank).await,
+        settler_quote_capped + settle_fee
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_serum_reduce_only_orders() -> Result<(), TransportError> {
+    let mut test_builder = TestContextBuilder::new();
+    test_builder.test().set_compute_max_units(120_000); // reduce-only needs to load the base bank
+    let context = test_builder.start_default().await;
+    let solana = &context.solana.clone();
+
+    //
+    // SETUP: Create a group, accounts, market etc
+    //
+    let deposit_amount = 1000;
+    let CommonSetup {
+        base_token,
+        mut order_placer,
+        ..
+    } = common_setup(&context, deposit_amount).await;
+    let account = order_placer.account;
+
+    //
+    // TEST: A reduce-only bid can't increase the long base position
+    //
+    assert!(order_placer
+        .reduce_only_order(Serum3Side::Bid, 1.0, 500)
+        .await
+        .is_none());
+    assert_eq!(order_placer._open_orders().await.native_quote_reserved(), 0);
+
+    //
+    // TEST: A reduce-only ask is limited to the base position
+    //
+    order_placer
+        .reduce_only_order(Serum3Side::Ask, 2.0, 2000)
+        .await
+        .unwrap();
+    check_prev_instruction_post_health(&solana, account).await;
+    assert_eq!(
+        order_placer._open_orders().await.native_base_reserved(),
+        1000
+    );
+    assert_eq!(account_position(solana, account, base_token.bank).await, 0);
+
+    //
+    // TEST: The base reserved for the ask is considered sold, no further reduce-only asks
+    //
+    assert!(order_placer
+        .reduce_only_order(Serum3Side::Ask, 2.0, 500)
+        .await
+        .is_none());
+    assert_eq!(
+        order_placer._open_orders().await.native_base_reserved(),
+        1000
+    );
+
+    //
+    // TEST: Bids are still not allowed, the base in open orders counts as a long position
+    //
+    assert!(order_placer
+        .reduce_only_order(Serum3Side::Bid, 1.0, 500)
+        .await
+        .is_none());
+
     Ok(())
 }
 
@@ -97,3 +97,134 @@ async fn test_token_update_index_and_rate() -> Result<(), TransportError> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_token_claim_rewards() -> Result<(), TransportError> {
+    let context = TestContext::new().await;
+    let solana = &context.solana.clone();
+
+    let admin = TestKeypair::new();
+    let owner = context.users[0].key;
+    let payer = context.users[1].key;
+    let mints = &context.mints[0..2];
+    let rewards_mint = context.mints[2].pubkey;
+
+    //
+    // SETUP: Create a group with a rewards vault for the first token
+    //
+
+    let GroupWithTokens { group, tokens, .. } = GroupWithTokensConfig {
+        admin,
+        payer,
+        mints: mints.to_vec(),
+        ..GroupWithTokensConfig::default()
+    }
+    .create(solana)
+    .await;
+
+    let rewards_vault = solana.create_token_account(&group, rewards_mint).await;
+    solana
+        .process_transaction(
+            &[spl_token::instruction::transfer(
+                &spl_token::id(),
+                &context.users[1].token_accounts[2],
+                &rewards_vault,
+                &payer.pubkey(),
+                &[],
+                1_000_000,
+            )
+            .unwrap()],
+            Some(&[payer]),
+        )
+        .await
+        .unwrap();
+
+    send_tx(
+        solana,
+        TokenEditRewards {
+            group,
+            admin,
+            mint: mints[0].pubkey,
+            rewards_mint,
+            rewards_vault,
+            rewards_per_second: 100,
+            rewards_deposit_share: 1.0,
+        },
+    )
+    .await
+    .unwrap();
+
+    let account =
+        create_funded_account(&solana, group, owner, 0, &context.users[1], mints, 10000, 0).await;
+
+    //
+    // TEST: Rewards accrue to the only depositor and can be claimed
+    //
+
+    let bank_before = solana.get_account::<Bank>(tokens[0].bank).await;
+    solana.advance_clock().await;
+    send_tx(
+        solana,
+        TokenUpdateIndexAndRateInstruction {
+            mint_info: tokens[0].mint_info,
+        },
+    )
+    .await
+    .unwrap();
+    let bank_after = solana.get_account::<Bank>(tokens[0].bank).await;
+    let emitted = 100 * (bank_after.index_last_updated - bank_before.index_last_updated);
+    assert!(emitted > 0);
+    assert_eq!(bank_after.borrow_rewards_index, 0);
+
+    let rewards_account = context.users[0].token_accounts[2];
+    let balance_before = solana.token_account_balance(rewards_account).await;
+    send_tx(
+        solana,
+        TokenClaimRewardsInstruction {
+            account,
+            owner,
+            bank: tokens[0].bank,
+            token_account: rewards_account,
+        },
+    )
+    .await
+    .unwrap();
+    let claimed = solana.token_account_balance(rewards_account).await - balance_before;
+    assert!(claimed <= emitted && claimed + 1 >= emitted);
+    assert_eq!(
+        solana.token_account_balance(rewards_vault).await,
+        1_000_000 - claimed
+    );
+
+    // nothing left to claim
+    send_tx(
+        solana,
+        TokenClaimRewardsInstruction {
+            account,
+            owner,
+            bank: tokens[0].bank,
+            token_account: rewards_account,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        solana.token_account_balance(rewards_account).await,
+        balance_before + claimed
+    );
+
+    // banks without rewards can't be claimed from
+    assert!(send_tx(
+        solana,
+        TokenClaimRewardsInstruction {
+            account,
+            owner,
+            bank: tokens[1].bank,
+            token_account: rewards_account,
+        },
+    )
+    .await
+    .is_err());
+
+    Ok(())
+}
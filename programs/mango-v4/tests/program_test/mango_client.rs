@@ -304,6 +304,60 @@ async fn derive_liquidation_remaining_account_metas(
         .collect()
 }
 
+async fn derive_health_snapshot_remaining_account_metas(
+    account_loader: &impl ClientAccountLoader,
+    accounts: &[MangoAccountValue],
+) -> Vec<AccountMeta> {
+    let group = accounts[0].fixed.group;
+
+    let mut banks = vec![];
+    let mut oracles = vec![];
+    let token_indexes = accounts
+        .iter()
+        .flat_map(|a| a.active_token_positions())
+        .map(|ta| ta.token_index)
+        .unique()
+        .collect_vec();
+    for token_index in token_indexes {
+        let mint_info =
+            get_mint_info_by_token_index(account_loader, &accounts[0], token_index).await;
+        banks.push(mint_info.first_bank());
+        oracles.push(mint_info.oracle);
+    }
+
+    let perp_markets: Vec<Pubkey> = accounts
+        .iter()
+        .flat_map(|a| a.active_perp_positions())
+        .map(|perp| get_perp_market_address_by_index(group, perp.market_index))
+        .unique()
+        .collect();
+
+    let mut perp_oracles = vec![];
+    for &perp in &perp_markets {
+        perp_oracles.push(get_oracle_address_from_perp_market_address(account_loader, &perp).await)
+    }
+
+    let serum_oos = accounts
+        .iter()
+        .flat_map(|a| a.active_serum3_orders())
+        .map(|s| s.open_orders);
+
+    let to_account_meta = |pubkey| AccountMeta {
+        pubkey,
+        is_writable: false,
+        is_signer: false,
+    };
+
+    banks
+        .into_iter()
+        .chain(oracles.into_iter())
+        .chain(perp_markets.into_iter())
+        .chain(perp_oracles.into_iter())
+        .chain(serum_oos)
+        .map(to_account_meta)
+        .collect()
+}
+
 fn from_serum_style_pubkey(d: &[u64; 4]) -> Pubkey {
     Pubkey::new(bytemuck::cast_slice(d as &[_]))
 }
@@ -555,6 +609,141 @@ impl ClientInstruction for FlashLoanEndInstruction {
     }
 }
 
+/// Flash loan of the input token, to be swapped for the output token
+pub struct FlashLoanSwapBeginInstruction {
+    pub account: Pubkey,
+    pub group: Pubkey,
+    pub owner: TestKeypair,
+    pub input_bank: Pubkey,
+    pub input_vault: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_bank: Pubkey,
+    pub output_vault: Pubkey,
+    pub output_token_account: Pubkey,
+    pub withdraw_amount: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for FlashLoanSwapBeginInstruction {
+    type Accounts = mango_v4::accounts::FlashLoanBegin;
+    type Instruction = mango_v4::instruction::FlashLoanBegin;
+    async fn to_instruction(
+        &self,
+        _account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let accounts = Self::Accounts {
+            account: self.account,
+            owner: self.owner.pubkey(),
+            token_program: Token::id(),
+            instructions: solana_program::sysvar::instructions::id(),
+        };
+
+        let instruction = Self::Instruction {
+            loan_amounts: vec![self.withdraw_amount, 0],
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        for pubkey in [
+            self.input_bank,
+            self.output_bank,
+            self.input_vault,
+            self.output_vault,
+            self.input_token_account,
+            self.output_token_account,
+        ] {
+            instruction.accounts.push(AccountMeta {
+                pubkey,
+                is_writable: true,
+                is_signer: false,
+            });
+        }
+        instruction.accounts.push(AccountMeta {
+            pubkey: self.group,
+            is_writable: false,
+            is_signer: false,
+        });
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
+pub struct FlashLoanSwapEndInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub input_bank: Pubkey,
+    pub input_vault: Pubkey,
+    pub input_token_account: Pubkey,
+    pub output_vault: Pubkey,
+    pub output_token_account: Pubkey,
+    pub flash_loan_type: mango_v4::accounts_ix::FlashLoanType,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for FlashLoanSwapEndInstruction {
+    type Accounts = mango_v4::accounts::FlashLoanEnd;
+    type Instruction = mango_v4::instruction::FlashLoanEnd;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            flash_loan_type: self.flash_loan_type,
+        };
+
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+
+        // the output token is expected to have an active position already
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            &account_loader,
+            &account,
+            Some(self.input_bank),
+            true,
+            None,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            account: self.account,
+            owner: self.owner.pubkey(),
+            token_program: Token::id(),
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction.accounts.extend(health_check_metas.into_iter());
+        for pubkey in [
+            self.input_vault,
+            self.output_vault,
+            self.input_token_account,
+            self.output_token_account,
+        ] {
+            instruction.accounts.push(AccountMeta {
+                pubkey,
+                is_writable: true,
+                is_signer: false,
+            });
+        }
+        instruction.accounts.push(AccountMeta {
+            pubkey: account.fixed.group,
+            is_writable: false,
+            is_signer: false,
+        });
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
 pub struct TokenWithdrawInstruction {
     pub amount: u64,
     pub allow_borrow: bool,
@@ -626,6 +815,44 @@ impl ClientInstruction for TokenWithdrawInstruction {
     }
 }
 
+pub struct TokenClaimRewardsInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub bank: Pubkey,
+    pub token_account: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for TokenClaimRewardsInstruction {
+    type Accounts = mango_v4::accounts::TokenClaimRewards;
+    type Instruction = mango_v4::instruction::TokenClaimRewards;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let bank: Bank = account_loader.load(&self.bank).await.unwrap();
+
+        let accounts = Self::Accounts {
+            group: bank.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+            bank: self.bank,
+            rewards_vault: bank.rewards_vault,
+            token_account: self.token_account,
+            token_program: Token::id(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
 pub struct TokenDepositInstruction {
     pub amount: u64,
     pub reduce_only: bool,
@@ -1072,6 +1299,75 @@ fn token_edit_instruction_default() -> mango_v4::instruction::TokenEdit {
         reset_net_borrow_limit: false,
         reduce_only_opt: None,
         name_opt: None,
+        interest_curve_opt: None,
+        rewards_mint_opt: None,
+        rewards_vault_opt: None,
+        rewards_per_second_opt: None,
+        rewards_deposit_share_opt: None,
+        deposit_limit_opt: None,
+    }
+}
+
+pub struct TokenEditRewards {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub mint: Pubkey,
+
+    pub rewards_mint: Pubkey,
+    pub rewards_vault: Pubkey,
+    pub rewards_per_second: u64,
+    pub rewards_deposit_share: f32,
+}
+
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for TokenEditRewards {
+    type Accounts = mango_v4::accounts::TokenEdit;
+    type Instruction = mango_v4::instruction::TokenEdit;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let mint_info_key = Pubkey::find_program_address(
+            &[
+                b"MintInfo".as_ref(),
+                self.group.as_ref(),
+                self.mint.as_ref(),
+            ],
+            &program_id,
+        )
+        .0;
+        let mint_info: MintInfo = account_loader.load(&mint_info_key).await.unwrap();
+
+        let instruction = Self::Instruction {
+            rewards_mint_opt: Some(self.rewards_mint),
+            rewards_vault_opt: Some(self.rewards_vault),
+            rewards_per_second_opt: Some(self.rewards_per_second),
+            rewards_deposit_share_opt: Some(self.rewards_deposit_share),
+            ..token_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            mint_info: mint_info_key,
+            oracle: mint_info.oracle,
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction
+            .accounts
+            .extend(mint_info.banks().iter().map(|&k| AccountMeta {
+                pubkey: k,
+                is_signer: false,
+                is_writable: true,
+            }));
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
     }
 }
 
@@ -1517,6 +1813,13 @@ fn group_edit_instruction_default() -> mango_v4::instruction::GroupEdit {
         buyback_fees_swap_mango_account_opt: None,
         mngo_token_index_opt: None,
         buyback_fees_expiry_interval_opt: None,
+        perp_fee_tiers_opt: None,
+        liquidation_auction_duration_opt: None,
+        liquidation_auction_start_fraction_opt: None,
+        liquidation_auction_full_fee_depth_opt: None,
+        liquidation_close_factor_opt: None,
+        liquidation_close_factor_window_opt: None,
+        self_liquidation_fee_fraction_opt: None,
     }
 }
 
@@ -1558,22 +1861,25 @@ impl ClientInstruction for GroupEditFeeParameters {
     }
 }
 
-pub struct IxGateSetInstruction {
+pub struct GroupEditLiquidationCloseFactor {
     pub group: Pubkey,
     pub admin: TestKeypair,
-    pub ix_gate: u128,
+    pub close_factor: f32,
+    pub window: u64,
 }
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for IxGateSetInstruction {
-    type Accounts = mango_v4::accounts::IxGateSet;
-    type Instruction = mango_v4::instruction::IxGateSet;
+impl ClientInstruction for GroupEditLiquidationCloseFactor {
+    type Accounts = mango_v4::accounts::GroupEdit;
+    type Instruction = mango_v4::instruction::GroupEdit;
     async fn to_instruction(
         &self,
         _account_loader: impl ClientAccountLoader + 'async_trait,
     ) -> (Self::Accounts, instruction::Instruction) {
         let program_id = mango_v4::id();
         let instruction = Self::Instruction {
-            ix_gate: self.ix_gate,
+            liquidation_close_factor_opt: Some(self.close_factor),
+            liquidation_close_factor_window_opt: Some(self.window),
+            ..group_edit_instruction_default()
         };
 
         let accounts = Self::Accounts {
@@ -1590,34 +1896,28 @@ impl ClientInstruction for IxGateSetInstruction {
     }
 }
 
-pub struct GroupCloseInstruction {
-    pub admin: TestKeypair,
+pub struct GroupEditSelfLiquidationFee {
     pub group: Pubkey,
-    pub sol_destination: Pubkey,
+    pub admin: TestKeypair,
+    pub fee_fraction: f32,
 }
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for GroupCloseInstruction {
-    type Accounts = mango_v4::accounts::GroupClose;
-    type Instruction = mango_v4::instruction::GroupClose;
+impl ClientInstruction for GroupEditSelfLiquidationFee {
+    type Accounts = mango_v4::accounts::GroupEdit;
+    type Instruction = mango_v4::instruction::GroupEdit;
     async fn to_instruction(
         &self,
         _account_loader: impl ClientAccountLoader + 'async_trait,
     ) -> (Self::Accounts, instruction::Instruction) {
         let program_id = mango_v4::id();
-        let instruction = Self::Instruction {};
-
-        let insurance_vault = Pubkey::find_program_address(
-            &[b"InsuranceVault".as_ref(), self.group.as_ref()],
-            &program_id,
-        )
-        .0;
+        let instruction = Self::Instruction {
+            self_liquidation_fee_fraction_opt: Some(self.fee_fraction),
+            ..group_edit_instruction_default()
+        };
 
         let accounts = Self::Accounts {
             group: self.group,
             admin: self.admin.pubkey(),
-            insurance_vault,
-            sol_destination: self.sol_destination,
-            token_program: Token::id(),
         };
 
         let instruction = make_instruction(program_id, &accounts, instruction);
@@ -1629,15 +1929,86 @@ impl ClientInstruction for GroupCloseInstruction {
     }
 }
 
-pub struct AccountCreateInstruction {
-    pub account_num: u32,
-    pub token_count: u8,
-    pub serum3_count: u8,
-    pub perp_count: u8,
-    pub perp_oo_count: u8,
+pub struct IxGateSetInstruction {
     pub group: Pubkey,
-    pub owner: TestKeypair,
-    pub payer: TestKeypair,
+    pub admin: TestKeypair,
+    pub ix_gate: u128,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for IxGateSetInstruction {
+    type Accounts = mango_v4::accounts::IxGateSet;
+    type Instruction = mango_v4::instruction::IxGateSet;
+    async fn to_instruction(
+        &self,
+        _account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            ix_gate: self.ix_gate,
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct GroupCloseInstruction {
+    pub admin: TestKeypair,
+    pub group: Pubkey,
+    pub sol_destination: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for GroupCloseInstruction {
+    type Accounts = mango_v4::accounts::GroupClose;
+    type Instruction = mango_v4::instruction::GroupClose;
+    async fn to_instruction(
+        &self,
+        _account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let insurance_vault = Pubkey::find_program_address(
+            &[b"InsuranceVault".as_ref(), self.group.as_ref()],
+            &program_id,
+        )
+        .0;
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            insurance_vault,
+            sol_destination: self.sol_destination,
+            token_program: Token::id(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct AccountCreateInstruction {
+    pub account_num: u32,
+    pub token_count: u8,
+    pub serum3_count: u8,
+    pub perp_count: u8,
+    pub perp_oo_count: u8,
+    pub group: Pubkey,
+    pub owner: TestKeypair,
+    pub payer: TestKeypair,
 }
 #[async_trait::async_trait(?Send)]
 impl ClientInstruction for AccountCreateInstruction {
@@ -1739,6 +2110,62 @@ impl ClientInstruction for AccountExpandInstruction {
     }
 }
 
+pub struct AccountExpandV2Instruction {
+    pub account_num: u32,
+    pub group: Pubkey,
+    pub owner: TestKeypair,
+    pub payer: TestKeypair,
+    pub token_count: u8,
+    pub serum3_count: u8,
+    pub perp_count: u8,
+    pub perp_oo_count: u8,
+    pub isolated_perp_count: u8,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountExpandV2Instruction {
+    type Accounts = mango_v4::accounts::AccountExpand;
+    type Instruction = mango_v4::instruction::AccountExpandV2;
+    async fn to_instruction(
+        &self,
+        _account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = mango_v4::instruction::AccountExpandV2 {
+            token_count: self.token_count,
+            serum3_count: self.serum3_count,
+            perp_count: self.perp_count,
+            perp_oo_count: self.perp_oo_count,
+            isolated_perp_count: self.isolated_perp_count,
+        };
+
+        let account = Pubkey::find_program_address(
+            &[
+                b"MangoAccount".as_ref(),
+                self.group.as_ref(),
+                self.owner.pubkey().as_ref(),
+                &self.account_num.to_le_bytes(),
+            ],
+            &program_id,
+        )
+        .0;
+
+        let accounts = mango_v4::accounts::AccountExpand {
+            group: self.group,
+            account,
+            owner: self.owner.pubkey(),
+            payer: self.payer.pubkey(),
+            system_program: System::id(),
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner, self.payer]
+    }
+}
+
 pub struct AccountEditInstruction {
     pub account_num: u32,
     pub group: Pubkey,
@@ -2212,6 +2639,38 @@ impl ClientInstruction for Serum3PlaceOrderInstruction {
     }
 }
 
+pub struct Serum3PlaceOrderV2Instruction {
+    pub v1: Serum3PlaceOrderInstruction,
+    pub reduce_only: bool,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for Serum3PlaceOrderV2Instruction {
+    type Accounts = mango_v4::accounts::Serum3PlaceOrder;
+    type Instruction = mango_v4::instruction::Serum3PlaceOrderV2;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let (accounts, mut instruction) = self.v1.to_instruction(account_loader).await;
+        instruction.data = anchor_lang::InstructionData::data(&Self::Instruction {
+            side: self.v1.side,
+            limit_price: self.v1.limit_price,
+            max_base_qty: self.v1.max_base_qty,
+            max_native_quote_qty_including_fees: self.v1.max_native_quote_qty_including_fees,
+            self_trade_behavior: self.v1.self_trade_behavior,
+            order_type: self.v1.order_type,
+            client_order_id: self.v1.client_order_id,
+            limit: self.v1.limit,
+            reduce_only: self.reduce_only,
+        });
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        self.v1.signers()
+    }
+}
+
 pub struct Serum3CancelOrderInstruction {
     pub side: Serum3Side,
     pub order_id: u128,
@@ -2503,6 +2962,89 @@ impl ClientInstruction for Serum3SettleFundsV2Instruction {
     }
 }
 
+pub struct Serum3SettleFundsPermissionlessInstruction {
+    pub account: Pubkey,
+    pub serum_market: Pubkey,
+
+    pub settler: Pubkey,
+    pub settler_owner: TestKeypair,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for Serum3SettleFundsPermissionlessInstruction {
+    type Accounts = mango_v4::accounts::Serum3SettleFundsPermissionless;
+    type Instruction = mango_v4::instruction::Serum3SettleFundsPermissionless;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        // the account layout is the same as for the owner-signed settle, except
+        // that the settler owner signs in place of the account owner
+        let (v2_accounts, _) = Serum3SettleFundsV2Instruction {
+            account: self.account,
+            owner: self.settler_owner,
+            serum_market: self.serum_market,
+            fees_to_dao: true,
+        }
+        .to_instruction(account_loader)
+        .await;
+
+        let accounts = Self::Accounts {
+            v1: v2_accounts.v1,
+            v2: v2_accounts.v2,
+            settler: self.settler,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.settler_owner]
+    }
+}
+
+pub struct Serum3SetSettleFeeInstruction {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub serum_market: Pubkey,
+    pub settle_fee_flat: f32,
+    pub settle_fee_amount_threshold: f32,
+    pub settle_rebates_to_account: bool,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for Serum3SetSettleFeeInstruction {
+    type Accounts = mango_v4::accounts::Serum3EditMarket;
+    type Instruction = mango_v4::instruction::Serum3EditMarket;
+    async fn to_instruction(
+        &self,
+        _account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            reduce_only_opt: None,
+            settle_fee_flat_opt: Some(self.settle_fee_flat),
+            settle_fee_amount_threshold_opt: Some(self.settle_fee_amount_threshold),
+            settle_rebates_to_account_opt: Some(self.settle_rebates_to_account),
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            market: self.serum_market,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
 pub struct Serum3LiqForceCancelOrdersInstruction {
     pub account: Pubkey,
     pub serum_market: Pubkey,
@@ -2908,6 +3450,9 @@ fn perp_edit_instruction_default() -> mango_v4::instruction::PerpEditMarket {
         reset_stable_price: false,
         positive_pnl_liquidation_fee_opt: None,
         name_opt: None,
+        referrer_fee_share_opt: None,
+        adl_enabled_opt: None,
+        adl_min_score_opt: None,
     }
 }
 
@@ -2990,14 +3535,15 @@ impl ClientInstruction for PerpSetSettleLimitWindow {
     }
 }
 
-pub struct PerpMakeReduceOnly {
+pub struct PerpSetReferrerFeeShare {
     pub group: Pubkey,
     pub admin: TestKeypair,
     pub perp_market: Pubkey,
+    pub referrer_fee_share: f32,
 }
 
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for PerpMakeReduceOnly {
+impl ClientInstruction for PerpSetReferrerFeeShare {
     type Accounts = mango_v4::accounts::PerpEditMarket;
     type Instruction = mango_v4::instruction::PerpEditMarket;
     async fn to_instruction(
@@ -3009,7 +3555,7 @@ impl ClientInstruction for PerpMakeReduceOnly {
         let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
 
         let instruction = Self::Instruction {
-            reduce_only_opt: Some(true),
+            referrer_fee_share_opt: Some(self.referrer_fee_share),
             ..perp_edit_instruction_default()
         };
 
@@ -3029,16 +3575,14 @@ impl ClientInstruction for PerpMakeReduceOnly {
     }
 }
 
-pub struct PerpChangeWeights {
+pub struct PerpMakeReduceOnly {
     pub group: Pubkey,
     pub admin: TestKeypair,
     pub perp_market: Pubkey,
-    pub init_overall_asset_weight: f32,
-    pub maint_overall_asset_weight: f32,
 }
 
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for PerpChangeWeights {
+impl ClientInstruction for PerpMakeReduceOnly {
     type Accounts = mango_v4::accounts::PerpEditMarket;
     type Instruction = mango_v4::instruction::PerpEditMarket;
     async fn to_instruction(
@@ -3050,8 +3594,7 @@ impl ClientInstruction for PerpChangeWeights {
         let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
 
         let instruction = Self::Instruction {
-            init_overall_asset_weight_opt: Some(self.init_overall_asset_weight),
-            maint_overall_asset_weight_opt: Some(self.maint_overall_asset_weight),
+            reduce_only_opt: Some(true),
             ..perp_edit_instruction_default()
         };
 
@@ -3071,32 +3614,37 @@ impl ClientInstruction for PerpChangeWeights {
     }
 }
 
-pub struct PerpCloseMarketInstruction {
+pub struct PerpEditAdl {
+    pub group: Pubkey,
     pub admin: TestKeypair,
     pub perp_market: Pubkey,
-    pub sol_destination: Pubkey,
+    pub adl_enabled_opt: Option<bool>,
+    pub adl_min_score_opt: Option<f32>,
 }
+
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for PerpCloseMarketInstruction {
-    type Accounts = mango_v4::accounts::PerpCloseMarket;
-    type Instruction = mango_v4::instruction::PerpCloseMarket;
+impl ClientInstruction for PerpEditAdl {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
     async fn to_instruction(
         &self,
         account_loader: impl ClientAccountLoader + 'async_trait,
     ) -> (Self::Accounts, instruction::Instruction) {
         let program_id = mango_v4::id();
-        let instruction = Self::Instruction {};
+
         let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
 
+        let instruction = Self::Instruction {
+            adl_enabled_opt: self.adl_enabled_opt,
+            adl_min_score_opt: self.adl_min_score_opt,
+            ..perp_edit_instruction_default()
+        };
+
         let accounts = Self::Accounts {
-            group: perp_market.group,
+            group: self.group,
             admin: self.admin.pubkey(),
             perp_market: self.perp_market,
-            bids: perp_market.bids,
-            asks: perp_market.asks,
-            event_queue: perp_market.event_queue,
-            token_program: Token::id(),
-            sol_destination: self.sol_destination,
+            oracle: perp_market.oracle,
         };
 
         let instruction = make_instruction(program_id, &accounts, instruction);
@@ -3108,26 +3656,145 @@ impl ClientInstruction for PerpCloseMarketInstruction {
     }
 }
 
-pub struct PerpDeactivatePositionInstruction {
-    pub account: Pubkey,
+pub struct PerpEditGroupInsuranceFund {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
     pub perp_market: Pubkey,
-    pub owner: TestKeypair,
+    pub group_insurance_fund: bool,
 }
+
 #[async_trait::async_trait(?Send)]
-impl ClientInstruction for PerpDeactivatePositionInstruction {
-    type Accounts = mango_v4::accounts::PerpDeactivatePosition;
-    type Instruction = mango_v4::instruction::PerpDeactivatePosition;
+impl ClientInstruction for PerpEditGroupInsuranceFund {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
     async fn to_instruction(
         &self,
         account_loader: impl ClientAccountLoader + 'async_trait,
     ) -> (Self::Accounts, instruction::Instruction) {
         let program_id = mango_v4::id();
+
         let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
 
-        let instruction = Self::Instruction {};
-        let accounts = Self::Accounts {
-            group: perp_market.group,
-            account: self.account,
+        let instruction = Self::Instruction {
+            group_insurance_fund_opt: Some(self.group_insurance_fund),
+            ..perp_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct PerpChangeWeights {
+    pub group: Pubkey,
+    pub admin: TestKeypair,
+    pub perp_market: Pubkey,
+    pub init_overall_asset_weight: f32,
+    pub maint_overall_asset_weight: f32,
+}
+
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpChangeWeights {
+    type Accounts = mango_v4::accounts::PerpEditMarket;
+    type Instruction = mango_v4::instruction::PerpEditMarket;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let instruction = Self::Instruction {
+            init_overall_asset_weight_opt: Some(self.init_overall_asset_weight),
+            maint_overall_asset_weight_opt: Some(self.maint_overall_asset_weight),
+            ..perp_edit_instruction_default()
+        };
+
+        let accounts = Self::Accounts {
+            group: self.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct PerpCloseMarketInstruction {
+    pub admin: TestKeypair,
+    pub perp_market: Pubkey,
+    pub sol_destination: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpCloseMarketInstruction {
+    type Accounts = mango_v4::accounts::PerpCloseMarket;
+    type Instruction = mango_v4::instruction::PerpCloseMarket;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let accounts = Self::Accounts {
+            group: perp_market.group,
+            admin: self.admin.pubkey(),
+            perp_market: self.perp_market,
+            bids: perp_market.bids,
+            asks: perp_market.asks,
+            event_queue: perp_market.event_queue,
+            token_program: Token::id(),
+            sol_destination: self.sol_destination,
+        };
+
+        let instruction = make_instruction(program_id, &accounts, instruction);
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.admin]
+    }
+}
+
+pub struct PerpDeactivatePositionInstruction {
+    pub account: Pubkey,
+    pub perp_market: Pubkey,
+    pub owner: TestKeypair,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpDeactivatePositionInstruction {
+    type Accounts = mango_v4::accounts::PerpDeactivatePosition;
+    type Instruction = mango_v4::instruction::PerpDeactivatePosition;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+
+        let instruction = Self::Instruction {};
+        let accounts = Self::Accounts {
+            group: perp_market.group,
+            account: self.account,
             perp_market: self.perp_market,
             owner: self.owner.pubkey(),
         };
@@ -3208,6 +3875,33 @@ impl ClientInstruction for PerpPlaceOrderInstruction {
     }
 }
 
+/// PerpPlaceOrder with the referrer account appended after the health accounts
+pub struct PerpPlaceOrderWithReferrerInstruction {
+    pub place_order: PerpPlaceOrderInstruction,
+    pub referrer: Pubkey,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpPlaceOrderWithReferrerInstruction {
+    type Accounts = mango_v4::accounts::PerpPlaceOrder;
+    type Instruction = mango_v4::instruction::PerpPlaceOrder;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let (accounts, mut instruction) = self.place_order.to_instruction(account_loader).await;
+        instruction.accounts.push(AccountMeta {
+            pubkey: self.referrer,
+            is_signer: false,
+            is_writable: false,
+        });
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        self.place_order.signers()
+    }
+}
+
 pub struct PerpPlaceOrderPeggedInstruction {
     pub account: Pubkey,
     pub perp_market: Pubkey,
@@ -3773,6 +4467,236 @@ impl ClientInstruction for PerpLiqNegativePnlOrBankruptcyInstruction {
     }
 }
 
+pub struct PerpAdlInstruction {
+    pub perp_market: Pubkey,
+    pub liqee: Pubkey,
+    pub counterparty: Pubkey,
+    pub candidates: Vec<Pubkey>,
+    pub max_base_transfer: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpAdlInstruction {
+    type Accounts = mango_v4::accounts::PerpAdl;
+    type Instruction = mango_v4::instruction::PerpAdl;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            max_base_transfer: self.max_base_transfer,
+            num_candidates: self.candidates.len().try_into().unwrap(),
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let group: Group = account_loader.load(&perp_market.group).await.unwrap();
+        let mut accounts_data = vec![];
+        for address in [self.liqee, self.counterparty]
+            .iter()
+            .chain(self.candidates.iter())
+        {
+            accounts_data.push(account_loader.load_mango_account(address).await.unwrap());
+        }
+        let health_check_metas =
+            derive_health_snapshot_remaining_account_metas(&account_loader, &accounts_data).await;
+
+        let accounts = Self::Accounts {
+            group: perp_market.group,
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+            liqee: self.liqee,
+            counterparty: self.counterparty,
+            insurance_vault: group.insurance_vault,
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction
+            .accounts
+            .extend(self.candidates.iter().map(|&pubkey| AccountMeta {
+                pubkey,
+                is_writable: false,
+                is_signer: false,
+            }));
+        instruction.accounts.extend(health_check_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
+pub struct PerpIsolatedDepositInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub perp_market: Pubkey,
+    pub settle_bank: Pubkey,
+    pub amount: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpIsolatedDepositInstruction {
+    type Accounts = mango_v4::accounts::PerpIsolatedCollateral;
+    type Instruction = mango_v4::instruction::PerpIsolatedDeposit;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            amount: self.amount,
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let settle_bank: Bank = account_loader.load(&self.settle_bank).await.unwrap();
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            &account_loader,
+            &account,
+            None,
+            false,
+            Some(perp_market.perp_market_index),
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+            perp_market: self.perp_market,
+            settle_bank: self.settle_bank,
+            settle_oracle: settle_bank.oracle,
+        };
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction.accounts.extend(health_check_metas);
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
+pub struct PerpIsolatedWithdrawInstruction {
+    pub account: Pubkey,
+    pub owner: TestKeypair,
+    pub perp_market: Pubkey,
+    pub settle_bank: Pubkey,
+    pub amount: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpIsolatedWithdrawInstruction {
+    type Accounts = mango_v4::accounts::PerpIsolatedCollateral;
+    type Instruction = mango_v4::instruction::PerpIsolatedWithdraw;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            amount: self.amount,
+        };
+
+        let settle_bank: Bank = account_loader.load(&self.settle_bank).await.unwrap();
+        let account = account_loader
+            .load_mango_account(&self.account)
+            .await
+            .unwrap();
+        let health_check_metas = derive_health_check_remaining_account_metas(
+            &account_loader,
+            &account,
+            None,
+            false,
+            None,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: account.fixed.group,
+            account: self.account,
+            owner: self.owner.pubkey(),
+            perp_market: self.perp_market,
+            settle_bank: self.settle_bank,
+            settle_oracle: settle_bank.oracle,
+        };
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction.accounts.extend(health_check_metas);
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.owner]
+    }
+}
+
+pub struct PerpLiqIsolatedInstruction {
+    pub liqor: Pubkey,
+    pub liqor_owner: TestKeypair,
+    pub liqee: Pubkey,
+    pub perp_market: Pubkey,
+    pub settle_bank: Pubkey,
+    pub max_base_transfer: i64,
+    pub max_pnl_transfer: u64,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for PerpLiqIsolatedInstruction {
+    type Accounts = mango_v4::accounts::PerpLiqIsolated;
+    type Instruction = mango_v4::instruction::PerpLiqIsolated;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {
+            max_base_transfer: self.max_base_transfer,
+            max_pnl_transfer: self.max_pnl_transfer,
+        };
+
+        let perp_market: PerpMarket = account_loader.load(&self.perp_market).await.unwrap();
+        let liqor = account_loader
+            .load_mango_account(&self.liqor)
+            .await
+            .unwrap();
+        let liqee = account_loader
+            .load_mango_account(&self.liqee)
+            .await
+            .unwrap();
+        let health_check_metas = derive_liquidation_remaining_account_metas(
+            &account_loader,
+            &liqee,
+            &liqor,
+            TokenIndex::MAX,
+            0,
+            TokenIndex::MAX,
+            0,
+        )
+        .await;
+
+        let accounts = Self::Accounts {
+            group: perp_market.group,
+            perp_market: self.perp_market,
+            oracle: perp_market.oracle,
+            liqor: self.liqor,
+            liqor_owner: self.liqor_owner.pubkey(),
+            liqee: self.liqee,
+            settle_bank: self.settle_bank,
+        };
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction.accounts.extend(health_check_metas);
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![self.liqor_owner]
+    }
+}
+
 pub struct BenchmarkInstruction {}
 #[async_trait::async_trait(?Send)]
 impl ClientInstruction for BenchmarkInstruction {
@@ -3881,6 +4805,50 @@ impl ClientInstruction for ComputeAccountDataInstruction {
     }
 }
 
+pub struct AccountHealthSnapshotInstruction {
+    pub accounts: Vec<Pubkey>,
+}
+#[async_trait::async_trait(?Send)]
+impl ClientInstruction for AccountHealthSnapshotInstruction {
+    type Accounts = mango_v4::accounts::AccountHealthSnapshot;
+    type Instruction = mango_v4::instruction::AccountHealthSnapshot;
+    async fn to_instruction(
+        &self,
+        account_loader: impl ClientAccountLoader + 'async_trait,
+    ) -> (Self::Accounts, instruction::Instruction) {
+        let program_id = mango_v4::id();
+        let instruction = Self::Instruction {};
+
+        let mut accounts_data = vec![];
+        for account in &self.accounts {
+            accounts_data.push(account_loader.load_mango_account(account).await.unwrap());
+        }
+
+        let health_metas =
+            derive_health_snapshot_remaining_account_metas(&account_loader, &accounts_data).await;
+
+        let accounts = Self::Accounts {
+            group: accounts_data[0].fixed.group,
+        };
+
+        let mut instruction = make_instruction(program_id, &accounts, instruction);
+        instruction
+            .accounts
+            .extend(self.accounts.iter().map(|&pubkey| AccountMeta {
+                pubkey,
+                is_writable: false,
+                is_signer: false,
+            }));
+        instruction.accounts.extend(health_metas.into_iter());
+
+        (accounts, instruction)
+    }
+
+    fn signers(&self) -> Vec<TestKeypair> {
+        vec![]
+    }
+}
+
 pub struct HealthRegionBeginInstruction {
     pub account: Pubkey,
 }
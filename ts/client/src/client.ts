@@ -55,7 +55,12 @@ import { OPENBOOK_PROGRAM_ID } from './constants';
 import { Id } from './ids';
 import { IDL, MangoV4 } from './mango_v4';
 import { I80F48 } from './numbers/I80F48';
-import { FlashLoanType, InterestRateParams, OracleConfigParams } from './types';
+import {
+  FlashLoanType,
+  InterestRateParams,
+  OracleConfigParams,
+  PerpFeeTier,
+} from './types';
 import {
   I64_MAX_BN,
   U64_MAX_BN,
@@ -176,6 +181,13 @@ export class MangoClient {
     feesSwapMangoAccount?: PublicKey,
     feesMngoTokenIndex?: TokenIndex,
     feesExpiryInterval?: BN,
+    perpFeeTiers?: PerpFeeTier[],
+    liquidationAuctionDuration?: BN,
+    liquidationAuctionStartFraction?: number,
+    liquidationAuctionFullFeeDepth?: number,
+    liquidationCloseFactor?: number,
+    liquidationCloseFactorWindow?: BN,
+    selfLiquidationFeeFraction?: number,
   ): Promise<TransactionSignature> {
     const ix = await this.program.methods
       .groupEdit(
@@ -190,6 +202,13 @@ export class MangoClient {
         feesSwapMangoAccount ?? null,
         feesMngoTokenIndex ?? null,
         feesExpiryInterval ?? null,
+        perpFeeTiers ?? null,
+        liquidationAuctionDuration ?? null,
+        liquidationAuctionStartFraction ?? null,
+        liquidationAuctionFullFeeDepth ?? null,
+        liquidationCloseFactor ?? null,
+        liquidationCloseFactorWindow ?? null,
+        selfLiquidationFeeFraction ?? null,
       )
       .accounts({
         group: group.publicKey,
@@ -398,6 +417,14 @@ export class MangoClient {
         params.resetNetBorrowLimit ?? false,
         params.reduceOnly,
         params.name,
+        params.interestCurve,
+        params.rewardsMint,
+        params.rewardsVault,
+        params.rewardsPerSecond !== null
+          ? new BN(params.rewardsPerSecond)
+          : null,
+        params.rewardsDepositShare,
+        params.depositLimit !== null ? new BN(params.depositLimit) : null,
       )
       .accounts({
         group: group.publicKey,
@@ -1998,6 +2025,9 @@ export class MangoClient {
         params.resetStablePrice ?? false,
         params.positivePnlLiquidationFee,
         params.name,
+        params.referrerFeeShare,
+        params.adlEnabled,
+        params.adlMinScore,
       )
       .accounts({
         group: group.publicKey,
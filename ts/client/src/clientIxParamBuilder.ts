@@ -1,6 +1,10 @@
 import { BN } from '@coral-xyz/anchor';
 import { PublicKey } from '@solana/web3.js';
-import { InterestRateParams, OracleConfigParams } from './types';
+import {
+  InterestCurvePointParams,
+  InterestRateParams,
+  OracleConfigParams,
+} from './types';
 
 export interface TokenEditParams {
   oracle: PublicKey | null;
@@ -26,6 +30,12 @@ export interface TokenEditParams {
   resetNetBorrowLimit: boolean | null;
   reduceOnly: boolean | null;
   name: string | null;
+  interestCurve: InterestCurvePointParams[] | null;
+  rewardsMint: PublicKey | null;
+  rewardsVault: PublicKey | null;
+  rewardsPerSecond: number | null;
+  rewardsDepositShare: number | null;
+  depositLimit: number | null;
 }
 
 export const NullTokenEditParams: TokenEditParams = {
@@ -52,6 +62,12 @@ export const NullTokenEditParams: TokenEditParams = {
   resetNetBorrowLimit: null,
   reduceOnly: null,
   name: null,
+  interestCurve: null,
+  rewardsMint: null,
+  rewardsVault: null,
+  rewardsPerSecond: null,
+  rewardsDepositShare: null,
+  depositLimit: null,
 };
 
 export interface PerpEditParams {
@@ -84,6 +100,9 @@ export interface PerpEditParams {
   resetStablePrice: boolean | null;
   positivePnlLiquidationFee: number | null;
   name: string | null;
+  referrerFeeShare: number | null;
+  adlEnabled: boolean | null;
+  adlMinScore: number | null;
 }
 
 export const NullPerpEditParams: PerpEditParams = {
@@ -116,6 +135,9 @@ export const NullPerpEditParams: PerpEditParams = {
   resetStablePrice: null,
   positivePnlLiquidationFee: null,
   name: null,
+  referrerFeeShare: null,
+  adlEnabled: null,
+  adlMinScore: null,
 };
 
 // Use with TrueIxGateParams and buildIxGate
@@ -172,6 +194,13 @@ export interface IxGateParams {
   TokenUpdateIndexAndRate: boolean;
   TokenWithdraw: boolean;
   AccountBuybackFeesWithMngo: boolean;
+  TokenClaimRewards: boolean;
+  FlashLoanSelfLiquidation: boolean;
+  PerpAdl: boolean;
+  PerpIsolatedCollateral: boolean;
+  PerpLiqIsolated: boolean;
+  AccountHealthSnapshot: boolean;
+  Serum3SettleFundsPermissionless: boolean;
 }
 
 // Default with all ixs enabled, use with buildIxGate
@@ -228,6 +257,13 @@ export const TrueIxGateParams: IxGateParams = {
   TokenUpdateIndexAndRate: true,
   TokenWithdraw: true,
   AccountBuybackFeesWithMngo: true,
+  TokenClaimRewards: true,
+  FlashLoanSelfLiquidation: true,
+  PerpAdl: true,
+  PerpIsolatedCollateral: true,
+  PerpLiqIsolated: true,
+  AccountHealthSnapshot: true,
+  Serum3SettleFundsPermissionless: true,
 };
 
 // build ix gate e.g. buildIxGate(Builder(TrueIxGateParams).TokenDeposit(false).build()).toNumber(),
@@ -294,6 +330,13 @@ export function buildIxGate(p: IxGateParams): BN {
   toggleIx(ixGate, p, 'TokenUpdateIndexAndRate', 46);
   toggleIx(ixGate, p, 'TokenWithdraw', 47);
   toggleIx(ixGate, p, 'AccountBuybackFeesWithMngo', 48);
+  toggleIx(ixGate, p, 'TokenClaimRewards', 49);
+  toggleIx(ixGate, p, 'FlashLoanSelfLiquidation', 50);
+  toggleIx(ixGate, p, 'PerpAdl', 51);
+  toggleIx(ixGate, p, 'PerpIsolatedCollateral', 52);
+  toggleIx(ixGate, p, 'PerpLiqIsolated', 53);
+  toggleIx(ixGate, p, 'AccountHealthSnapshot', 54);
+  toggleIx(ixGate, p, 'Serum3SettleFundsPermissionless', 55);
 
   return ixGate;
 }
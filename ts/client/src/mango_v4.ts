@@ -174,6 +174,52 @@ export type MangoV4 = {
           "type": {
             "option": "u64"
           }
+        },
+        {
+          "name": "perpFeeTiersOpt",
+          "type": {
+            "option": {
+              "vec": {
+                "defined": "PerpFeeTier"
+              }
+            }
+          }
+        },
+        {
+          "name": "liquidationAuctionDurationOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "liquidationAuctionStartFractionOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationAuctionFullFeeDepthOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationCloseFactorOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationCloseFactorWindowOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "selfLiquidationFeeFractionOpt",
+          "type": {
+            "option": "f32"
+          }
         }
       ]
     },
@@ -723,6 +769,46 @@ export type MangoV4 = {
           "type": {
             "option": "string"
           }
+        },
+        {
+          "name": "interestCurveOpt",
+          "type": {
+            "option": {
+              "vec": {
+                "defined": "InterestCurvePointParams"
+              }
+            }
+          }
+        },
+        {
+          "name": "rewardsMintOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "rewardsVaultOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "rewardsPerSecondOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "rewardsDepositShareOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "depositLimitOpt",
+          "type": {
+            "option": "u64"
+          }
         }
       ]
     },
@@ -1034,6 +1120,58 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "accountExpandV2",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "payer",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "systemProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "tokenCount",
+          "type": "u8"
+        },
+        {
+          "name": "serum3Count",
+          "type": "u8"
+        },
+        {
+          "name": "perpCount",
+          "type": "u8"
+        },
+        {
+          "name": "perpOoCount",
+          "type": "u8"
+        },
+        {
+          "name": "isolatedPerpCount",
+          "type": "u8"
+        }
+      ]
+    },
     {
       "name": "accountEdit",
       "accounts": [
@@ -1473,6 +1611,47 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "tokenClaimRewards",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "bank",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "rewardsVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenAccount",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "flashLoanBegin",
       "accounts": [
@@ -1705,6 +1884,24 @@ export type MangoV4 = {
           "type": {
             "option": "bool"
           }
+        },
+        {
+          "name": "settleFeeFlatOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleFeeAmountThresholdOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleRebatesToAccountOpt",
+          "type": {
+            "option": "bool"
+          }
         }
       ]
     },
@@ -2010,7 +2207,11 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "serum3CancelOrder",
+      "name": "serum3PlaceOrderV2",
+      "docs": [
+        "Like Serum3PlaceOrder, but with `reduce_only` the order is reduced such that",
+        "the account's net base position can't grow in magnitude."
+      ],
       "accounts": [
         {
           "name": "group",
@@ -2061,87 +2262,235 @@ export type MangoV4 = {
           "name": "marketEventQueue",
           "isMut": true,
           "isSigner": false
-        }
-      ],
-      "args": [
-        {
-          "name": "side",
-          "type": {
-            "defined": "Serum3Side"
-          }
-        },
-        {
-          "name": "orderId",
-          "type": "u128"
-        }
-      ]
-    },
-    {
-      "name": "serum3CancelAllOrders",
-      "accounts": [
-        {
-          "name": "group",
-          "isMut": false,
-          "isSigner": false
         },
         {
-          "name": "account",
-          "isMut": false,
+          "name": "marketRequestQueue",
+          "isMut": true,
           "isSigner": false
         },
         {
-          "name": "owner",
-          "isMut": false,
-          "isSigner": true
-        },
-        {
-          "name": "openOrders",
+          "name": "marketBaseVault",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "serumMarket",
-          "isMut": false,
+          "name": "marketQuoteVault",
+          "isMut": true,
           "isSigner": false
         },
         {
-          "name": "serumProgram",
+          "name": "marketVaultSigner",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "needed for the automatic settle_funds call"
+          ]
         },
         {
-          "name": "serumMarketExternal",
+          "name": "payerBank",
           "isMut": true,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "The bank that pays for the order, if necessary"
+          ]
         },
         {
-          "name": "marketBids",
+          "name": "payerVault",
           "isMut": true,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "The bank vault that pays for the order, if necessary"
+          ]
         },
         {
-          "name": "marketAsks",
-          "isMut": true,
+          "name": "payerOracle",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "marketEventQueue",
-          "isMut": true,
+          "name": "tokenProgram",
+          "isMut": false,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "limit",
-          "type": "u8"
-        }
-      ]
-    },
-    {
-      "name": "serum3SettleFunds",
-      "docs": [
-        "Settles all free funds from the OpenOrders account into the MangoAccount.",
-        "",
+          "name": "side",
+          "type": {
+            "defined": "Serum3Side"
+          }
+        },
+        {
+          "name": "limitPrice",
+          "type": "u64"
+        },
+        {
+          "name": "maxBaseQty",
+          "type": "u64"
+        },
+        {
+          "name": "maxNativeQuoteQtyIncludingFees",
+          "type": "u64"
+        },
+        {
+          "name": "selfTradeBehavior",
+          "type": {
+            "defined": "Serum3SelfTradeBehavior"
+          }
+        },
+        {
+          "name": "orderType",
+          "type": {
+            "defined": "Serum3OrderType"
+          }
+        },
+        {
+          "name": "clientOrderId",
+          "type": "u64"
+        },
+        {
+          "name": "limit",
+          "type": "u16"
+        },
+        {
+          "name": "reduceOnly",
+          "type": "bool"
+        }
+      ]
+    },
+    {
+      "name": "serum3CancelOrder",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "openOrders",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarket",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumProgram",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarketExternal",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketBids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketAsks",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketEventQueue",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "side",
+          "type": {
+            "defined": "Serum3Side"
+          }
+        },
+        {
+          "name": "orderId",
+          "type": "u128"
+        }
+      ]
+    },
+    {
+      "name": "serum3CancelAllOrders",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "openOrders",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarket",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumProgram",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarketExternal",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketBids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketAsks",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketEventQueue",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "limit",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "serum3SettleFunds",
+      "docs": [
+        "Settles all free funds from the OpenOrders account into the MangoAccount.",
+        "",
         "Any serum \"referrer rebates\" (ui fees) are considered Mango fees."
       ],
       "accounts": [
@@ -2339,6 +2688,119 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "serum3SettleFundsPermissionless",
+      "docs": [
+        "Settles free funds into the MangoAccount's bank positions. Anyone may call this,",
+        "the caller's `settler` account may receive a fee."
+      ],
+      "accounts": [
+        {
+          "name": "v1",
+          "accounts": [
+            {
+              "name": "group",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "account",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "owner",
+              "isMut": false,
+              "isSigner": true
+            },
+            {
+              "name": "openOrders",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "serumMarket",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "serumProgram",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "serumMarketExternal",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketBaseVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketQuoteVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketVaultSigner",
+              "isMut": false,
+              "isSigner": false,
+              "docs": [
+                "needed for the automatic settle_funds call"
+              ]
+            },
+            {
+              "name": "quoteBank",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "quoteVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "baseBank",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "baseVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "tokenProgram",
+              "isMut": false,
+              "isSigner": false
+            }
+          ]
+        },
+        {
+          "name": "v2",
+          "accounts": [
+            {
+              "name": "quoteOracle",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "baseOracle",
+              "isMut": false,
+              "isSigner": false
+            }
+          ]
+        },
+        {
+          "name": "settler",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "serum3LiqForceCancelOrders",
       "accounts": [
@@ -3015,6 +3477,24 @@ export type MangoV4 = {
           "type": {
             "option": "string"
           }
+        },
+        {
+          "name": "referrerFeeShareOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "adlEnabledOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "adlMinScoreOpt",
+          "type": {
+            "option": "f32"
+          }
         }
       ]
     },
@@ -3562,45 +4042,207 @@ export type MangoV4 = {
       "name": "perpSettleFees",
       "accounts": [
         {
-          "name": "group",
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxSettleAmount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqBaseOrPositivePnl",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqForceCancelOrders",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "bids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "asks",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "limit",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqNegativePnlOrBankruptcy",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "oracle",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "perpMarket",
+          "name": "settleBank",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "account",
+          "name": "settleVault",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "oracle",
+          "name": "settleOracle",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "settleBank",
+          "name": "insuranceVault",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleOracle",
+          "name": "tokenProgram",
           "isMut": false,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "maxSettleAmount",
+          "name": "maxLiabTransfer",
           "type": "u64"
         }
       ]
     },
     {
-      "name": "perpLiqBaseOrPositivePnl",
+      "name": "perpAdl",
       "accounts": [
         {
           "name": "group",
@@ -3617,50 +4259,38 @@ export type MangoV4 = {
           "isMut": false,
           "isSigner": false
         },
-        {
-          "name": "liqor",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "liqorOwner",
-          "isMut": false,
-          "isSigner": true
-        },
         {
           "name": "liqee",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleBank",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "settleVault",
+          "name": "counterparty",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleOracle",
+          "name": "insuranceVault",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "Deleveraging only starts once the group insurance fund is exhausted"
+          ]
         }
       ],
       "args": [
         {
           "name": "maxBaseTransfer",
-          "type": "i64"
+          "type": "u64"
         },
         {
-          "name": "maxPnlTransfer",
-          "type": "u64"
+          "name": "numCandidates",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "perpLiqForceCancelOrders",
+      "name": "perpIsolatedDeposit",
       "accounts": [
         {
           "name": "group",
@@ -3672,31 +4302,36 @@ export type MangoV4 = {
           "isMut": true,
           "isSigner": false
         },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
         {
           "name": "perpMarket",
-          "isMut": true,
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "bids",
+          "name": "settleBank",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "asks",
-          "isMut": true,
+          "name": "settleOracle",
+          "isMut": false,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "limit",
-          "type": "u8"
+          "name": "amount",
+          "type": "u64"
         }
       ]
     },
     {
-      "name": "perpLiqNegativePnlOrBankruptcy",
+      "name": "perpIsolatedWithdraw",
       "accounts": [
         {
           "name": "group",
@@ -3704,59 +4339,84 @@ export type MangoV4 = {
           "isSigner": false
         },
         {
-          "name": "liqor",
+          "name": "account",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "liqorOwner",
+          "name": "owner",
           "isMut": false,
           "isSigner": true
         },
         {
-          "name": "liqee",
-          "isMut": true,
+          "name": "perpMarket",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "perpMarket",
+          "name": "settleBank",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "oracle",
+          "name": "settleOracle",
           "isMut": false,
           "isSigner": false
-        },
+        }
+      ],
+      "args": [
         {
-          "name": "settleBank",
-          "isMut": true,
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqIsolated",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "settleVault",
+          "name": "perpMarket",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleOracle",
+          "name": "oracle",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "insuranceVault",
+          "name": "liqor",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "tokenProgram",
+          "name": "liqorOwner",
           "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "maxLiabTransfer",
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
           "type": "u64"
         }
       ]
@@ -3840,6 +4500,17 @@ export type MangoV4 = {
       ],
       "args": []
     },
+    {
+      "name": "accountHealthSnapshot",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "benchmark",
       "docs": [
@@ -4125,12 +4796,111 @@ export type MangoV4 = {
             "name": "reduceOnly",
             "type": "u8"
           },
+          {
+            "name": "interestCurveLen",
+            "docs": [
+              "Number of points in interest_curve that are in use.",
+              "",
+              "If zero, the interest rate curve is defined by util0, rate0, util1, rate1 and max_rate."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                14
+              ]
+            }
+          },
+          {
+            "name": "interestCurve",
+            "docs": [
+              "Points of a piecewise-linear interest rate curve, starting from (0, 0)",
+              "",
+              "The utilization of the first point is the optimal utilization used for",
+              "rate adjustments, the last point is at 100% utilization."
+            ],
+            "type": {
+              "array": [
+                {
+                  "defined": "InterestCurvePoint"
+                },
+                8
+              ]
+            }
+          },
+          {
+            "name": "rewardsMint",
+            "docs": [
+              "Mint of the liquidity mining rewards, zero if no rewards are configured"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "rewardsVault",
+            "docs": [
+              "Token account of rewards_mint owned by the group, rewards are claimed from here"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "depositRewardsIndex",
+            "docs": [
+              "Accumulated rewards (in rewards_mint native) per indexed deposit"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "borrowRewardsIndex",
+            "docs": [
+              "Accumulated rewards (in rewards_mint native) per indexed borrow"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "rewardsPerSecond",
+            "docs": [
+              "Rewards emitted per second, in rewards_mint native units"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "rewardsDepositShare",
+            "docs": [
+              "Fraction of the emissions that goes to depositors, the rest goes to borrowers"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding2",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "depositLimit",
+            "docs": [
+              "Limit for the total native deposits of the token, set to 0 to disable",
+              "",
+              "Deposits that would make native_deposits() exceed this fail."
+            ],
+            "type": "u64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                2119
+                1728
               ]
             }
           }
@@ -4192,56 +4962,153 @@ export type MangoV4 = {
             "type": "u8"
           },
           {
-            "name": "buybackFees",
+            "name": "buybackFees",
+            "type": "u8"
+          },
+          {
+            "name": "buybackFeesMngoBonusFactor",
+            "type": "f32"
+          },
+          {
+            "name": "addressLookupTables",
+            "type": {
+              "array": [
+                "publicKey",
+                20
+              ]
+            }
+          },
+          {
+            "name": "securityAdmin",
+            "type": "publicKey"
+          },
+          {
+            "name": "depositLimitQuote",
+            "type": "u64"
+          },
+          {
+            "name": "ixGate",
+            "type": "u128"
+          },
+          {
+            "name": "buybackFeesSwapMangoAccount",
+            "type": "publicKey"
+          },
+          {
+            "name": "buybackFeesExpiryInterval",
+            "docs": [
+              "Number of seconds after which fees that could be used with the fees buyback feature expire.",
+              "",
+              "The actual expiry is staggered such that the fees users accumulate are always",
+              "available for at least this interval - but may be available for up to twice this time.",
+              "",
+              "When set to 0, there's no expiry of buyback fees."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "perpFeeTiers",
+            "docs": [
+              "Perp fee tiers by the account's perp volume over the last 30 days.",
+              "",
+              "Only the first perp_fee_tiers_count entries are in use, ordered by min_volume."
+            ],
+            "type": {
+              "array": [
+                {
+                  "defined": "PerpFeeTier"
+                },
+                8
+              ]
+            }
+          },
+          {
+            "name": "perpFeeTiersCount",
             "type": "u8"
           },
           {
-            "name": "buybackFeesMngoBonusFactor",
-            "type": "f32"
-          },
-          {
-            "name": "addressLookupTables",
+            "name": "padding2",
             "type": {
               "array": [
-                "publicKey",
-                20
+                "u8",
+                7
               ]
             }
           },
           {
-            "name": "securityAdmin",
-            "type": "publicKey"
-          },
-          {
-            "name": "depositLimitQuote",
+            "name": "liquidationAuctionDuration",
+            "docs": [
+              "Liquidation fee auction duration in seconds, 0 disables the auction.",
+              "",
+              "When enabled, liquidators of token and perp base positions only get a fraction",
+              "of the configured liquidation fees. See liquidation_fee_fraction()."
+            ],
             "type": "u64"
           },
           {
-            "name": "ixGate",
-            "type": "u128"
+            "name": "liquidationAuctionStartFraction",
+            "docs": [
+              "Fraction of the liquidation fees that applies when an account starts being liquidated"
+            ],
+            "type": "f32"
           },
           {
-            "name": "buybackFeesSwapMangoAccount",
-            "type": "publicKey"
+            "name": "liquidationAuctionFullFeeDepth",
+            "docs": [
+              "Maint health deficit, as a fraction of maint liabilities, at which the full",
+              "liquidation fees apply. 0 disables the depth component."
+            ],
+            "type": "f32"
           },
           {
-            "name": "buybackFeesExpiryInterval",
+            "name": "liquidationCloseFactor",
             "docs": [
-              "Number of seconds after which fees that could be used with the fees buyback feature expire.",
-              "",
-              "The actual expiry is staggered such that the fees users accumulate are always",
-              "available for at least this interval - but may be available for up to twice this time.",
+              "Maximum fraction of a liab token or perp base position that liquidators may",
+              "transfer within one liquidation_close_factor_window, 0 disables the limit.",
               "",
-              "When set to 0, there's no expiry of buyback fees."
+              "The fraction is relative to the position size at the start of the window."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding3",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "liquidationCloseFactorWindow",
+            "docs": [
+              "Length of the close factor window in seconds"
             ],
             "type": "u64"
           },
+          {
+            "name": "selfLiquidationFeeFraction",
+            "docs": [
+              "Fraction of the liab token's liquidation fee that accounts pay when they",
+              "liquidate themselves with a FlashLoanType::SelfLiquidation flash loan"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding4",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                1824
+                1648
               ]
             }
           }
@@ -4356,12 +5223,54 @@ export type MangoV4 = {
             ],
             "type": "u64"
           },
+          {
+            "name": "perpVolumeCurrent",
+            "docs": [
+              "Perp volume in quote native of the current and the previous window of",
+              "PERP_VOLUME_WINDOW seconds, for perp fee tiers."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumePrevious",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumeWindowEnd",
+            "docs": [
+              "End timestamp of the current perp volume window."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSince",
+            "docs": [
+              "Timestamp at which being_liquidated was last set, for liquidation fee auctions."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartTs",
+            "docs": [
+              "Liquidation close factor window for cancelling serum3 orders: start of the window,",
+              "the number of open serum3 orders at that time and the number cancelled since."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartOrders",
+            "type": "u32"
+          },
+          {
+            "name": "serum3LiqWindowCancelledOrders",
+            "type": "u32"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                208
+                160
               ]
             }
           },
@@ -4425,6 +5334,18 @@ export type MangoV4 = {
                 "defined": "PerpOpenOrder"
               }
             }
+          },
+          {
+            "name": "padding8",
+            "type": "u32"
+          },
+          {
+            "name": "isolatedPerps",
+            "type": {
+              "vec": {
+                "defined": "IsolatedPerpPosition"
+              }
+            }
           }
         ]
       }
@@ -4967,12 +5888,20 @@ export type MangoV4 = {
             ],
             "type": "u8"
           },
+          {
+            "name": "adlEnabled",
+            "docs": [
+              "If true, PerpAdl may close the base position of bankrupt accounts against the",
+              "most profitable and leveraged opposite-side positions at the bankruptcy price."
+            ],
+            "type": "u8"
+          },
           {
             "name": "padding4",
             "type": {
               "array": [
                 "u8",
-                7
+                6
               ]
             }
           },
@@ -4997,12 +5926,53 @@ export type MangoV4 = {
               "defined": "I80F48"
             }
           },
+          {
+            "name": "referrerFeeShare",
+            "docs": [
+              "Fraction of the taker fee that goes to the referrer of a taker order, if any."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding5",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "adlMinScore",
+            "docs": [
+              "Counterparties of PerpAdl need at least this adl score, see PerpPosition::adl_score()"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "adlNextLong",
+            "docs": [
+              "The best-ranked long position that the last PerpAdl against longs passed as a",
+              "candidate but didn't deleverage. Later PerpAdl calls against longs must pass it",
+              "as a candidate again, so the ranking can't be skipped by leaving it out."
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "adlNextShort",
+            "docs": [
+              "Like adl_next_long, for PerpAdl calls against shorts"
+            ],
+            "type": "publicKey"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                1888
+                1800
               ]
             }
           }
@@ -5077,12 +6047,36 @@ export type MangoV4 = {
             "name": "registrationTime",
             "type": "u64"
           },
+          {
+            "name": "settleFeeFlat",
+            "docs": [
+              "Fee paid by the account to the caller of Serum3SettleFundsPermissionless,",
+              "in $ native units (like USDC native)"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "settleFeeAmountThreshold",
+            "docs": [
+              "The fee is only charged when settling funds worth at least this amount,",
+              "in $ native units (like USDC native)"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "settleRebatesToAccount",
+            "docs": [
+              "Whether Serum3SettleFundsPermissionless credits serum referrer rebates to the",
+              "settled account instead of collecting them as fees, see Serum3SettleFundsV2"
+            ],
+            "type": "u8"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                128
+                119
               ]
             }
           }
@@ -5148,6 +6142,22 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "InterestCurvePointParams",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "util",
+            "type": "f32"
+          },
+          {
+            "name": "rate",
+            "type": "f32"
+          }
+        ]
+      }
+    },
     {
       "name": "Equity",
       "type": {
@@ -5420,6 +6430,35 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "IsolatedPerpInfo",
+      "docs": [
+        "A perp position that is margined in isolation, with its dedicated collateral.",
+        "",
+        "It does not contribute to the account's health. Instead its own health is",
+        "the sum of the collateral and the perp health contributions."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "perp",
+            "type": {
+              "defined": "PerpInfo"
+            }
+          },
+          {
+            "name": "collateral",
+            "docs": [
+              "Info for the perp market's settle token, balance_native is the isolated collateral"
+            ],
+            "type": {
+              "defined": "TokenInfo"
+            }
+          }
+        ]
+      }
+    },
     {
       "name": "HealthCache",
       "type": {
@@ -5449,6 +6488,14 @@ export type MangoV4 = {
               }
             }
           },
+          {
+            "name": "isolatedPerpInfos",
+            "type": {
+              "vec": {
+                "defined": "IsolatedPerpInfo"
+              }
+            }
+          },
           {
             "name": "beingLiquidated",
             "type": "bool"
@@ -5457,37 +6504,96 @@ export type MangoV4 = {
       }
     },
     {
-      "name": "FlashLoanTokenDetail",
+      "name": "FlashLoanTokenDetail",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "tokenIndex",
+            "type": "u16"
+          },
+          {
+            "name": "changeAmount",
+            "type": "i128"
+          },
+          {
+            "name": "loan",
+            "type": "i128"
+          },
+          {
+            "name": "loanOriginationFee",
+            "type": "i128"
+          },
+          {
+            "name": "depositIndex",
+            "type": "i128"
+          },
+          {
+            "name": "borrowIndex",
+            "type": "i128"
+          },
+          {
+            "name": "price",
+            "type": "i128"
+          }
+        ]
+      }
+    },
+    {
+      "name": "InterestCurvePoint",
+      "docs": [
+        "A point on a Bank's interest rate curve"
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "util",
+            "docs": [
+              "Utilization, between 0 and 1"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "rate",
+            "docs": [
+              "Borrow interest rate in APR at this utilization"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "PerpFeeTier",
+      "docs": [
+        "Perp fees for accounts that traded at least min_volume in the last 30 days"
+      ],
       "type": {
         "kind": "struct",
         "fields": [
           {
-            "name": "tokenIndex",
-            "type": "u16"
-          },
-          {
-            "name": "changeAmount",
-            "type": "i128"
-          },
-          {
-            "name": "loan",
-            "type": "i128"
-          },
-          {
-            "name": "loanOriginationFee",
-            "type": "i128"
-          },
-          {
-            "name": "depositIndex",
-            "type": "i128"
+            "name": "minVolume",
+            "docs": [
+              "Minimum perp volume of the account, in quote native (like USDC native)"
+            ],
+            "type": "u64"
           },
           {
-            "name": "borrowIndex",
-            "type": "i128"
+            "name": "makerFee",
+            "docs": [
+              "Fees for accounts in this tier. They only apply on markets where they",
+              "are lower than the market's own maker_fee and taker_fee."
+            ],
+            "type": "f32"
           },
           {
-            "name": "price",
-            "type": "i128"
+            "name": "takerFee",
+            "type": "f32"
           }
         ]
       }
@@ -5543,12 +6649,48 @@ export type MangoV4 = {
             "name": "cumulativeBorrowInterest",
             "type": "f64"
           },
+          {
+            "name": "previousRewardsIndex",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "unclaimedRewards",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "liqWindowStartTs",
+            "type": "u64"
+          },
+          {
+            "name": "liqWindowStartLiab",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "liqWindowLiquidated",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "cumulativeDeposits",
+            "type": "f64"
+          },
+          {
+            "name": "cumulativeWithdraws",
+            "type": "f64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                128
+                40
               ]
             }
           }
@@ -5595,23 +6737,78 @@ export type MangoV4 = {
             "name": "quoteTokenIndex",
             "type": "u16"
           },
+          {
+            "name": "fillsTracked",
+            "docs": [
+              "Set once last_seen_base_total and last_seen_quote_total are valid.",
+              "Older orders start tracking fills at the first settle or place order."
+            ],
+            "type": "u8"
+          },
           {
             "name": "padding",
             "type": {
               "array": [
                 "u8",
-                2
+                1
               ]
             }
           },
           {
-            "name": "reserved",
-            "type": {
-              "array": [
-                "u8",
-                64
-              ]
-            }
+            "name": "baseBoughtNative",
+            "docs": [
+              "Cumulative native base bought in fills and the native quote paid for it,",
+              "including taker fees and net of maker rebates"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "quotePaidNative",
+            "type": "u64"
+          },
+          {
+            "name": "baseSoldNative",
+            "docs": [
+              "Cumulative native base sold in fills and the native quote received for it,",
+              "net of taker fees and including maker rebates"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "quoteReceivedNative",
+            "type": "u64"
+          },
+          {
+            "name": "referrerRebatesSettledNative",
+            "docs": [
+              "Cumulative native quote referrer rebates that were settled out of the open orders",
+              "account, either to the account or to the DAO as fees"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "lastSeenBaseTotal",
+            "docs": [
+              "Open orders base and quote totals when they were last seen. Changes of the totals",
+              "that are not caused by deposits or withdrawals are fills."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "lastSeenQuoteTotal",
+            "type": "u64"
+          },
+          {
+            "name": "estimatedNetFeesNative",
+            "docs": [
+              "Estimate of the cumulative native quote taker fees paid minus maker rebates",
+              "received in fills.",
+              "",
+              "The open orders account doesn't report fees. They are derived from the quote",
+              "paid and received with the serum base fee tier rates, so the estimate is off",
+              "on markets with other rates, like stable markets."
+            ],
+            "type": "i64"
           }
         ]
       }
@@ -5833,12 +7030,38 @@ export type MangoV4 = {
               "defined": "I80F48"
             }
           },
+          {
+            "name": "cumulativeReferrerRewards",
+            "docs": [
+              "Cumulative share of taker fees received as the referrer of other accounts'",
+              "orders, in quote native units",
+              "",
+              "(Display only)"
+            ],
+            "type": "f64"
+          },
+          {
+            "name": "liqWindowStartTs",
+            "docs": [
+              "Liquidation close factor bookkeeping: start of the current window, the base",
+              "position at that time and the base lots liquidated since"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "liqWindowStartBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "liqWindowLiquidatedBaseLots",
+            "type": "i64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                88
+                56
               ]
             }
           }
@@ -5896,6 +7119,64 @@ export type MangoV4 = {
         ]
       }
     },
+    {
+      "name": "IsolatedPerpPosition",
+      "docs": [
+        "Dedicated collateral for a perp position that is margined in isolation.",
+        "",
+        "The PerpPosition for the market stays in the account's perp positions, but it",
+        "doesn't count toward the account's health. Instead its health is computed from",
+        "the position and this collateral alone, and liquidating it only touches this",
+        "collateral."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "marketIndex",
+            "docs": [
+              "The perp market the collateral is dedicated to, PerpMarketIndex::MAX if unused"
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "tokenIndex",
+            "docs": [
+              "The collateral token, always the perp market's settle token"
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "indexedCollateral",
+            "docs": [
+              "The collateral in deposit_index scaled units of the settle token bank.",
+              "Like a positive TokenPosition it earns deposit interest, but never rewards."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "reserved",
+            "type": {
+              "array": [
+                "u8",
+                40
+              ]
+            }
+          }
+        ]
+      }
+    },
     {
       "name": "MangoAccountFixed",
       "type": {
@@ -5975,12 +7256,40 @@ export type MangoV4 = {
             "name": "buybackFeesExpiryTimestamp",
             "type": "u64"
           },
+          {
+            "name": "perpVolumeCurrent",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumePrevious",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumeWindowEnd",
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSince",
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartTs",
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartOrders",
+            "type": "u32"
+          },
+          {
+            "name": "serum3LiqWindowCancelledOrders",
+            "type": "u32"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                208
+                160
               ]
             }
           }
@@ -6402,13 +7711,11 @@ export type MangoV4 = {
             "type": "publicKey"
           },
           {
-            "name": "padding2",
-            "type": {
-              "array": [
-                "u8",
-                32
-              ]
-            }
+            "name": "referrer",
+            "docs": [
+              "MangoAccount that receives a share of the taker fee, or the default pubkey"
+            ],
+            "type": "publicKey"
           },
           {
             "name": "makerTimestamp",
@@ -6698,6 +8005,9 @@ export type MangoV4 = {
           },
           {
             "name": "Swap"
+          },
+          {
+            "name": "SelfLiquidation"
           }
         ]
       }
@@ -6820,8 +8130,8 @@ export type MangoV4 = {
       "docs": [
         "Enum for lookup into ix gate",
         "note:",
-        "total ix files 56,",
-        "ix files included 48,",
+        "total ix files 61,",
+        "ix files included 53,",
         "ix files not included 8,",
         "- Benchmark,",
         "- ComputeAccountData,",
@@ -6981,6 +8291,27 @@ export type MangoV4 = {
           },
           {
             "name": "AccountBuybackFeesWithMngo"
+          },
+          {
+            "name": "TokenClaimRewards"
+          },
+          {
+            "name": "FlashLoanSelfLiquidation"
+          },
+          {
+            "name": "PerpAdl"
+          },
+          {
+            "name": "PerpIsolatedCollateral"
+          },
+          {
+            "name": "PerpLiqIsolated"
+          },
+          {
+            "name": "AccountHealthSnapshot"
+          },
+          {
+            "name": "Serum3SettleFundsPermissionless"
           }
         ]
       }
@@ -7284,34 +8615,69 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "quotePosition",
-          "type": "i128",
+          "name": "quotePosition",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "longSettledFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "shortSettledFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "longFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "shortFunding",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "TokenBalanceLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "longSettledFunding",
-          "type": "i128",
+          "name": "tokenIndex",
+          "type": "u16",
           "index": false
         },
         {
-          "name": "shortSettledFunding",
+          "name": "indexedPosition",
           "type": "i128",
           "index": false
         },
         {
-          "name": "longFunding",
+          "name": "depositIndex",
           "type": "i128",
           "index": false
         },
         {
-          "name": "shortFunding",
+          "name": "borrowIndex",
           "type": "i128",
           "index": false
         }
       ]
     },
     {
-      "name": "TokenBalanceLog",
+      "name": "TokenBalanceLogV2",
       "fields": [
         {
           "name": "mangoGroup",
@@ -7342,6 +8708,26 @@ export type MangoV4 = {
           "name": "borrowIndex",
           "type": "i128",
           "index": false
+        },
+        {
+          "name": "cumulativeDepositInterest",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeBorrowInterest",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeDeposits",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeWithdraws",
+          "type": "f64",
+          "index": false
         }
       ]
     },
@@ -7376,6 +8762,51 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "FlashLoanSelfLiquidationLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "assetTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liabTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liabRepaid",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "fee",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqEndHealthBefore",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liqEndHealthAfter",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "WithdrawLog",
       "fields": [
@@ -7621,6 +9052,146 @@ export type MangoV4 = {
         }
       ]
     },
+    {
+      "name": "FillLogV3",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "takerSide",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "makerSlot",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "makerOut",
+          "type": "bool",
+          "index": false
+        },
+        {
+          "name": "timestamp",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "seqNum",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "makerClientOrderId",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "makerFee",
+          "type": "f32",
+          "index": false
+        },
+        {
+          "name": "makerTimestamp",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "taker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "takerClientOrderId",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "takerFee",
+          "type": "f32",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quantity",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "makerRealizedPnl",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "makerEntryPricePerBaseLot",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "takerRealizedPnl",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "takerEntryPricePerBaseLot",
+          "type": "f64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpReferrerRewardLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "referrer",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "taker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "seqNum",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "reward",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "PerpUpdateFundingLog",
       "fields": [
@@ -7810,24 +9381,74 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "assetPrice",
-          "type": "i128",
+          "name": "assetPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liabPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "bankruptcy",
+          "type": "bool",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Serum3OpenOrdersBalanceLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "baseTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTotal",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseFree",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteTotal",
+          "type": "u64",
           "index": false
         },
         {
-          "name": "liabPrice",
-          "type": "i128",
+          "name": "quoteFree",
+          "type": "u64",
           "index": false
         },
         {
-          "name": "bankruptcy",
-          "type": "bool",
+          "name": "referrerRebatesAccrued",
+          "type": "u64",
           "index": false
         }
       ]
     },
     {
-      "name": "Serum3OpenOrdersBalanceLog",
+      "name": "Serum3OpenOrdersBalanceLogV2",
       "fields": [
         {
           "name": "mangoGroup",
@@ -7839,6 +9460,11 @@ export type MangoV4 = {
           "type": "publicKey",
           "index": false
         },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
         {
           "name": "baseTokenIndex",
           "type": "u16",
@@ -7877,7 +9503,7 @@ export type MangoV4 = {
       ]
     },
     {
-      "name": "Serum3OpenOrdersBalanceLogV2",
+      "name": "Serum3OpenOrdersBalanceLogV3",
       "fields": [
         {
           "name": "mangoGroup",
@@ -7928,6 +9554,76 @@ export type MangoV4 = {
           "name": "referrerRebatesAccrued",
           "type": "u64",
           "index": false
+        },
+        {
+          "name": "baseBought",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quotePaid",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseSold",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteReceived",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "estimatedNetFees",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "referrerRebatesSettled",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Serum3SettleFundsPermissionlessLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settler",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "settledBase",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "settledQuote",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "fee",
+          "type": "i128",
+          "index": false
         }
       ]
     },
@@ -8292,34 +9988,214 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "socializedLoss",
-          "type": "i128",
+          "name": "socializedLoss",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "startingShortFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingLongFunding",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "endingShortFunding",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpAdlLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "counterparty",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTransferLiqee",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqee",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "bankruptcyPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "adlScore",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqNegativePnlOrBankruptcyLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settlement",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpSettlePnlLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccountA",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccountB",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settlement",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "settler",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "fee",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpSettleFeesLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "settlement",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "AccountBuybackFeesWithMngoLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "startingLongFunding",
+          "name": "buybackFees",
           "type": "i128",
           "index": false
         },
         {
-          "name": "startingShortFunding",
+          "name": "buybackMngo",
           "type": "i128",
           "index": false
         },
         {
-          "name": "endingLongFunding",
+          "name": "mngoBuybackPrice",
           "type": "i128",
           "index": false
         },
         {
-          "name": "endingShortFunding",
+          "name": "oraclePrice",
           "type": "i128",
           "index": false
         }
       ]
     },
     {
-      "name": "PerpLiqNegativePnlOrBankruptcyLog",
+      "name": "TokenClaimRewardsLog",
       "fields": [
         {
           "name": "mangoGroup",
@@ -8327,29 +10203,29 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "liqee",
+          "name": "mangoAccount",
           "type": "publicKey",
           "index": false
         },
         {
-          "name": "liqor",
-          "type": "publicKey",
+          "name": "tokenIndex",
+          "type": "u16",
           "index": false
         },
         {
-          "name": "perpMarketIndex",
-          "type": "u16",
+          "name": "rewardsMint",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "settlement",
-          "type": "i128",
+          "name": "amount",
+          "type": "u64",
           "index": false
         }
       ]
     },
     {
-      "name": "PerpSettlePnlLog",
+      "name": "PerpIsolatedCollateralLog",
       "fields": [
         {
           "name": "mangoGroup",
@@ -8357,39 +10233,39 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "mangoAccountA",
+          "name": "mangoAccount",
           "type": "publicKey",
           "index": false
         },
         {
-          "name": "mangoAccountB",
-          "type": "publicKey",
+          "name": "perpMarketIndex",
+          "type": "u16",
           "index": false
         },
         {
-          "name": "perpMarketIndex",
+          "name": "tokenIndex",
           "type": "u16",
           "index": false
         },
         {
-          "name": "settlement",
+          "name": "change",
           "type": "i128",
           "index": false
         },
         {
-          "name": "settler",
-          "type": "publicKey",
+          "name": "indexedCollateral",
+          "type": "i128",
           "index": false
         },
         {
-          "name": "fee",
+          "name": "depositIndex",
           "type": "i128",
           "index": false
         }
       ]
     },
     {
-      "name": "PerpSettleFeesLog",
+      "name": "PerpLiqIsolatedLog",
       "fields": [
         {
           "name": "mangoGroup",
@@ -8397,24 +10273,49 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "mangoAccount",
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
           "type": "publicKey",
           "index": false
         },
         {
-          "name": "perpMarketIndex",
-          "type": "u16",
+          "name": "liqee",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "settlement",
+          "name": "baseTransfer",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "collateralTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "bankruptcyLoss",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "price",
           "type": "i128",
           "index": false
         }
       ]
     },
     {
-      "name": "AccountBuybackFeesWithMngoLog",
+      "name": "AccountHealthSnapshotLog",
       "fields": [
         {
           "name": "mangoGroup",
@@ -8427,24 +10328,29 @@ export type MangoV4 = {
           "index": false
         },
         {
-          "name": "buybackFees",
+          "name": "initHealth",
           "type": "i128",
           "index": false
         },
         {
-          "name": "buybackMngo",
+          "name": "maintHealth",
           "type": "i128",
           "index": false
         },
         {
-          "name": "mngoBuybackPrice",
+          "name": "liquidationEndHealth",
           "type": "i128",
           "index": false
         },
         {
-          "name": "oraclePrice",
+          "name": "equity",
           "type": "i128",
           "index": false
+        },
+        {
+          "name": "beingLiquidated",
+          "type": "bool",
+          "index": false
         }
       ]
     }
@@ -8674,6 +10580,36 @@ export type MangoV4 = {
       "code": 6044,
       "name": "PerpOrderIdNotFound",
       "msg": "perp order id not found on the orderbook"
+    },
+    {
+      "code": 6045,
+      "name": "HealthRegionBadInnerInstruction",
+      "msg": "HealthRegions allow only specific instructions between Begin and End"
+    },
+    {
+      "code": 6046,
+      "name": "BankDepositLimit",
+      "msg": "deposit crosses the token's deposit limit"
+    },
+    {
+      "code": 6047,
+      "name": "LiquidationCloseFactorReached",
+      "msg": "the liquidation close factor limit for this window has been reached"
+    },
+    {
+      "code": 6048,
+      "name": "NoFreeIsolatedPerpPositionIndex",
+      "msg": "no free isolated perp position index"
+    },
+    {
+      "code": 6049,
+      "name": "IsolatedPerpPositionDoesNotExist",
+      "msg": "isolated perp position does not exist"
+    },
+    {
+      "code": 6050,
+      "name": "PerpPositionIsIsolated",
+      "msg": "the perp position is margined in isolation"
     }
   ]
 };
@@ -8854,6 +10790,52 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "u64"
           }
+        },
+        {
+          "name": "perpFeeTiersOpt",
+          "type": {
+            "option": {
+              "vec": {
+                "defined": "PerpFeeTier"
+              }
+            }
+          }
+        },
+        {
+          "name": "liquidationAuctionDurationOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "liquidationAuctionStartFractionOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationAuctionFullFeeDepthOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationCloseFactorOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "liquidationCloseFactorWindowOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "selfLiquidationFeeFractionOpt",
+          "type": {
+            "option": "f32"
+          }
         }
       ]
     },
@@ -9403,6 +11385,46 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "string"
           }
+        },
+        {
+          "name": "interestCurveOpt",
+          "type": {
+            "option": {
+              "vec": {
+                "defined": "InterestCurvePointParams"
+              }
+            }
+          }
+        },
+        {
+          "name": "rewardsMintOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "rewardsVaultOpt",
+          "type": {
+            "option": "publicKey"
+          }
+        },
+        {
+          "name": "rewardsPerSecondOpt",
+          "type": {
+            "option": "u64"
+          }
+        },
+        {
+          "name": "rewardsDepositShareOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "depositLimitOpt",
+          "type": {
+            "option": "u64"
+          }
         }
       ]
     },
@@ -9714,6 +11736,58 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "accountExpandV2",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "payer",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "systemProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "tokenCount",
+          "type": "u8"
+        },
+        {
+          "name": "serum3Count",
+          "type": "u8"
+        },
+        {
+          "name": "perpCount",
+          "type": "u8"
+        },
+        {
+          "name": "perpOoCount",
+          "type": "u8"
+        },
+        {
+          "name": "isolatedPerpCount",
+          "type": "u8"
+        }
+      ]
+    },
     {
       "name": "accountEdit",
       "accounts": [
@@ -10151,7 +12225,48 @@ export const IDL: MangoV4 = {
           "name": "allowBorrow",
           "type": "bool"
         }
-      ]
+      ]
+    },
+    {
+      "name": "tokenClaimRewards",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "bank",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "rewardsVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenAccount",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
     },
     {
       "name": "flashLoanBegin",
@@ -10385,6 +12500,24 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "bool"
           }
+        },
+        {
+          "name": "settleFeeFlatOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleFeeAmountThresholdOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "settleRebatesToAccountOpt",
+          "type": {
+            "option": "bool"
+          }
         }
       ]
     },
@@ -10689,6 +12822,158 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "serum3PlaceOrderV2",
+      "docs": [
+        "Like Serum3PlaceOrder, but with `reduce_only` the order is reduced such that",
+        "the account's net base position can't grow in magnitude."
+      ],
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "openOrders",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarket",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumProgram",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "serumMarketExternal",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketBids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketAsks",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketEventQueue",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketRequestQueue",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketBaseVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketQuoteVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "marketVaultSigner",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "needed for the automatic settle_funds call"
+          ]
+        },
+        {
+          "name": "payerBank",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The bank that pays for the order, if necessary"
+          ]
+        },
+        {
+          "name": "payerVault",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "The bank vault that pays for the order, if necessary"
+          ]
+        },
+        {
+          "name": "payerOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "side",
+          "type": {
+            "defined": "Serum3Side"
+          }
+        },
+        {
+          "name": "limitPrice",
+          "type": "u64"
+        },
+        {
+          "name": "maxBaseQty",
+          "type": "u64"
+        },
+        {
+          "name": "maxNativeQuoteQtyIncludingFees",
+          "type": "u64"
+        },
+        {
+          "name": "selfTradeBehavior",
+          "type": {
+            "defined": "Serum3SelfTradeBehavior"
+          }
+        },
+        {
+          "name": "orderType",
+          "type": {
+            "defined": "Serum3OrderType"
+          }
+        },
+        {
+          "name": "clientOrderId",
+          "type": "u64"
+        },
+        {
+          "name": "limit",
+          "type": "u16"
+        },
+        {
+          "name": "reduceOnly",
+          "type": "bool"
+        }
+      ]
+    },
     {
       "name": "serum3CancelOrder",
       "accounts": [
@@ -10879,38 +13164,151 @@ export const IDL: MangoV4 = {
           ]
         },
         {
-          "name": "quoteBank",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "quoteVault",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "baseBank",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "baseVault",
-          "isMut": true,
-          "isSigner": false
-        },
-        {
-          "name": "tokenProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "quoteBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "quoteVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "baseBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "baseVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "serum3SettleFundsV2",
+      "docs": [
+        "Like Serum3SettleFunds, but `fees_to_dao` determines if referrer rebates are considered fees",
+        "or are credited to the MangoAccount."
+      ],
+      "accounts": [
+        {
+          "name": "v1",
+          "accounts": [
+            {
+              "name": "group",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "account",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "owner",
+              "isMut": false,
+              "isSigner": true
+            },
+            {
+              "name": "openOrders",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "serumMarket",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "serumProgram",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "serumMarketExternal",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketBaseVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketQuoteVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "marketVaultSigner",
+              "isMut": false,
+              "isSigner": false,
+              "docs": [
+                "needed for the automatic settle_funds call"
+              ]
+            },
+            {
+              "name": "quoteBank",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "quoteVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "baseBank",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "baseVault",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "tokenProgram",
+              "isMut": false,
+              "isSigner": false
+            }
+          ]
+        },
+        {
+          "name": "v2",
+          "accounts": [
+            {
+              "name": "quoteOracle",
+              "isMut": false,
+              "isSigner": false
+            },
+            {
+              "name": "baseOracle",
+              "isMut": false,
+              "isSigner": false
+            }
+          ]
         }
       ],
-      "args": []
+      "args": [
+        {
+          "name": "feesToDao",
+          "type": "bool"
+        }
+      ]
     },
     {
-      "name": "serum3SettleFundsV2",
+      "name": "serum3SettleFundsPermissionless",
       "docs": [
-        "Like Serum3SettleFunds, but `fees_to_dao` determines if referrer rebates are considered fees",
-        "or are credited to the MangoAccount."
+        "Settles free funds into the MangoAccount's bank positions. Anyone may call this,",
+        "the caller's `settler` account may receive a fee."
       ],
       "accounts": [
         {
@@ -11010,14 +13408,14 @@ export const IDL: MangoV4 = {
               "isSigner": false
             }
           ]
-        }
-      ],
-      "args": [
+        },
         {
-          "name": "feesToDao",
-          "type": "bool"
+          "name": "settler",
+          "isMut": true,
+          "isSigner": false
         }
-      ]
+      ],
+      "args": []
     },
     {
       "name": "serum3LiqForceCancelOrders",
@@ -11695,6 +14093,24 @@ export const IDL: MangoV4 = {
           "type": {
             "option": "string"
           }
+        },
+        {
+          "name": "referrerFeeShareOpt",
+          "type": {
+            "option": "f32"
+          }
+        },
+        {
+          "name": "adlEnabledOpt",
+          "type": {
+            "option": "bool"
+          }
+        },
+        {
+          "name": "adlMinScoreOpt",
+          "type": {
+            "option": "f32"
+          }
         }
       ]
     },
@@ -12293,54 +14709,204 @@ export const IDL: MangoV4 = {
           "isSigner": false
         },
         {
-          "name": "oracle",
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqForceCancelOrders",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "account",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "bids",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "asks",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "limit",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqNegativePnlOrBankruptcy",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "liqor",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "liqorOwner",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "perpMarket",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "oracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleOracle",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "insuranceVault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "maxLiabTransfer",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpAdl",
+      "accounts": [
+        {
+          "name": "group",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "liqor",
+          "name": "perpMarket",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "liqorOwner",
+          "name": "oracle",
           "isMut": false,
-          "isSigner": true
-        },
-        {
-          "name": "liqee",
-          "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleBank",
+          "name": "liqee",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleVault",
+          "name": "counterparty",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleOracle",
+          "name": "insuranceVault",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "Deleveraging only starts once the group insurance fund is exhausted"
+          ]
         }
       ],
       "args": [
         {
           "name": "maxBaseTransfer",
-          "type": "i64"
+          "type": "u64"
         },
         {
-          "name": "maxPnlTransfer",
-          "type": "u64"
+          "name": "numCandidates",
+          "type": "u8"
         }
       ]
     },
     {
-      "name": "perpLiqForceCancelOrders",
+      "name": "perpIsolatedDeposit",
       "accounts": [
         {
           "name": "group",
@@ -12352,31 +14918,36 @@ export const IDL: MangoV4 = {
           "isMut": true,
           "isSigner": false
         },
+        {
+          "name": "owner",
+          "isMut": false,
+          "isSigner": true
+        },
         {
           "name": "perpMarket",
-          "isMut": true,
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "bids",
+          "name": "settleBank",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "asks",
-          "isMut": true,
+          "name": "settleOracle",
+          "isMut": false,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "limit",
-          "type": "u8"
+          "name": "amount",
+          "type": "u64"
         }
       ]
     },
     {
-      "name": "perpLiqNegativePnlOrBankruptcy",
+      "name": "perpIsolatedWithdraw",
       "accounts": [
         {
           "name": "group",
@@ -12384,59 +14955,84 @@ export const IDL: MangoV4 = {
           "isSigner": false
         },
         {
-          "name": "liqor",
+          "name": "account",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "liqorOwner",
+          "name": "owner",
           "isMut": false,
           "isSigner": true
         },
         {
-          "name": "liqee",
-          "isMut": true,
+          "name": "perpMarket",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "perpMarket",
+          "name": "settleBank",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "oracle",
+          "name": "settleOracle",
           "isMut": false,
           "isSigner": false
-        },
+        }
+      ],
+      "args": [
         {
-          "name": "settleBank",
-          "isMut": true,
+          "name": "amount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "perpLiqIsolated",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "settleVault",
+          "name": "perpMarket",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "settleOracle",
+          "name": "oracle",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "insuranceVault",
+          "name": "liqor",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "tokenProgram",
+          "name": "liqorOwner",
           "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "liqee",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "settleBank",
+          "isMut": true,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "maxLiabTransfer",
+          "name": "maxBaseTransfer",
+          "type": "i64"
+        },
+        {
+          "name": "maxPnlTransfer",
           "type": "u64"
         }
       ]
@@ -12520,6 +15116,17 @@ export const IDL: MangoV4 = {
       ],
       "args": []
     },
+    {
+      "name": "accountHealthSnapshot",
+      "accounts": [
+        {
+          "name": "group",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "benchmark",
       "docs": [
@@ -12805,12 +15412,111 @@ export const IDL: MangoV4 = {
             "name": "reduceOnly",
             "type": "u8"
           },
+          {
+            "name": "interestCurveLen",
+            "docs": [
+              "Number of points in interest_curve that are in use.",
+              "",
+              "If zero, the interest rate curve is defined by util0, rate0, util1, rate1 and max_rate."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                14
+              ]
+            }
+          },
+          {
+            "name": "interestCurve",
+            "docs": [
+              "Points of a piecewise-linear interest rate curve, starting from (0, 0)",
+              "",
+              "The utilization of the first point is the optimal utilization used for",
+              "rate adjustments, the last point is at 100% utilization."
+            ],
+            "type": {
+              "array": [
+                {
+                  "defined": "InterestCurvePoint"
+                },
+                8
+              ]
+            }
+          },
+          {
+            "name": "rewardsMint",
+            "docs": [
+              "Mint of the liquidity mining rewards, zero if no rewards are configured"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "rewardsVault",
+            "docs": [
+              "Token account of rewards_mint owned by the group, rewards are claimed from here"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "depositRewardsIndex",
+            "docs": [
+              "Accumulated rewards (in rewards_mint native) per indexed deposit"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "borrowRewardsIndex",
+            "docs": [
+              "Accumulated rewards (in rewards_mint native) per indexed borrow"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "rewardsPerSecond",
+            "docs": [
+              "Rewards emitted per second, in rewards_mint native units"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "rewardsDepositShare",
+            "docs": [
+              "Fraction of the emissions that goes to depositors, the rest goes to borrowers"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding2",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "depositLimit",
+            "docs": [
+              "Limit for the total native deposits of the token, set to 0 to disable",
+              "",
+              "Deposits that would make native_deposits() exceed this fail."
+            ],
+            "type": "u64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                2119
+                1728
               ]
             }
           }
@@ -12916,12 +15622,109 @@ export const IDL: MangoV4 = {
             ],
             "type": "u64"
           },
+          {
+            "name": "perpFeeTiers",
+            "docs": [
+              "Perp fee tiers by the account's perp volume over the last 30 days.",
+              "",
+              "Only the first perp_fee_tiers_count entries are in use, ordered by min_volume."
+            ],
+            "type": {
+              "array": [
+                {
+                  "defined": "PerpFeeTier"
+                },
+                8
+              ]
+            }
+          },
+          {
+            "name": "perpFeeTiersCount",
+            "type": "u8"
+          },
+          {
+            "name": "padding2",
+            "type": {
+              "array": [
+                "u8",
+                7
+              ]
+            }
+          },
+          {
+            "name": "liquidationAuctionDuration",
+            "docs": [
+              "Liquidation fee auction duration in seconds, 0 disables the auction.",
+              "",
+              "When enabled, liquidators of token and perp base positions only get a fraction",
+              "of the configured liquidation fees. See liquidation_fee_fraction()."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "liquidationAuctionStartFraction",
+            "docs": [
+              "Fraction of the liquidation fees that applies when an account starts being liquidated"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "liquidationAuctionFullFeeDepth",
+            "docs": [
+              "Maint health deficit, as a fraction of maint liabilities, at which the full",
+              "liquidation fees apply. 0 disables the depth component."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "liquidationCloseFactor",
+            "docs": [
+              "Maximum fraction of a liab token or perp base position that liquidators may",
+              "transfer within one liquidation_close_factor_window, 0 disables the limit.",
+              "",
+              "The fraction is relative to the position size at the start of the window."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding3",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "liquidationCloseFactorWindow",
+            "docs": [
+              "Length of the close factor window in seconds"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "selfLiquidationFeeFraction",
+            "docs": [
+              "Fraction of the liab token's liquidation fee that accounts pay when they",
+              "liquidate themselves with a FlashLoanType::SelfLiquidation flash loan"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding4",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                1824
+                1648
               ]
             }
           }
@@ -13036,12 +15839,54 @@ export const IDL: MangoV4 = {
             ],
             "type": "u64"
           },
+          {
+            "name": "perpVolumeCurrent",
+            "docs": [
+              "Perp volume in quote native of the current and the previous window of",
+              "PERP_VOLUME_WINDOW seconds, for perp fee tiers."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumePrevious",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumeWindowEnd",
+            "docs": [
+              "End timestamp of the current perp volume window."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSince",
+            "docs": [
+              "Timestamp at which being_liquidated was last set, for liquidation fee auctions."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartTs",
+            "docs": [
+              "Liquidation close factor window for cancelling serum3 orders: start of the window,",
+              "the number of open serum3 orders at that time and the number cancelled since."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartOrders",
+            "type": "u32"
+          },
+          {
+            "name": "serum3LiqWindowCancelledOrders",
+            "type": "u32"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                208
+                160
               ]
             }
           },
@@ -13105,6 +15950,18 @@ export const IDL: MangoV4 = {
                 "defined": "PerpOpenOrder"
               }
             }
+          },
+          {
+            "name": "padding8",
+            "type": "u32"
+          },
+          {
+            "name": "isolatedPerps",
+            "type": {
+              "vec": {
+                "defined": "IsolatedPerpPosition"
+              }
+            }
           }
         ]
       }
@@ -13647,12 +16504,20 @@ export const IDL: MangoV4 = {
             ],
             "type": "u8"
           },
+          {
+            "name": "adlEnabled",
+            "docs": [
+              "If true, PerpAdl may close the base position of bankrupt accounts against the",
+              "most profitable and leveraged opposite-side positions at the bankruptcy price."
+            ],
+            "type": "u8"
+          },
           {
             "name": "padding4",
             "type": {
               "array": [
                 "u8",
-                7
+                6
               ]
             }
           },
@@ -13677,12 +16542,53 @@ export const IDL: MangoV4 = {
               "defined": "I80F48"
             }
           },
+          {
+            "name": "referrerFeeShare",
+            "docs": [
+              "Fraction of the taker fee that goes to the referrer of a taker order, if any."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "padding5",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "adlMinScore",
+            "docs": [
+              "Counterparties of PerpAdl need at least this adl score, see PerpPosition::adl_score()"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "adlNextLong",
+            "docs": [
+              "The best-ranked long position that the last PerpAdl against longs passed as a",
+              "candidate but didn't deleverage. Later PerpAdl calls against longs must pass it",
+              "as a candidate again, so the ranking can't be skipped by leaving it out."
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "adlNextShort",
+            "docs": [
+              "Like adl_next_long, for PerpAdl calls against shorts"
+            ],
+            "type": "publicKey"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                1888
+                1800
               ]
             }
           }
@@ -13757,12 +16663,36 @@ export const IDL: MangoV4 = {
             "name": "registrationTime",
             "type": "u64"
           },
+          {
+            "name": "settleFeeFlat",
+            "docs": [
+              "Fee paid by the account to the caller of Serum3SettleFundsPermissionless,",
+              "in $ native units (like USDC native)"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "settleFeeAmountThreshold",
+            "docs": [
+              "The fee is only charged when settling funds worth at least this amount,",
+              "in $ native units (like USDC native)"
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "settleRebatesToAccount",
+            "docs": [
+              "Whether Serum3SettleFundsPermissionless credits serum referrer rebates to the",
+              "settled account instead of collecting them as fees, see Serum3SettleFundsV2"
+            ],
+            "type": "u8"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                128
+                119
               ]
             }
           }
@@ -13828,6 +16758,22 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "InterestCurvePointParams",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "util",
+            "type": "f32"
+          },
+          {
+            "name": "rate",
+            "type": "f32"
+          }
+        ]
+      }
+    },
     {
       "name": "Equity",
       "type": {
@@ -14100,6 +17046,35 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "IsolatedPerpInfo",
+      "docs": [
+        "A perp position that is margined in isolation, with its dedicated collateral.",
+        "",
+        "It does not contribute to the account's health. Instead its own health is",
+        "the sum of the collateral and the perp health contributions."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "perp",
+            "type": {
+              "defined": "PerpInfo"
+            }
+          },
+          {
+            "name": "collateral",
+            "docs": [
+              "Info for the perp market's settle token, balance_native is the isolated collateral"
+            ],
+            "type": {
+              "defined": "TokenInfo"
+            }
+          }
+        ]
+      }
+    },
     {
       "name": "HealthCache",
       "type": {
@@ -14129,6 +17104,14 @@ export const IDL: MangoV4 = {
               }
             }
           },
+          {
+            "name": "isolatedPerpInfos",
+            "type": {
+              "vec": {
+                "defined": "IsolatedPerpInfo"
+              }
+            }
+          },
           {
             "name": "beingLiquidated",
             "type": "bool"
@@ -14172,6 +17155,65 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "InterestCurvePoint",
+      "docs": [
+        "A point on a Bank's interest rate curve"
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "util",
+            "docs": [
+              "Utilization, between 0 and 1"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "rate",
+            "docs": [
+              "Borrow interest rate in APR at this utilization"
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "PerpFeeTier",
+      "docs": [
+        "Perp fees for accounts that traded at least min_volume in the last 30 days"
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "minVolume",
+            "docs": [
+              "Minimum perp volume of the account, in quote native (like USDC native)"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "makerFee",
+            "docs": [
+              "Fees for accounts in this tier. They only apply on markets where they",
+              "are lower than the market's own maker_fee and taker_fee."
+            ],
+            "type": "f32"
+          },
+          {
+            "name": "takerFee",
+            "type": "f32"
+          }
+        ]
+      }
+    },
     {
       "name": "TokenPosition",
       "type": {
@@ -14223,12 +17265,48 @@ export const IDL: MangoV4 = {
             "name": "cumulativeBorrowInterest",
             "type": "f64"
           },
+          {
+            "name": "previousRewardsIndex",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "unclaimedRewards",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "liqWindowStartTs",
+            "type": "u64"
+          },
+          {
+            "name": "liqWindowStartLiab",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "liqWindowLiquidated",
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "cumulativeDeposits",
+            "type": "f64"
+          },
+          {
+            "name": "cumulativeWithdraws",
+            "type": "f64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                128
+                40
               ]
             }
           }
@@ -14245,53 +17323,108 @@ export const IDL: MangoV4 = {
             "type": "publicKey"
           },
           {
-            "name": "baseBorrowsWithoutFee",
+            "name": "baseBorrowsWithoutFee",
+            "docs": [
+              "Tracks the amount of borrows that have flowed into the serum open orders account.",
+              "These borrows did not have the loan origination fee applied, and that may happen",
+              "later (in serum3_settle_funds) if we can guarantee that the funds were used.",
+              "In particular a place-on-book, cancel, settle should not cost fees."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "quoteBorrowsWithoutFee",
+            "type": "u64"
+          },
+          {
+            "name": "marketIndex",
+            "type": "u16"
+          },
+          {
+            "name": "baseTokenIndex",
+            "docs": [
+              "Store the base/quote token index, so health computations don't need",
+              "to get passed the static SerumMarket to find which tokens a market",
+              "uses and look up the correct oracles."
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "quoteTokenIndex",
+            "type": "u16"
+          },
+          {
+            "name": "fillsTracked",
+            "docs": [
+              "Set once last_seen_base_total and last_seen_quote_total are valid.",
+              "Older orders start tracking fills at the first settle or place order."
+            ],
+            "type": "u8"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                1
+              ]
+            }
+          },
+          {
+            "name": "baseBoughtNative",
             "docs": [
-              "Tracks the amount of borrows that have flowed into the serum open orders account.",
-              "These borrows did not have the loan origination fee applied, and that may happen",
-              "later (in serum3_settle_funds) if we can guarantee that the funds were used.",
-              "In particular a place-on-book, cancel, settle should not cost fees."
+              "Cumulative native base bought in fills and the native quote paid for it,",
+              "including taker fees and net of maker rebates"
             ],
             "type": "u64"
           },
           {
-            "name": "quoteBorrowsWithoutFee",
+            "name": "quotePaidNative",
             "type": "u64"
           },
           {
-            "name": "marketIndex",
-            "type": "u16"
+            "name": "baseSoldNative",
+            "docs": [
+              "Cumulative native base sold in fills and the native quote received for it,",
+              "net of taker fees and including maker rebates"
+            ],
+            "type": "u64"
           },
           {
-            "name": "baseTokenIndex",
+            "name": "quoteReceivedNative",
+            "type": "u64"
+          },
+          {
+            "name": "referrerRebatesSettledNative",
             "docs": [
-              "Store the base/quote token index, so health computations don't need",
-              "to get passed the static SerumMarket to find which tokens a market",
-              "uses and look up the correct oracles."
+              "Cumulative native quote referrer rebates that were settled out of the open orders",
+              "account, either to the account or to the DAO as fees"
             ],
-            "type": "u16"
+            "type": "u64"
           },
           {
-            "name": "quoteTokenIndex",
-            "type": "u16"
+            "name": "lastSeenBaseTotal",
+            "docs": [
+              "Open orders base and quote totals when they were last seen. Changes of the totals",
+              "that are not caused by deposits or withdrawals are fills."
+            ],
+            "type": "u64"
           },
           {
-            "name": "padding",
-            "type": {
-              "array": [
-                "u8",
-                2
-              ]
-            }
+            "name": "lastSeenQuoteTotal",
+            "type": "u64"
           },
           {
-            "name": "reserved",
-            "type": {
-              "array": [
-                "u8",
-                64
-              ]
-            }
+            "name": "estimatedNetFeesNative",
+            "docs": [
+              "Estimate of the cumulative native quote taker fees paid minus maker rebates",
+              "received in fills.",
+              "",
+              "The open orders account doesn't report fees. They are derived from the quote",
+              "paid and received with the serum base fee tier rates, so the estimate is off",
+              "on markets with other rates, like stable markets."
+            ],
+            "type": "i64"
           }
         ]
       }
@@ -14513,12 +17646,38 @@ export const IDL: MangoV4 = {
               "defined": "I80F48"
             }
           },
+          {
+            "name": "cumulativeReferrerRewards",
+            "docs": [
+              "Cumulative share of taker fees received as the referrer of other accounts'",
+              "orders, in quote native units",
+              "",
+              "(Display only)"
+            ],
+            "type": "f64"
+          },
+          {
+            "name": "liqWindowStartTs",
+            "docs": [
+              "Liquidation close factor bookkeeping: start of the current window, the base",
+              "position at that time and the base lots liquidated since"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "liqWindowStartBaseLots",
+            "type": "i64"
+          },
+          {
+            "name": "liqWindowLiquidatedBaseLots",
+            "type": "i64"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                88
+                56
               ]
             }
           }
@@ -14576,6 +17735,64 @@ export const IDL: MangoV4 = {
         ]
       }
     },
+    {
+      "name": "IsolatedPerpPosition",
+      "docs": [
+        "Dedicated collateral for a perp position that is margined in isolation.",
+        "",
+        "The PerpPosition for the market stays in the account's perp positions, but it",
+        "doesn't count toward the account's health. Instead its health is computed from",
+        "the position and this collateral alone, and liquidating it only touches this",
+        "collateral."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "marketIndex",
+            "docs": [
+              "The perp market the collateral is dedicated to, PerpMarketIndex::MAX if unused"
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "tokenIndex",
+            "docs": [
+              "The collateral token, always the perp market's settle token"
+            ],
+            "type": "u16"
+          },
+          {
+            "name": "padding",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "indexedCollateral",
+            "docs": [
+              "The collateral in deposit_index scaled units of the settle token bank.",
+              "Like a positive TokenPosition it earns deposit interest, but never rewards."
+            ],
+            "type": {
+              "defined": "I80F48"
+            }
+          },
+          {
+            "name": "reserved",
+            "type": {
+              "array": [
+                "u8",
+                40
+              ]
+            }
+          }
+        ]
+      }
+    },
     {
       "name": "MangoAccountFixed",
       "type": {
@@ -14655,12 +17872,40 @@ export const IDL: MangoV4 = {
             "name": "buybackFeesExpiryTimestamp",
             "type": "u64"
           },
+          {
+            "name": "perpVolumeCurrent",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumePrevious",
+            "type": "u64"
+          },
+          {
+            "name": "perpVolumeWindowEnd",
+            "type": "u64"
+          },
+          {
+            "name": "beingLiquidatedSince",
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartTs",
+            "type": "u64"
+          },
+          {
+            "name": "serum3LiqWindowStartOrders",
+            "type": "u32"
+          },
+          {
+            "name": "serum3LiqWindowCancelledOrders",
+            "type": "u32"
+          },
           {
             "name": "reserved",
             "type": {
               "array": [
                 "u8",
-                208
+                160
               ]
             }
           }
@@ -15082,13 +18327,11 @@ export const IDL: MangoV4 = {
             "type": "publicKey"
           },
           {
-            "name": "padding2",
-            "type": {
-              "array": [
-                "u8",
-                32
-              ]
-            }
+            "name": "referrer",
+            "docs": [
+              "MangoAccount that receives a share of the taker fee, or the default pubkey"
+            ],
+            "type": "publicKey"
           },
           {
             "name": "makerTimestamp",
@@ -15378,6 +18621,9 @@ export const IDL: MangoV4 = {
           },
           {
             "name": "Swap"
+          },
+          {
+            "name": "SelfLiquidation"
           }
         ]
       }
@@ -15500,8 +18746,8 @@ export const IDL: MangoV4 = {
       "docs": [
         "Enum for lookup into ix gate",
         "note:",
-        "total ix files 56,",
-        "ix files included 48,",
+        "total ix files 61,",
+        "ix files included 53,",
         "ix files not included 8,",
         "- Benchmark,",
         "- ComputeAccountData,",
@@ -15661,6 +18907,27 @@ export const IDL: MangoV4 = {
           },
           {
             "name": "AccountBuybackFeesWithMngo"
+          },
+          {
+            "name": "TokenClaimRewards"
+          },
+          {
+            "name": "FlashLoanSelfLiquidation"
+          },
+          {
+            "name": "PerpAdl"
+          },
+          {
+            "name": "PerpIsolatedCollateral"
+          },
+          {
+            "name": "PerpLiqIsolated"
+          },
+          {
+            "name": "AccountHealthSnapshot"
+          },
+          {
+            "name": "Serum3SettleFundsPermissionless"
           }
         ]
       }
@@ -16025,6 +19292,61 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "TokenBalanceLogV2",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "indexedPosition",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "depositIndex",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "borrowIndex",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "cumulativeDepositInterest",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeBorrowInterest",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeDeposits",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "cumulativeWithdraws",
+          "type": "f64",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "FlashLoanLog",
       "fields": [
@@ -16034,24 +19356,69 @@ export const IDL: MangoV4 = {
           "index": false
         },
         {
-          "name": "mangoAccount",
-          "type": "publicKey",
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenLoanDetails",
+          "type": {
+            "vec": {
+              "defined": "FlashLoanTokenDetail"
+            }
+          },
+          "index": false
+        },
+        {
+          "name": "flashLoanType",
+          "type": {
+            "defined": "FlashLoanType"
+          },
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "FlashLoanSelfLiquidationLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "assetTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liabTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liabRepaid",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "fee",
+          "type": "i128",
           "index": false
         },
         {
-          "name": "tokenLoanDetails",
-          "type": {
-            "vec": {
-              "defined": "FlashLoanTokenDetail"
-            }
-          },
+          "name": "liqEndHealthBefore",
+          "type": "i128",
           "index": false
         },
         {
-          "name": "flashLoanType",
-          "type": {
-            "defined": "FlashLoanType"
-          },
+          "name": "liqEndHealthAfter",
+          "type": "i128",
           "index": false
         }
       ]
@@ -16301,6 +19668,146 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "FillLogV3",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "takerSide",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "makerSlot",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "makerOut",
+          "type": "bool",
+          "index": false
+        },
+        {
+          "name": "timestamp",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "seqNum",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "makerClientOrderId",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "makerFee",
+          "type": "f32",
+          "index": false
+        },
+        {
+          "name": "makerTimestamp",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "taker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "takerClientOrderId",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "takerFee",
+          "type": "f32",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quantity",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "makerRealizedPnl",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "makerEntryPricePerBaseLot",
+          "type": "f64",
+          "index": false
+        },
+        {
+          "name": "takerRealizedPnl",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "takerEntryPricePerBaseLot",
+          "type": "f64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpReferrerRewardLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "referrer",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "taker",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "seqNum",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "reward",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "PerpUpdateFundingLog",
       "fields": [
@@ -16557,7 +20064,147 @@ export const IDL: MangoV4 = {
       ]
     },
     {
-      "name": "Serum3OpenOrdersBalanceLogV2",
+      "name": "Serum3OpenOrdersBalanceLogV2",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTotal",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseFree",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteTotal",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteFree",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "referrerRebatesAccrued",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Serum3OpenOrdersBalanceLogV3",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "marketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "quoteTokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTotal",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseFree",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteTotal",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteFree",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "referrerRebatesAccrued",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseBought",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quotePaid",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "baseSold",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "quoteReceived",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "estimatedNetFees",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "referrerRebatesSettled",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Serum3SettleFundsPermissionlessLog",
       "fields": [
         {
           "name": "mangoGroup",
@@ -16575,38 +20222,23 @@ export const IDL: MangoV4 = {
           "index": false
         },
         {
-          "name": "baseTokenIndex",
-          "type": "u16",
-          "index": false
-        },
-        {
-          "name": "quoteTokenIndex",
-          "type": "u16",
-          "index": false
-        },
-        {
-          "name": "baseTotal",
-          "type": "u64",
-          "index": false
-        },
-        {
-          "name": "baseFree",
-          "type": "u64",
+          "name": "settler",
+          "type": "publicKey",
           "index": false
         },
         {
-          "name": "quoteTotal",
+          "name": "settledBase",
           "type": "u64",
           "index": false
         },
         {
-          "name": "quoteFree",
+          "name": "settledQuote",
           "type": "u64",
           "index": false
         },
         {
-          "name": "referrerRebatesAccrued",
-          "type": "u64",
+          "name": "fee",
+          "type": "i128",
           "index": false
         }
       ]
@@ -16998,6 +20630,56 @@ export const IDL: MangoV4 = {
         }
       ]
     },
+    {
+      "name": "PerpAdlLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "counterparty",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "baseTransferLiqee",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransferLiqee",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "bankruptcyPrice",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "adlScore",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
     {
       "name": "PerpLiqNegativePnlOrBankruptcyLog",
       "fields": [
@@ -17127,6 +20809,166 @@ export const IDL: MangoV4 = {
           "index": false
         }
       ]
+    },
+    {
+      "name": "TokenClaimRewardsLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "rewardsMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpIsolatedCollateralLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "tokenIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "change",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "indexedCollateral",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "depositIndex",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "PerpLiqIsolatedLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "perpMarketIndex",
+          "type": "u16",
+          "index": false
+        },
+        {
+          "name": "liqor",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "liqee",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "baseTransfer",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "quoteTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "collateralTransfer",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "bankruptcyLoss",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "price",
+          "type": "i128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "AccountHealthSnapshotLog",
+      "fields": [
+        {
+          "name": "mangoGroup",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mangoAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "initHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "maintHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "liquidationEndHealth",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "equity",
+          "type": "i128",
+          "index": false
+        },
+        {
+          "name": "beingLiquidated",
+          "type": "bool",
+          "index": false
+        }
+      ]
     }
   ],
   "errors": [
@@ -17354,6 +21196,36 @@ export const IDL: MangoV4 = {
       "code": 6044,
       "name": "PerpOrderIdNotFound",
       "msg": "perp order id not found on the orderbook"
+    },
+    {
+      "code": 6045,
+      "name": "HealthRegionBadInnerInstruction",
+      "msg": "HealthRegions allow only specific instructions between Begin and End"
+    },
+    {
+      "code": 6046,
+      "name": "BankDepositLimit",
+      "msg": "deposit crosses the token's deposit limit"
+    },
+    {
+      "code": 6047,
+      "name": "LiquidationCloseFactorReached",
+      "msg": "the liquidation close factor limit for this window has been reached"
+    },
+    {
+      "code": 6048,
+      "name": "NoFreeIsolatedPerpPositionIndex",
+      "msg": "no free isolated perp position index"
+    },
+    {
+      "code": 6049,
+      "name": "IsolatedPerpPositionDoesNotExist",
+      "msg": "isolated perp position does not exist"
+    },
+    {
+      "code": 6050,
+      "name": "PerpPositionIsIsolated",
+      "msg": "the perp position is margined in isolation"
     }
   ]
 };
@@ -10,6 +10,7 @@ export class FlashLoanWithdraw {
 export class FlashLoanType {
   static unknown = { unknown: {} };
   static swap = { swap: {} };
+  static selfLiquidation = { selfLiquidation: {} };
 }
 
 export class InterestRateParams {
@@ -21,7 +22,18 @@ export class InterestRateParams {
   adjustmentFactor: number;
 }
 
+export class InterestCurvePointParams {
+  util: number;
+  rate: number;
+}
+
 export class OracleConfigParams {
   confFilter: number;
   maxStalenessSlots: number | null;
 }
+
+export class PerpFeeTier {
+  minVolume: BN;
+  makerFee: number;
+  takerFee: number;
+}